@@ -0,0 +1,251 @@
+//! Typed client for the HTTP API of the ITSF player database, e.g. for integrations into tournament software.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), playerdb_client::Error> {
+//! let client = playerdb_client::Client::new("https://players.example.org").with_credentials("user", "password");
+//! let player = client.player(84000895).await?;
+//! println!("{} {}", player.first_name, player.last_name);
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+pub mod types;
+
+pub use types::*;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request failed or the response couldn't be decoded.
+    Http(reqwest::Error),
+    /// The server rejected the request, e.g. with 404 for unknown players.
+    Api { status: u16, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "request failed: {}", err),
+            Error::Api { status, message } => write!(f, "server returned {}: {}", status, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct JsonOk<T> {
+    data: T,
+    /// Only sent with pages of a list.
+    #[serde(default)]
+    next_cursor: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonErr {
+    error: serde_json::Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    credentials: Option<(String, String)>,
+}
+
+impl Client {
+    pub fn new(base_url: &str) -> Self {
+        Client {
+            http: reqwest::Client::new(),
+            base_url: String::from(base_url.trim_end_matches('/')),
+            credentials: None,
+        }
+    }
+
+    /// Logs in with Basic auth, needed for all changes and to see hidden players and internal comments.
+    pub fn with_credentials(mut self, user_id: &str, password: &str) -> Self {
+        self.credentials = Some((String::from(user_id), String::from(password)));
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.credentials {
+            Some((user_id, password)) => request.basic_auth(user_id, Some(password)),
+            None => request,
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T, Error> {
+        Ok(self.send_page(request).await?.data)
+    }
+
+    async fn send_page<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<JsonOk<T>, Error> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.json::<JsonOk<T>>().await?);
+        }
+        let body = response.text().await?;
+        let message = match serde_json::from_str::<JsonErr>(&body) {
+            Ok(JsonErr {
+                error: serde_json::Value::String(message),
+            }) => message,
+            Ok(JsonErr { error }) => error.to_string(),
+            Err(_) => body,
+        };
+        Err(Error::Api {
+            status: status.as_u16(),
+            message,
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T, Error> {
+        self.send(self.request(reqwest::Method::GET, path).query(query)).await
+    }
+
+    async fn post<B: serde::Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T, Error> {
+        self.send(self.request(reqwest::Method::POST, path).json(body)).await
+    }
+
+    /// The full profile, including all sections.
+    pub async fn player(&self, itsf_lic: i32) -> Result<Player, Error> {
+        self.player_sections(itsf_lic, &["all"]).await
+    }
+
+    /// The profile with only the given sections, e.g. `rankings` or `comments`; the others are empty.
+    pub async fn player_sections(&self, itsf_lic: i32, sections: &[&str]) -> Result<Player, Error> {
+        self.get(&format!("/player/{}", itsf_lic), &[("include", sections.join(","))])
+            .await
+    }
+
+    pub async fn player_by_dtfb_license(&self, dtfb_lic: i32) -> Result<Player, Error> {
+        self.get(
+            &format!("/player/dtfb/{}", dtfb_lic),
+            &[("include", String::from("all"))],
+        )
+        .await
+    }
+
+    /// Players matching all words of the query in their name, license, country or tags.
+    pub async fn search(&self, query: &str, limit: Option<usize>) -> Result<Vec<PlayerSummary>, Error> {
+        let mut params = vec![("q", String::from(query))];
+        if let Some(limit) = limit {
+            params.push(("limit", limit.to_string()));
+        }
+        self.get("/search", &params).await
+    }
+
+    /// All players, or only those with the given tag.
+    pub async fn players(&self, tag: Option<&str>) -> Result<Vec<PlayerSummary>, Error> {
+        let params: Vec<(&str, String)> = tag.map(|tag| ("tag", String::from(tag))).into_iter().collect();
+        self.get("/listplayers", &params).await
+    }
+
+    /// A page of all players ordered by license, starting after the `next_cursor` of the previous page.
+    pub async fn players_page(&self, cursor: Option<&str>, limit: usize) -> Result<Page<PlayerSummary>, Error> {
+        let mut params = vec![("limit", limit.to_string())];
+        if let Some(cursor) = cursor {
+            params.push(("cursor", String::from(cursor)));
+        }
+        let page = self
+            .send_page(self.request(reqwest::Method::GET, "/listplayers").query(&params))
+            .await?;
+        Ok(Page {
+            items: page.data,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    pub async fn lists(&self) -> Result<Vec<PlayerList>, Error> {
+        self.get("/lists", &[]).await
+    }
+
+    pub async fn list_players(&self, list_id: i32) -> Result<Vec<PlayerSummary>, Error> {
+        self.get(&format!("/list/{}/players", list_id), &[]).await
+    }
+
+    pub async fn add_comment(&self, itsf_lic: i32, comment: &str, visibility: CommentVisibility) -> Result<(), Error> {
+        let body = serde_json::json!({ "itsf_lic": itsf_lic, "comment": comment, "visibility": visibility });
+        self.post::<_, serde_json::Value>("/add_comment", &body).await?;
+        Ok(())
+    }
+
+    /// Opens or resolves the comment, `None` stops tracking it.
+    pub async fn set_comment_status(
+        &self,
+        itsf_lic: i32,
+        comment: &Comment,
+        status: Option<CommentStatus>,
+    ) -> Result<Comment, Error> {
+        let body = serde_json::json!({
+            "itsf_lic": itsf_lic,
+            "timestamp": comment.timestamp,
+            "text": comment.text,
+            "status": status,
+        });
+        self.post("/comment_status", &body).await
+    }
+
+    /// Gives the comment a thumbs-up, or takes it back.
+    pub async fn set_comment_thumbs_up(
+        &self,
+        itsf_lic: i32,
+        comment: &Comment,
+        thumbs_up: bool,
+    ) -> Result<Comment, Error> {
+        let body = serde_json::json!({
+            "itsf_lic": itsf_lic,
+            "timestamp": comment.timestamp,
+            "text": comment.text,
+            "thumbs_up": thumbs_up,
+        });
+        self.post("/comment_reaction", &body).await
+    }
+
+    /// Imports comments, reporting invalid rows instead of failing. With `dry_run`, only validates them.
+    pub async fn import_comments(&self, comments: &[CommentImport], dry_run: bool) -> Result<ImportReport, Error> {
+        let request = self
+            .request(reqwest::Method::POST, "/import/comments")
+            .query(&[("dry_run", dry_run)])
+            .json(comments);
+        self.send(request).await
+    }
+
+    pub async fn add_tag(&self, itsf_lic: i32, tag: &str) -> Result<(), Error> {
+        let body = serde_json::json!({ "itsf_lic": itsf_lic, "tag": tag });
+        self.post::<_, serde_json::Value>("/add_tag", &body).await?;
+        Ok(())
+    }
+
+    pub async fn remove_tag(&self, itsf_lic: i32, tag: &str) -> Result<(), Error> {
+        let body = serde_json::json!({ "itsf_lic": itsf_lic, "tag": tag });
+        self.post::<_, serde_json::Value>("/remove_tag", &body).await?;
+        Ok(())
+    }
+
+    /// Adds the tag to or removes it from all players.
+    pub async fn bulk_tag(&self, players: &[i32], tag: &str, operation: TagOperation) -> Result<BulkTagSummary, Error> {
+        let body = serde_json::json!({ "players": players, "tag": tag, "operation": operation });
+        self.post("/tags/bulk", &body).await
+    }
+
+    /// Adds the tag to or removes it from all players of the list.
+    pub async fn bulk_tag_list(
+        &self,
+        list_id: i32,
+        tag: &str,
+        operation: TagOperation,
+    ) -> Result<BulkTagSummary, Error> {
+        let body = serde_json::json!({ "list_id": list_id, "tag": tag, "operation": operation });
+        self.post("/tags/bulk", &body).await
+    }
+}
@@ -0,0 +1,292 @@
+//! Request and response types of the player database API.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingCategory {
+    Open,
+    Women,
+    Junior,
+    Senior,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingClass {
+    Singles,
+    Doubles,
+    Combined,
+}
+
+/// Where a ranking or result was scraped from.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Provenance {
+    pub source_url: String,
+    pub scraped_at: i64,
+    /// Id of the download job in the job history.
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ItsfRanking {
+    pub year: i32,
+    pub place: i32,
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    /// Place in percent of the ranked players.
+    #[serde(default)]
+    pub percentile: Option<f64>,
+    /// Only returned with `include_provenance`.
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChampionshipCategory {
+    Men,
+    Women,
+    Junior,
+    Senior,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChampionshipClass {
+    Singles,
+    Doubles,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DtfbRanking {
+    pub year: i32,
+    pub place: i32,
+    pub category: ChampionshipCategory,
+    /// Only returned with `include_provenance`.
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+}
+
+/// Placement at a German national championship.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChampionshipResult {
+    pub year: i32,
+    pub place: i32,
+    pub category: ChampionshipCategory,
+    pub class: ChampionshipClass,
+    /// Only returned with `include_provenance`.
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+}
+
+/// Team in the German national league.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LeagueTeam {
+    pub year: i32,
+    pub name: String,
+    /// Only returned with `include_provenance`.
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentVisibility {
+    #[default]
+    Public,
+    /// Only visible to logged in users.
+    Internal,
+}
+
+/// Follow-up state of a comment that asks for something to be done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentStatus {
+    Open,
+    Resolved,
+}
+
+/// Player referenced as `#{ITSF-ID}` in a comment, the name is missing for unknown or hidden players.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Mention {
+    pub itsf_lic: i32,
+    pub url: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Comment {
+    pub timestamp: u32,
+    pub text: String,
+    pub visibility: CommentVisibility,
+    pub author: Option<String>,
+    pub mentions: Vec<Mention>,
+    /// Missing for comments that aren't tracked.
+    #[serde(default)]
+    pub status: Option<CommentStatus>,
+    #[serde(default)]
+    pub resolved_by: Option<String>,
+    #[serde(default)]
+    pub resolved_at: Option<u32>,
+    #[serde(default)]
+    pub thumbs_up: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FormerName {
+    pub first_name: String,
+    pub last_name: String,
+    pub timestamp: u32,
+}
+
+/// Team competition of an ITSF World Championship a player played in for a national team.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NationalTeamAppearance {
+    pub year: i32,
+    pub event_id: i32,
+    pub event: String,
+    pub competition: String,
+    pub country_code: String,
+    pub place: Option<i32>,
+    /// Only returned with `include_provenance`.
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CountryChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub year: i32,
+}
+
+/// Full player profile, as returned by `/player/{ITSF-ID}`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Player {
+    pub first_name: String,
+    pub last_name: String,
+    /// `Firstname Lastname` unless another `name_style` was requested.
+    #[serde(default)]
+    pub display_name: String,
+    pub birth_year: i32,
+    pub country_code: String,
+    /// Relative to the server URL, none if the server doesn't hand out the image.
+    #[serde(default)]
+    pub image_url: Option<String>,
+    /// The sections below are only returned if requested with `?include=`.
+    #[serde(default)]
+    pub itsf_rankings: Vec<ItsfRanking>,
+    #[serde(default)]
+    pub dtfb_rankings: Vec<DtfbRanking>,
+    #[serde(default)]
+    pub dm_placements: Vec<ChampionshipResult>,
+    #[serde(default)]
+    pub dtfl_teams: Vec<LeagueTeam>,
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+    pub tags: Vec<String>,
+    pub former_names: Vec<FormerName>,
+    #[serde(default)]
+    pub country_changes: Vec<CountryChange>,
+    #[serde(default)]
+    pub national_team_appearances: Vec<NationalTeamAppearance>,
+    pub hidden: bool,
+    pub scraped_at: Option<i64>,
+    pub stale: bool,
+    #[serde(default)]
+    pub data_warnings: Vec<DataWarning>,
+    /// Change of age category with the next season, derived from the birth year.
+    #[serde(default)]
+    pub category_transition: Option<CategoryTransition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CategoryTransition {
+    LeavesJunior,
+    BecomesSenior,
+}
+
+/// Hint that a player's data may be incomplete because its last download failed.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DataWarning {
+    /// `profile_refresh_failed` or `image_refresh_failed`.
+    pub code: String,
+    pub failed_at: i64,
+    pub message: String,
+}
+
+/// A page of a list, see `Client::players_page`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor of the next page, `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Short player entry of player listings and search results.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlayerSummary {
+    pub itsf_lic: i32,
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(default)]
+    pub display_name: String,
+    pub tags: Vec<String>,
+    pub scraped_at: Option<i64>,
+    pub stale: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlayerList {
+    pub list_id: i32,
+    pub name: String,
+    pub description: String,
+    pub players: Vec<i32>,
+    pub created: u32,
+    /// The workspace the list belongs to, `None` for shared lists.
+    #[serde(default)]
+    pub workspace: Option<String>,
+}
+
+/// A comment for `/import/comments`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CommentImport {
+    pub license: i32,
+    pub comment: String,
+    pub author: Option<String>,
+    /// `YYYY-MM-DD`, `DD.MM.YYYY` or RFC 3339, the time of the import if missing.
+    pub date: Option<String>,
+    pub visibility: CommentVisibility,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ImportError {
+    pub row: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub errors: Vec<ImportError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagOperation {
+    Add,
+    Remove,
+}
+
+/// Summary of `/tags/bulk`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BulkTagSummary {
+    pub changed: usize,
+    pub unchanged: usize,
+    /// Licences without a stored player, skipped.
+    pub unknown: Vec<i32>,
+}
@@ -1,5 +1,8 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, Weak};
 
+static NEXT_JOB: AtomicUsize = AtomicUsize::new(0);
+
 struct BackgroundOperationInner {
     progress: usize,
     max: usize,
@@ -8,6 +11,9 @@ struct BackgroundOperationInner {
 
 pub struct BackgroundOperationProgress {
     title: String,
+    /// e.g. `1760520000-3`, the start time and a counter of the jobs started by this process.
+    id: String,
+    started_at: i64,
     inner: Mutex<BackgroundOperationInner>,
 }
 
@@ -16,6 +22,14 @@ impl BackgroundOperationProgress {
         &self.title
     }
 
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_started_at(&self) -> i64 {
+        self.started_at
+    }
+
     pub fn get_progress(&self) -> (usize, usize) {
         let inner = self.inner.lock().expect("failed to lock mutex");
         (inner.progress, inner.max)
@@ -44,8 +58,11 @@ impl BackgroundOperationProgress {
     }
 
     pub fn new(title: &str, max: usize) -> (Arc<BackgroundOperationProgress>, Weak<BackgroundOperationProgress>) {
+        let started_at = chrono::Utc::now().timestamp();
         let this = BackgroundOperationProgress {
             title: title.into(),
+            id: format!("{}-{}", started_at, NEXT_JOB.fetch_add(1, Ordering::Relaxed)),
+            started_at,
             inner: Mutex::new(BackgroundOperationInner {
                 progress: 0,
                 max,
@@ -0,0 +1,110 @@
+//! Recently served requests and latency statistics for replay benchmarks, which check the effect of caching
+//! and indexing changes on the real request mix.
+
+use lazy_static::lazy_static;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Number of recent requests kept for replay.
+const RECORDED_REQUESTS: usize = 1000;
+
+lazy_static! {
+    static ref RECORDED: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+}
+
+/// Remembers the URI of a served request for replay, dropping the oldest one when full.
+pub fn record(uri: String) {
+    let mut recorded = RECORDED.lock().unwrap();
+    if recorded.len() == RECORDED_REQUESTS {
+        recorded.pop_front();
+    }
+    recorded.push_back(uri);
+}
+
+/// The recorded requests, oldest first.
+pub fn recorded_requests() -> Vec<String> {
+    RECORDED.lock().unwrap().iter().cloned().collect()
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct Latencies {
+    pub requests: usize,
+    /// Responses that weren't successful, e.g. for players deleted since a replayed request was recorded.
+    pub errors: usize,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Latencies {
+    /// Percentiles of `durations`, which must not be empty.
+    pub fn from(mut durations: Vec<Duration>, errors: usize) -> Self {
+        durations.sort();
+        let percentile = |p: f64| {
+            let index = ((p * durations.len() as f64).ceil() as usize).clamp(1, durations.len()) - 1;
+            durations[index].as_secs_f64() * 1000.0
+        };
+        Latencies {
+            requests: durations.len(),
+            errors,
+            p50_ms: percentile(0.5),
+            p90_ms: percentile(0.9),
+            p99_ms: percentile(0.99),
+            max_ms: percentile(1.0),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct EndpointLatencies {
+    /// Route pattern like `/player/{itsf_lic}`, or the path if no route matched.
+    pub endpoint: String,
+    #[serde(flatten)]
+    pub latencies: Latencies,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct BenchReport {
+    pub total: Latencies,
+    /// Slowest endpoints by p90 first.
+    pub endpoints: Vec<EndpointLatencies>,
+}
+
+/// Collects the durations of replayed requests per endpoint.
+#[derive(Default)]
+pub struct BenchTimings {
+    durations: Vec<Duration>,
+    errors: usize,
+    endpoints: HashMap<String, (Vec<Duration>, usize)>,
+}
+
+impl BenchTimings {
+    pub fn add(&mut self, endpoint: String, elapsed: Duration, success: bool) {
+        let entry = self.endpoints.entry(endpoint).or_default();
+        entry.0.push(elapsed);
+        self.durations.push(elapsed);
+        if !success {
+            entry.1 += 1;
+            self.errors += 1;
+        }
+    }
+
+    /// The report of the added requests, of which there must be at least one.
+    pub fn report(self) -> BenchReport {
+        let mut endpoints: Vec<EndpointLatencies> = self
+            .endpoints
+            .into_iter()
+            .map(|(endpoint, (durations, errors))| EndpointLatencies {
+                endpoint,
+                latencies: Latencies::from(durations, errors),
+            })
+            .collect();
+        endpoints.sort_by(|a, b| b.latencies.p90_ms.total_cmp(&a.latencies.p90_ms));
+        BenchReport {
+            total: Latencies::from(self.durations, self.errors),
+            endpoints,
+        }
+    }
+}
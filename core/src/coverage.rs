@@ -0,0 +1,159 @@
+//! Which ITSF rankings and DTFB seasons are stored, to spot gaps before a backfill and to offer only
+//! years with data.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::data::itsf::{RankingCategory, RankingClass, RankingDownload};
+use crate::data::season::Season;
+use crate::data::DatabaseRef;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RankingCoverage {
+    pub year: i32,
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    /// Whether the ranking was downloaded or any of its placements are stored.
+    pub exists: bool,
+    /// Unix timestamp of the last download, unknown for rankings downloaded before downloads were recorded.
+    pub scraped_at: Option<i64>,
+    /// Number of stored placements.
+    pub entries: usize,
+    /// "Last update" date of the ranking page at the last download.
+    pub source_updated: Option<chrono::NaiveDate>,
+}
+
+/// Coverage of every available year, category and class, oldest year first.
+pub fn ranking_coverage(db: &DatabaseRef) -> Vec<RankingCoverage> {
+    let entries: HashMap<(i32, RankingCategory, RankingClass), usize> = db.aggregate_players(|players| {
+        let mut entries = HashMap::new();
+        for ranking in players.flat_map(|player| player.itsf_rankings.iter()) {
+            *entries
+                .entry((ranking.year, ranking.category, ranking.class))
+                .or_default() += 1;
+        }
+        entries
+    });
+    let downloads: HashMap<(i32, RankingCategory, RankingClass), RankingDownload> = db
+        .get_ranking_downloads()
+        .into_iter()
+        .map(|download| ((download.year, download.category, download.class), download))
+        .collect();
+
+    let mut coverage = Vec::new();
+    for season in Season::all_itsf() {
+        for category in RankingCategory::ALL {
+            for class in RankingClass::ALL {
+                let key = (season.year(), category, class);
+                let entries = entries.get(&key).copied().unwrap_or(0);
+                let download = downloads.get(&key);
+                coverage.push(RankingCoverage {
+                    year: season.year(),
+                    category,
+                    class,
+                    exists: entries > 0 || download.is_some(),
+                    scraped_at: download.map(|download| download.scraped_at),
+                    entries,
+                    source_updated: download.and_then(|download| download.source_updated),
+                });
+            }
+        }
+    }
+    coverage
+}
+
+impl RankingCoverage {
+    /// Whether the ranking is missing, or was last downloaded before its year ended and longer than `max_age` ago.
+    /// Rankings stored before downloads were recorded count as complete.
+    pub fn needs_download(&self, now: i64, max_age: i64) -> bool {
+        let year_end = chrono::NaiveDate::from_ymd_opt(self.year + 1, 1, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|end| end.timestamp())
+            .unwrap_or(i64::MAX);
+        if !self.exists {
+            return true;
+        }
+        match self.scraped_at {
+            Some(scraped_at) => scraped_at < year_end && now - scraped_at > max_age,
+            None => false,
+        }
+    }
+}
+
+/// A year with stored data.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DataYear {
+    pub year: i32,
+    /// ITSF placements, or DTFB rankings, championship results and team memberships.
+    pub entries: usize,
+    /// Unix timestamp of the most recent download, unknown for data downloaded before downloads were recorded.
+    pub scraped_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DataYears {
+    pub itsf: Vec<DataYear>,
+    /// DTFB seasons by the year they started in.
+    pub dtfb: Vec<DataYear>,
+}
+
+fn data_years(entries: BTreeMap<i32, usize>, downloads: impl Iterator<Item = (i32, i64)>) -> Vec<DataYear> {
+    let mut years: BTreeMap<i32, DataYear> = entries
+        .into_iter()
+        .map(|(year, entries)| {
+            (
+                year,
+                DataYear {
+                    year,
+                    entries,
+                    scraped_at: None,
+                },
+            )
+        })
+        .collect();
+    for (year, scraped_at) in downloads {
+        let data_year = years.entry(year).or_insert(DataYear {
+            year,
+            entries: 0,
+            scraped_at: None,
+        });
+        data_year.scraped_at = data_year.scraped_at.max(Some(scraped_at));
+    }
+    years.into_values().collect()
+}
+
+/// Years with downloaded ITSF and DTFB data, oldest first.
+pub fn stored_years(db: &DatabaseRef) -> DataYears {
+    let (itsf, dtfb) = db.aggregate_players(|players| {
+        let mut itsf: BTreeMap<i32, usize> = BTreeMap::new();
+        let mut dtfb: BTreeMap<i32, usize> = BTreeMap::new();
+        for player in players {
+            for ranking in &player.itsf_rankings {
+                *itsf.entry(ranking.year).or_default() += 1;
+            }
+            let dtfb_years = player
+                .dtfb_national_rankings
+                .iter()
+                .map(|ranking| ranking.year)
+                .chain(player.dtfb_championship_results.iter().map(|result| result.year))
+                .chain(player.dtfb_league_teams.iter().map(|team| team.year));
+            for year in dtfb_years {
+                *dtfb.entry(year).or_default() += 1;
+            }
+        }
+        (itsf, dtfb)
+    });
+    DataYears {
+        itsf: data_years(
+            itsf,
+            db.get_ranking_downloads()
+                .into_iter()
+                .map(|download| (download.year, download.scraped_at)),
+        ),
+        dtfb: data_years(
+            dtfb,
+            db.get_dtfb_downloads()
+                .into_iter()
+                .map(|download| (download.year, download.scraped_at)),
+        ),
+    }
+}
@@ -0,0 +1,68 @@
+//! Directory data of clubs, maintained by users. Clubs are identified by their name as it appears in the
+//! DTFB league tables, which links them to the players of their league teams.
+
+/// A weekly training session, e.g. Tuesday from 19:00.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TrainingNight {
+    pub weekday: chrono::Weekday,
+    /// Free text like `19:00-22:00`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Club {
+    #[serde(default)]
+    pub venue_address: Option<String>,
+    #[serde(default)]
+    pub training_nights: Vec<TrainingNight>,
+    #[serde(default)]
+    pub contact_email: Option<String>,
+    /// Unix timestamp of the last edit, set by the server.
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+impl Club {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.venue_address.as_ref().is_some_and(|address| address.len() > 300) {
+            return Err(String::from("venue address is too long"));
+        }
+        if self.training_nights.len() > 14 {
+            return Err(String::from("too many training nights"));
+        }
+        if self
+            .training_nights
+            .iter()
+            .any(|night| night.time.as_ref().is_some_and(|time| time.len() > 50))
+        {
+            return Err(String::from("training time is too long"));
+        }
+        if let Some(email) = &self.contact_email {
+            if !is_valid_email(email) {
+                return Err(format!("invalid contact email: '{}'", email));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Club names are used in URLs, e.g. `Kickertreff Hamburg`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 100 && name.trim() == name && !name.chars().any(char::is_control)
+}
+
+fn is_valid_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            email.len() <= 254
+                && !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains('@')
+                && !email.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
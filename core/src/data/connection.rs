@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Tuning of the database connections, configured via `DATABASE_BUSY_TIMEOUT`, `DATABASE_SLOW_WAIT`
+/// and `DATABASE_SLOW_QUERY`.
+#[derive(Debug, Clone)]
+pub struct ConnectionSettings {
+    /// How long SQLite retries a statement while another instance holds the write lock.
+    pub busy_timeout: Duration,
+    /// Waiting longer than this for the connection gets logged.
+    pub slow_wait: Duration,
+    /// Holding the connection longer than this gets logged along with the calling query.
+    pub slow_query: Duration,
+}
+
+fn millis_from_env(name: &str, default: u64) -> Duration {
+    match std::env::var(name) {
+        Ok(millis) => Duration::from_millis(millis.parse::<u64>().unwrap_or_else(|_| panic!("invalid {}", name))),
+        Err(_) => Duration::from_millis(default),
+    }
+}
+
+impl ConnectionSettings {
+    pub fn from_env() -> Self {
+        ConnectionSettings {
+            busy_timeout: millis_from_env("DATABASE_BUSY_TIMEOUT", 5000),
+            slow_wait: millis_from_env("DATABASE_SLOW_WAIT", 100),
+            slow_query: millis_from_env("DATABASE_SLOW_QUERY", 250),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConnectionStatsSnapshot {
+    pub checkouts: u64,
+    pub slow_checkouts: u64,
+    pub total_wait_us: u64,
+    pub max_wait_us: u64,
+}
+
+/// Counts how long requests wait for the shared database connection.
+#[derive(Default)]
+pub struct ConnectionStats {
+    checkouts: AtomicU64,
+    slow_checkouts: AtomicU64,
+    total_wait_us: AtomicU64,
+    max_wait_us: AtomicU64,
+}
+
+impl ConnectionStats {
+    pub fn record(&self, wait: Duration, slow: bool) {
+        let wait_us = wait.as_micros() as u64;
+        self.checkouts.fetch_add(1, Ordering::Relaxed);
+        if slow {
+            self.slow_checkouts.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_wait_us.fetch_add(wait_us, Ordering::Relaxed);
+        self.max_wait_us.fetch_max(wait_us, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ConnectionStatsSnapshot {
+        ConnectionStatsSnapshot {
+            checkouts: self.checkouts.load(Ordering::Relaxed),
+            slow_checkouts: self.slow_checkouts.load(Ordering::Relaxed),
+            total_wait_us: self.total_wait_us.load(Ordering::Relaxed),
+            max_wait_us: self.max_wait_us.load(Ordering::Relaxed),
+        }
+    }
+}
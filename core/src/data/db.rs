@@ -0,0 +1,1379 @@
+use diesel::sqlite::SqliteConnection;
+use diesel::{prelude::*, Insertable, Queryable};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
+
+use super::samples::RequestSample;
+use super::snapshots::{self, Placement};
+use crate::schema::*;
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../migrations");
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = players)]
+struct DbPlayer {
+    itsf_id: i32,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = player_lists)]
+struct DbPlayerList {
+    list_id: i32,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = subscriptions)]
+struct DbSubscription {
+    user_id: String,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = events)]
+struct DbEvent {
+    event_id: i32,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = leagues)]
+struct DbLeague {
+    season: i32,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = ranking_downloads)]
+struct DbRankingDownload {
+    ranking: String,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = dtfb_downloads)]
+struct DbDtfbDownload {
+    season: i32,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = clubs)]
+struct DbClub {
+    name: String,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = parse_quality)]
+struct DbParseQuality {
+    quality_key: String,
+    day: i64,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = download_presets)]
+struct DbDownloadPreset {
+    name: String,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = feature_flags)]
+struct DbFeatureFlag {
+    feature: String,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = replication_state)]
+struct DbReplicationState {
+    primary_url: String,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = staged_actions)]
+struct DbStagedAction {
+    action_id: i64,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = workspace_notes)]
+struct DbWorkspaceNotes {
+    notes_key: String,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = geocodes)]
+struct DbGeocode {
+    place: String,
+    json_data: Vec<u8>,
+}
+
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = job_locks)]
+struct DbJobLock {
+    name: String,
+    token: String,
+    expires_at: i64,
+    log: Vec<u8>,
+}
+
+#[derive(Queryable)]
+struct DbRequestSample {
+    timestamp: i64,
+    method: String,
+    path: String,
+    endpoint: Option<String>,
+    status: i32,
+    latency_ms: i64,
+    client: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = request_samples)]
+struct DbNewRequestSample<'a> {
+    timestamp: i64,
+    method: &'a str,
+    path: &'a str,
+    endpoint: Option<&'a str>,
+    status: i32,
+    latency_ms: i64,
+    client: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = player_overrides)]
+struct DbNewPlayerOverride {
+    itsf_id: i32,
+    json_data: Vec<u8>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = job_runs)]
+struct DbNewJobRun {
+    started_at: i64,
+    json_data: Vec<u8>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = ranking_placements)]
+struct DbRankingPlacement<'a> {
+    ranking_key: &'a str,
+    itsf_id: i32,
+    place: i32,
+    scraped_at: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = ranking_snapshots)]
+struct DbNewRankingSnapshot<'a> {
+    ranking_key: &'a str,
+    scraped_at: i64,
+    entries: i32,
+    data: Vec<u8>,
+}
+
+#[derive(QueryableByName)]
+struct DbTableName {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    name: String,
+}
+
+#[derive(QueryableByName)]
+struct DbTableColumn {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    table_name: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    column_name: String,
+}
+
+#[derive(QueryableByName)]
+struct DbRowCount {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    rows: i64,
+}
+
+pub struct DbConnection {
+    conn: SqliteConnection,
+}
+
+/// `(itsf_id, place, scraped_at)` of the latest snapshot of the ranking, best place first.
+fn read_ranking_placements(
+    conn: &mut SqliteConnection,
+    key: &str,
+) -> Result<Vec<(i32, i32, i64)>, diesel::result::Error> {
+    use crate::schema::ranking_placements::dsl;
+
+    dsl::ranking_placements
+        .filter(dsl::ranking_key.eq(key))
+        .select((dsl::itsf_id, dsl::place, dsl::scraped_at))
+        .order((dsl::place, dsl::itsf_id))
+        .load(conn)
+}
+
+fn expect_result<T>(result: Result<T, diesel::result::Error>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => panic!("SQL Error: {:?}", err),
+    }
+}
+
+impl DbConnection {
+    pub fn open(path: &str, busy_timeout: Duration) -> Self {
+        let conn = SqliteConnection::establish(path).expect("Failed to open DB");
+        Self::configure(conn, busy_timeout)
+    }
+
+    /// Opens a replica of the database, refusing any writes.
+    pub fn open_read_only(path: &str, busy_timeout: Duration) -> Self {
+        let conn = SqliteConnection::establish(&format!("file:{}?mode=ro", path)).expect("Failed to open replica DB");
+        Self::configure(conn, busy_timeout)
+    }
+
+    fn configure(mut conn: SqliteConnection, busy_timeout: Duration) -> Self {
+        let result =
+            diesel::sql_query(format!("PRAGMA busy_timeout = {}", busy_timeout.as_millis())).execute(&mut conn);
+        expect_result(result);
+        Self { conn }
+    }
+
+    /// Names of the migrations that were not applied to this database yet.
+    pub fn get_pending_migrations(&mut self) -> Result<Vec<String>, String> {
+        let migrations = self
+            .conn
+            .pending_migrations(MIGRATIONS)
+            .map_err(|err| err.to_string())?;
+        Ok(migrations
+            .iter()
+            .map(|migration| migration.name().to_string())
+            .collect())
+    }
+
+    /// `(table, column)` of all tables except SQLite's and diesel's own.
+    pub fn get_table_columns(&mut self) -> Vec<(String, String)> {
+        let columns = diesel::sql_query(
+            "SELECT m.name AS table_name, p.name AS column_name \
+             FROM sqlite_master m JOIN pragma_table_info(m.name) p \
+             WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%' AND m.name != '__diesel_schema_migrations' \
+             ORDER BY m.name, p.cid",
+        )
+        .load::<DbTableColumn>(&mut self.conn);
+
+        expect_result(columns)
+            .into_iter()
+            .map(|column| (column.table_name, column.column_name))
+            .collect()
+    }
+
+    /// Applies all pending migrations, returning their versions.
+    pub fn run_pending_migrations(&mut self) -> Result<Vec<String>, String> {
+        let migrations = self
+            .conn
+            .run_pending_migrations(MIGRATIONS)
+            .map_err(|err| err.to_string())?;
+        Ok(migrations.iter().map(|migration| migration.to_string()).collect())
+    }
+
+    pub fn get_player_ids(&mut self) -> Vec<i32> {
+        use crate::schema::players::dsl;
+
+        let ids = dsl::players.select(dsl::itsf_id).load(&mut self.conn);
+
+        expect_result(ids)
+    }
+
+    pub fn write_player_json<T: Serialize>(&mut self, itsf_id: i32, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let player = DbPlayer { itsf_id, json_data };
+
+        use crate::schema::players::dsl;
+
+        let result = diesel::insert_into(dsl::players)
+            .values(&player)
+            .on_conflict(dsl::itsf_id)
+            .do_update()
+            .set(&player)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for player insert: {}", result);
+        }
+    }
+
+    pub fn read_player_json<T: DeserializeOwned>(&mut self, itsf_id: i32) -> Result<T, String> {
+        use crate::schema::players::dsl;
+
+        let player = dsl::players
+            .filter(dsl::itsf_id.eq(itsf_id))
+            .first::<DbPlayer>(&mut self.conn)
+            .optional();
+
+        match expect_result(player) {
+            Some(player) => serde_json::from_slice(&player.json_data)
+                .map_err(|err| format!("JSON Error when loading player {}: {}", itsf_id, err)),
+            None => Err(format!("No player data found for player {}", itsf_id)),
+        }
+    }
+
+    pub fn get_list_ids(&mut self) -> Vec<i32> {
+        use crate::schema::player_lists::dsl;
+
+        let ids = dsl::player_lists.select(dsl::list_id).load(&mut self.conn);
+
+        expect_result(ids)
+    }
+
+    pub fn write_list_json<T: Serialize>(&mut self, list_id: i32, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let list = DbPlayerList { list_id, json_data };
+
+        use crate::schema::player_lists::dsl;
+
+        let result = diesel::insert_into(dsl::player_lists)
+            .values(&list)
+            .on_conflict(dsl::list_id)
+            .do_update()
+            .set(&list)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for list insert: {}", result);
+        }
+    }
+
+    pub fn read_list_json<T: DeserializeOwned>(&mut self, list_id: i32) -> Result<T, String> {
+        use crate::schema::player_lists::dsl;
+
+        let list = dsl::player_lists
+            .filter(dsl::list_id.eq(list_id))
+            .first::<DbPlayerList>(&mut self.conn)
+            .optional();
+
+        match expect_result(list) {
+            Some(list) => serde_json::from_slice(&list.json_data)
+                .map_err(|err| format!("JSON Error when loading list {}: {}", list_id, err)),
+            None => Err(format!("No list data found for list {}", list_id)),
+        }
+    }
+
+    pub fn delete_list(&mut self, list_id: i32) {
+        use crate::schema::player_lists::dsl;
+
+        let result = diesel::delete(dsl::player_lists.filter(dsl::list_id.eq(list_id))).execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    pub fn get_subscription_user_ids(&mut self) -> Vec<String> {
+        use crate::schema::subscriptions::dsl;
+
+        let ids = dsl::subscriptions.select(dsl::user_id).load(&mut self.conn);
+
+        expect_result(ids)
+    }
+
+    pub fn write_subscription_json<T: Serialize>(&mut self, user_id: &str, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let subscription = DbSubscription {
+            user_id: String::from(user_id),
+            json_data,
+        };
+
+        use crate::schema::subscriptions::dsl;
+
+        let result = diesel::insert_into(dsl::subscriptions)
+            .values(&subscription)
+            .on_conflict(dsl::user_id)
+            .do_update()
+            .set(&subscription)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for subscription insert: {}", result);
+        }
+    }
+
+    pub fn read_subscription_json<T: DeserializeOwned>(&mut self, user_id: &str) -> Result<T, String> {
+        use crate::schema::subscriptions::dsl;
+
+        let subscription = dsl::subscriptions
+            .filter(dsl::user_id.eq(user_id))
+            .first::<DbSubscription>(&mut self.conn)
+            .optional();
+
+        match expect_result(subscription) {
+            Some(subscription) => serde_json::from_slice(&subscription.json_data)
+                .map_err(|err| format!("JSON Error when loading subscriptions of {}: {}", user_id, err)),
+            None => Err(format!("No subscription data found for user {}", user_id)),
+        }
+    }
+
+    pub fn get_event_ids(&mut self) -> Vec<i32> {
+        use crate::schema::events::dsl;
+
+        let ids = dsl::events.select(dsl::event_id).load(&mut self.conn);
+
+        expect_result(ids)
+    }
+
+    pub fn write_event_json<T: Serialize>(&mut self, event_id: i32, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let event = DbEvent { event_id, json_data };
+
+        use crate::schema::events::dsl;
+
+        let result = diesel::insert_into(dsl::events)
+            .values(&event)
+            .on_conflict(dsl::event_id)
+            .do_update()
+            .set(&event)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for event insert: {}", result);
+        }
+    }
+
+    pub fn delete_event(&mut self, event_id: i32) {
+        use crate::schema::events::dsl;
+
+        let result = diesel::delete(dsl::events.filter(dsl::event_id.eq(event_id))).execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    pub fn read_event_json<T: DeserializeOwned>(&mut self, event_id: i32) -> Result<T, String> {
+        use crate::schema::events::dsl;
+
+        let event = dsl::events
+            .filter(dsl::event_id.eq(event_id))
+            .first::<DbEvent>(&mut self.conn)
+            .optional();
+
+        match expect_result(event) {
+            Some(event) => serde_json::from_slice(&event.json_data)
+                .map_err(|err| format!("JSON Error when loading event {}: {}", event_id, err)),
+            None => Err(format!("No event data found for event {}", event_id)),
+        }
+    }
+
+    pub fn get_league_seasons(&mut self) -> Vec<i32> {
+        use crate::schema::leagues::dsl;
+
+        let seasons = dsl::leagues.select(dsl::season).load(&mut self.conn);
+
+        expect_result(seasons)
+    }
+
+    pub fn write_league_json<T: Serialize>(&mut self, season: i32, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let league = DbLeague { season, json_data };
+
+        use crate::schema::leagues::dsl;
+
+        let result = diesel::insert_into(dsl::leagues)
+            .values(&league)
+            .on_conflict(dsl::season)
+            .do_update()
+            .set(&league)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for league insert: {}", result);
+        }
+    }
+
+    pub fn read_league_json<T: DeserializeOwned>(&mut self, season: i32) -> Result<T, String> {
+        use crate::schema::leagues::dsl;
+
+        let league = dsl::leagues
+            .filter(dsl::season.eq(season))
+            .first::<DbLeague>(&mut self.conn)
+            .optional();
+
+        match expect_result(league) {
+            Some(league) => serde_json::from_slice(&league.json_data)
+                .map_err(|err| format!("JSON Error when loading leagues of season {}: {}", season, err)),
+            None => Err(format!("No league data found for season {}", season)),
+        }
+    }
+
+    pub fn get_dtfb_download_seasons(&mut self) -> Vec<i32> {
+        use crate::schema::dtfb_downloads::dsl;
+
+        let seasons = dsl::dtfb_downloads.select(dsl::season).load(&mut self.conn);
+
+        expect_result(seasons)
+    }
+
+    pub fn write_dtfb_download_json<T: Serialize>(&mut self, season: i32, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let download = DbDtfbDownload { season, json_data };
+
+        use crate::schema::dtfb_downloads::dsl;
+
+        let result = diesel::insert_into(dsl::dtfb_downloads)
+            .values(&download)
+            .on_conflict(dsl::season)
+            .do_update()
+            .set(&download)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for DTFB download insert: {}", result);
+        }
+    }
+
+    pub fn read_dtfb_download_json<T: DeserializeOwned>(&mut self, season: i32) -> Result<T, String> {
+        use crate::schema::dtfb_downloads::dsl;
+
+        let download = dsl::dtfb_downloads
+            .filter(dsl::season.eq(season))
+            .first::<DbDtfbDownload>(&mut self.conn)
+            .optional();
+
+        match expect_result(download) {
+            Some(download) => serde_json::from_slice(&download.json_data)
+                .map_err(|err| format!("JSON Error when loading DTFB download of season {}: {}", season, err)),
+            None => Err(format!("No DTFB download found for season {}", season)),
+        }
+    }
+
+    pub fn get_geocode_places(&mut self) -> Vec<String> {
+        use crate::schema::geocodes::dsl;
+
+        let places = dsl::geocodes.select(dsl::place).load(&mut self.conn);
+
+        expect_result(places)
+    }
+
+    pub fn write_geocode_json<T: Serialize>(&mut self, place: &str, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let geocode = DbGeocode {
+            place: String::from(place),
+            json_data,
+        };
+
+        use crate::schema::geocodes::dsl;
+
+        let result = diesel::insert_into(dsl::geocodes)
+            .values(&geocode)
+            .on_conflict(dsl::place)
+            .do_update()
+            .set(&geocode)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for geocode insert: {}", result);
+        }
+    }
+
+    pub fn read_geocode_json<T: DeserializeOwned>(&mut self, place: &str) -> Result<T, String> {
+        use crate::schema::geocodes::dsl;
+
+        let geocode = dsl::geocodes
+            .filter(dsl::place.eq(place))
+            .first::<DbGeocode>(&mut self.conn)
+            .optional();
+
+        match expect_result(geocode) {
+            Some(geocode) => serde_json::from_slice(&geocode.json_data)
+                .map_err(|err| format!("JSON Error when loading geocode of {}: {}", place, err)),
+            None => Err(format!("No geocode found for {}", place)),
+        }
+    }
+
+    pub fn get_ranking_download_keys(&mut self) -> Vec<String> {
+        use crate::schema::ranking_downloads::dsl;
+
+        let keys = dsl::ranking_downloads.select(dsl::ranking).load(&mut self.conn);
+
+        expect_result(keys)
+    }
+
+    pub fn write_ranking_download_json<T: Serialize>(&mut self, ranking: &str, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let download = DbRankingDownload {
+            ranking: String::from(ranking),
+            json_data,
+        };
+
+        use crate::schema::ranking_downloads::dsl;
+
+        let result = diesel::insert_into(dsl::ranking_downloads)
+            .values(&download)
+            .on_conflict(dsl::ranking)
+            .do_update()
+            .set(&download)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for ranking download insert: {}", result);
+        }
+    }
+
+    pub fn read_ranking_download_json<T: DeserializeOwned>(&mut self, ranking: &str) -> Result<T, String> {
+        use crate::schema::ranking_downloads::dsl;
+
+        let download = dsl::ranking_downloads
+            .filter(dsl::ranking.eq(ranking))
+            .first::<DbRankingDownload>(&mut self.conn)
+            .optional();
+
+        match expect_result(download) {
+            Some(download) => serde_json::from_slice(&download.json_data)
+                .map_err(|err| format!("JSON Error when loading ranking download {}: {}", ranking, err)),
+            None => Err(format!("No data found for ranking download {}", ranking)),
+        }
+    }
+
+    pub fn get_workspace_notes_keys(&mut self) -> Vec<String> {
+        use crate::schema::workspace_notes::dsl;
+
+        let keys = dsl::workspace_notes.select(dsl::notes_key).load(&mut self.conn);
+
+        expect_result(keys)
+    }
+
+    pub fn write_workspace_notes_json<T: Serialize>(&mut self, notes_key: &str, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let notes = DbWorkspaceNotes {
+            notes_key: String::from(notes_key),
+            json_data,
+        };
+
+        use crate::schema::workspace_notes::dsl;
+
+        let result = diesel::insert_into(dsl::workspace_notes)
+            .values(&notes)
+            .on_conflict(dsl::notes_key)
+            .do_update()
+            .set(&notes)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for workspace notes insert: {}", result);
+        }
+    }
+
+    pub fn insert_player_override_json<T: Serialize>(&mut self, itsf_id: i32, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+
+        use crate::schema::player_overrides::dsl;
+
+        let result = diesel::insert_into(dsl::player_overrides)
+            .values(&DbNewPlayerOverride { itsf_id, json_data })
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for player override insert: {}", result);
+        }
+    }
+
+    /// All overrides in the order they were recorded.
+    pub fn read_player_overrides_json<T: DeserializeOwned>(&mut self) -> Vec<T> {
+        use crate::schema::player_overrides::dsl;
+
+        let overrides = dsl::player_overrides
+            .select(dsl::json_data)
+            .order(dsl::override_id.asc())
+            .load::<Vec<u8>>(&mut self.conn);
+
+        expect_result(overrides)
+            .iter()
+            .filter_map(|json_data| match serde_json::from_slice(json_data) {
+                Ok(entry) => Some(entry),
+                Err(err) => {
+                    log::error!("JSON Error when loading player override: {}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn delete_player_overrides(&mut self, itsf_id: i32) {
+        use crate::schema::player_overrides::dsl;
+
+        let result = diesel::delete(dsl::player_overrides.filter(dsl::itsf_id.eq(itsf_id))).execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    pub fn delete_workspace_notes(&mut self, notes_key: &str) {
+        use crate::schema::workspace_notes::dsl;
+
+        let result = diesel::delete(dsl::workspace_notes.filter(dsl::notes_key.eq(notes_key))).execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    pub fn read_workspace_notes_json<T: DeserializeOwned>(&mut self, notes_key: &str) -> Result<T, String> {
+        use crate::schema::workspace_notes::dsl;
+
+        let notes = dsl::workspace_notes
+            .filter(dsl::notes_key.eq(notes_key))
+            .first::<DbWorkspaceNotes>(&mut self.conn)
+            .optional();
+
+        match expect_result(notes) {
+            Some(notes) => serde_json::from_slice(&notes.json_data)
+                .map_err(|err| format!("JSON Error when loading workspace notes {}: {}", notes_key, err)),
+            None => Err(format!("No data found for workspace notes {}", notes_key)),
+        }
+    }
+
+    pub fn get_download_preset_names(&mut self) -> Vec<String> {
+        use crate::schema::download_presets::dsl;
+
+        let names = dsl::download_presets.select(dsl::name).load(&mut self.conn);
+
+        expect_result(names)
+    }
+
+    pub fn write_download_preset_json<T: Serialize>(&mut self, name: &str, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let preset = DbDownloadPreset {
+            name: String::from(name),
+            json_data,
+        };
+
+        use crate::schema::download_presets::dsl;
+
+        let result = diesel::insert_into(dsl::download_presets)
+            .values(&preset)
+            .on_conflict(dsl::name)
+            .do_update()
+            .set(&preset)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for download preset insert: {}", result);
+        }
+    }
+
+    pub fn read_download_preset_json<T: DeserializeOwned>(&mut self, name: &str) -> Result<T, String> {
+        use crate::schema::download_presets::dsl;
+
+        let preset = dsl::download_presets
+            .filter(dsl::name.eq(name))
+            .first::<DbDownloadPreset>(&mut self.conn)
+            .optional();
+
+        match expect_result(preset) {
+            Some(preset) => serde_json::from_slice(&preset.json_data)
+                .map_err(|err| format!("JSON Error when loading download preset {}: {}", name, err)),
+            None => Err(format!("No data found for download preset {}", name)),
+        }
+    }
+
+    pub fn delete_download_preset(&mut self, name: &str) {
+        use crate::schema::download_presets::dsl;
+
+        let result = diesel::delete(dsl::download_presets.filter(dsl::name.eq(name))).execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    pub fn get_club_names(&mut self) -> Vec<String> {
+        use crate::schema::clubs::dsl;
+
+        let names = dsl::clubs.select(dsl::name).load(&mut self.conn);
+
+        expect_result(names)
+    }
+
+    pub fn write_club_json<T: Serialize>(&mut self, name: &str, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let club = DbClub {
+            name: String::from(name),
+            json_data,
+        };
+
+        use crate::schema::clubs::dsl;
+
+        let result = diesel::insert_into(dsl::clubs)
+            .values(&club)
+            .on_conflict(dsl::name)
+            .do_update()
+            .set(&club)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for club insert: {}", result);
+        }
+    }
+
+    pub fn read_club_json<T: DeserializeOwned>(&mut self, name: &str) -> Result<T, String> {
+        use crate::schema::clubs::dsl;
+
+        let club = dsl::clubs
+            .filter(dsl::name.eq(name))
+            .first::<DbClub>(&mut self.conn)
+            .optional();
+
+        match expect_result(club) {
+            Some(club) => serde_json::from_slice(&club.json_data)
+                .map_err(|err| format!("JSON Error when loading club {}: {}", name, err)),
+            None => Err(format!("No data found for club {}", name)),
+        }
+    }
+
+    pub fn write_parse_quality_json<T: Serialize>(&mut self, quality_key: &str, day: i64, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let quality = DbParseQuality {
+            quality_key: String::from(quality_key),
+            day,
+            json_data,
+        };
+
+        use crate::schema::parse_quality::dsl;
+
+        let result = diesel::insert_into(dsl::parse_quality)
+            .values(&quality)
+            .on_conflict(dsl::quality_key)
+            .do_update()
+            .set(&quality)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for parse quality insert: {}", result);
+        }
+    }
+
+    pub fn read_parse_quality_json<T: DeserializeOwned>(&mut self, quality_key: &str) -> Option<T> {
+        use crate::schema::parse_quality::dsl;
+
+        let quality = dsl::parse_quality
+            .filter(dsl::quality_key.eq(quality_key))
+            .select(dsl::json_data)
+            .first::<Vec<u8>>(&mut self.conn)
+            .optional();
+
+        expect_result(quality).and_then(|json_data| match serde_json::from_slice(&json_data) {
+            Ok(quality) => Some(quality),
+            Err(err) => {
+                log::error!("JSON Error when loading parse quality {}: {}", quality_key, err);
+                None
+            }
+        })
+    }
+
+    /// Parse quality of `day` and later, oldest first.
+    pub fn read_parse_quality_since_json<T: DeserializeOwned>(&mut self, day: i64) -> Vec<T> {
+        use crate::schema::parse_quality::dsl;
+
+        let rows = dsl::parse_quality
+            .filter(dsl::day.ge(day))
+            .select(dsl::json_data)
+            .order((dsl::day.asc(), dsl::quality_key.asc()))
+            .load::<Vec<u8>>(&mut self.conn);
+
+        expect_result(rows)
+            .iter()
+            .filter_map(|json_data| match serde_json::from_slice(json_data) {
+                Ok(quality) => Some(quality),
+                Err(err) => {
+                    log::error!("JSON Error when loading parse quality: {}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn delete_club(&mut self, name: &str) {
+        use crate::schema::clubs::dsl;
+
+        let result = diesel::delete(dsl::clubs.filter(dsl::name.eq(name))).execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    pub fn get_feature_flag_names(&mut self) -> Vec<String> {
+        use crate::schema::feature_flags::dsl;
+
+        let names = dsl::feature_flags.select(dsl::feature).load(&mut self.conn);
+
+        expect_result(names)
+    }
+
+    pub fn write_feature_flag_json<T: Serialize>(&mut self, feature: &str, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let flag = DbFeatureFlag {
+            feature: String::from(feature),
+            json_data,
+        };
+
+        use crate::schema::feature_flags::dsl;
+
+        let result = diesel::insert_into(dsl::feature_flags)
+            .values(&flag)
+            .on_conflict(dsl::feature)
+            .do_update()
+            .set(&flag)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for feature flag insert: {}", result);
+        }
+    }
+
+    pub fn read_feature_flag_json<T: DeserializeOwned>(&mut self, feature: &str) -> Result<T, String> {
+        use crate::schema::feature_flags::dsl;
+
+        let flag = dsl::feature_flags
+            .filter(dsl::feature.eq(feature))
+            .first::<DbFeatureFlag>(&mut self.conn)
+            .optional();
+
+        match expect_result(flag) {
+            Some(flag) => serde_json::from_slice(&flag.json_data)
+                .map_err(|err| format!("JSON Error when loading feature flag {}: {}", feature, err)),
+            None => Err(format!("No data found for feature flag {}", feature)),
+        }
+    }
+
+    pub fn write_replication_state_json<T: Serialize>(&mut self, primary_url: &str, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let state = DbReplicationState {
+            primary_url: String::from(primary_url),
+            json_data,
+        };
+
+        use crate::schema::replication_state::dsl;
+
+        let result = diesel::insert_into(dsl::replication_state)
+            .values(&state)
+            .on_conflict(dsl::primary_url)
+            .do_update()
+            .set(&state)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for replication state insert: {}", result);
+        }
+    }
+
+    pub fn read_replication_state_json<T: DeserializeOwned>(&mut self, primary_url: &str) -> Option<T> {
+        use crate::schema::replication_state::dsl;
+
+        let state = dsl::replication_state
+            .filter(dsl::primary_url.eq(primary_url))
+            .first::<DbReplicationState>(&mut self.conn)
+            .optional();
+
+        match serde_json::from_slice(&expect_result(state)?.json_data) {
+            Ok(state) => Some(state),
+            Err(err) => {
+                log::error!("JSON Error when loading replication state of {}: {}", primary_url, err);
+                None
+            }
+        }
+    }
+
+    pub fn write_staged_action_json<T: Serialize>(&mut self, action_id: i64, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+        let action = DbStagedAction { action_id, json_data };
+
+        use crate::schema::staged_actions::dsl;
+
+        let result = diesel::insert_into(dsl::staged_actions)
+            .values(&action)
+            .on_conflict(dsl::action_id)
+            .do_update()
+            .set(&action)
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for staged action insert: {}", result);
+        }
+    }
+
+    /// All staged actions in the order they were staged.
+    pub fn read_staged_actions_json<T: DeserializeOwned>(&mut self) -> Vec<T> {
+        use crate::schema::staged_actions::dsl;
+
+        let actions = dsl::staged_actions
+            .select(dsl::json_data)
+            .order(dsl::action_id.asc())
+            .load::<Vec<u8>>(&mut self.conn);
+
+        expect_result(actions)
+            .iter()
+            .filter_map(|json_data| match serde_json::from_slice(json_data) {
+                Ok(action) => Some(action),
+                Err(err) => {
+                    log::error!("JSON Error when loading staged action: {}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn delete_staged_action(&mut self, action_id: i64) {
+        use crate::schema::staged_actions::dsl;
+
+        let result = diesel::delete(dsl::staged_actions.filter(dsl::action_id.eq(action_id))).execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    /// Takes the named lock unless another holder's lock is still valid at `now`.
+    pub fn try_acquire_job_lock(&mut self, name: &str, token: &str, now: i64, expires_at: i64) -> bool {
+        use crate::schema::job_locks::dsl;
+
+        let result = self.conn.immediate_transaction(|conn| {
+            let holder = dsl::job_locks
+                .filter(dsl::name.eq(name))
+                .filter(dsl::expires_at.ge(now))
+                .select(dsl::token)
+                .first::<String>(conn)
+                .optional()?;
+            if holder.is_some() {
+                return Ok::<bool, diesel::result::Error>(false);
+            }
+
+            let lock = DbJobLock {
+                name: String::from(name),
+                token: String::from(token),
+                expires_at,
+                log: b"[]".to_vec(),
+            };
+            diesel::insert_into(dsl::job_locks)
+                .values(&lock)
+                .on_conflict(dsl::name)
+                .do_update()
+                .set(&lock)
+                .execute(conn)?;
+            Ok(true)
+        });
+
+        expect_result(result)
+    }
+
+    pub fn refresh_job_lock<T: Serialize>(&mut self, name: &str, token: &str, expires_at: i64, log: &T) {
+        let log = serde_json::to_vec(log).expect("JSON serialization failed");
+
+        use crate::schema::job_locks::dsl;
+
+        let result = diesel::update(dsl::job_locks.filter(dsl::name.eq(name)).filter(dsl::token.eq(token)))
+            .set((dsl::expires_at.eq(expires_at), dsl::log.eq(log)))
+            .execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    pub fn release_job_lock(&mut self, name: &str, token: &str) {
+        use crate::schema::job_locks::dsl;
+
+        let result = diesel::delete(dsl::job_locks.filter(dsl::name.eq(name)).filter(dsl::token.eq(token)))
+            .execute(&mut self.conn);
+
+        expect_result(result);
+    }
+
+    /// The log of the named lock's holder, if the lock is valid at `now`.
+    pub fn read_job_lock_log<T: DeserializeOwned>(&mut self, name: &str, now: i64) -> Option<T> {
+        use crate::schema::job_locks::dsl;
+
+        let log = dsl::job_locks
+            .filter(dsl::name.eq(name))
+            .filter(dsl::expires_at.ge(now))
+            .select(dsl::log)
+            .first::<Vec<u8>>(&mut self.conn)
+            .optional();
+
+        expect_result(log).and_then(|log| serde_json::from_slice(&log).ok())
+    }
+
+    /// Number of locks that expired before `timestamp`, left behind by jobs that didn't release them.
+    pub fn count_job_locks_expired_before(&mut self, timestamp: i64) -> usize {
+        use crate::schema::job_locks::dsl;
+
+        let count = dsl::job_locks
+            .filter(dsl::expires_at.lt(timestamp))
+            .count()
+            .get_result::<i64>(&mut self.conn);
+
+        expect_result(count) as usize
+    }
+
+    pub fn delete_job_locks_expired_before(&mut self, timestamp: i64) -> usize {
+        use crate::schema::job_locks::dsl;
+
+        let result = diesel::delete(dsl::job_locks.filter(dsl::expires_at.lt(timestamp))).execute(&mut self.conn);
+
+        expect_result(result)
+    }
+
+    /// Placements and download time of the latest snapshot of the ranking, best place first.
+    pub fn read_ranking_placements(&mut self, key: &str) -> Option<(i64, Vec<Placement>)> {
+        let rows = read_ranking_placements(&mut self.conn, key);
+
+        let rows = expect_result(rows);
+        let scraped_at = rows.first()?.2;
+        let placements = rows
+            .into_iter()
+            .map(|(itsf_id, place, _)| Placement { place, itsf_id })
+            .collect();
+        Some((scraped_at, placements))
+    }
+
+    /// Makes `placements` the latest snapshot of the ranking, archiving the previous one.
+    pub fn replace_ranking_placements(&mut self, key: &str, scraped_at: i64, placements: &[Placement]) {
+        use crate::schema::ranking_placements::dsl;
+
+        let result = self.conn.transaction(|conn| {
+            let previous = read_ranking_placements(conn, key)?;
+            if let Some((_, _, previous_scraped_at)) = previous.first() {
+                let previous: Vec<Placement> = previous
+                    .iter()
+                    .map(|(itsf_id, place, _)| Placement {
+                        place: *place,
+                        itsf_id: *itsf_id,
+                    })
+                    .collect();
+                diesel::insert_into(ranking_snapshots::table)
+                    .values(&DbNewRankingSnapshot {
+                        ranking_key: key,
+                        scraped_at: *previous_scraped_at,
+                        entries: previous.len() as i32,
+                        data: snapshots::compress(&previous),
+                    })
+                    .execute(conn)?;
+                diesel::delete(dsl::ranking_placements.filter(dsl::ranking_key.eq(key))).execute(conn)?;
+            }
+            for placement in placements {
+                diesel::insert_into(dsl::ranking_placements)
+                    .values(&DbRankingPlacement {
+                        ranking_key: key,
+                        itsf_id: placement.itsf_id,
+                        place: placement.place,
+                        scraped_at,
+                    })
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+            Ok::<(), diesel::result::Error>(())
+        });
+
+        expect_result(result);
+    }
+
+    /// `(scraped_at, entries)` of the archived snapshots of the ranking, newest first.
+    pub fn read_ranking_snapshot_infos(&mut self, key: &str) -> Vec<(i64, usize)> {
+        use crate::schema::ranking_snapshots::dsl;
+
+        let snapshots = dsl::ranking_snapshots
+            .filter(dsl::ranking_key.eq(key))
+            .select((dsl::scraped_at, dsl::entries))
+            .order((dsl::scraped_at.desc(), dsl::snapshot_id.desc()))
+            .load::<(i64, i32)>(&mut self.conn);
+
+        expect_result(snapshots)
+            .into_iter()
+            .map(|(scraped_at, entries)| (scraped_at, entries as usize))
+            .collect()
+    }
+
+    pub fn read_archived_ranking_snapshot(&mut self, key: &str, scraped_at: i64) -> Result<Vec<Placement>, String> {
+        use crate::schema::ranking_snapshots::dsl;
+
+        let data = dsl::ranking_snapshots
+            .filter(dsl::ranking_key.eq(key))
+            .filter(dsl::scraped_at.eq(scraped_at))
+            .select(dsl::data)
+            .first::<Vec<u8>>(&mut self.conn)
+            .optional();
+
+        match expect_result(data) {
+            Some(data) => snapshots::decompress(&data),
+            None => Err(format!("No snapshot of {} from {}", key, scraped_at)),
+        }
+    }
+
+    pub fn insert_job_run<T: Serialize>(&mut self, started_at: i64, data: &T) {
+        let json_data = serde_json::to_vec(&data).expect("JSON serialization failed");
+
+        use crate::schema::job_runs::dsl;
+
+        let result = diesel::insert_into(dsl::job_runs)
+            .values(&DbNewJobRun { started_at, json_data })
+            .execute(&mut self.conn);
+
+        let result = expect_result(result);
+        if result != 1 {
+            panic!("invalid query result for job run insert: {}", result);
+        }
+    }
+
+    /// The latest runs, newest first.
+    pub fn read_job_runs_json<T: DeserializeOwned>(&mut self, limit: usize) -> Vec<T> {
+        use crate::schema::job_runs::dsl;
+
+        let runs = dsl::job_runs
+            .select(dsl::json_data)
+            .order((dsl::started_at.desc(), dsl::run_id.desc()))
+            .limit(limit as i64)
+            .load::<Vec<u8>>(&mut self.conn);
+
+        expect_result(runs)
+            .iter()
+            .filter_map(|json_data| match serde_json::from_slice(json_data) {
+                Ok(run) => Some(run),
+                Err(err) => {
+                    log::error!("JSON Error when loading job run: {}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn count_job_runs_before(&mut self, timestamp: i64) -> usize {
+        use crate::schema::job_runs::dsl;
+
+        let count = dsl::job_runs
+            .filter(dsl::started_at.lt(timestamp))
+            .count()
+            .get_result::<i64>(&mut self.conn);
+
+        expect_result(count) as usize
+    }
+
+    pub fn delete_job_runs_before(&mut self, timestamp: i64) -> usize {
+        use crate::schema::job_runs::dsl;
+
+        let result = diesel::delete(dsl::job_runs.filter(dsl::started_at.lt(timestamp))).execute(&mut self.conn);
+
+        expect_result(result)
+    }
+
+    /// Row counts of all tables except SQLite's and diesel's own.
+    pub fn count_table_rows(&mut self) -> Vec<(String, usize)> {
+        let tables = diesel::sql_query(
+            "SELECT name FROM sqlite_master WHERE type = 'table' \
+             AND name NOT LIKE 'sqlite_%' AND name != '__diesel_schema_migrations'",
+        )
+        .load::<DbTableName>(&mut self.conn);
+
+        expect_result(tables)
+            .into_iter()
+            .map(|table| {
+                // the name comes from sqlite_master, quoting only guards against odd characters
+                let rows = diesel::sql_query(format!("SELECT COUNT(*) AS rows FROM \"{}\"", table.name))
+                    .get_result::<DbRowCount>(&mut self.conn);
+                (table.name, expect_result(rows).rows as usize)
+            })
+            .collect()
+    }
+
+    /// Updates the query planner statistics of the table.
+    pub fn analyze(&mut self, table: &str) {
+        let result = diesel::sql_query(format!("ANALYZE \"{}\"", table)).execute(&mut self.conn);
+        expect_result(result);
+    }
+
+    /// Rebuilds the database file, returning the space of deleted rows to the file system.
+    pub fn vacuum(&mut self) {
+        let result = diesel::sql_query("VACUUM").execute(&mut self.conn);
+        expect_result(result);
+    }
+
+    pub fn insert_request_samples(&mut self, samples: &[RequestSample]) {
+        use crate::schema::request_samples::dsl;
+
+        let result = self.conn.transaction(|conn| {
+            for sample in samples {
+                let sample = DbNewRequestSample {
+                    timestamp: sample.timestamp,
+                    method: &sample.method,
+                    path: &sample.path,
+                    endpoint: sample.endpoint.as_deref(),
+                    status: sample.status as i32,
+                    latency_ms: sample.latency_ms,
+                    client: &sample.client,
+                };
+                diesel::insert_into(dsl::request_samples)
+                    .values(&sample)
+                    .execute(conn)?;
+            }
+            Ok::<(), diesel::result::Error>(())
+        });
+
+        expect_result(result);
+    }
+
+    /// Deletes the samples taken before `timestamp`, returning how many were deleted.
+    pub fn count_request_samples_before(&mut self, timestamp: i64) -> usize {
+        use crate::schema::request_samples::dsl;
+
+        let count = dsl::request_samples
+            .filter(dsl::timestamp.lt(timestamp))
+            .count()
+            .get_result::<i64>(&mut self.conn);
+
+        expect_result(count) as usize
+    }
+
+    pub fn delete_request_samples_before(&mut self, timestamp: i64) -> usize {
+        use crate::schema::request_samples::dsl;
+
+        let result = diesel::delete(dsl::request_samples.filter(dsl::timestamp.lt(timestamp))).execute(&mut self.conn);
+
+        expect_result(result)
+    }
+
+    pub fn read_request_samples_since(&mut self, timestamp: i64) -> Vec<RequestSample> {
+        use crate::schema::request_samples::dsl;
+
+        let samples = dsl::request_samples
+            .filter(dsl::timestamp.ge(timestamp))
+            .order(dsl::timestamp)
+            .select((
+                dsl::timestamp,
+                dsl::method,
+                dsl::path,
+                dsl::endpoint,
+                dsl::status,
+                dsl::latency_ms,
+                dsl::client,
+            ))
+            .load::<DbRequestSample>(&mut self.conn);
+
+        expect_result(samples)
+            .into_iter()
+            .map(|sample| RequestSample {
+                timestamp: sample.timestamp,
+                method: sample.method,
+                path: sample.path,
+                endpoint: sample.endpoint,
+                status: sample.status as u16,
+                latency_ms: sample.latency_ms,
+                client: sample.client,
+            })
+            .collect()
+    }
+}
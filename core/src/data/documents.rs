@@ -0,0 +1,177 @@
+//! Versioned documents the players are stored as. `Player` is the in-memory model and has no serialization of
+//! its own: it's written as the latest document version, and documents of older versions are upgraded when
+//! they're loaded, so the model can change without breaking stored data or API responses.
+
+use std::collections::BTreeMap;
+
+use serde::de::Error as _;
+
+use super::{dtfb, itsf, overrides, CountryChange, FormerName, Player, PlayerComment, RefreshError};
+
+/// Version written by `PlayerDocument::new`.
+pub const PLAYER_DOCUMENT_VERSION: u32 = 1;
+
+/// A stored player of any known version.
+#[derive(Debug, Clone)]
+pub enum PlayerDocument {
+    V1(PlayerDocumentV1),
+}
+
+impl PlayerDocument {
+    /// The player as document of the latest version.
+    pub fn new(player: &Player) -> Self {
+        Self::V1(PlayerDocumentV1::from(player))
+    }
+
+    pub fn into_player(self) -> Player {
+        match self {
+            Self::V1(document) => document.into(),
+        }
+    }
+}
+
+impl serde::Serialize for PlayerDocument {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::V1(document) => document.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for PlayerDocument {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let document = serde_json::Value::deserialize(deserializer)?;
+        // documents written before they were versioned have the layout of version 1
+        let version = match document.get("document_version") {
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| D::Error::custom("invalid document_version"))?,
+            None => 1,
+        };
+        match version {
+            1 => serde_json::from_value(document).map(Self::V1).map_err(D::Error::custom),
+            version => Err(D::Error::custom(format!(
+                "unknown player document version {}, the latest known is {}",
+                version, PLAYER_DOCUMENT_VERSION
+            ))),
+        }
+    }
+}
+
+fn version_1() -> u32 {
+    1
+}
+
+/// Version 1 of the stored player.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlayerDocumentV1 {
+    #[serde(default = "version_1")]
+    pub document_version: u32,
+
+    pub itsf_id: i32,
+
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_year: i32,
+    pub country_code: Option<String>,
+    pub category: itsf::PlayerCategory,
+
+    pub itsf_rankings: Vec<itsf::Ranking>,
+
+    pub dtfb_id: Option<i32>,
+    pub dtfb_national_rankings: Vec<dtfb::NationalRanking>,
+    pub dtfb_championship_results: Vec<dtfb::NationalChampionshipResult>,
+    pub dtfb_league_teams: Vec<dtfb::NationalTeam>,
+
+    #[serde(default)]
+    pub comments: Vec<PlayerComment>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub former_names: Vec<FormerName>,
+
+    #[serde(default)]
+    pub country_changes: Vec<CountryChange>,
+
+    #[serde(default)]
+    pub national_team_appearances: Vec<itsf::NationalTeamAppearance>,
+
+    #[serde(default)]
+    pub hidden: bool,
+
+    #[serde(default)]
+    pub scraped_at: Option<i64>,
+
+    #[serde(default)]
+    pub refresh_errors: Vec<RefreshError>,
+
+    #[serde(default)]
+    pub anonymized: bool,
+
+    #[serde(default)]
+    pub revision: u64,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overridden: BTreeMap<overrides::OverrideField, serde_json::Value>,
+}
+
+impl From<&Player> for PlayerDocumentV1 {
+    fn from(player: &Player) -> Self {
+        PlayerDocumentV1 {
+            document_version: 1,
+            itsf_id: player.itsf_id,
+            first_name: player.first_name.clone(),
+            last_name: player.last_name.clone(),
+            birth_year: player.birth_year,
+            country_code: player.country_code.clone(),
+            category: player.category,
+            itsf_rankings: player.itsf_rankings.clone(),
+            dtfb_id: player.dtfb_id,
+            dtfb_national_rankings: player.dtfb_national_rankings.clone(),
+            dtfb_championship_results: player.dtfb_championship_results.clone(),
+            dtfb_league_teams: player.dtfb_league_teams.clone(),
+            comments: player.comments.clone(),
+            tags: player.tags.clone(),
+            former_names: player.former_names.clone(),
+            country_changes: player.country_changes.clone(),
+            national_team_appearances: player.national_team_appearances.clone(),
+            hidden: player.hidden,
+            scraped_at: player.scraped_at,
+            refresh_errors: player.refresh_errors.clone(),
+            anonymized: player.anonymized,
+            revision: player.revision,
+            overridden: player.overridden.clone(),
+        }
+    }
+}
+
+impl From<PlayerDocumentV1> for Player {
+    fn from(document: PlayerDocumentV1) -> Self {
+        Player {
+            itsf_id: document.itsf_id,
+            first_name: document.first_name,
+            last_name: document.last_name,
+            birth_year: document.birth_year,
+            country_code: document.country_code,
+            category: document.category,
+            itsf_rankings: document.itsf_rankings,
+            dtfb_id: document.dtfb_id,
+            dtfb_national_rankings: document.dtfb_national_rankings,
+            dtfb_championship_results: document.dtfb_championship_results,
+            dtfb_league_teams: document.dtfb_league_teams,
+            comments: document.comments,
+            tags: document.tags,
+            former_names: document.former_names,
+            country_changes: document.country_changes,
+            national_team_appearances: document.national_team_appearances,
+            hidden: document.hidden,
+            scraped_at: document.scraped_at,
+            refresh_errors: document.refresh_errors,
+            anonymized: document.anonymized,
+            revision: document.revision,
+            overridden: document.overridden,
+        }
+    }
+}
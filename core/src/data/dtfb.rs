@@ -0,0 +1,82 @@
+use super::Provenance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[repr(i8)]
+pub enum ChampionshipCategory {
+    #[serde(rename = "men")]
+    Men,
+    #[serde(rename = "women")]
+    Women,
+    #[serde(rename = "junior")]
+    Junior,
+    #[serde(rename = "senior")]
+    Senior,
+}
+
+impl ChampionshipCategory {
+    pub const ALL: [Self; 4] = [Self::Men, Self::Women, Self::Junior, Self::Senior];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[repr(i8)]
+pub enum ChampionshipClass {
+    #[serde(rename = "singles")]
+    Singles,
+    #[serde(rename = "doubles")]
+    Doubles,
+}
+
+impl ChampionshipClass {
+    pub const ALL: [Self; 2] = [Self::Singles, Self::Doubles];
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NationalChampionshipResult {
+    pub year: i32,
+    pub place: i32,
+    pub category: ChampionshipCategory,
+    pub class: ChampionshipClass,
+    /// Missing for entries stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl NationalChampionshipResult {
+    pub fn matches(&self, other_ranking: &Self) -> bool {
+        self.year == other_ranking.year && self.category == other_ranking.category && self.class == other_ranking.class
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NationalRanking {
+    pub year: i32,
+    pub place: i32,
+    pub category: ChampionshipCategory,
+    /// Missing for entries stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl NationalRanking {
+    pub fn matches(&self, other_ranking: &Self) -> bool {
+        self.year == other_ranking.year && self.category == other_ranking.category
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NationalTeam {
+    pub year: i32,
+    pub name: String,
+    /// Missing for entries stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// Metadata of the download of a DTFB season, the results themselves are stored with the players.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SeasonDownload {
+    /// Year the season started in.
+    pub year: i32,
+    /// Unix timestamp of the download.
+    pub scraped_at: i64,
+}
@@ -0,0 +1,27 @@
+use chrono::NaiveDate;
+
+use super::geocodes::Coordinates;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Event {
+    pub event_id: i32,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub location: String,
+    pub country_code: Option<String>,
+    pub category: String,
+    pub url: String,
+    /// Of the location, if geocoding is enabled and found it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinates: Option<Coordinates>,
+}
+
+impl Event {
+    /// Whether national teams compete at the event, i.e. it is an ITSF World Championship.
+    pub fn is_world_championship(&self) -> bool {
+        [&self.name, &self.category]
+            .iter()
+            .any(|text| text.to_lowercase().contains("world championship"))
+    }
+}
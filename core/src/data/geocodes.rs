@@ -0,0 +1,30 @@
+/// A point on earth in degrees.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Coordinates {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Coordinates {
+    /// Parses `lat,lon`, e.g. `53.55,9.99`.
+    pub fn parse(coordinates: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid coordinates: '{}'", coordinates);
+        let (lat, lon) = coordinates.split_once(',').ok_or_else(invalid)?;
+        let lat = lat.trim().parse::<f64>().map_err(|_| invalid())?;
+        let lon = lon.trim().parse::<f64>().map_err(|_| invalid())?;
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(invalid());
+        }
+        Ok(Coordinates { lat, lon })
+    }
+
+    /// Great-circle distance in kilometers.
+    pub fn distance_km(&self, other: &Coordinates) -> f64 {
+        const EARTH_RADIUS_KM: f64 = 6371.0;
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.lon - self.lon).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
+}
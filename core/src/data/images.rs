@@ -0,0 +1,115 @@
+//! Every stored player image is decoded and re-encoded as JPEG first, which drops EXIF and other
+//! metadata and scales it down to `IMAGE_MAX_SIZE` pixels (default 1000) on its longer side.
+
+use lazy_static::lazy_static;
+use std::io::Cursor;
+
+/// Inputs with larger dimensions are rejected before decoding, to guard against decompression bombs.
+const MAX_INPUT_SIZE: u32 = 10_000;
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 90;
+
+/// Maximum width and height of stored images.
+pub fn max_size() -> u32 {
+    lazy_static! {
+        static ref MAX_SIZE: u32 = match std::env::var("IMAGE_MAX_SIZE") {
+            Ok(size) => size
+                .parse::<u32>()
+                .ok()
+                .filter(|size| *size > 0)
+                .expect("invalid IMAGE_MAX_SIZE"),
+            Err(_) => 1000,
+        };
+    }
+    *MAX_SIZE
+}
+
+/// Decodes a JPEG or PNG image and re-encodes it as a metadata free JPEG of at most the configured size.
+pub fn sanitize(image_data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = image::ImageReader::new(Cursor::new(image_data))
+        .with_guessed_format()
+        .map_err(|err| err.to_string())?;
+    match reader.format() {
+        Some(image::ImageFormat::Jpeg | image::ImageFormat::Png) => {}
+        Some(format) => return Err(format!("unsupported image format {:?}", format)),
+        None => return Err(String::from("unknown image format")),
+    }
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_INPUT_SIZE);
+    limits.max_image_height = Some(MAX_INPUT_SIZE);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    reader.limits(limits);
+
+    let mut image = reader.decode().map_err(|err| err.to_string())?;
+    let max_size = max_size();
+    if image.width() > max_size || image.height() > max_size {
+        image = image.resize(max_size, max_size, image::imageops::FilterType::Lanczos3);
+    }
+
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode_image(&image.to_rgb8())
+        .map_err(|err| err.to_string())?;
+    Ok(jpeg)
+}
+
+/// Result of a garbage collection of the image directory.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    /// Images in the directory.
+    pub scanned: usize,
+    /// Images of unknown or anonymized players, deleted unless in a dry run.
+    pub orphaned: Vec<String>,
+    /// Size of the orphaned images.
+    pub reclaimed_bytes: u64,
+}
+
+/// The player of an image file name like `12345678.jpg`, `None` for other files.
+pub fn itsf_id_of(file_name: &str) -> Option<i32> {
+    file_name.strip_suffix(".jpg")?.parse::<i32>().ok()
+}
+
+/// Who may download player images, on top of the access mode and signed image URLs. Applies to the image
+/// endpoint, the thumbnails of offline bundles and the images in the database download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageAccess {
+    /// Images of the players the request may read.
+    Public,
+    /// Only images of players that aren't hidden, also when logged in.
+    Visible,
+    /// Only logged-in users, signed URLs aren't enough.
+    Login,
+    /// No images at all.
+    Off,
+}
+
+impl ImageAccess {
+    pub fn from_env() -> Self {
+        match std::env::var("IMAGE_ACCESS") {
+            Ok(access) => Self::try_from_str(&access).expect("invalid IMAGE_ACCESS"),
+            Err(_) => Self::Public,
+        }
+    }
+
+    pub fn try_from_str(access: &str) -> Result<Self, String> {
+        match access {
+            "public" => Ok(Self::Public),
+            "visible" => Ok(Self::Visible),
+            "login" => Ok(Self::Login),
+            "off" => Ok(Self::Off),
+            _ => Err(format!("invalid image access: '{}'", access)),
+        }
+    }
+
+    /// Whether the image of a player that is `hidden` or not may be handed out to a request.
+    pub fn allows(self, authenticated: bool, hidden: bool) -> bool {
+        match self {
+            Self::Public => true,
+            Self::Visible => !hidden,
+            Self::Login => authenticated,
+            Self::Off => false,
+        }
+    }
+}
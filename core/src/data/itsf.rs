@@ -0,0 +1,203 @@
+use super::Provenance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[repr(i8)]
+pub enum PlayerCategory {
+    Men,
+    Women,
+    JuniorMale,
+    JuniorFemale,
+    SeniorMale,
+    SeniorFemale,
+}
+
+impl PlayerCategory {
+    pub const ALL: [Self; 6] = [
+        Self::Men,
+        Self::Women,
+        Self::JuniorMale,
+        Self::JuniorFemale,
+        Self::SeniorMale,
+        Self::SeniorFemale,
+    ];
+
+    pub fn try_from_str(category: &str) -> Result<Self, String> {
+        match category {
+            "MEN" => Ok(Self::Men),
+            "WOMEN" => Ok(Self::Women),
+            "JUNIOR MALE" => Ok(Self::JuniorMale),
+            "JUNIOR FEMALE" => Ok(Self::JuniorFemale),
+            "SENIOR MALE" => Ok(Self::SeniorMale),
+            "SENIOR FEMALE" => Ok(Self::SeniorFemale),
+            _ => Err(format!("invalid category: '{}'", category)),
+        }
+    }
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Men => "MEN",
+            Self::Women => "WOMEN",
+            Self::JuniorMale => "JUNIOR MALE",
+            Self::JuniorFemale => "JUNIOR FEMALE",
+            Self::SeniorMale => "SENIOR MALE",
+            Self::SeniorFemale => "SENIOR FEMALE",
+        }
+    }
+
+    /// Stable code used in responses, e.g. `junior_male`. The stored players keep the serde names.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Men => "men",
+            Self::Women => "women",
+            Self::JuniorMale => "junior_male",
+            Self::JuniorFemale => "junior_female",
+            Self::SeniorMale => "senior_male",
+            Self::SeniorFemale => "senior_female",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(i8)]
+pub enum RankingCategory {
+    #[serde(rename = "open")]
+    Open,
+    #[serde(rename = "women")]
+    Women,
+    #[serde(rename = "junior")]
+    Junior,
+    #[serde(rename = "senior")]
+    Senior,
+}
+
+impl RankingCategory {
+    pub const ALL: [Self; 4] = [Self::Open, Self::Women, Self::Senior, Self::Junior];
+
+    /// Parses the code used in JSON, e.g. `women`.
+    pub fn try_from_str(category: &str) -> Result<Self, String> {
+        match category {
+            "open" => Ok(Self::Open),
+            "women" => Ok(Self::Women),
+            "junior" => Ok(Self::Junior),
+            "senior" => Ok(Self::Senior),
+            _ => Err(format!("invalid ranking category: '{}'", category)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(i8)]
+pub enum RankingClass {
+    #[serde(rename = "singles")]
+    Singles,
+    #[serde(rename = "doubles")]
+    Doubles,
+    #[serde(rename = "combined")]
+    Combined,
+}
+
+impl RankingClass {
+    pub const ALL: [Self; 3] = [Self::Singles, Self::Doubles, Self::Combined];
+
+    /// Parses the code used in JSON, e.g. `doubles`.
+    pub fn try_from_str(class: &str) -> Result<Self, String> {
+        match class {
+            "singles" => Ok(Self::Singles),
+            "doubles" => Ok(Self::Doubles),
+            "combined" => Ok(Self::Combined),
+            _ => Err(format!("invalid ranking class: '{}'", class)),
+        }
+    }
+}
+
+/// Marks the final ranking of a season as closed, later downloads don't replace it unless forced.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RankingClosure {
+    /// Unix timestamp of the closing.
+    pub closed_at: i64,
+    pub closed_by: String,
+    /// `scraped_at` of the closed snapshot.
+    pub scraped_at: i64,
+    /// SHA-256 of the closed snapshot's placements, see `snapshots::checksum`.
+    pub checksum: String,
+}
+
+/// Metadata of a ranking download, the placements themselves are stored with the players.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RankingDownload {
+    pub year: i32,
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    /// Unix timestamp of the download.
+    pub scraped_at: i64,
+    pub entries: usize,
+    /// Number of places that were requested, missing for downloads before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rank: Option<usize>,
+    /// "Last update" date shown on the ranking page, if it could be parsed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_updated: Option<chrono::NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed: Option<RankingClosure>,
+}
+
+impl RankingDownload {
+    /// Key the download is stored under, e.g. `2022/Open/Singles`.
+    pub fn key(&self) -> String {
+        Self::key_of(self.year, self.category, self.class)
+    }
+
+    pub fn key_of(year: i32, category: RankingCategory, class: RankingClass) -> String {
+        format!("{}/{:?}/{:?}", year, category, class)
+    }
+
+    /// Whether this download already covers `max_rank` places of a ranking last updated on `source_updated`.
+    pub fn is_up_to_date(&self, max_rank: usize, source_updated: chrono::NaiveDate) -> bool {
+        self.source_updated == Some(source_updated) && self.max_rank.is_some_and(|downloaded| downloaded >= max_rank)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Ranking {
+    pub year: i32,
+    pub place: i32,
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    /// Place in percent of the ranked players, comparable across years with differently sized rankings.
+    /// Missing for rankings downloaded before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentile: Option<f64>,
+    /// Missing for entries stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// Place in percent of `entries` ranked players, rounded to two decimals.
+pub fn percentile(place: i32, entries: usize) -> Option<f64> {
+    if entries == 0 {
+        return None;
+    }
+    Some((place as f64 * 10000.0 / entries as f64).round() / 100.0)
+}
+
+impl Ranking {
+    pub fn matches(&self, other_ranking: &Self) -> bool {
+        self.year == other_ranking.year && self.category == other_ranking.category && self.class == other_ranking.class
+    }
+}
+
+/// A player's appearance for a national team in a team competition of an ITSF World Championship.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct NationalTeamAppearance {
+    pub year: i32,
+    pub event_id: i32,
+    pub event: String,
+    /// e.g. `Men's Teams`
+    pub competition: String,
+    pub country_code: String,
+    /// Final place of the team, missing if the results don't list one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub place: Option<i32>,
+    /// Missing for entries stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
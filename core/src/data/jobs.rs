@@ -0,0 +1,13 @@
+/// A finished run of a background job, kept in the job history.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct JobRun {
+    /// e.g. `maintenance`
+    pub job: String,
+    /// Id of a download job, which the provenance of its data refers to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Unix timestamps.
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub log: Vec<String>,
+}
@@ -0,0 +1,14 @@
+/// A team's position in a league table.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LeagueStanding {
+    pub position: i32,
+    pub team: String,
+    pub points: i32,
+}
+
+/// Standings of one division, e.g. "1. Bundesliga Nord", at the time of the download.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LeagueTable {
+    pub division: String,
+    pub standings: Vec<LeagueStanding>,
+}
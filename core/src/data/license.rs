@@ -0,0 +1,108 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// An ITSF license number, at most eight digits and written zero-padded to eight digits in its canonical form.
+/// ITSF license numbers carry no checksum, so only their format can be validated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LicenseNumber(i32);
+
+impl LicenseNumber {
+    const MAX: i32 = 99_999_999;
+
+    pub fn new(value: i32) -> Result<Self, String> {
+        if (1..=Self::MAX).contains(&value) {
+            Ok(Self(value))
+        } else {
+            Err(format!("invalid ITSF license number: {}", value))
+        }
+    }
+
+    pub fn get(self) -> i32 {
+        self.0
+    }
+}
+
+impl FromStr for LicenseNumber {
+    type Err = String;
+
+    fn from_str(license: &str) -> Result<Self, Self::Err> {
+        let digits = license.trim();
+        if digits.is_empty() || digits.len() > 8 || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(format!("invalid ITSF license number: '{}'", license));
+        }
+        Self::new(digits.parse::<i32>().map_err(|err| err.to_string())?)
+    }
+}
+
+impl fmt::Display for LicenseNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08}", self.0)
+    }
+}
+
+impl serde::Serialize for LicenseNumber {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(self.0)
+    }
+}
+
+/// Accepts numbers as well as strings, e.g. path segments or zero-padded licenses.
+impl<'de> serde::Deserialize<'de> for LicenseNumber {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(i64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(value) => i32::try_from(value)
+                .map_err(|_| format!("invalid ITSF license number: {}", value))
+                .and_then(Self::new),
+            Raw::Text(text) => text.parse(),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn licenses_are_one_to_eight_digits() {
+        assert_eq!("84000895".parse::<LicenseNumber>().unwrap().get(), 84000895);
+        assert_eq!(" 00000042 ".parse::<LicenseNumber>().unwrap().get(), 42);
+        assert_eq!("99999999".parse::<LicenseNumber>().unwrap().get(), 99_999_999);
+        for license in ["", "0", "00000000", "123456789", "-1", "+1", "8400 0895", "84OOO895"] {
+            assert!(license.parse::<LicenseNumber>().is_err(), "{}", license);
+        }
+    }
+
+    #[test]
+    fn new_rejects_values_out_of_range() {
+        assert!(LicenseNumber::new(1).is_ok());
+        assert!(LicenseNumber::new(0).is_err());
+        assert!(LicenseNumber::new(-84000895).is_err());
+        assert!(LicenseNumber::new(100_000_000).is_err());
+    }
+
+    #[test]
+    fn displayed_zero_padded() {
+        assert_eq!(LicenseNumber::new(42).unwrap().to_string(), "00000042");
+        assert_eq!(LicenseNumber::new(84000895).unwrap().to_string(), "84000895");
+    }
+
+    #[test]
+    fn deserialized_from_numbers_and_strings() {
+        let parse = |json: &str| serde_json::from_str::<LicenseNumber>(json).map(LicenseNumber::get);
+        assert_eq!(parse("84000895").unwrap(), 84000895);
+        assert_eq!(parse("\"00000042\"").unwrap(), 42);
+        assert!(parse("0").is_err());
+        assert!(parse("4294967296").is_err());
+        assert!(parse("\"abc\"").is_err());
+        assert!(parse("1.5").is_err());
+        assert_eq!(serde_json::to_string(&LicenseNumber::new(42).unwrap()).unwrap(), "42");
+    }
+}
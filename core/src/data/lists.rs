@@ -0,0 +1,26 @@
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlayerList {
+    pub list_id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub players: Vec<i32>,
+    pub created: u32,
+    /// Lists of a workspace are only visible in it, those without are shared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+}
+
+impl PlayerList {
+    pub fn add_players(&mut self, itsf_ids: &[i32]) {
+        for itsf_id in itsf_ids {
+            if !self.players.contains(itsf_id) {
+                self.players.push(*itsf_id);
+            }
+        }
+    }
+
+    pub fn remove_players(&mut self, itsf_ids: &[i32]) {
+        self.players.retain(|itsf_id| !itsf_ids.contains(itsf_id));
+    }
+}
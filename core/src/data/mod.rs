@@ -0,0 +1,2127 @@
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::{Deref, DerefMut},
+    panic::Location,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use zip::{CompressionMethod, ZipWriter};
+
+pub mod clubs;
+pub mod connection;
+mod db;
+pub mod documents;
+pub mod dtfb;
+pub mod events;
+pub mod geocodes;
+pub mod images;
+pub mod itsf;
+pub mod jobs;
+pub mod leagues;
+pub mod license;
+pub mod lists;
+pub mod overrides;
+pub mod presets;
+pub mod quality;
+pub mod replication;
+pub mod samples;
+pub mod season;
+pub mod snapshots;
+pub mod staged;
+pub mod subscriptions;
+pub mod workspaces;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum CommentVisibility {
+    #[default]
+    #[serde(rename = "public")]
+    Public,
+    #[serde(rename = "internal")]
+    Internal,
+}
+
+/// Follow-up state of a comment that asks for something to be done, e.g. "verify birth year".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum CommentStatus {
+    #[serde(rename = "open")]
+    Open,
+    #[serde(rename = "resolved")]
+    Resolved,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlayerComment {
+    pub timestamp: u32,
+    pub text: String,
+    #[serde(default)]
+    pub visibility: CommentVisibility,
+    /// Who wrote the comment, only known for imported comments.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Missing for comments that aren't tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<CommentStatus>,
+    /// User who resolved the comment and when, cleared when it is opened again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<u32>,
+    /// Users who gave the comment a thumbs-up.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thumbs_up: Vec<String>,
+}
+
+impl PlayerComment {
+    /// License numbers referenced as `#123456` in the text, in order of appearance and without duplicates.
+    pub fn mentions(&self) -> Vec<i32> {
+        const MAX_LICENSE_DIGITS: usize = 8;
+        let mut mentions = Vec::new();
+        let mut rest = self.text.as_str();
+        while let Some(pos) = rest.find('#') {
+            rest = &rest[pos + 1..];
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits > 0 && digits <= MAX_LICENSE_DIGITS {
+                let itsf_id = rest[..digits].parse::<i32>().expect("digits are a valid number");
+                if !mentions.contains(&itsf_id) {
+                    mentions.push(itsf_id);
+                }
+            }
+            rest = &rest[digits..];
+        }
+        mentions
+    }
+
+    /// Changes the follow-up state, remembering who resolved the comment.
+    pub fn set_status(&mut self, status: Option<CommentStatus>, user_id: &str) {
+        let resolved = status == Some(CommentStatus::Resolved);
+        if resolved && self.status != status {
+            self.resolved_by = Some(String::from(user_id));
+            self.resolved_at = Some(chrono::Utc::now().timestamp() as u32);
+        } else if !resolved {
+            self.resolved_by = None;
+            self.resolved_at = None;
+        }
+        self.status = status;
+    }
+
+    /// Adds or removes the user's thumbs-up.
+    pub fn set_thumbs_up(&mut self, user_id: &str, thumbs_up: bool) {
+        self.thumbs_up.retain(|user| user != user_id);
+        if thumbs_up {
+            self.thumbs_up.push(String::from(user_id));
+        }
+    }
+}
+
+/// A name a player was previously known under, e.g. before marriage or a correction.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FormerName {
+    pub first_name: String,
+    pub last_name: String,
+    /// When the name was replaced by a newer one.
+    pub timestamp: u32,
+}
+
+impl FormerName {
+    pub fn full_name(&self) -> String {
+        format!("{} {}", self.first_name, self.last_name)
+    }
+}
+
+/// A switch of the federation a player plays for, as noticed between two downloads.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CountryChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Year of the download that first showed the new country.
+    pub year: i32,
+}
+
+/// A player as kept in memory, stored as `documents::PlayerDocument`.
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub itsf_id: i32,
+
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_year: i32,
+    pub country_code: Option<String>,
+    pub category: itsf::PlayerCategory,
+
+    pub itsf_rankings: Vec<itsf::Ranking>,
+
+    pub dtfb_id: Option<i32>,
+    pub dtfb_national_rankings: Vec<dtfb::NationalRanking>,
+    pub dtfb_championship_results: Vec<dtfb::NationalChampionshipResult>,
+    pub dtfb_league_teams: Vec<dtfb::NationalTeam>,
+
+    pub comments: Vec<PlayerComment>,
+
+    pub tags: Vec<String>,
+
+    pub former_names: Vec<FormerName>,
+
+    /// Oldest first, `country_code` is always the latest country.
+    pub country_changes: Vec<CountryChange>,
+
+    pub national_team_appearances: Vec<itsf::NationalTeamAppearance>,
+
+    /// Hidden players are only visible to authenticated users, e.g. after a takedown request.
+    pub hidden: bool,
+
+    /// Unix timestamp of the last download of the player's ITSF profile, unknown for older records.
+    pub scraped_at: Option<i64>,
+
+    /// Failed downloads since the last successful one, at most one per target.
+    pub refresh_errors: Vec<RefreshError>,
+
+    /// Personal data was removed after a formal request, downloads no longer restore it.
+    pub anonymized: bool,
+
+    /// Revision of the last write, increasing across all players, see `get_players_changed_since`.
+    pub revision: u64,
+
+    /// Downloaded values of the fields replaced by manual overrides, restored when an override is revoked.
+    pub overridden: BTreeMap<overrides::OverrideField, serde_json::Value>,
+}
+
+/// Where a stored ranking or result was scraped from, to trace disputed data back to its page.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Provenance {
+    pub source_url: String,
+    /// Unix timestamp of the download.
+    pub scraped_at: i64,
+    /// Id of the download job, as listed in the job history.
+    pub job_id: String,
+}
+
+/// Heavy parts of a player profile, only copied out of the store when requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerSection {
+    /// ITSF and DTFB rankings.
+    Rankings,
+    /// German championship placements.
+    Results,
+    /// League teams and national team appearances.
+    Teams,
+    Comments,
+}
+
+impl PlayerSection {
+    pub const ALL: [Self; 4] = [Self::Rankings, Self::Results, Self::Teams, Self::Comments];
+
+    /// Parses the code used in `?include=`, e.g. `teams`.
+    pub fn try_from_str(section: &str) -> Result<Self, String> {
+        match section {
+            "rankings" => Ok(Self::Rankings),
+            "results" => Ok(Self::Results),
+            "teams" => Ok(Self::Teams),
+            "comments" => Ok(Self::Comments),
+            _ => Err(format!("invalid section: '{}'", section)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RefreshTarget {
+    #[serde(rename = "profile")]
+    Profile,
+    #[serde(rename = "image")]
+    Image,
+}
+
+/// A failed download or parse of part of a player's data.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RefreshError {
+    pub target: RefreshTarget,
+    /// Unix timestamp of the failed attempt.
+    pub timestamp: i64,
+    pub message: String,
+}
+
+/// Seconds after which a player's profile counts as stale, configured in days via `DATA_STALE_AFTER`.
+pub(crate) fn stale_after() -> i64 {
+    lazy_static! {
+        static ref STALE_AFTER: i64 = match std::env::var("DATA_STALE_AFTER") {
+            Ok(days) => days.parse::<i64>().expect("invalid DATA_STALE_AFTER") * 24 * 60 * 60,
+            Err(_) => 180 * 24 * 60 * 60,
+        };
+    }
+    *STALE_AFTER
+}
+
+impl Player {
+    /// Copies the player, leaving the sections that were not requested empty.
+    fn clone_sections(&self, sections: &[PlayerSection]) -> Player {
+        let copy = |section: PlayerSection| sections.contains(&section);
+        Player {
+            itsf_id: self.itsf_id,
+            first_name: self.first_name.clone(),
+            last_name: self.last_name.clone(),
+            birth_year: self.birth_year,
+            country_code: self.country_code.clone(),
+            category: self.category,
+            itsf_rankings: if copy(PlayerSection::Rankings) {
+                self.itsf_rankings.clone()
+            } else {
+                Vec::new()
+            },
+            dtfb_id: self.dtfb_id,
+            dtfb_national_rankings: if copy(PlayerSection::Rankings) {
+                self.dtfb_national_rankings.clone()
+            } else {
+                Vec::new()
+            },
+            dtfb_championship_results: if copy(PlayerSection::Results) {
+                self.dtfb_championship_results.clone()
+            } else {
+                Vec::new()
+            },
+            dtfb_league_teams: if copy(PlayerSection::Teams) {
+                self.dtfb_league_teams.clone()
+            } else {
+                Vec::new()
+            },
+            comments: if copy(PlayerSection::Comments) {
+                self.comments.clone()
+            } else {
+                Vec::new()
+            },
+            tags: self.tags.clone(),
+            former_names: self.former_names.clone(),
+            country_changes: self.country_changes.clone(),
+            national_team_appearances: if copy(PlayerSection::Teams) {
+                self.national_team_appearances.clone()
+            } else {
+                Vec::new()
+            },
+            hidden: self.hidden,
+            scraped_at: self.scraped_at,
+            refresh_errors: self.refresh_errors.clone(),
+            anonymized: self.anonymized,
+            revision: self.revision,
+            overridden: self.overridden.clone(),
+        }
+    }
+
+    /// Replaces the name with a placeholder and drops everything identifying the player, keeping
+    /// country, category and results for statistics.
+    fn strip_personal_data(&mut self) {
+        self.first_name = String::from("Anonymized");
+        self.last_name = String::from("Player");
+        self.birth_year = 0;
+        self.dtfb_id = None;
+        self.comments.clear();
+        self.tags.clear();
+        self.former_names.clear();
+        self.refresh_errors.clear();
+        self.overridden.clear();
+        self.anonymized = true;
+    }
+
+    /// Whether the profile should be downloaded again, always true if it's unknown when it was downloaded.
+    pub fn is_stale(&self) -> bool {
+        match self.scraped_at {
+            Some(scraped_at) => chrono::Utc::now().timestamp() - scraped_at > stale_after(),
+            None => true,
+        }
+    }
+}
+
+/// Normalizes a free-form player tag, e.g. " Pin Shooter" to "pin shooter".
+pub fn normalize_tag(tag: &str) -> Result<String, String> {
+    const MAX_TAG_LENGTH: usize = 64;
+    let tag = tag.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase();
+    if tag.is_empty() {
+        Err(String::from("empty tag"))
+    } else if tag.chars().count() > MAX_TAG_LENGTH {
+        Err(format!("tag longer than {} characters", MAX_TAG_LENGTH))
+    } else {
+        Ok(tag)
+    }
+}
+
+pub struct PlayerImage {
+    pub itsf_id: i32,
+    pub image_data: Vec<u8>,
+    pub image_format: String,
+}
+
+struct DatabaseInner {
+    db: RefCell<db::DbConnection>,
+    /// Read-only replica used for queries that don't need to see the latest writes.
+    replica: Option<RefCell<db::DbConnection>>,
+    players: HashMap<i32, Player>,
+    /// ITSF license of the player with a DTFB license, kept up to date on every player write.
+    dtfb_ids: HashMap<i32, i32>,
+    /// DTFB license of every player with one as of their last write, to update `dtfb_ids` without a full scan.
+    player_dtfb_ids: HashMap<i32, i32>,
+    lists: HashMap<i32, lists::PlayerList>,
+    /// Deleted lists that are still stored until the deletion is finalized, see `staged_actions`.
+    deleted_lists: HashMap<i32, lists::PlayerList>,
+    /// Actions that can still be undone, oldest first, stored so they survive restarts.
+    staged_actions: Vec<staged::PendingAction>,
+    next_action_id: u64,
+    subscriptions: HashMap<String, subscriptions::Subscription>,
+    events: HashMap<i32, events::Event>,
+    /// League tables per DTFB season start year.
+    leagues: HashMap<i32, Vec<leagues::LeagueTable>>,
+    dtfb_downloads: HashMap<i32, dtfb::SeasonDownload>,
+    /// Coordinates by geocoded place, `None` for places that weren't found.
+    geocodes: HashMap<String, Option<geocodes::Coordinates>>,
+    ranking_downloads: HashMap<String, itsf::RankingDownload>,
+    /// Comments and tags of workspaces by workspace and player.
+    workspace_notes: HashMap<(String, i32), workspaces::PlayerNotes>,
+    /// Ledger of manual corrections by player, oldest first.
+    overrides: HashMap<i32, Vec<overrides::FieldOverride>>,
+    /// Features switched on or off at runtime, overriding the configured defaults.
+    feature_flags: HashMap<String, bool>,
+    download_presets: HashMap<String, presets::DownloadPreset>,
+    clubs: HashMap<String, clubs::Club>,
+    player_listeners: Vec<UnboundedSender<Player>>,
+    /// Incremented on every player write, so derived data can tell when it is outdated.
+    player_generation: u64,
+    /// Latest revision of any player, stored with the players so it survives restarts.
+    player_revision: u64,
+}
+
+impl DatabaseInner {
+    fn reader(&self) -> &RefCell<db::DbConnection> {
+        self.replica.as_ref().unwrap_or(&self.db)
+    }
+
+    fn next_player_revision(&mut self) -> u64 {
+        self.player_revision += 1;
+        self.player_revision
+    }
+
+    fn notify_player_write(&mut self, itsf_id: i32) {
+        self.player_generation += 1;
+        if let Some(previous) = self.player_dtfb_ids.remove(&itsf_id) {
+            // another player may have taken over the license since
+            if self.dtfb_ids.get(&previous) == Some(&itsf_id) {
+                self.dtfb_ids.remove(&previous);
+            }
+        }
+        if let Some(player) = self.players.get(&itsf_id) {
+            if let Some(dtfb_id) = player.dtfb_id {
+                self.dtfb_ids.insert(dtfb_id, itsf_id);
+                self.player_dtfb_ids.insert(itsf_id, dtfb_id);
+            }
+            let player = player.clone();
+            self.player_listeners
+                .retain(|listener| listener.send(player.clone()).is_ok());
+        }
+    }
+}
+
+/// The checked out connection, logging the calling query if it is held for too long.
+struct ConnectionGuard<'a> {
+    inner: MutexGuard<'a, DatabaseInner>,
+    acquired: Instant,
+    slow_query: Duration,
+    caller: &'static Location<'static>,
+}
+
+impl Deref for ConnectionGuard<'_> {
+    type Target = DatabaseInner;
+
+    fn deref(&self) -> &DatabaseInner {
+        &self.inner
+    }
+}
+
+impl DerefMut for ConnectionGuard<'_> {
+    fn deref_mut(&mut self) -> &mut DatabaseInner {
+        &mut self.inner
+    }
+}
+
+impl Drop for ConnectionGuard<'_> {
+    fn drop(&mut self) {
+        let held = self.acquired.elapsed();
+        if held > self.slow_query {
+            log::warn!(
+                "Slow query: database held for {} ms by {}",
+                held.as_millis(),
+                self.caller
+            );
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DatabaseRef {
+    database_path: String,
+    image_directory: String,
+    settings: connection::ConnectionSettings,
+    stats: Arc<connection::ConnectionStats>,
+    /// Images of frequently requested players kept in memory, see `warm_player_images`.
+    image_cache: Arc<Mutex<HashMap<i32, Vec<u8>>>>,
+    /// Content hashes of player images, computed on first use.
+    image_hashes: Arc<Mutex<HashMap<i32, Option<String>>>>,
+    inner: Arc<Mutex<DatabaseInner>>,
+}
+
+fn add_zip_file(
+    writer: &mut ZipWriter<Cursor<&mut Vec<u8>>>,
+    compression: CompressionMethod,
+    path: &str,
+) -> Result<(), String> {
+    let mut f = File::open(path).map_err(|err| err.to_string())?;
+    let mut data = Vec::new();
+    f.read_to_end(&mut data).map_err(|err| err.to_string())?;
+
+    let options = zip::write::FileOptions::default().compression_method(compression);
+    // writer.start_file(path.split("/").last().unwrap(), options).map_err(|_| ())?;
+    writer.start_file(path, options).map_err(|err| err.to_string())?;
+    writer.write(&data).map_err(|err| err.to_string())?;
+
+    Ok(())
+}
+
+/// Creates the database at `path` if needed and applies all pending migrations, returning their versions.
+pub fn run_migrations(path: &str, settings: &connection::ConnectionSettings) -> Result<Vec<String>, String> {
+    db::DbConnection::open(path, settings.busy_timeout).run_pending_migrations()
+}
+
+/// Versions of the migrations that were not applied to the database at `path` yet, without applying them.
+pub fn get_pending_migrations(path: &str, settings: &connection::ConnectionSettings) -> Result<Vec<String>, String> {
+    db::DbConnection::open(path, settings.busy_timeout).get_pending_migrations()
+}
+
+/// Fails if migrations are pending or tables or columns that the migrations create are missing from the
+/// database at `path`, e.g. after a manual change, so the server doesn't start with a schema it can't query.
+pub fn check_schema(path: &str, settings: &connection::ConnectionSettings) -> Result<(), String> {
+    let mut live = db::DbConnection::open(path, settings.busy_timeout);
+    let pending = live.get_pending_migrations()?;
+    if !pending.is_empty() {
+        return Err(format!(
+            "database schema of {} is outdated, pending migrations: {}; run `diesel migration run`",
+            path,
+            pending.join(", ")
+        ));
+    }
+
+    let mut expected = db::DbConnection::open(":memory:", settings.busy_timeout);
+    expected.run_pending_migrations()?;
+    let live_columns = live.get_table_columns();
+    let missing: Vec<String> = expected
+        .get_table_columns()
+        .into_iter()
+        .filter(|column| !live_columns.contains(column))
+        .map(|(table, column)| format!("{}.{}", table, column))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "database schema of {} doesn't match its migrations, missing columns: {}",
+            path,
+            missing.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+impl DatabaseRef {
+    /// Loads all data from `path`, which all writes go to. The replica at `replica_path`, if given, only serves
+    /// queries that don't lead to writes, as it may lag behind and the in-memory data is written back as a whole.
+    pub fn load(
+        path: &str,
+        replica_path: Option<&str>,
+        image_directory: &str,
+        settings: connection::ConnectionSettings,
+    ) -> Self {
+        let mut primary = db::DbConnection::open(path, settings.busy_timeout);
+        let replica = replica_path.map(|path| db::DbConnection::open_read_only(path, settings.busy_timeout));
+        let db = &mut primary;
+        let mut players = HashMap::new();
+
+        for player_id in db.get_player_ids() {
+            let player = db
+                .read_player_json::<documents::PlayerDocument>(player_id)
+                .expect("failed to read player")
+                .into_player();
+            players.insert(player_id, player);
+        }
+        log::error!("Loaded {} players", players.len());
+        let player_revision = players
+            .values()
+            .map(|player: &Player| player.revision)
+            .max()
+            .unwrap_or(0);
+        let player_dtfb_ids: HashMap<i32, i32> = players
+            .values()
+            .filter_map(|player: &Player| Some((player.itsf_id, player.dtfb_id?)))
+            .collect();
+        let dtfb_ids = player_dtfb_ids
+            .iter()
+            .map(|(itsf_id, dtfb_id)| (*dtfb_id, *itsf_id))
+            .collect();
+
+        let mut lists = HashMap::new();
+        for list_id in db.get_list_ids() {
+            let list = db.read_list_json(list_id).expect("failed to read player list");
+            lists.insert(list_id, list);
+        }
+        let staged_actions: Vec<staged::PendingAction> = db.read_staged_actions_json();
+        let deleted_lists = staged_actions
+            .iter()
+            .filter_map(|pending| {
+                let list_id = pending.action.deleted_list();
+                Some((list_id, lists.remove(&list_id)?))
+            })
+            .collect();
+        let next_action_id = staged_actions
+            .iter()
+            .map(|pending| pending.action_id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let mut subscriptions = HashMap::new();
+        for user_id in db.get_subscription_user_ids() {
+            let subscription = db
+                .read_subscription_json(&user_id)
+                .expect("failed to read subscription");
+            subscriptions.insert(user_id, subscription);
+        }
+
+        let mut events = HashMap::new();
+        for event_id in db.get_event_ids() {
+            let event = db.read_event_json(event_id).expect("failed to read event");
+            events.insert(event_id, event);
+        }
+
+        let mut leagues = HashMap::new();
+        for season in db.get_league_seasons() {
+            let tables = db.read_league_json(season).expect("failed to read league tables");
+            leagues.insert(season, tables);
+        }
+
+        let mut dtfb_downloads = HashMap::new();
+        for season in db.get_dtfb_download_seasons() {
+            let download = db
+                .read_dtfb_download_json(season)
+                .expect("failed to read DTFB download");
+            dtfb_downloads.insert(season, download);
+        }
+
+        let mut geocodes = HashMap::new();
+        for place in db.get_geocode_places() {
+            let coordinates = db.read_geocode_json(&place).expect("failed to read geocode");
+            geocodes.insert(place, coordinates);
+        }
+
+        let mut ranking_downloads = HashMap::new();
+        for key in db.get_ranking_download_keys() {
+            let download = db
+                .read_ranking_download_json(&key)
+                .expect("failed to read ranking download");
+            ranking_downloads.insert(key, download);
+        }
+
+        let mut workspace_notes = HashMap::new();
+        for key in db.get_workspace_notes_keys() {
+            let notes: workspaces::PlayerNotes = db
+                .read_workspace_notes_json(&key)
+                .expect("failed to read workspace notes");
+            workspace_notes.insert((notes.workspace.clone(), notes.itsf_id), notes);
+        }
+
+        let mut overrides: HashMap<i32, Vec<overrides::FieldOverride>> = HashMap::new();
+        for entry in db.read_player_overrides_json::<overrides::FieldOverride>() {
+            overrides.entry(entry.itsf_id).or_default().push(entry);
+        }
+
+        let mut feature_flags = HashMap::new();
+        for feature in db.get_feature_flag_names() {
+            let enabled = db
+                .read_feature_flag_json(&feature)
+                .expect("failed to read feature flag");
+            feature_flags.insert(feature, enabled);
+        }
+
+        let mut download_presets = HashMap::new();
+        for name in db.get_download_preset_names() {
+            let preset = db
+                .read_download_preset_json(&name)
+                .expect("failed to read download preset");
+            download_presets.insert(name, preset);
+        }
+
+        let mut clubs = HashMap::new();
+        for name in db.get_club_names() {
+            let club = db.read_club_json(&name).expect("failed to read club");
+            clubs.insert(name, club);
+        }
+
+        let inner = DatabaseInner {
+            db: RefCell::new(primary),
+            replica: replica.map(RefCell::new),
+            players,
+            dtfb_ids,
+            player_dtfb_ids,
+            lists,
+            deleted_lists,
+            staged_actions,
+            next_action_id,
+            subscriptions,
+            events,
+            leagues,
+            dtfb_downloads,
+            geocodes,
+            ranking_downloads,
+            workspace_notes,
+            overrides,
+            feature_flags,
+            download_presets,
+            clubs,
+            player_listeners: Vec::new(),
+            player_generation: 0,
+            player_revision,
+        };
+
+        let path_info = std::fs::metadata(image_directory).unwrap_or_else(|_| panic!("Can't open {}", image_directory));
+        assert!(path_info.is_dir(), "Not a directory: {}", image_directory);
+
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+            image_directory: String::from(image_directory),
+            database_path: String::from(path),
+            settings,
+            stats: Arc::new(connection::ConnectionStats::default()),
+            image_cache: Arc::new(Mutex::new(HashMap::new())),
+            image_hashes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Checks out the connection, logging slow waits. A panic while holding the connection
+    /// happens before the in-memory data is modified, so a poisoned lock is safe to reuse.
+    #[track_caller]
+    fn lock(&self) -> ConnectionGuard<'_> {
+        let caller = Location::caller();
+        let start = Instant::now();
+        let inner = self.inner.lock().unwrap_or_else(|poisoned| {
+            log::error!("Database connection was poisoned by a failed query, reusing it");
+            poisoned.into_inner()
+        });
+        let wait = start.elapsed();
+        let slow = wait > self.settings.slow_wait;
+        if slow {
+            log::warn!("Waited {} ms for the database connection", wait.as_millis());
+        }
+        self.stats.record(wait, slow);
+        ConnectionGuard {
+            inner,
+            acquired: Instant::now(),
+            slow_query: self.settings.slow_query,
+            caller,
+        }
+    }
+
+    pub fn get_connection_stats(&self) -> connection::ConnectionStatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    pub fn get_player(&self, itsf_id: i32) -> Option<Player> {
+        let inner = self.lock();
+        inner.players.get(&itsf_id).cloned()
+    }
+
+    pub fn get_player_by_dtfb_id(&self, dtfb_id: i32) -> Option<Player> {
+        let inner = self.lock();
+        let itsf_id = inner.dtfb_ids.get(&dtfb_id)?;
+        inner.players.get(itsf_id).cloned()
+    }
+
+    /// Like `get_player`, but only copies the requested sections, the others are left empty.
+    pub fn get_player_sections(&self, itsf_id: i32, sections: &[PlayerSection]) -> Option<Player> {
+        let inner = self.lock();
+        inner
+            .players
+            .get(&itsf_id)
+            .map(|player| player.clone_sections(sections))
+    }
+
+    pub fn get_itsf_id_by_dtfb_id(&self, dtfb_id: i32) -> Option<i32> {
+        let inner = self.lock();
+        inner.dtfb_ids.get(&dtfb_id).copied()
+    }
+
+    /// Runs an aggregation over all players without copying them.
+    pub fn aggregate_players<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut dyn Iterator<Item = &Player>) -> T,
+    {
+        let inner = self.lock();
+        f(&mut inner.players.values())
+    }
+
+    /// Changes whenever any player is written.
+    pub fn get_player_generation(&self) -> u64 {
+        self.lock().player_generation
+    }
+
+    /// The latest revision of any player.
+    pub fn get_player_revision(&self) -> u64 {
+        self.lock().player_revision
+    }
+
+    /// Players written after `revision`, oldest change first.
+    pub fn get_players_changed_since(&self, revision: u64) -> Vec<Player> {
+        let inner = self.lock();
+        let mut players: Vec<Player> = inner
+            .players
+            .values()
+            .filter(|player| player.revision > revision)
+            .cloned()
+            .collect();
+        players.sort_by_key(|player| player.revision);
+        players
+    }
+
+    /// Stores a player replicated from another instance as it is there, under a new local revision. The image of
+    /// an anonymized player is deleted.
+    pub fn replicate_player(&self, mut player: Player) {
+        let itsf_id = player.itsf_id;
+        let anonymized = player.anonymized;
+        {
+            let mut inner = self.lock();
+            player.revision = inner.next_player_revision();
+            inner
+                .db
+                .borrow_mut()
+                .write_player_json(itsf_id, &documents::PlayerDocument::new(&player));
+            inner.players.insert(itsf_id, player);
+            inner.notify_player_write(itsf_id);
+        }
+        if anonymized {
+            self.delete_player_image(itsf_id);
+        }
+    }
+
+    /// How far the players of the primary at `primary_url` were replicated, see `replication`.
+    pub fn get_replication_state(&self, primary_url: &str) -> Option<replication::ReplicationState> {
+        let inner = self.lock();
+        let state = inner.db.borrow_mut().read_replication_state_json(primary_url);
+        state
+    }
+
+    pub fn set_replication_state(&self, primary_url: &str, state: &replication::ReplicationState) {
+        let inner = self.lock();
+        inner.db.borrow_mut().write_replication_state_json(primary_url, state);
+    }
+
+    pub fn get_player_ids(&self) -> Vec<i32> {
+        let inner = self.lock();
+        inner.players.keys().copied().collect()
+    }
+
+    /// Adds or replaces a player, remembering the previous name and country if they changed. Manual overrides
+    /// are applied on top, so downloads never replace corrected fields.
+    pub fn add_player(&self, mut player: Player) {
+        let mut inner = self.lock();
+        let itsf_id = player.itsf_id;
+        if let Some(ledger) = inner.overrides.get(&itsf_id) {
+            overrides::apply(ledger, &mut player);
+        }
+        if let Some(old) = inner.players.get(&itsf_id) {
+            let mut former_names = old.former_names.clone();
+            if (&old.first_name, &old.last_name) != (&player.first_name, &player.last_name) {
+                former_names.push(FormerName {
+                    first_name: old.first_name.clone(),
+                    last_name: old.last_name.clone(),
+                    timestamp: chrono::Utc::now().naive_local().timestamp() as u32,
+                });
+            }
+            former_names.retain(|name| (&name.first_name, &name.last_name) != (&player.first_name, &player.last_name));
+            player.former_names = former_names;
+            let mut country_changes = old.country_changes.clone();
+            if old.country_code != player.country_code {
+                use chrono::Datelike;
+                country_changes.push(CountryChange {
+                    from: old.country_code.clone(),
+                    to: player.country_code.clone(),
+                    year: chrono::Utc::now().year(),
+                });
+            }
+            player.country_changes = country_changes;
+            player.hidden = old.hidden;
+            if old.anonymized {
+                player.strip_personal_data();
+            }
+        }
+        player.revision = inner.next_player_revision();
+        inner
+            .db
+            .borrow_mut()
+            .write_player_json(itsf_id, &documents::PlayerDocument::new(&player));
+        inner.players.insert(itsf_id, player);
+        inner.notify_player_write(itsf_id);
+    }
+
+    /// Updates the profile fields of a stored player from a fresh download, keeping everything else.
+    pub fn refresh_player_info(&self, player: Player) {
+        let old = match self.get_player(player.itsf_id) {
+            Some(old) => old,
+            None => return self.add_player(player),
+        };
+        self.add_player(Player {
+            first_name: player.first_name,
+            last_name: player.last_name,
+            birth_year: player.birth_year,
+            country_code: player.country_code,
+            category: player.category,
+            scraped_at: player.scraped_at,
+            overridden: BTreeMap::new(),
+            refresh_errors: old
+                .refresh_errors
+                .into_iter()
+                .filter(|error| error.target != RefreshTarget::Profile)
+                .collect(),
+            ..old
+        });
+    }
+
+    /// Records a failed refresh of a stored player, replacing an earlier error of the same target.
+    pub fn record_refresh_error(&self, itsf_id: i32, target: RefreshTarget, message: String) {
+        if self.get_player(itsf_id).is_none() {
+            return;
+        }
+        self.modify_player(itsf_id, |player| {
+            player.refresh_errors.retain(|error| error.target != target);
+            player.refresh_errors.push(RefreshError {
+                target,
+                timestamp: chrono::Utc::now().timestamp(),
+                message,
+            });
+        });
+    }
+
+    fn clear_refresh_error(&self, itsf_id: i32, target: RefreshTarget) {
+        let failed = self
+            .get_player(itsf_id)
+            .is_some_and(|player| player.refresh_errors.iter().any(|error| error.target == target));
+        if failed {
+            self.modify_player(itsf_id, |player| {
+                player.refresh_errors.retain(|error| error.target != target)
+            });
+        }
+    }
+
+    /// Returns a receiver getting a copy of every player written from now on.
+    pub fn subscribe_player_writes(&self) -> UnboundedReceiver<Player> {
+        let (sender, receiver) = unbounded_channel();
+        let mut inner = self.lock();
+        inner.player_listeners.push(sender);
+        receiver
+    }
+
+    fn read_player_image(&self, itsf_id: i32) -> Option<Vec<u8>> {
+        let path = format!("{}/{}.jpg", self.image_directory, itsf_id);
+        std::fs::read(path).ok()
+    }
+
+    pub fn get_player_image(&self, itsf_id: i32) -> Option<PlayerImage> {
+        let cached = self.image_cache.lock().unwrap().get(&itsf_id).cloned();
+        cached
+            .or_else(|| self.read_player_image(itsf_id))
+            .map(|image_data| PlayerImage {
+                itsf_id,
+                image_data,
+                image_format: String::from("jpg"),
+            })
+    }
+
+    pub fn has_player_image(&self, itsf_id: i32) -> bool {
+        std::path::Path::new(&format!("{}/{}.jpg", self.image_directory, itsf_id)).exists()
+    }
+
+    /// Stores the image re-encoded by [`images::sanitize`], fails if it can't be decoded.
+    /// Images of anonymized players are dropped. A changed image is a new revision of the player, so sync
+    /// clients pick it up.
+    pub fn set_player_image(&self, player_image: PlayerImage) -> Result<(), String> {
+        let itsf_id = player_image.itsf_id;
+        if self.get_player(itsf_id).is_some_and(|player| player.anonymized) {
+            return Ok(());
+        }
+        let image_data = images::sanitize(&player_image.image_data)
+            .map_err(|err| format!("Image of player {}: {}", itsf_id, err))?;
+        let changed = self
+            .get_player_image(itsf_id)
+            .is_none_or(|old| old.image_data != image_data);
+        self.image_cache.lock().unwrap().remove(&itsf_id);
+        self.image_hashes.lock().unwrap().remove(&itsf_id);
+        let path = format!("{}/{}.jpg", self.image_directory, itsf_id);
+        std::fs::write(&path, image_data).unwrap_or_else(|_| panic!("Failed to write {}", path));
+        if changed {
+            self.modify_player(itsf_id, |_| {});
+        }
+        self.clear_refresh_error(itsf_id, RefreshTarget::Image);
+        Ok(())
+    }
+
+    /// Short hash of the player image's content, `None` if there is no image.
+    pub fn get_player_image_hash(&self, itsf_id: i32) -> Option<String> {
+        if let Some(hash) = self.image_hashes.lock().unwrap().get(&itsf_id) {
+            return hash.clone();
+        }
+        let hash = self.get_player_image(itsf_id).map(|image| {
+            let digest = Sha256::digest(&image.image_data);
+            digest[..8]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        });
+        self.image_hashes.lock().unwrap().insert(itsf_id, hash.clone());
+        hash
+    }
+
+    /// Replaces the in-memory image cache with the images of the given players, returns how many were found.
+    pub fn warm_player_images(&self, itsf_ids: &[i32]) -> usize {
+        let images: HashMap<i32, Vec<u8>> = itsf_ids
+            .iter()
+            .filter_map(|itsf_id| Some((*itsf_id, self.read_player_image(*itsf_id)?)))
+            .collect();
+        let count = images.len();
+        *self.image_cache.lock().unwrap() = images;
+        count
+    }
+
+    fn modify_player<F>(&self, itsf_id: i32, f: F)
+    where
+        F: FnOnce(&mut Player),
+    {
+        let mut inner = self.lock();
+
+        let revision = inner.next_player_revision();
+        let DatabaseInner { players, overrides, .. } = &mut *inner;
+        if let Some(player) = players.get_mut(&itsf_id) {
+            f(player);
+            if let Some(ledger) = overrides.get(&itsf_id) {
+                overrides::apply(ledger, player);
+            }
+            player.revision = revision;
+        }
+
+        if let Some(player) = inner.players.get(&itsf_id) {
+            inner
+                .db
+                .borrow_mut()
+                .write_player_json(itsf_id, &documents::PlayerDocument::new(player));
+        }
+        inner.notify_player_write(itsf_id);
+    }
+
+    pub fn add_player_itsf_ranking(&self, itsf_id: i32, ranking: itsf::Ranking) {
+        self.modify_player(itsf_id, |player| {
+            player.itsf_rankings.retain(|r| !ranking.matches(r));
+            player.itsf_rankings.push(ranking);
+        });
+    }
+
+    /// Replaces the national team appearances at the event with `appearances` by ITSF ID, leaving out
+    /// players that aren't stored. Returns the number of stored players that appeared.
+    pub fn set_national_team_appearances(
+        &self,
+        event_id: i32,
+        appearances: Vec<(i32, itsf::NationalTeamAppearance)>,
+    ) -> usize {
+        let mut by_player: HashMap<i32, Vec<itsf::NationalTeamAppearance>> = self.aggregate_players(|players| {
+            players
+                .filter(|player| {
+                    player
+                        .national_team_appearances
+                        .iter()
+                        .any(|appearance| appearance.event_id == event_id)
+                })
+                .map(|player| (player.itsf_id, Vec::new()))
+                .collect()
+        });
+        let mut appeared = HashSet::new();
+        for (itsf_id, appearance) in appearances {
+            if self.get_player(itsf_id).is_some() {
+                appeared.insert(itsf_id);
+                by_player.entry(itsf_id).or_default().push(appearance);
+            }
+        }
+        for (itsf_id, appearances) in by_player {
+            self.modify_player(itsf_id, |player| {
+                player
+                    .national_team_appearances
+                    .retain(|appearance| appearance.event_id != event_id);
+                player.national_team_appearances.extend(appearances);
+                player
+                    .national_team_appearances
+                    .sort_by_key(|appearance| appearance.year);
+            });
+        }
+        appeared.len()
+    }
+
+    pub fn set_player_dtfb_id(&self, itsf_id: i32, dtfb_id: i32) {
+        self.modify_player(itsf_id, |player| {
+            if !player.anonymized {
+                player.dtfb_id = Some(dtfb_id);
+            }
+        });
+    }
+
+    /// Moves the DTFB license to another player, e.g. after it was matched to the wrong ITSF license.
+    /// Returns the player it was linked to before.
+    pub fn relink_dtfb_id(&self, dtfb_id: i32, itsf_id: i32) -> Result<Option<i32>, String> {
+        let player = self.get_player(itsf_id).ok_or(format!("no player {}", itsf_id))?;
+        if player.anonymized {
+            return Err(format!("player {} is anonymized", itsf_id));
+        }
+        let previous = self.get_itsf_id_by_dtfb_id(dtfb_id);
+        if previous == Some(itsf_id) {
+            return Ok(previous);
+        }
+        if let Some(previous) = previous {
+            self.modify_player(previous, |player| player.dtfb_id = None);
+        }
+        self.set_player_dtfb_id(itsf_id, dtfb_id);
+        Ok(previous)
+    }
+
+    /// Records manual overrides of a player and applies them, keeping the replaced name and country in the
+    /// player's history like those of downloads. A `null` value revokes the previous override of the field,
+    /// restoring the downloaded value.
+    pub fn add_player_overrides(&self, itsf_id: i32, entries: Vec<overrides::FieldOverride>) -> Result<(), String> {
+        let player = self.get_player(itsf_id).ok_or(format!("no player {}", itsf_id))?;
+        if player.anonymized {
+            return Err(format!("player {} is anonymized", itsf_id));
+        }
+        for entry in &entries {
+            if entry.itsf_id != itsf_id {
+                return Err(format!(
+                    "override of player {} given for player {}",
+                    entry.itsf_id, itsf_id
+                ));
+            }
+            entry.validate(&player)?;
+        }
+        {
+            let mut inner = self.lock();
+            for entry in entries {
+                inner.db.borrow_mut().insert_player_override_json(itsf_id, &entry);
+                inner.overrides.entry(itsf_id).or_default().push(entry);
+            }
+        }
+        self.add_player(player);
+        Ok(())
+    }
+
+    /// The ledger of manual overrides of a player, oldest first.
+    pub fn get_player_overrides(&self, itsf_id: i32) -> Vec<overrides::FieldOverride> {
+        let inner = self.lock();
+        inner.overrides.get(&itsf_id).cloned().unwrap_or_default()
+    }
+
+    /// Corrects the name of a player by overriding both names.
+    pub fn fix_player_name(&self, itsf_id: i32, first_name: &str, last_name: &str, author: &str) -> Result<(), String> {
+        let entry = |field, value: &str| overrides::FieldOverride {
+            itsf_id,
+            field,
+            value: Some(serde_json::json!(value)),
+            author: String::from(author),
+            reason: String::from("repair fix-name"),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        self.add_player_overrides(
+            itsf_id,
+            vec![
+                entry(overrides::OverrideField::FirstName, first_name),
+                entry(overrides::OverrideField::LastName, last_name),
+            ],
+        )
+    }
+
+    /// Corrects the country of a player by overriding it.
+    pub fn set_player_country(&self, itsf_id: i32, country_code: &str, author: &str) -> Result<(), String> {
+        let entry = overrides::FieldOverride {
+            itsf_id,
+            field: overrides::OverrideField::CountryCode,
+            value: Some(serde_json::json!(country_code.trim().to_uppercase())),
+            author: String::from(author),
+            reason: String::from("repair set-country"),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        self.add_player_overrides(itsf_id, vec![entry])
+    }
+
+    /// Removes a wrongly attributed ITSF ranking from a player. The ranking's snapshots are kept.
+    pub fn delete_player_itsf_ranking(
+        &self,
+        itsf_id: i32,
+        year: i32,
+        category: itsf::RankingCategory,
+        class: itsf::RankingClass,
+    ) -> Result<itsf::Ranking, String> {
+        let player = self.get_player(itsf_id).ok_or(format!("no player {}", itsf_id))?;
+        let ranking = player
+            .itsf_rankings
+            .iter()
+            .find(|r| (r.year, r.category, r.class) == (year, category, class))
+            .cloned()
+            .ok_or(format!("player {} has no such ranking", itsf_id))?;
+        self.modify_player(itsf_id, |player| {
+            player.itsf_rankings.retain(|r| !ranking.matches(r));
+        });
+        Ok(ranking)
+    }
+
+    pub fn add_player_dtfb_championship_result(&self, itsf_id: i32, result: dtfb::NationalChampionshipResult) {
+        self.modify_player(itsf_id, |player| {
+            player.dtfb_championship_results.retain(|r| !result.matches(r));
+            player.dtfb_championship_results.push(result);
+        });
+    }
+
+    pub fn add_player_dtfb_ranking(&self, itsf_id: i32, ranking: dtfb::NationalRanking) {
+        self.modify_player(itsf_id, |player| {
+            player.dtfb_national_rankings.retain(|r| !ranking.matches(r));
+            player.dtfb_national_rankings.push(ranking);
+        });
+    }
+
+    pub fn add_player_dtfb_team(&self, itsf_id: i32, team: dtfb::NationalTeam) {
+        self.modify_player(itsf_id, |player| {
+            player.dtfb_league_teams.retain(|t| t.year != team.year);
+            player.dtfb_league_teams.push(team);
+        });
+    }
+
+    /// Applies `f` to the comments and tags of the player, the shared ones stored with the player
+    /// without workspace, those of the workspace otherwise.
+    fn modify_notes<F>(&self, workspace: Option<&str>, itsf_id: i32, f: F)
+    where
+        F: FnOnce(&mut Vec<PlayerComment>, &mut Vec<String>),
+    {
+        let workspace = match workspace {
+            Some(workspace) => workspace,
+            None => return self.modify_player(itsf_id, |player| f(&mut player.comments, &mut player.tags)),
+        };
+        let mut inner = self.lock();
+        if !inner.players.contains_key(&itsf_id) {
+            return;
+        }
+        let notes = inner
+            .workspace_notes
+            .entry((String::from(workspace), itsf_id))
+            .or_insert_with(|| workspaces::PlayerNotes::new(workspace, itsf_id));
+        f(&mut notes.comments, &mut notes.tags);
+        let notes = notes.clone();
+        inner.db.borrow_mut().write_workspace_notes_json(&notes.key(), &notes);
+    }
+
+    /// The player with the comments and tags of the workspace instead of the shared ones, unchanged without workspace.
+    pub fn with_workspace_notes(&self, workspace: Option<&str>, mut player: Player) -> Player {
+        if let Some(workspace) = workspace {
+            let inner = self.lock();
+            let notes = inner
+                .workspace_notes
+                .get(&(String::from(workspace), player.itsf_id))
+                .cloned();
+            notes
+                .unwrap_or_else(|| workspaces::PlayerNotes::new(workspace, player.itsf_id))
+                .apply(&mut player);
+        }
+        player
+    }
+
+    /// All notes of the workspace by player.
+    pub fn get_workspace_notes(&self, workspace: &str) -> HashMap<i32, workspaces::PlayerNotes> {
+        let inner = self.lock();
+        inner
+            .workspace_notes
+            .values()
+            .filter(|notes| notes.workspace == workspace)
+            .map(|notes| (notes.itsf_id, notes.clone()))
+            .collect()
+    }
+
+    pub fn add_player_comment(
+        &self,
+        workspace: Option<&str>,
+        itsf_id: i32,
+        text: String,
+        visibility: CommentVisibility,
+        status: Option<CommentStatus>,
+    ) {
+        self.modify_notes(workspace, itsf_id, |comments, _| {
+            let timestamp = chrono::Utc::now().naive_local().timestamp() as u32;
+            comments.push(PlayerComment {
+                timestamp,
+                text,
+                visibility,
+                author: None,
+                status,
+                resolved_by: None,
+                resolved_at: None,
+                thumbs_up: Vec::new(),
+            });
+            comments.sort_by_key(|c| c.timestamp);
+        });
+    }
+
+    /// Adds a comment keeping its original time and author, e.g. from a spreadsheet.
+    pub fn import_player_comment(&self, workspace: Option<&str>, itsf_id: i32, comment: PlayerComment) {
+        self.modify_notes(workspace, itsf_id, |comments, _| {
+            comments.push(comment);
+            comments.sort_by_key(|c| c.timestamp);
+        });
+    }
+
+    /// Changes the comment identified by its time and text, like imports tell duplicates apart.
+    pub fn update_player_comment<F>(
+        &self,
+        workspace: Option<&str>,
+        itsf_id: i32,
+        timestamp: u32,
+        text: &str,
+        f: F,
+    ) -> Result<PlayerComment, String>
+    where
+        F: FnOnce(&mut PlayerComment),
+    {
+        let is_comment = |comment: &PlayerComment| comment.timestamp == timestamp && comment.text == text;
+        let player = self.get_player(itsf_id).ok_or("No such player")?;
+        let player = self.with_workspace_notes(workspace, player);
+        if !player.comments.iter().any(is_comment) {
+            return Err(String::from("No such comment"));
+        }
+        let mut updated = None;
+        self.modify_notes(workspace, itsf_id, |comments, _| {
+            if let Some(comment) = comments.iter_mut().find(|comment| is_comment(comment)) {
+                f(comment);
+                updated = Some(comment.clone());
+            }
+        });
+        updated.ok_or(String::from("No such comment"))
+    }
+
+    pub fn add_player_tag(&self, workspace: Option<&str>, itsf_id: i32, tag: String) {
+        self.modify_notes(workspace, itsf_id, |_, tags| {
+            if let Err(pos) = tags.binary_search(&tag) {
+                tags.insert(pos, tag);
+            }
+        });
+    }
+
+    /// Adds the tag to or removes it from every given player, returns the number of players that changed.
+    pub fn set_players_tag(&self, workspace: Option<&str>, itsf_ids: &[i32], tag: &str, add: bool) -> usize {
+        let mut changed = 0;
+        for itsf_id in itsf_ids {
+            self.modify_notes(workspace, *itsf_id, |_, tags| {
+                match (tags.binary_search_by(|t| t.as_str().cmp(tag)), add) {
+                    (Err(pos), true) => {
+                        tags.insert(pos, String::from(tag));
+                        changed += 1;
+                    }
+                    (Ok(pos), false) => {
+                        tags.remove(pos);
+                        changed += 1;
+                    }
+                    _ => {}
+                }
+            });
+        }
+        changed
+    }
+
+    /// Irreversibly removes the player's name, image, comments and tags, including those of all workspaces.
+    pub fn anonymize_player(&self, itsf_id: i32) {
+        self.modify_player(itsf_id, Player::strip_personal_data);
+        {
+            let mut inner = self.lock();
+            if inner.overrides.remove(&itsf_id).is_some() {
+                inner.db.borrow_mut().delete_player_overrides(itsf_id);
+            }
+            let keys: Vec<(String, i32)> = inner
+                .workspace_notes
+                .keys()
+                .filter(|(_, id)| *id == itsf_id)
+                .cloned()
+                .collect();
+            for key in keys {
+                if let Some(notes) = inner.workspace_notes.remove(&key) {
+                    inner.db.borrow_mut().delete_workspace_notes(&notes.key());
+                }
+            }
+        }
+        self.delete_player_image(itsf_id);
+    }
+
+    pub fn delete_player_image(&self, itsf_id: i32) {
+        self.image_cache.lock().unwrap().remove(&itsf_id);
+        self.image_hashes.lock().unwrap().remove(&itsf_id);
+        let path = format!("{}/{}.jpg", self.image_directory, itsf_id);
+        if let Err(err) = std::fs::remove_file(&path) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                panic!("Failed to delete {}: {}", path, err);
+            }
+        }
+    }
+
+    /// Deletes images that don't belong to a stored player, or belong to an anonymized one, e.g. after a
+    /// player was removed from the database or an anonymization failed halfway. Other files are kept.
+    pub fn collect_image_garbage(&self, dry_run: bool) -> Result<images::GcReport, String> {
+        let mut report = images::GcReport {
+            dry_run,
+            scanned: 0,
+            orphaned: Vec::new(),
+            reclaimed_bytes: 0,
+        };
+        let dir = std::fs::read_dir(&self.image_directory).map_err(|err| err.to_string())?;
+        for entry in dir {
+            let entry = entry.map_err(|err| err.to_string())?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let itsf_id = match images::itsf_id_of(&file_name) {
+                Some(itsf_id) => itsf_id,
+                None => continue,
+            };
+            report.scanned += 1;
+            if self.get_player(itsf_id).is_some_and(|player| !player.anonymized) {
+                continue;
+            }
+            let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+            if !dry_run {
+                self.image_cache.lock().unwrap().remove(&itsf_id);
+                self.image_hashes.lock().unwrap().remove(&itsf_id);
+                std::fs::remove_file(entry.path()).map_err(|err| format!("Failed to delete {}: {}", file_name, err))?;
+            }
+            report.orphaned.push(file_name);
+            report.reclaimed_bytes += size;
+        }
+        report.orphaned.sort();
+        Ok(report)
+    }
+
+    pub fn set_player_hidden(&self, itsf_id: i32, hidden: bool) {
+        self.modify_player(itsf_id, |player| {
+            player.hidden = hidden;
+        });
+    }
+
+    pub fn remove_player_tag(&self, workspace: Option<&str>, itsf_id: i32, tag: &str) {
+        self.modify_notes(workspace, itsf_id, |_, tags| {
+            tags.retain(|t| t != tag);
+        });
+    }
+
+    /// All tags in use, shared or in the workspace, with the number of players carrying them.
+    pub fn get_tags(&self, workspace: Option<&str>) -> Vec<(String, usize)> {
+        let inner = self.lock();
+        let player_tags: Vec<&Vec<String>> = match workspace {
+            Some(workspace) => inner
+                .workspace_notes
+                .values()
+                .filter(|notes| notes.workspace == workspace)
+                .map(|notes| &notes.tags)
+                .collect(),
+            None => inner.players.values().map(|player| &player.tags).collect(),
+        };
+        let mut tags: HashMap<&str, usize> = HashMap::new();
+        for tag in player_tags.into_iter().flatten() {
+            *tags.entry(tag).or_default() += 1;
+        }
+        let mut tags: Vec<(String, usize)> = tags.into_iter().map(|(tag, count)| (tag.to_string(), count)).collect();
+        tags.sort();
+        tags
+    }
+
+    pub fn get_lists(&self) -> Vec<lists::PlayerList> {
+        let inner = self.lock();
+        let mut lists: Vec<lists::PlayerList> = inner.lists.values().cloned().collect();
+        lists.sort_by_key(|list| list.list_id);
+        lists
+    }
+
+    pub fn get_list(&self, list_id: i32) -> Option<lists::PlayerList> {
+        let inner = self.lock();
+        inner.lists.get(&list_id).cloned()
+    }
+
+    pub fn create_list(
+        &self,
+        workspace: Option<&str>,
+        name: String,
+        description: String,
+        players: &[i32],
+    ) -> lists::PlayerList {
+        let mut inner = self.lock();
+        let list_id = inner
+            .lists
+            .keys()
+            .chain(inner.deleted_lists.keys())
+            .max()
+            .copied()
+            .unwrap_or(0)
+            + 1;
+        let mut list = lists::PlayerList {
+            list_id,
+            name,
+            description,
+            players: Vec::new(),
+            created: chrono::Utc::now().naive_local().timestamp() as u32,
+            workspace: workspace.map(String::from),
+        };
+        list.add_players(players);
+        inner.db.borrow_mut().write_list_json(list_id, &list);
+        inner.lists.insert(list_id, list.clone());
+        list
+    }
+
+    /// Applies `f` to the list and persists it, returns `None` if there is no such list.
+    pub fn modify_list<F>(&self, list_id: i32, f: F) -> Option<lists::PlayerList>
+    where
+        F: FnOnce(&mut lists::PlayerList),
+    {
+        let mut inner = self.lock();
+        let list = inner.lists.get_mut(&list_id)?;
+        f(list);
+        let list = list.clone();
+        inner.db.borrow_mut().write_list_json(list_id, &list);
+        Some(list)
+    }
+
+    /// Removes the list from all queries, but keeps it stored until the deletion is finalized, so it can
+    /// still be restored. Lists whose deletion wasn't staged with `stage_action` come back after a restart.
+    pub fn delete_list(&self, list_id: i32) -> bool {
+        let mut inner = self.lock();
+        match inner.lists.remove(&list_id) {
+            Some(list) => {
+                inner.deleted_lists.insert(list_id, list);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds the players of `list_id` to `into_list_id` and deletes `list_id` like `delete_list`. Returns the
+    /// players that weren't in `into_list_id` yet, or `None` if either list doesn't exist.
+    pub fn merge_list(&self, list_id: i32, into_list_id: i32) -> Option<Vec<i32>> {
+        let mut inner = self.lock();
+        if list_id == into_list_id || !inner.lists.contains_key(&into_list_id) {
+            return None;
+        }
+        let list = inner.lists.remove(&list_id)?;
+        let into = inner.lists.get_mut(&into_list_id)?;
+        let added_players: Vec<i32> = list
+            .players
+            .iter()
+            .copied()
+            .filter(|itsf_id| !into.players.contains(itsf_id))
+            .collect();
+        into.add_players(&added_players);
+        let into = into.clone();
+        inner.db.borrow_mut().write_list_json(into_list_id, &into);
+        inner.deleted_lists.insert(list_id, list);
+        Some(added_players)
+    }
+
+    /// Records an action that was carried out, e.g. by `delete_list`, so it can be undone until `finalize_at`.
+    pub fn stage_action(
+        &self,
+        action: staged::StagedAction,
+        user_id: String,
+        finalize_at: i64,
+    ) -> staged::PendingAction {
+        let mut inner = self.lock();
+        let pending = staged::PendingAction {
+            action_id: inner.next_action_id,
+            action,
+            user_id,
+            staged_at: chrono::Utc::now().timestamp(),
+            finalize_at,
+        };
+        inner.next_action_id += 1;
+        inner
+            .db
+            .borrow_mut()
+            .write_staged_action_json(pending.action_id as i64, &pending);
+        inner.staged_actions.push(pending.clone());
+        pending
+    }
+
+    pub fn get_staged_actions(&self) -> Vec<staged::PendingAction> {
+        let inner = self.lock();
+        inner.staged_actions.clone()
+    }
+
+    /// Reverts a staged action, returns `None` if it doesn't exist or was already finalized.
+    pub fn undo_staged_action(&self, action_id: u64) -> Option<staged::PendingAction> {
+        let mut inner = self.lock();
+        let index = inner
+            .staged_actions
+            .iter()
+            .position(|pending| pending.action_id == action_id)?;
+        let pending = inner.staged_actions.remove(index);
+        let list_id = pending.action.deleted_list();
+        if let Some(list) = inner.deleted_lists.remove(&list_id) {
+            inner.lists.insert(list_id, list);
+        }
+        if let staged::StagedAction::MergeList {
+            into_list_id,
+            added_players,
+            ..
+        } = &pending.action
+        {
+            if let Some(into) = inner.lists.get_mut(into_list_id) {
+                into.remove_players(added_players);
+                let into = into.clone();
+                inner.db.borrow_mut().write_list_json(*into_list_id, &into);
+            }
+        }
+        inner.db.borrow_mut().delete_staged_action(action_id as i64);
+        Some(pending)
+    }
+
+    /// Makes the staged actions whose undo window ended by `now` permanent and returns them.
+    pub fn finalize_staged_actions(&self, now: i64) -> Vec<staged::PendingAction> {
+        let mut inner = self.lock();
+        let (expired, pending): (Vec<staged::PendingAction>, Vec<staged::PendingAction>) =
+            std::mem::take(&mut inner.staged_actions)
+                .into_iter()
+                .partition(|pending| pending.finalize_at <= now);
+        inner.staged_actions = pending;
+        for pending in &expired {
+            let list_id = pending.action.deleted_list();
+            if inner.deleted_lists.remove(&list_id).is_some() {
+                inner.db.borrow_mut().delete_list(list_id);
+            }
+            inner.db.borrow_mut().delete_staged_action(pending.action_id as i64);
+        }
+        expired
+    }
+
+    pub fn get_subscriptions(&self) -> Vec<subscriptions::Subscription> {
+        let inner = self.lock();
+        inner.subscriptions.values().cloned().collect()
+    }
+
+    pub fn get_subscription(&self, user_id: &str) -> subscriptions::Subscription {
+        let inner = self.lock();
+        match inner.subscriptions.get(user_id) {
+            Some(subscription) => subscription.clone(),
+            None => subscriptions::Subscription {
+                user_id: String::from(user_id),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn modify_subscription<F>(&self, user_id: &str, f: F) -> subscriptions::Subscription
+    where
+        F: FnOnce(&mut subscriptions::Subscription),
+    {
+        let mut inner = self.lock();
+        let subscription =
+            inner
+                .subscriptions
+                .entry(String::from(user_id))
+                .or_insert_with(|| subscriptions::Subscription {
+                    user_id: String::from(user_id),
+                    ..Default::default()
+                });
+        f(subscription);
+        let subscription = subscription.clone();
+        inner.db.borrow_mut().write_subscription_json(user_id, &subscription);
+        subscription
+    }
+
+    pub fn get_league_tables(&self, season: season::Season) -> Option<Vec<leagues::LeagueTable>> {
+        let inner = self.lock();
+        inner.leagues.get(&season.year()).cloned()
+    }
+
+    /// Replaces all league tables of the season.
+    pub fn set_league_tables(&self, season: season::Season, tables: Vec<leagues::LeagueTable>) {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().write_league_json(season.year(), &tables);
+        inner.leagues.insert(season.year(), tables);
+    }
+
+    pub fn get_dtfb_downloads(&self) -> Vec<dtfb::SeasonDownload> {
+        let inner = self.lock();
+        inner.dtfb_downloads.values().cloned().collect()
+    }
+
+    pub fn record_dtfb_download(&self, download: dtfb::SeasonDownload) {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().write_dtfb_download_json(download.year, &download);
+        inner.dtfb_downloads.insert(download.year, download);
+    }
+
+    pub fn get_ranking_downloads(&self) -> Vec<itsf::RankingDownload> {
+        let inner = self.lock();
+        inner.ranking_downloads.values().cloned().collect()
+    }
+
+    pub fn get_ranking_download(
+        &self,
+        year: i32,
+        category: itsf::RankingCategory,
+        class: itsf::RankingClass,
+    ) -> Option<itsf::RankingDownload> {
+        let inner = self.lock();
+        inner
+            .ranking_downloads
+            .get(&itsf::RankingDownload::key_of(year, category, class))
+            .cloned()
+    }
+
+    pub fn record_ranking_download(&self, download: itsf::RankingDownload) {
+        let mut inner = self.lock();
+        let key = download.key();
+        inner.db.borrow_mut().write_ranking_download_json(&key, &download);
+        inner.ranking_downloads.insert(key, download);
+    }
+
+    /// Closes the ranking at its latest snapshot, `None` if it wasn't downloaded yet. Closing it
+    /// again records the checksum of the now latest snapshot.
+    pub fn close_ranking(
+        &self,
+        year: i32,
+        category: itsf::RankingCategory,
+        class: itsf::RankingClass,
+        closed_by: &str,
+    ) -> Option<itsf::RankingClosure> {
+        let mut inner = self.lock();
+        let key = itsf::RankingDownload::key_of(year, category, class);
+        // the checksum guards the ranking against overwrites, so it must be the latest snapshot
+        let (scraped_at, placements) = inner.db.borrow_mut().read_ranking_placements(&key)?;
+        let mut download = inner.ranking_downloads.get(&key)?.clone();
+        let closure = itsf::RankingClosure {
+            closed_at: chrono::Utc::now().timestamp(),
+            closed_by: String::from(closed_by),
+            scraped_at,
+            checksum: snapshots::checksum(&placements),
+        };
+        download.closed = Some(closure.clone());
+        inner.db.borrow_mut().write_ranking_download_json(&key, &download);
+        inner.ranking_downloads.insert(key, download);
+        Some(closure)
+    }
+
+    /// The closure of the ranking and whether its snapshot still matches the recorded checksum,
+    /// `None` if the ranking isn't closed.
+    pub fn verify_ranking_closure(
+        &self,
+        year: i32,
+        category: itsf::RankingCategory,
+        class: itsf::RankingClass,
+    ) -> Option<(itsf::RankingClosure, bool)> {
+        let closure = self.get_ranking_download(year, category, class)?.closed?;
+        let intact = self
+            .get_ranking_snapshot(year, category, class, closure.scraped_at)
+            .is_some_and(|snapshot| snapshots::checksum(&snapshot.placements) == closure.checksum);
+        Some((closure, intact))
+    }
+
+    pub fn get_feature_flag(&self, feature: &str) -> Option<bool> {
+        let inner = self.lock();
+        inner.feature_flags.get(feature).copied()
+    }
+
+    pub fn set_feature_flag(&self, feature: &str, enabled: bool) {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().write_feature_flag_json(feature, &enabled);
+        inner.feature_flags.insert(String::from(feature), enabled);
+    }
+
+    /// All download presets by name, sorted by name.
+    pub fn get_download_presets(&self) -> Vec<(String, presets::DownloadPreset)> {
+        let inner = self.lock();
+        let mut presets: Vec<(String, presets::DownloadPreset)> = inner
+            .download_presets
+            .iter()
+            .map(|(name, preset)| (name.clone(), preset.clone()))
+            .collect();
+        presets.sort_by(|a, b| a.0.cmp(&b.0));
+        presets
+    }
+
+    pub fn get_download_preset(&self, name: &str) -> Option<presets::DownloadPreset> {
+        let inner = self.lock();
+        inner.download_presets.get(name).cloned()
+    }
+
+    pub fn set_download_preset(&self, name: &str, preset: presets::DownloadPreset) {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().write_download_preset_json(name, &preset);
+        inner.download_presets.insert(String::from(name), preset);
+    }
+
+    /// Returns whether the preset existed.
+    pub fn delete_download_preset(&self, name: &str) -> bool {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().delete_download_preset(name);
+        inner.download_presets.remove(name).is_some()
+    }
+
+    /// All clubs by name, sorted by name.
+    pub fn get_clubs(&self) -> Vec<(String, clubs::Club)> {
+        let inner = self.lock();
+        let mut clubs: Vec<(String, clubs::Club)> = inner
+            .clubs
+            .iter()
+            .map(|(name, club)| (name.clone(), club.clone()))
+            .collect();
+        clubs.sort_by(|a, b| a.0.cmp(&b.0));
+        clubs
+    }
+
+    pub fn get_club(&self, name: &str) -> Option<clubs::Club> {
+        let inner = self.lock();
+        inner.clubs.get(name).cloned()
+    }
+
+    pub fn set_club(&self, name: &str, club: clubs::Club) {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().write_club_json(name, &club);
+        inner.clubs.insert(String::from(name), club);
+    }
+
+    /// Returns whether the club existed.
+    pub fn delete_club(&self, name: &str) -> bool {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().delete_club(name);
+        inner.clubs.remove(name).is_some()
+    }
+
+    /// Adds the counts to the stored ones of the parser and day.
+    pub fn add_parse_quality(&self, parser: quality::Parser, day: i64, counts: &quality::ParseCounts) {
+        let inner = self.lock();
+        let key = quality::ParseQuality::key(parser, day);
+        let mut db = inner.db.borrow_mut();
+        let mut stored = db
+            .read_parse_quality_json::<quality::ParseQuality>(&key)
+            .unwrap_or(quality::ParseQuality {
+                parser,
+                day,
+                counts: quality::ParseCounts::default(),
+            });
+        stored.counts.add(counts);
+        db.write_parse_quality_json(&key, day, &stored);
+    }
+
+    /// Parse quality of all parsers since the day starting at `day`, oldest first.
+    pub fn get_parse_quality_since(&self, day: i64) -> Vec<quality::ParseQuality> {
+        let inner = self.lock();
+        let quality = inner.reader().borrow_mut().read_parse_quality_since_json(day);
+        quality
+    }
+
+    /// Events ending on or after `from`, ordered by start date.
+    pub fn get_events_from(&self, from: chrono::NaiveDate) -> Vec<events::Event> {
+        let inner = self.lock();
+        let mut events: Vec<events::Event> = inner
+            .events
+            .values()
+            .filter(|event| event.end_date >= from)
+            .cloned()
+            .collect();
+        events.sort_by_key(|event| (event.start_date, event.event_id));
+        events
+    }
+
+    /// Events overlapping the range, ordered by start date; open ends aren't limited.
+    pub fn get_events_between(
+        &self,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+    ) -> Vec<events::Event> {
+        let inner = self.lock();
+        let mut events: Vec<events::Event> = inner
+            .events
+            .values()
+            .filter(|event| from.is_none_or(|from| event.end_date >= from))
+            .filter(|event| to.is_none_or(|to| event.start_date <= to))
+            .cloned()
+            .collect();
+        events.sort_by_key(|event| (event.start_date, event.event_id));
+        events
+    }
+
+    /// Replaces the events starting in the year with a fresh download of its calendar, returns the
+    /// number of events that were removed from it.
+    pub fn replace_events_of_year(&self, year: i32, events: Vec<events::Event>) -> usize {
+        use chrono::Datelike;
+        let mut inner = self.lock();
+        let removed: Vec<i32> = inner
+            .events
+            .values()
+            .filter(|event| event.start_date.year() == year)
+            .filter(|event| !events.iter().any(|new| new.event_id == event.event_id))
+            .map(|event| event.event_id)
+            .collect();
+        for event_id in &removed {
+            inner.events.remove(event_id);
+            inner.db.borrow_mut().delete_event(*event_id);
+        }
+        for mut event in events {
+            // coordinates stay valid as long as the location doesn't change
+            if let Some(old) = inner.events.get(&event.event_id) {
+                if (&old.location, &old.country_code) == (&event.location, &event.country_code) {
+                    event.coordinates = old.coordinates;
+                }
+            }
+            inner.db.borrow_mut().write_event_json(event.event_id, &event);
+            inner.events.insert(event.event_id, event);
+        }
+        removed.len()
+    }
+
+    pub fn get_event(&self, event_id: i32) -> Option<events::Event> {
+        let inner = self.lock();
+        inner.events.get(&event_id).cloned()
+    }
+
+    pub fn set_event(&self, event: events::Event) {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().write_event_json(event.event_id, &event);
+        inner.events.insert(event.event_id, event);
+    }
+
+    /// The stored result of geocoding the place, `None` if it wasn't geocoded yet.
+    pub fn get_geocode(&self, place: &str) -> Option<Option<geocodes::Coordinates>> {
+        let inner = self.lock();
+        inner.geocodes.get(place).copied()
+    }
+
+    pub fn set_geocode(&self, place: &str, coordinates: Option<geocodes::Coordinates>) {
+        let mut inner = self.lock();
+        inner.db.borrow_mut().write_geocode_json(place, &coordinates);
+        inner.geocodes.insert(String::from(place), coordinates);
+    }
+
+    /// Events that ended before `date`.
+    pub fn get_events_before(&self, date: chrono::NaiveDate) -> Vec<events::Event> {
+        let inner = self.lock();
+        inner
+            .events
+            .values()
+            .filter(|event| event.end_date < date)
+            .cloned()
+            .collect()
+    }
+
+    pub fn delete_events(&self, event_ids: &[i32]) {
+        let mut inner = self.lock();
+        for event_id in event_ids {
+            if inner.events.remove(event_id).is_some() {
+                inner.db.borrow_mut().delete_event(*event_id);
+            }
+        }
+    }
+
+    pub fn count_job_locks_expired_before(&self, timestamp: i64) -> usize {
+        let inner = self.lock();
+        let count = inner.db.borrow_mut().count_job_locks_expired_before(timestamp);
+        count
+    }
+
+    /// Deletes locks, and with them the logs, of jobs that didn't release their lock before it expired.
+    pub fn prune_job_locks(&self, timestamp: i64) -> usize {
+        let inner = self.lock();
+        let deleted = inner.db.borrow_mut().delete_job_locks_expired_before(timestamp);
+        deleted
+    }
+
+    pub fn try_acquire_job_lock(&self, name: &str, token: &str, ttl: i64) -> bool {
+        let inner = self.lock();
+        let now = chrono::Utc::now().timestamp();
+        let acquired = inner.db.borrow_mut().try_acquire_job_lock(name, token, now, now + ttl);
+        acquired
+    }
+
+    pub fn refresh_job_lock(&self, name: &str, token: &str, ttl: i64, log: &[String]) {
+        let inner = self.lock();
+        let expires_at = chrono::Utc::now().timestamp() + ttl;
+        inner.db.borrow_mut().refresh_job_lock(name, token, expires_at, &log);
+    }
+
+    pub fn release_job_lock(&self, name: &str, token: &str) {
+        let inner = self.lock();
+        inner.db.borrow_mut().release_job_lock(name, token);
+    }
+
+    pub fn get_job_lock_log(&self, name: &str) -> Option<Vec<String>> {
+        let inner = self.lock();
+        let now = chrono::Utc::now().timestamp();
+        let log = inner.reader().borrow_mut().read_job_lock_log(name, now);
+        log
+    }
+
+    /// Stores the placements of a ranking download as its latest snapshot, archiving the previous one.
+    pub fn record_ranking_snapshot(&self, snapshot: &snapshots::RankingSnapshot) {
+        let key = itsf::RankingDownload::key_of(snapshot.year, snapshot.category, snapshot.class);
+        let inner = self.lock();
+        inner
+            .db
+            .borrow_mut()
+            .replace_ranking_placements(&key, snapshot.scraped_at, &snapshot.placements);
+    }
+
+    /// All snapshots of the ranking, newest first.
+    pub fn get_ranking_snapshots(
+        &self,
+        year: i32,
+        category: itsf::RankingCategory,
+        class: itsf::RankingClass,
+    ) -> Vec<snapshots::SnapshotInfo> {
+        let key = itsf::RankingDownload::key_of(year, category, class);
+        let inner = self.lock();
+        let mut reader = inner.reader().borrow_mut();
+        let latest = reader
+            .read_ranking_placements(&key)
+            .map(|(scraped_at, placements)| snapshots::SnapshotInfo {
+                scraped_at,
+                entries: placements.len(),
+                archived: false,
+            });
+        let archived = reader
+            .read_ranking_snapshot_infos(&key)
+            .into_iter()
+            .map(|(scraped_at, entries)| snapshots::SnapshotInfo {
+                scraped_at,
+                entries,
+                archived: true,
+            });
+        latest.into_iter().chain(archived).collect()
+    }
+
+    /// The snapshot of the ranking downloaded at `scraped_at`, wherever it is stored.
+    pub fn get_ranking_snapshot(
+        &self,
+        year: i32,
+        category: itsf::RankingCategory,
+        class: itsf::RankingClass,
+        scraped_at: i64,
+    ) -> Option<snapshots::RankingSnapshot> {
+        let key = itsf::RankingDownload::key_of(year, category, class);
+        let inner = self.lock();
+        let mut reader = inner.reader().borrow_mut();
+        let placements = match reader.read_ranking_placements(&key) {
+            Some((latest, placements)) if latest == scraped_at => placements,
+            _ => match reader.read_archived_ranking_snapshot(&key, scraped_at) {
+                Ok(placements) => placements,
+                Err(err) => {
+                    log::debug!("{}", err);
+                    return None;
+                }
+            },
+        };
+        Some(snapshots::RankingSnapshot {
+            year,
+            category,
+            class,
+            scraped_at,
+            placements,
+        })
+    }
+
+    pub fn add_job_run(&self, run: &jobs::JobRun) {
+        let inner = self.lock();
+        inner.db.borrow_mut().insert_job_run(run.started_at, run);
+    }
+
+    /// The latest runs of background jobs, newest first.
+    pub fn get_job_runs(&self, limit: usize) -> Vec<jobs::JobRun> {
+        let inner = self.lock();
+        let runs = inner.reader().borrow_mut().read_job_runs_json(limit);
+        runs
+    }
+
+    pub fn count_job_runs_before(&self, timestamp: i64) -> usize {
+        let inner = self.lock();
+        let count = inner.db.borrow_mut().count_job_runs_before(timestamp);
+        count
+    }
+
+    pub fn prune_job_runs(&self, timestamp: i64) -> usize {
+        let inner = self.lock();
+        let deleted = inner.db.borrow_mut().delete_job_runs_before(timestamp);
+        deleted
+    }
+
+    /// Row counts of the tables, largest first.
+    pub fn get_table_sizes(&self) -> Vec<(String, usize)> {
+        let inner = self.lock();
+        let mut tables = inner.db.borrow_mut().count_table_rows();
+        tables.sort_by_key(|(name, rows)| (std::cmp::Reverse(*rows), name.clone()));
+        tables
+    }
+
+    pub fn analyze_table(&self, table: &str) {
+        let inner = self.lock();
+        inner.db.borrow_mut().analyze(table);
+    }
+
+    pub fn vacuum(&self) {
+        let inner = self.lock();
+        inner.db.borrow_mut().vacuum();
+    }
+
+    pub fn add_request_samples(&self, samples: &[samples::RequestSample]) {
+        let inner = self.lock();
+        inner.db.borrow_mut().insert_request_samples(samples);
+    }
+
+    pub fn count_request_samples_before(&self, timestamp: i64) -> usize {
+        let inner = self.lock();
+        let count = inner.db.borrow_mut().count_request_samples_before(timestamp);
+        count
+    }
+
+    /// Deletes samples taken before `timestamp`, returning how many were deleted.
+    pub fn prune_request_samples(&self, timestamp: i64) -> usize {
+        let inner = self.lock();
+        let deleted = inner.db.borrow_mut().delete_request_samples_before(timestamp);
+        deleted
+    }
+
+    pub fn get_request_samples(&self, since: i64) -> Vec<samples::RequestSample> {
+        let inner = self.lock();
+        let samples = inner.reader().borrow_mut().read_request_samples_since(since);
+        samples
+    }
+
+    /// The database file and the images `image_access` allows to logged-in users.
+    pub fn create_zip_file(&self, image_access: images::ImageAccess) -> Result<Vec<u8>, String> {
+        let mut buffer = Vec::new();
+        {
+            let mut zip = ZipWriter::new(Cursor::new(&mut buffer));
+            add_zip_file(&mut zip, CompressionMethod::Deflated, &self.database_path)?;
+
+            let options = zip::write::FileOptions::default().compression_method(CompressionMethod::Stored);
+            zip.add_directory("images", options).map_err(|err| err.to_string())?;
+
+            let dir = std::fs::read_dir(&self.image_directory).map_err(|err| err.to_string())?;
+            for file in dir {
+                let file = file.map_err(|err| err.to_string())?.path();
+                let hidden = file
+                    .file_stem()
+                    .and_then(|stem| stem.to_str()?.parse::<i32>().ok())
+                    .and_then(|itsf_id| self.get_player(itsf_id))
+                    .is_some_and(|player| player.hidden);
+                if !image_access.allows(true, hidden) {
+                    continue;
+                }
+                let file = file.to_str().ok_or_else(|| format!("invalid file name: {:?}", file))?;
+                add_zip_file(&mut zip, CompressionMethod::Deflated, file)?;
+            }
+        }
+
+        Ok(buffer)
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use super::{itsf::PlayerCategory, Player};
+
+/// A downloaded profile field that can be corrected by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideField {
+    FirstName,
+    LastName,
+    BirthYear,
+    CountryCode,
+    Category,
+}
+
+impl OverrideField {
+    pub const ALL: [Self; 5] = [
+        Self::FirstName,
+        Self::LastName,
+        Self::BirthYear,
+        Self::CountryCode,
+        Self::Category,
+    ];
+
+    fn get(self, player: &Player) -> serde_json::Value {
+        match self {
+            Self::FirstName => serde_json::json!(player.first_name),
+            Self::LastName => serde_json::json!(player.last_name),
+            Self::BirthYear => serde_json::json!(player.birth_year),
+            Self::CountryCode => serde_json::json!(player.country_code),
+            Self::Category => serde_json::json!(player.category),
+        }
+    }
+
+    /// Sets the field, failing if the value doesn't fit it.
+    fn set(self, player: &mut Player, value: &serde_json::Value) -> Result<(), String> {
+        let invalid = |err: serde_json::Error| format!("invalid value for {:?}: {}", self, err);
+        match self {
+            Self::FirstName | Self::LastName => {
+                let name: String = serde_json::from_value(value.clone()).map_err(invalid)?;
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err(String::from("names must not be empty"));
+                }
+                match self {
+                    Self::FirstName => player.first_name = String::from(name),
+                    _ => player.last_name = String::from(name),
+                }
+            }
+            Self::BirthYear => player.birth_year = serde_json::from_value(value.clone()).map_err(invalid)?,
+            Self::CountryCode => {
+                let country_code: Option<String> = serde_json::from_value(value.clone()).map_err(invalid)?;
+                if let Some(country_code) = &country_code {
+                    if country_code.len() != 3 || !country_code.chars().all(|c| c.is_ascii_uppercase()) {
+                        return Err(format!("invalid country code: '{}'", country_code));
+                    }
+                }
+                player.country_code = country_code;
+            }
+            Self::Category => {
+                player.category = serde_json::from_value::<PlayerCategory>(value.clone()).map_err(invalid)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An entry of the ledger of manual corrections. Entries are never changed, a later one of the same player and
+/// field replaces the earlier one.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FieldOverride {
+    pub itsf_id: i32,
+    pub field: OverrideField,
+    /// `null` revokes the previous override, so downloads set the field again.
+    pub value: Option<serde_json::Value>,
+    pub author: String,
+    pub reason: String,
+    /// Unix timestamp.
+    pub timestamp: i64,
+}
+
+impl FieldOverride {
+    /// Checks that the value fits the field.
+    pub fn validate(&self, player: &Player) -> Result<(), String> {
+        if self.reason.trim().is_empty() {
+            return Err(String::from("a reason is required"));
+        }
+        match &self.value {
+            Some(value) => self.field.set(&mut player.clone(), value),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The overrides in effect, the latest entry per field unless it was revoked.
+pub fn active(ledger: &[FieldOverride]) -> BTreeMap<OverrideField, &serde_json::Value> {
+    let mut latest: BTreeMap<OverrideField, Option<&serde_json::Value>> = BTreeMap::new();
+    for entry in ledger {
+        latest.insert(entry.field, entry.value.as_ref());
+    }
+    latest
+        .into_iter()
+        .filter_map(|(field, value)| Some((field, value?)))
+        .collect()
+}
+
+/// Sets the overridden fields, keeping the values they replace in `overridden`, and restores the values of
+/// fields whose override was revoked. Anonymized players are left alone.
+pub fn apply(ledger: &[FieldOverride], player: &mut Player) {
+    if player.anonymized {
+        return;
+    }
+    let active = active(ledger);
+    let revoked: Vec<OverrideField> = player
+        .overridden
+        .keys()
+        .filter(|field| !active.contains_key(*field))
+        .copied()
+        .collect();
+    for field in revoked {
+        if let Some(value) = player.overridden.remove(&field) {
+            if let Err(err) = field.set(player, &value) {
+                log::error!("failed to restore {:?} of player {}: {}", field, player.itsf_id, err);
+            }
+        }
+    }
+    for (field, value) in active {
+        let current = field.get(player);
+        if current == *value {
+            continue;
+        }
+        match field.set(player, value) {
+            Ok(()) => {
+                player.overridden.insert(field, current);
+            }
+            Err(err) => log::error!("failed to apply {:?} to player {}: {}", field, player.itsf_id, err),
+        }
+    }
+}
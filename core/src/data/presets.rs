@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use super::itsf::{RankingCategory, RankingClass};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PresetSource {
+    #[serde(rename = "itsf")]
+    Itsf,
+    #[serde(rename = "dtfb")]
+    Dtfb,
+    #[serde(rename = "events")]
+    Events,
+}
+
+/// A named combination of download parameters, started with `POST /download_preset/{name}`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DownloadPreset {
+    pub source: PresetSource,
+    /// Seasons like `2022` or `2022/23`, `latest` or `all`, the latest season if empty.
+    /// Calendar years for events, where `latest` means the current and the next year.
+    #[serde(default)]
+    pub seasons: Vec<String>,
+    /// ITSF rankings only, all if empty.
+    #[serde(default)]
+    pub categories: Vec<RankingCategory>,
+    #[serde(default)]
+    pub classes: Vec<RankingClass>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rank: Option<usize>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub max_rank_per_category: HashMap<RankingCategory, usize>,
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Preset names are used in URLs, e.g. `nightly-current-year`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+/// A scraper parsing one kind of page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub enum Parser {
+    #[serde(rename = "itsf_players")]
+    ItsfPlayers,
+    #[serde(rename = "itsf_rankings")]
+    ItsfRankings,
+    #[serde(rename = "itsf_events")]
+    ItsfEvents,
+    #[serde(rename = "itsf_teams")]
+    ItsfTeams,
+    #[serde(rename = "dtfb_players")]
+    DtfbPlayers,
+    #[serde(rename = "dtfb_leagues")]
+    DtfbLeagues,
+}
+
+impl Parser {
+    pub const ALL: [Self; 6] = [
+        Self::ItsfPlayers,
+        Self::ItsfRankings,
+        Self::ItsfEvents,
+        Self::ItsfTeams,
+        Self::DtfbPlayers,
+        Self::DtfbLeagues,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ItsfPlayers => "itsf_players",
+            Self::ItsfRankings => "itsf_rankings",
+            Self::ItsfEvents => "itsf_events",
+            Self::ItsfTeams => "itsf_teams",
+            Self::DtfbPlayers => "dtfb_players",
+            Self::DtfbLeagues => "dtfb_leagues",
+        }
+    }
+}
+
+/// What a parser made of the pages it was given, network errors aren't counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParseCounts {
+    pub pages: u64,
+    /// Pages the parser rejected.
+    pub failed: u64,
+    /// Pages that parsed, but without any records, e.g. after the markup of a table changed.
+    pub empty: u64,
+    pub records: u64,
+    /// Records that had the field, by field name.
+    pub present: BTreeMap<String, u64>,
+}
+
+impl ParseCounts {
+    pub fn add(&mut self, other: &ParseCounts) {
+        self.pages += other.pages;
+        self.failed += other.failed;
+        self.empty += other.empty;
+        self.records += other.records;
+        for (field, present) in &other.present {
+            *self.present.entry(field.clone()).or_default() += present;
+        }
+    }
+}
+
+/// The counts of a parser on one day.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParseQuality {
+    pub parser: Parser,
+    /// Unix timestamp of the day's start, UTC.
+    pub day: i64,
+    #[serde(flatten)]
+    pub counts: ParseCounts,
+}
+
+impl ParseQuality {
+    pub fn key(parser: Parser, day: i64) -> String {
+        format!("{}/{}", parser.name(), day)
+    }
+}
@@ -0,0 +1,8 @@
+/// Position of the replication from a primary instance.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReplicationState {
+    /// Revision of the primary the players were replicated up to, the `since_revision` of the next sync.
+    pub revision: u64,
+    /// Unix timestamp of the last successful sync.
+    pub synced_at: Option<i64>,
+}
@@ -0,0 +1,13 @@
+/// A sampled request, for capacity planning. The client is anonymized before it is stored.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RequestSample {
+    pub timestamp: i64,
+    pub method: String,
+    /// Without the query string, which may contain search terms.
+    pub path: String,
+    /// Route pattern like `/player/{itsf_lic}`, missing if no route matched.
+    pub endpoint: Option<String>,
+    pub status: u16,
+    pub latency_ms: i64,
+    pub client: String,
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+/// First season any data is downloaded for.
+pub const FIRST_YEAR: i32 = 2010;
+
+/// The period rankings, championships and league tables belong to.
+///
+/// ITSF rankings run per calendar year, while DTFB seasons span two calendar years, e.g. 2022/23.
+/// DTFB data is stored under the year the season starts in, which is what DTFB calls the season.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Season {
+    Itsf(i32),
+    Dtfb(i32),
+}
+
+impl Season {
+    /// Parses a calendar year like `2022`.
+    pub fn parse_itsf(season: &str) -> Result<Self, String> {
+        let year = season
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| format!("invalid ITSF season: '{}'", season))?;
+        Ok(Self::Itsf(year))
+    }
+
+    /// Parses a DTFB season like `2022/23`, `2022/2023`, `2022-23` or just its start year `2022`.
+    pub fn parse_dtfb(season: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid DTFB season: '{}'", season);
+        let (start, end) = match season.trim().split_once(['/', '-']) {
+            Some((start, end)) => (start, Some(end)),
+            None => (season.trim(), None),
+        };
+        let start = start.parse::<i32>().map_err(|_| invalid())?;
+        if let Some(end) = end {
+            let end = end.parse::<i32>().map_err(|_| invalid())?;
+            let expected = start + 1;
+            if end != expected && end != expected % 100 {
+                return Err(invalid());
+            }
+        }
+        Ok(Self::Dtfb(start))
+    }
+
+    /// The year the season's data is stored under.
+    pub fn year(self) -> i32 {
+        match self {
+            Self::Itsf(year) | Self::Dtfb(year) => year,
+        }
+    }
+
+    /// Whether data is downloaded for the season, i.e. it is neither before `FIRST_YEAR` nor in the future.
+    pub fn is_available(self) -> bool {
+        self.year() >= FIRST_YEAR && self.year() <= current_year()
+    }
+
+    /// All available ITSF seasons, oldest first.
+    pub fn all_itsf() -> Vec<Self> {
+        (FIRST_YEAR..=current_year()).map(Self::Itsf).collect()
+    }
+
+    /// All available DTFB seasons, oldest first.
+    pub fn all_dtfb() -> Vec<Self> {
+        (FIRST_YEAR..=current_year()).map(Self::Dtfb).collect()
+    }
+}
+
+fn current_year() -> i32 {
+    use chrono::Datelike;
+    chrono::Utc::now().naive_local().year()
+}
+
+impl fmt::Display for Season {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Itsf(year) => write!(f, "{}", year),
+            Self::Dtfb(year) => write!(f, "{}/{:02}", year, (year + 1) % 100),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dtfb_seasons_span_two_years() {
+        for season in ["2022/23", "2022/2023", "2022-23", " 2022 "] {
+            assert_eq!(Season::parse_dtfb(season), Ok(Season::Dtfb(2022)), "{}", season);
+        }
+        assert_eq!(Season::parse_dtfb("1999/00"), Ok(Season::Dtfb(1999)));
+        for season in ["2022/24", "2022/2022", "2022/", "/23", "22/23x", "season"] {
+            assert!(Season::parse_dtfb(season).is_err(), "{}", season);
+        }
+    }
+
+    #[test]
+    fn itsf_seasons_are_calendar_years() {
+        assert_eq!(Season::parse_itsf("2022"), Ok(Season::Itsf(2022)));
+        assert!(Season::parse_itsf("2022/23").is_err());
+        assert!(Season::parse_itsf("").is_err());
+    }
+
+    #[test]
+    fn displayed_like_they_are_parsed() {
+        assert_eq!(Season::Itsf(2022).to_string(), "2022");
+        assert_eq!(Season::Dtfb(2022).to_string(), "2022/23");
+        assert_eq!(Season::Dtfb(1999).to_string(), "1999/00");
+        assert_eq!(
+            Season::parse_dtfb(&Season::Dtfb(2009).to_string()),
+            Ok(Season::Dtfb(2009))
+        );
+    }
+
+    #[test]
+    fn available_from_the_first_year_until_now() {
+        let now = current_year();
+        assert!(Season::Itsf(FIRST_YEAR).is_available());
+        assert!(Season::Dtfb(now).is_available());
+        assert!(!Season::Itsf(FIRST_YEAR - 1).is_available());
+        assert!(!Season::Dtfb(now + 1).is_available());
+        assert_eq!(Season::all_itsf().len(), (now - FIRST_YEAR + 1) as usize);
+        assert_eq!(Season::all_dtfb().first(), Some(&Season::Dtfb(FIRST_YEAR)));
+        assert_eq!(Season::all_dtfb().last(), Some(&Season::Dtfb(now)));
+    }
+}
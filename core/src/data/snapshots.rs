@@ -0,0 +1,112 @@
+//! Every ITSF ranking download is kept as a snapshot of its placements. The latest snapshot of each ranking
+//! is stored in the normalized `ranking_placements` table, older ones are archived as gzip compressed JSON in
+//! `ranking_snapshots`, which keeps the history small. Both are read through the same types.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use sha2::{Digest, Sha256};
+
+use super::itsf::{RankingCategory, RankingClass};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Placement {
+    pub place: i32,
+    pub itsf_id: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RankingSnapshot {
+    pub year: i32,
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    /// Unix timestamp of the download.
+    pub scraped_at: i64,
+    /// Best place first.
+    pub placements: Vec<Placement>,
+}
+
+/// A snapshot without its placements, as listed in the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct SnapshotInfo {
+    pub scraped_at: i64,
+    pub entries: usize,
+    /// Whether the snapshot was moved to the compressed archive.
+    pub archived: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct PlaceChange {
+    pub itsf_id: i32,
+    pub from_place: i32,
+    pub to_place: i32,
+}
+
+/// Changes between two snapshots of the same ranking.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RankingDiff {
+    pub from: i64,
+    pub to: i64,
+    /// Placements of players missing from the older snapshot.
+    pub entered: Vec<Placement>,
+    /// Placements of players missing from the newer snapshot, as they were in the older one.
+    pub left: Vec<Placement>,
+    pub moved: Vec<PlaceChange>,
+}
+
+pub fn diff(from: &RankingSnapshot, to: &RankingSnapshot) -> RankingDiff {
+    let old_places: HashMap<i32, i32> = from.placements.iter().map(|p| (p.itsf_id, p.place)).collect();
+    let new_places: HashMap<i32, i32> = to.placements.iter().map(|p| (p.itsf_id, p.place)).collect();
+    RankingDiff {
+        from: from.scraped_at,
+        to: to.scraped_at,
+        entered: to
+            .placements
+            .iter()
+            .filter(|p| !old_places.contains_key(&p.itsf_id))
+            .copied()
+            .collect(),
+        left: from
+            .placements
+            .iter()
+            .filter(|p| !new_places.contains_key(&p.itsf_id))
+            .copied()
+            .collect(),
+        moved: to
+            .placements
+            .iter()
+            .filter_map(|p| {
+                let from_place = *old_places.get(&p.itsf_id)?;
+                (from_place != p.place).then_some(PlaceChange {
+                    itsf_id: p.itsf_id,
+                    from_place,
+                    to_place: p.place,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Hex encoded SHA-256 of the placements, recorded when a ranking is closed to detect later changes.
+pub fn checksum(placements: &[Placement]) -> String {
+    let json = serde_json::to_vec(placements).expect("JSON serialization failed");
+    Sha256::digest(&json)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn compress(placements: &[Placement]) -> Vec<u8> {
+    let json = serde_json::to_vec(placements).expect("JSON serialization failed");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&json).expect("compression to memory failed");
+    encoder.finish().expect("compression to memory failed")
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<Placement>, String> {
+    let mut json = Vec::new();
+    flate2::read::GzDecoder::new(data)
+        .read_to_end(&mut json)
+        .map_err(|err| format!("invalid archived snapshot: {}", err))?;
+    serde_json::from_slice(&json).map_err(|err| format!("invalid archived snapshot: {}", err))
+}
@@ -0,0 +1,34 @@
+/// A destructive action that is carried out at once, but can be reverted until it is finalized when its undo
+/// window ends. Until then, the data needed to revert it is kept stored.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StagedAction {
+    DeleteList {
+        list_id: i32,
+    },
+    /// The players of `list_id` were added to `into_list_id` and `list_id` was deleted. `added_players` are the
+    /// ones that weren't in `into_list_id` before, which are removed again when the merge is undone.
+    MergeList {
+        list_id: i32,
+        into_list_id: i32,
+        added_players: Vec<i32>,
+    },
+}
+
+impl StagedAction {
+    /// The list that is deleted, but kept stored until the action is finalized.
+    pub fn deleted_list(&self) -> i32 {
+        match self {
+            Self::DeleteList { list_id } | Self::MergeList { list_id, .. } => *list_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PendingAction {
+    pub action_id: u64,
+    pub action: StagedAction,
+    pub user_id: String,
+    pub staged_at: i64,
+    pub finalize_at: i64,
+}
@@ -0,0 +1,9 @@
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Subscription {
+    pub user_id: String,
+    pub players: Vec<i32>,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+}
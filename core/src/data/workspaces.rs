@@ -0,0 +1,35 @@
+use super::{Player, PlayerComment};
+
+/// Comments and tags a workspace, e.g. a club, keeps about a player. Workspaces share the scraped
+/// player data, but neither see each other's notes nor the shared ones stored with the player.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlayerNotes {
+    pub workspace: String,
+    pub itsf_id: i32,
+    #[serde(default)]
+    pub comments: Vec<PlayerComment>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl PlayerNotes {
+    pub fn new(workspace: &str, itsf_id: i32) -> Self {
+        PlayerNotes {
+            workspace: String::from(workspace),
+            itsf_id,
+            comments: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// Key the notes are stored under, e.g. `club/84000895`.
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.workspace, self.itsf_id)
+    }
+
+    /// Replaces the shared comments and tags of the player with these.
+    pub fn apply(self, player: &mut Player) {
+        player.comments = self.comments;
+        player.tags = self.tags;
+    }
+}
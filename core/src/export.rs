@@ -0,0 +1,146 @@
+use std::io::{Cursor, Write};
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::data::documents::PlayerDocument;
+use crate::data::images::ImageAccess;
+use crate::data::{CommentVisibility, DatabaseRef, Player};
+
+/// Bumped whenever the bundle layout changes, so the offline tool can reject bundles it doesn't understand.
+const BUNDLE_VERSION: u32 = 1;
+const THUMBNAIL_SIZE: u32 = 160;
+
+/// The business card of a player, as JSON or vCard.
+#[derive(serde::Serialize)]
+pub struct PlayerCard {
+    pub itsf_lic: i32,
+    pub license: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub display_name: String,
+    pub birth_year: i32,
+    pub country_code: String,
+    pub category: &'static str,
+    pub category_label: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    pub profile_url: String,
+    pub qr_url: String,
+}
+
+impl PlayerCard {
+    /// The card as vCard 3.0, with CRLF line endings.
+    pub fn to_vcard(&self) -> String {
+        let vcard = [
+            Some(String::from("BEGIN:VCARD")),
+            Some(String::from("VERSION:3.0")),
+            Some(format!("N:{};{};;;", self.last_name, self.first_name)),
+            Some(format!("FN:{}", self.display_name)),
+            Some(format!(
+                "NOTE:ITSF license {} ({}, {})",
+                self.license, self.country_code, self.category_label
+            )),
+            self.image_url.as_ref().map(|url| format!("PHOTO;VALUE=URI:{}", url)),
+            Some(format!("URL:{}", self.profile_url)),
+            Some(String::from("END:VCARD")),
+        ];
+        let vcard: Vec<String> = vcard.into_iter().flatten().collect();
+        vcard.join("\r\n") + "\r\n"
+    }
+}
+
+/// A QR code of `url` as PNG, at least 256 pixels wide.
+pub fn qr_png(url: &str) -> Result<Vec<u8>, String> {
+    let code = qrcode::QrCode::new(url.as_bytes()).map_err(|_| String::from("failed to encode QR code"))?;
+    let image = code.render::<image::Luma<u8>>().min_dimensions(256, 256).build();
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|_| String::from("failed to encode PNG"))?;
+    Ok(png)
+}
+
+#[derive(serde::Serialize)]
+struct Manifest {
+    version: u32,
+    created: i64,
+    players: usize,
+    thumbnails: usize,
+}
+
+fn thumbnail(image_data: &[u8]) -> Result<Vec<u8>, String> {
+    let image =
+        image::load_from_memory_with_format(image_data, image::ImageFormat::Jpeg).map_err(|err| err.to_string())?;
+    let mut jpeg = Vec::new();
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+        .map_err(|err| err.to_string())?;
+    Ok(jpeg)
+}
+
+/// Packs the players and thumbnails of their images into a zip archive for use without internet:
+/// `manifest.json`, `players.json` with the players as stored and `thumbnails/{itsf_id}.jpg`. Internal comments
+/// are only included if `authenticated`, thumbnails only as far as `image_access` allows.
+pub fn offline_bundle(
+    db: &DatabaseRef,
+    mut players: Vec<Player>,
+    authenticated: bool,
+    image_access: ImageAccess,
+) -> Result<Vec<u8>, String> {
+    if !authenticated {
+        for player in &mut players {
+            player
+                .comments
+                .retain(|comment| comment.visibility == CommentVisibility::Public);
+        }
+    }
+
+    let mut buffer = Vec::new();
+    {
+        let mut zip = ZipWriter::new(Cursor::new(&mut buffer));
+        let deflated = zip::write::FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let stored = zip::write::FileOptions::default().compression_method(CompressionMethod::Stored);
+
+        let mut thumbnails = 0;
+        zip.add_directory("thumbnails", stored).map_err(|err| err.to_string())?;
+        for player in &players {
+            if !image_access.allows(authenticated, player.hidden) {
+                continue;
+            }
+            let image = match db.get_player_image(player.itsf_id) {
+                Some(image) => image,
+                None => continue,
+            };
+            match thumbnail(&image.image_data) {
+                Ok(jpeg) => {
+                    zip.start_file(format!("thumbnails/{}.jpg", player.itsf_id), stored)
+                        .map_err(|err| err.to_string())?;
+                    zip.write_all(&jpeg).map_err(|err| err.to_string())?;
+                    thumbnails += 1;
+                }
+                Err(err) => log::error!("failed to create thumbnail for {}: {}", player.itsf_id, err),
+            }
+        }
+
+        zip.start_file("players.json", deflated)
+            .map_err(|err| err.to_string())?;
+        let documents: Vec<PlayerDocument> = players.iter().map(PlayerDocument::new).collect();
+        serde_json::to_writer(&mut zip, &documents).map_err(|err| err.to_string())?;
+
+        let manifest = Manifest {
+            version: BUNDLE_VERSION,
+            created: chrono::Utc::now().timestamp(),
+            players: players.len(),
+            thumbnails,
+        };
+        zip.start_file("manifest.json", deflated)
+            .map_err(|err| err.to_string())?;
+        serde_json::to_writer(&mut zip, &manifest).map_err(|err| err.to_string())?;
+
+        zip.finish().map_err(|err| err.to_string())?;
+    }
+
+    Ok(buffer)
+}
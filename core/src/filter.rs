@@ -0,0 +1,434 @@
+//! Filter expressions for `/players?filter=`, e.g. `country=GER AND best_rank<=32 AND birth_year>=1995`.
+//!
+//! Comparisons of a field with a value can be combined with `AND`, `OR`, `NOT` and parentheses.
+//! Text values containing spaces must be quoted: `category="junior male"`.
+
+use crate::data::itsf::{PlayerCategory, RankingClass};
+use crate::data::Player;
+
+/// Longest accepted filter, in characters.
+const MAX_LENGTH: usize = 1000;
+/// Deepest accepted nesting of parentheses, the parser recurses once per level.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn compare<T: PartialOrd>(self, a: T, b: T) -> bool {
+        match self {
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    ItsfLic,
+    FirstName,
+    LastName,
+    Country,
+    Category,
+    BirthYear,
+    BestRank,
+    DtfbId,
+    Tag,
+}
+
+impl Field {
+    fn try_from_str(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "itsf_lic" => Ok(Self::ItsfLic),
+            "first_name" => Ok(Self::FirstName),
+            "last_name" => Ok(Self::LastName),
+            "country" => Ok(Self::Country),
+            "category" => Ok(Self::Category),
+            "birth_year" => Ok(Self::BirthYear),
+            "best_rank" => Ok(Self::BestRank),
+            "dtfb_id" => Ok(Self::DtfbId),
+            "tag" => Ok(Self::Tag),
+            _ => Err(format!("unknown field '{}'", name)),
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Self::ItsfLic | Self::BirthYear | Self::BestRank | Self::DtfbId)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(i32),
+    Text(String),
+    Category(PlayerCategory),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Compare(Field, Op, Value),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+/// A parsed filter expression.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(String::from("unterminated quote")),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let followed_by_eq = chars.peek() == Some(&'=');
+                if followed_by_eq {
+                    chars.next();
+                }
+                let op = match (ch, followed_by_eq) {
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => return Err(String::from("expected '!='")),
+                };
+                tokens.push(Token::Op(op));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_' || **c == '-') {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c => return Err(format!("unexpected character '{}'", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Open parentheses around the current position.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut filter = self.parse_and()?;
+        while self.peek_keyword("OR") {
+            self.pos += 1;
+            filter = Expr::Or(Box::new(filter), Box::new(self.parse_and()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut filter = self.parse_term()?;
+        while self.peek_keyword("AND") {
+            self.pos += 1;
+            filter = Expr::And(Box::new(filter), Box::new(self.parse_term()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, String> {
+        // repeated NOTs cancel out in pairs
+        let mut negated = false;
+        while self.peek_keyword("NOT") {
+            self.pos += 1;
+            negated = !negated;
+        }
+        let term = self.parse_operand()?;
+        Ok(match negated {
+            true => Expr::Not(Box::new(term)),
+            false => term,
+        })
+    }
+
+    fn parse_operand(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Open) => {
+                if self.depth == MAX_DEPTH {
+                    return Err(String::from("filter nested too deeply"));
+                }
+                self.depth += 1;
+                let filter = self.parse_or()?;
+                self.depth -= 1;
+                match self.next() {
+                    Some(Token::Close) => Ok(filter),
+                    _ => Err(String::from("expected ')'")),
+                }
+            }
+            Some(Token::Word(name)) => {
+                let field = Field::try_from_str(&name)?;
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    _ => return Err(format!("expected comparison after '{}'", name)),
+                };
+                let value = match self.next() {
+                    Some(Token::Word(value)) | Some(Token::Text(value)) => value,
+                    _ => return Err(format!("expected value after '{}'", name)),
+                };
+                Self::comparison(field, op, value)
+            }
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err(String::from("unexpected end of filter")),
+        }
+    }
+
+    fn comparison(field: Field, op: Op, value: String) -> Result<Expr, String> {
+        let value = if field.is_numeric() {
+            Value::Number(
+                value
+                    .parse::<i32>()
+                    .map_err(|_| format!("invalid number '{}'", value))?,
+            )
+        } else if !matches!(op, Op::Eq | Op::Ne) {
+            return Err(String::from("text fields only support '=' and '!='"));
+        } else if let Field::Category = field {
+            Value::Category(PlayerCategory::try_from_str(&value.to_uppercase().replace('_', " "))?)
+        } else {
+            Value::Text(value.to_lowercase())
+        };
+        Ok(Expr::Compare(field, op, value))
+    }
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        if input.chars().count() > MAX_LENGTH {
+            return Err(format!("filter longer than {} characters", MAX_LENGTH));
+        }
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(Filter { expr }),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+        }
+    }
+
+    pub fn matches(&self, player: &Player) -> bool {
+        self.expr.matches(player)
+    }
+}
+
+impl Expr {
+    fn matches(&self, player: &Player) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(player) && b.matches(player),
+            Expr::Or(a, b) => a.matches(player) || b.matches(player),
+            Expr::Not(expr) => !expr.matches(player),
+            Expr::Compare(field, op, value) => Self::compare(player, *field, *op, value),
+        }
+    }
+
+    fn compare(player: &Player, field: Field, op: Op, value: &Value) -> bool {
+        let text = |text: &str| match value {
+            Value::Text(value) => op.compare(text.to_lowercase().as_str(), value.as_str()),
+            _ => false,
+        };
+        // players without a value for the field match no comparison
+        let number = |number: Option<i32>| match (number, value) {
+            (Some(number), Value::Number(value)) => op.compare(number, *value),
+            _ => false,
+        };
+
+        match field {
+            Field::ItsfLic => number(Some(player.itsf_id)),
+            Field::FirstName => text(&player.first_name),
+            Field::LastName => text(&player.last_name),
+            Field::Country => player.country_code.as_deref().is_some_and(text),
+            Field::Category => match value {
+                Value::Category(category) => op.compare(player.category as i8, *category as i8),
+                _ => false,
+            },
+            Field::BirthYear => number(Some(player.birth_year).filter(|year| *year != 0)),
+            Field::BestRank => number(
+                player
+                    .itsf_rankings
+                    .iter()
+                    .filter(|r| r.class != RankingClass::Combined)
+                    .map(|r| r.place)
+                    .min(),
+            ),
+            Field::DtfbId => number(player.dtfb_id),
+            Field::Tag => match value {
+                Value::Text(tag) => (op == Op::Eq) == player.tags.contains(tag),
+                _ => false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn player(country_code: &str, birth_year: i32, tags: &[&str]) -> Player {
+        Player {
+            itsf_id: 84000895,
+            first_name: String::from("Max"),
+            last_name: String::from("Mustermann"),
+            birth_year,
+            country_code: Some(String::from(country_code)),
+            category: PlayerCategory::Men,
+            itsf_rankings: Vec::new(),
+            dtfb_id: None,
+            dtfb_national_rankings: Vec::new(),
+            dtfb_championship_results: Vec::new(),
+            dtfb_league_teams: Vec::new(),
+            comments: Vec::new(),
+            tags: tags.iter().map(|tag| String::from(*tag)).collect(),
+            former_names: Vec::new(),
+            country_changes: Vec::new(),
+            national_team_appearances: Vec::new(),
+            hidden: false,
+            scraped_at: None,
+            refresh_errors: Vec::new(),
+            anonymized: false,
+            revision: 0,
+            overridden: BTreeMap::new(),
+        }
+    }
+
+    fn matches(filter: &str, player: &Player) -> bool {
+        Filter::parse(filter).unwrap().matches(player)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let austrian = player("AUT", 1990, &[]);
+        assert!(matches("country=AUT OR country=GER AND birth_year>2000", &austrian));
+        assert!(!matches("(country=AUT OR country=GER) AND birth_year>2000", &austrian));
+        assert!(matches("birth_year>2000 AND country=GER OR country=aut", &austrian));
+    }
+
+    #[test]
+    fn not_applies_to_the_next_term() {
+        let german = player("GER", 1990, &["goalie"]);
+        assert!(!matches("NOT country=GER", &german));
+        assert!(matches("NOT NOT country=GER", &german));
+        assert!(matches("NOT country=AUT AND tag=goalie", &german));
+        assert!(!matches("NOT (country=AUT OR tag=goalie)", &german));
+        assert!(matches("not tag!=goalie", &german));
+    }
+
+    #[test]
+    fn quoted_values_and_categories() {
+        let german = player("GER", 1990, &["left wing"]);
+        assert!(matches(r#"tag="left wing" AND category=men"#, &german));
+        assert!(!matches("category=junior_male", &german));
+        // players without birth year match no comparison of it
+        assert!(!matches("birth_year!=1990", &player("GER", 0, &[])));
+    }
+
+    #[test]
+    fn invalid_filters_are_rejected() {
+        for (filter, error) in [
+            ("country", "expected comparison after 'country'"),
+            ("country=", "expected value after 'country'"),
+            ("height>2", "unknown field 'height'"),
+            ("birth_year=old", "invalid number 'old'"),
+            ("country<GER", "text fields only support '=' and '!='"),
+            ("(country=GER", "expected ')'"),
+            ("country=GER)", "unexpected Close"),
+            ("tag=\"goalie", "unterminated quote"),
+            ("country=GER AND", "unexpected end of filter"),
+            ("country=GER;", "unexpected character ';'"),
+        ] {
+            assert_eq!(Filter::parse(filter).unwrap_err(), error, "{}", filter);
+        }
+    }
+
+    #[test]
+    fn nesting_and_length_are_limited() {
+        let nested = |depth: usize| format!("{}country=GER{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Filter::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            Filter::parse(&nested(MAX_DEPTH + 1)).unwrap_err(),
+            "filter nested too deeply"
+        );
+
+        let negated = format!("{}country=GER", "NOT ".repeat(200));
+        assert!(matches(&negated, &player("GER", 1990, &[])));
+        let negated = format!("{}country=GER", "NOT ".repeat(3000));
+        assert_eq!(
+            Filter::parse(&negated).unwrap_err(),
+            format!("filter longer than {} characters", MAX_LENGTH)
+        );
+    }
+}
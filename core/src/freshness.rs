@@ -0,0 +1,140 @@
+//! Ranks players by how much downloading their ITSF profile again is worth: stale profiles, often requested
+//! ones and ones lacking data come first. Once a day the top `REFRESH_PLAYERS_PER_DAY` players are downloaded
+//! again, but never more than `REFRESH_REQUESTS_PER_DAY` requests, so the profiles people look at stay
+//! current without full backfills.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::data::{self, DatabaseRef, Player};
+use crate::joblock::JobLock;
+use crate::scraping::{self, SourceHost};
+use crate::warmup;
+
+pub const JOB_NAME: &str = "player_refresh";
+
+/// Downloading a player takes two requests, its profile and its image.
+const REQUESTS_PER_PLAYER: usize = 2;
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct RefreshSettings {
+    /// Players downloaded again per day, 0 disables the daily refresh.
+    pub players_per_day: usize,
+    /// Requests to the ITSF site the daily refresh may send.
+    pub requests_per_day: usize,
+}
+
+fn count_from_env(name: &str, default: usize) -> usize {
+    match std::env::var(name) {
+        Ok(count) => count.parse::<usize>().unwrap_or_else(|_| panic!("invalid {}", name)),
+        Err(_) => default,
+    }
+}
+
+impl RefreshSettings {
+    pub fn from_env() -> Self {
+        RefreshSettings {
+            players_per_day: count_from_env("REFRESH_PLAYERS_PER_DAY", 100),
+            requests_per_day: count_from_env("REFRESH_REQUESTS_PER_DAY", 250),
+        }
+    }
+
+    /// Players a run downloads, the top ones that fit into the request budget.
+    pub fn players_per_run(&self) -> usize {
+        self.players_per_day.min(self.requests_per_day / REQUESTS_PER_PLAYER)
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RefreshCandidate {
+    pub itsf_lic: i32,
+    pub score: f64,
+    /// Days since the profile was downloaded, unknown for older records.
+    pub age_days: Option<i64>,
+    /// Profile and image requests since the server started.
+    pub requests: u64,
+    pub missing: Vec<&'static str>,
+}
+
+/// Data the profile lacks, e.g. `image` or `birth_year`.
+pub fn missing_data(db: &DatabaseRef, player: &Player) -> Vec<&'static str> {
+    [
+        ("image", !db.has_player_image(player.itsf_id)),
+        ("birth_year", player.birth_year == 0),
+        ("country_code", player.country_code.is_none()),
+        ("itsf_rankings", player.itsf_rankings.is_empty()),
+    ]
+    .into_iter()
+    .filter(|(_, missing)| *missing)
+    .map(|(name, _)| name)
+    .collect()
+}
+
+/// Staleness in multiples of `DATA_STALE_AFTER`, capped so players nobody requests can't crowd out popular
+/// ones forever, plus the logarithm of the requests and half a point per missing piece of data.
+fn score(age: Option<i64>, requests: u64, missing: usize) -> f64 {
+    const MAX_STALENESS: f64 = 2.0;
+    let staleness = match age {
+        Some(age) => (age as f64 / data::stale_after() as f64).clamp(0.0, MAX_STALENESS),
+        None => MAX_STALENESS,
+    };
+    staleness + (requests as f64).ln_1p() + 0.5 * missing as f64
+}
+
+/// The players most worth downloading again, highest score first. Anonymized players are left out, their
+/// profiles are never downloaded again.
+pub fn ranked(db: &DatabaseRef, limit: usize) -> Vec<RefreshCandidate> {
+    let requests: HashMap<i32, u64> = warmup::most_requested(usize::MAX).into_iter().collect();
+    let now = chrono::Utc::now().timestamp();
+    let mut candidates: Vec<RefreshCandidate> = db.aggregate_players(|players| {
+        players
+            .filter(|player| !player.anonymized)
+            .map(|player| {
+                let age = player.scraped_at.map(|scraped_at| now - scraped_at);
+                let requests = requests.get(&player.itsf_id).copied().unwrap_or(0);
+                let missing = missing_data(db, player);
+                RefreshCandidate {
+                    itsf_lic: player.itsf_id,
+                    score: score(age, requests, missing.len()),
+                    age_days: age.map(|age| age / (24 * 60 * 60)),
+                    requests,
+                    missing,
+                }
+            })
+            .collect()
+    });
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.itsf_lic.cmp(&b.itsf_lic)));
+    candidates.truncate(limit);
+    candidates
+}
+
+/// Downloads the top players again once a day, starting a day after startup when the request counts mean
+/// something. A day is skipped if another job is scraping the ITSF site.
+pub fn start_refresh_task(db: &DatabaseRef, job_lock: JobLock, settings: RefreshSettings) {
+    const INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+    if settings.players_per_run() == 0 {
+        return;
+    }
+    let db = db.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(INTERVAL).await;
+            let lock = match job_lock.try_acquire(SourceHost::Itsf.lock_name()).await {
+                Ok(Some(lock)) => lock,
+                Ok(None) => {
+                    log::info!("[Refresh] skipped, another job is scraping the ITSF site");
+                    continue;
+                }
+                Err(err) => {
+                    log::error!("[Refresh] failed to acquire download lock: {}", err);
+                    continue;
+                }
+            };
+            let itsf_ids = ranked(&db, settings.players_per_run())
+                .into_iter()
+                .map(|candidate| candidate.itsf_lic)
+                .collect();
+            scraping::start_player_refresh(db.clone(), itsf_ids, lock);
+        }
+    });
+}
@@ -0,0 +1,142 @@
+//! Optional geocoding of event locations, configured via `GEOCODING_PROVIDER` (`nominatim` or `photon`) and
+//! `GEOCODING_URL`. Results, including places that weren't found, are stored, so every location is only
+//! looked up once.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::background::BackgroundOperationProgress;
+pub use crate::data::geocodes::Coordinates;
+use crate::data::DatabaseRef;
+
+/// Public instances allow about one request per second.
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Provider {
+    Nominatim { url: String },
+    Photon { url: String },
+}
+
+impl Provider {
+    /// `None` unless `GEOCODING_PROVIDER` is set.
+    pub fn from_env() -> Option<Self> {
+        let provider = std::env::var("GEOCODING_PROVIDER").ok()?;
+        let url = std::env::var("GEOCODING_URL").ok();
+        match provider.as_str() {
+            "nominatim" => Some(Provider::Nominatim {
+                url: url.unwrap_or(String::from("https://nominatim.openstreetmap.org")),
+            }),
+            "photon" => Some(Provider::Photon {
+                url: url.unwrap_or(String::from("https://photon.komoot.io")),
+            }),
+            _ => panic!("invalid GEOCODING_PROVIDER"),
+        }
+    }
+
+    async fn locate(&self, place: &str) -> Result<Option<Coordinates>, String> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("itsf-playerdb/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|err| err.to_string())?;
+        match self {
+            Provider::Nominatim { url } => {
+                #[derive(serde::Deserialize)]
+                struct Place {
+                    lat: String,
+                    lon: String,
+                }
+                let places: Vec<Place> = client
+                    .get(format!("{}/search", url.trim_end_matches('/')))
+                    .query(&[("q", place), ("format", "json"), ("limit", "1")])
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|err| err.to_string())?
+                    .json()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                places
+                    .first()
+                    .map(|place| Coordinates::parse(&format!("{},{}", place.lat, place.lon)))
+                    .transpose()
+            }
+            Provider::Photon { url } => {
+                let collection: serde_json::Value = client
+                    .get(format!("{}/api", url.trim_end_matches('/')))
+                    .query(&[("q", place), ("limit", "1")])
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|err| err.to_string())?
+                    .json()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                // GeoJSON points are `[lon, lat]`
+                let point = &collection["features"][0]["geometry"]["coordinates"];
+                Ok(match (point[1].as_f64(), point[0].as_f64()) {
+                    (Some(lat), Some(lon)) => Some(Coordinates { lat, lon }),
+                    _ => None,
+                })
+            }
+        }
+    }
+}
+
+static MOCK_PROVIDER: OnceLock<Provider> = OnceLock::new();
+
+/// Geocodes with the Nominatim compatible mock at `url` instead of the configured provider, for demo mode.
+pub fn use_mock_provider(url: &str) -> Result<(), String> {
+    MOCK_PROVIDER
+        .set(Provider::Nominatim { url: String::from(url) })
+        .map_err(|_| String::from("geocoding provider is already in use"))
+}
+
+/// The provider lookups go to, `None` if geocoding is off.
+pub fn provider() -> Option<Provider> {
+    MOCK_PROVIDER.get().cloned().or_else(Provider::from_env)
+}
+
+/// The stored coordinates of the place, looked up and stored first if it wasn't looked up before.
+/// Fails only if the provider couldn't be asked, in which case the place is looked up again next time.
+pub async fn locate(db: &DatabaseRef, provider: &Provider, place: &str) -> Result<Option<Coordinates>, String> {
+    if let Some(coordinates) = db.get_geocode(place) {
+        return Ok(coordinates);
+    }
+    let coordinates = provider.locate(place).await?;
+    db.set_geocode(place, coordinates);
+    if MOCK_PROVIDER.get().is_none() {
+        tokio::time::sleep(MIN_INTERVAL).await;
+    }
+    Ok(coordinates)
+}
+
+/// Sets the coordinates of all events that don't have them yet, from their location and country.
+pub async fn locate_events(db: &DatabaseRef, progress: &BackgroundOperationProgress) {
+    let provider = match provider() {
+        Some(provider) => provider,
+        None => return,
+    };
+    let events: Vec<_> = db
+        .get_events_between(None, None)
+        .into_iter()
+        .filter(|event| event.coordinates.is_none() && !event.location.is_empty())
+        .collect();
+    for mut event in events {
+        let place = match &event.country_code {
+            Some(country_code) => format!("{}, {}", event.location, country_code),
+            None => event.location.clone(),
+        };
+        match locate(db, &provider, &place).await {
+            Ok(Some(coordinates)) => {
+                event.coordinates = Some(coordinates);
+                db.set_event(event);
+            }
+            Ok(None) => progress.log(format!("[Geo] Location of '{}' not found: {}", event.name, place)),
+            Err(err) => {
+                progress.log(format!("[Geo] Geocoding failed: {}", err));
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::{Duration, Utc};
+
+use crate::data::events::Event;
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line to at most 75 octets per line, as required by RFC 5545.
+fn fold(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+    let mut folded = String::new();
+    let mut octets = 0;
+    for ch in line.chars() {
+        if octets + ch.len_utf8() > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(ch);
+        octets += ch.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Renders the events as an iCalendar feed, `host` is used to build globally unique event ids.
+pub fn calendar(events: &[Event], host: &str) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//ITSF Player DB//Tournaments//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+        String::from("X-WR-CALNAME:ITSF Tournaments"),
+    ];
+
+    for event in events {
+        let location = match &event.country_code {
+            Some(country_code) => format!("{} ({})", event.location, country_code),
+            None => event.location.clone(),
+        };
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!("UID:itsf-event-{}@{}", event.event_id, host));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", event.start_date.format("%Y%m%d")));
+        // DTEND is exclusive for all-day events
+        let end_date = event.end_date + Duration::days(1);
+        lines.push(format!("DTEND;VALUE=DATE:{}", end_date.format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape(&event.name)));
+        lines.push(format!("LOCATION:{}", escape(&location)));
+        lines.push(format!("CATEGORIES:{}", escape(&event.category)));
+        lines.push(format!("URL:{}", event.url));
+        lines.push(String::from("END:VEVENT"));
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+    lines.iter().map(|line| fold(line)).collect()
+}
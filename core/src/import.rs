@@ -0,0 +1,203 @@
+use crate::data::{license::LicenseNumber, CommentVisibility, DatabaseRef, PlayerComment};
+
+/// A comment to import, e.g. a row of a scouting spreadsheet.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct CommentRow {
+    #[serde(alias = "itsf_lic")]
+    license: LicenseNumber,
+    comment: String,
+    #[serde(default)]
+    author: Option<String>,
+    /// `YYYY-MM-DD`, `DD.MM.YYYY` or RFC 3339, the time of the import if missing.
+    #[serde(default)]
+    date: Option<String>,
+    #[serde(default)]
+    visibility: CommentVisibility,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RowError {
+    /// 1-based index of the JSON array element, or line number in the CSV file.
+    pub row: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Rows matching an existing comment with the same text and time, so imports can be repeated safely.
+    pub duplicates: usize,
+    pub errors: Vec<RowError>,
+}
+
+fn parse_date(date: &str) -> Result<u32, String> {
+    let date = date.trim();
+    let timestamp = if let Ok(date) = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        date.and_hms_opt(0, 0, 0).unwrap().timestamp()
+    } else if let Ok(date) = chrono::NaiveDate::parse_from_str(date, "%d.%m.%Y") {
+        date.and_hms_opt(0, 0, 0).unwrap().timestamp()
+    } else if let Ok(date) = chrono::DateTime::parse_from_rfc3339(date) {
+        date.timestamp()
+    } else {
+        return Err(format!("invalid date: '{}'", date));
+    };
+    u32::try_from(timestamp).map_err(|_| format!("date out of range: '{}'", date))
+}
+
+fn import_row(
+    db: &DatabaseRef,
+    workspace: Option<&str>,
+    row: serde_json::Value,
+    dry_run: bool,
+) -> Result<bool, String> {
+    let row: CommentRow = serde_json::from_value(row).map_err(|err| err.to_string())?;
+    let itsf_id = row.license.get();
+    let player = db
+        .get_player(itsf_id)
+        .map(|player| db.with_workspace_notes(workspace, player))
+        .ok_or_else(|| format!("unknown player: {}", itsf_id))?;
+    let text = row.comment.trim();
+    if text.is_empty() {
+        return Err(String::from("empty comment"));
+    }
+    let timestamp = match &row.date {
+        Some(date) => parse_date(date)?,
+        None => chrono::Utc::now().naive_local().timestamp() as u32,
+    };
+
+    let comment = PlayerComment {
+        timestamp,
+        text: String::from(text),
+        visibility: row.visibility,
+        author: row
+            .author
+            .map(|author| String::from(author.trim()))
+            .filter(|author| !author.is_empty()),
+        status: None,
+        resolved_by: None,
+        resolved_at: None,
+        thumbs_up: Vec::new(),
+    };
+    let duplicate = player
+        .comments
+        .iter()
+        .any(|existing| (existing.timestamp, &existing.text) == (comment.timestamp, &comment.text));
+    if duplicate {
+        return Ok(false);
+    }
+    if !dry_run {
+        db.import_player_comment(workspace, itsf_id, comment);
+    }
+    Ok(true)
+}
+
+/// Imports numbered rows into the shared comments or those of the workspace, collecting the errors of invalid rows
+/// instead of rejecting the whole import. With `dry_run`, only validates the rows.
+pub fn import_comments(
+    db: &DatabaseRef,
+    workspace: Option<&str>,
+    rows: Vec<(usize, serde_json::Value)>,
+    dry_run: bool,
+) -> ImportReport {
+    let mut report = ImportReport::default();
+    for (row, value) in rows {
+        match import_row(db, workspace, value, dry_run) {
+            Ok(true) => report.imported += 1,
+            Ok(false) => report.duplicates += 1,
+            Err(error) => report.errors.push(RowError { row, error }),
+        }
+    }
+    report
+}
+
+/// Rows of a JSON array of objects with the fields `license`, `comment`, `author`, `date` and `visibility`.
+pub fn rows_from_json(body: &[u8]) -> Result<Vec<(usize, serde_json::Value)>, String> {
+    let rows: Vec<serde_json::Value> = serde_json::from_slice(body).map_err(|err| err.to_string())?;
+    Ok(rows.into_iter().enumerate().map(|(i, row)| (i + 1, row)).collect())
+}
+
+/// Splits CSV text into records, each with the line it starts in. Handles quoted fields containing separators,
+/// quotes and line breaks, and uses `;` as separator if the header has no comma, as spreadsheets in German do.
+fn parse_csv(text: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let header = text.lines().next().unwrap_or_default();
+    let separator = if !header.contains(',') && header.contains(';') {
+        ';'
+    } else {
+        ','
+    };
+
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut record)));
+                line += 1;
+                record_line = line;
+            }
+            c if c == separator => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("unterminated quote in line {}", record_line));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+    records.retain(|(_, record)| record.iter().any(|field| !field.trim().is_empty()));
+    Ok(records)
+}
+
+/// Rows of a CSV file with a header line naming the columns, in any order: `license`, `comment` and optionally
+/// `author`, `date` and `visibility`. Empty cells count as missing.
+pub fn rows_from_csv(text: &str) -> Result<Vec<(usize, serde_json::Value)>, String> {
+    let mut records = parse_csv(text)?.into_iter();
+    let header: Vec<String> = match records.next() {
+        Some((_, header)) => header.iter().map(|column| column.trim().to_lowercase()).collect(),
+        None => return Ok(Vec::new()),
+    };
+    for required in ["license", "comment"] {
+        if !header.iter().any(|column| column == required) {
+            return Err(format!("missing column: {}", required));
+        }
+    }
+
+    Ok(records
+        .map(|(line, record)| {
+            let row: serde_json::Map<String, serde_json::Value> = header
+                .iter()
+                .zip(record)
+                .filter(|(_, value)| !value.trim().is_empty())
+                .map(|(column, value)| (column.clone(), serde_json::Value::String(value)))
+                .collect();
+            (line, serde_json::Value::Object(row))
+        })
+        .collect())
+}
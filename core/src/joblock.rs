@@ -0,0 +1,245 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use crate::background::BackgroundOperationProgress;
+use crate::data::DatabaseRef;
+
+/// Locks expire after this time unless refreshed, so a crashed instance can't block jobs forever.
+const LOCK_TTL: Duration = Duration::from_secs(60);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// How often a job waiting for a lock checks whether it's free.
+const WAIT_INTERVAL: Duration = Duration::from_secs(2);
+
+const RELEASE_SCRIPT: &str = r#"
+if redis.call("get", KEYS[1]) == ARGV[1] then
+    redis.call("del", KEYS[2])
+    return redis.call("del", KEYS[1])
+else
+    return 0
+end
+"#;
+
+const REFRESH_SCRIPT: &str = r#"
+if redis.call("get", KEYS[1]) == ARGV[1] then
+    redis.call("set", KEYS[2], ARGV[3], "PX", ARGV[2])
+    return redis.call("pexpire", KEYS[1], ARGV[2])
+else
+    return 0
+end
+"#;
+
+#[derive(Clone)]
+enum Backend {
+    /// A row in the `job_locks` table, shared by all instances using the same database.
+    Database(DatabaseRef),
+    /// A Redis key, for deployments whose instances don't share a database file.
+    Redis(redis::Client),
+}
+
+/// Lock shared between all instances of a deployment, so only one of them runs a given job.
+/// While a job runs, its holder publishes the job log with the lock so other instances can report it.
+#[derive(Clone)]
+pub struct JobLock {
+    backend: Backend,
+}
+
+/// Held for the duration of a job, releases the lock when dropped.
+pub struct JobLockGuard {
+    backend: Backend,
+    name: String,
+    token: String,
+    progress: Arc<Mutex<Weak<BackgroundOperationProgress>>>,
+    refresh_task: tokio::task::JoinHandle<()>,
+}
+
+fn redis_keys(name: &str) -> (String, String) {
+    (format!("playerdb:lock:{}", name), format!("playerdb:lock:{}:log", name))
+}
+
+fn lock_token() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("{}-{}", std::process::id(), nanos)
+}
+
+impl Backend {
+    async fn try_acquire(&self, name: &str, token: &str) -> Result<bool, String> {
+        match self {
+            Backend::Database(db) => Ok(db.try_acquire_job_lock(name, token, LOCK_TTL.as_secs() as i64)),
+            Backend::Redis(client) => {
+                let mut conn = client
+                    .get_multiplexed_async_connection()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                let acquired: Option<String> = redis::cmd("SET")
+                    .arg(redis_keys(name).0)
+                    .arg(token)
+                    .arg("NX")
+                    .arg("PX")
+                    .arg(LOCK_TTL.as_millis() as u64)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                Ok(acquired.is_some())
+            }
+        }
+    }
+
+    async fn refresh(&self, name: &str, token: &str, log: &[String]) -> Result<(), String> {
+        match self {
+            Backend::Database(db) => {
+                db.refresh_job_lock(name, token, LOCK_TTL.as_secs() as i64, log);
+                Ok(())
+            }
+            Backend::Redis(client) => {
+                let (key, log_key) = redis_keys(name);
+                let log = serde_json::to_string(log).map_err(|err| err.to_string())?;
+                let mut conn = client
+                    .get_multiplexed_async_connection()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                redis::Script::new(REFRESH_SCRIPT)
+                    .key(key)
+                    .key(log_key)
+                    .arg(token)
+                    .arg(LOCK_TTL.as_millis() as u64)
+                    .arg(log)
+                    .invoke_async::<_, i32>(&mut conn)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                Ok(())
+            }
+        }
+    }
+
+    async fn release(&self, name: &str, token: &str) -> Result<(), String> {
+        match self {
+            Backend::Database(db) => {
+                db.release_job_lock(name, token);
+                Ok(())
+            }
+            Backend::Redis(client) => {
+                let (key, log_key) = redis_keys(name);
+                let mut conn = client
+                    .get_multiplexed_async_connection()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                redis::Script::new(RELEASE_SCRIPT)
+                    .key(key)
+                    .key(log_key)
+                    .arg(token)
+                    .invoke_async::<_, i32>(&mut conn)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                Ok(())
+            }
+        }
+    }
+
+    async fn running_job_log(&self, name: &str) -> Result<Option<Vec<String>>, String> {
+        match self {
+            Backend::Database(db) => Ok(db.get_job_lock_log(name)),
+            Backend::Redis(client) => {
+                let (key, log_key) = redis_keys(name);
+                let mut conn = client
+                    .get_multiplexed_async_connection()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                let (holder, log): (Option<String>, Option<String>) = redis::pipe()
+                    .get(key)
+                    .get(log_key)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                Ok(holder.map(|_| log.and_then(|log| serde_json::from_str(&log).ok()).unwrap_or_default()))
+            }
+        }
+    }
+}
+
+impl JobLock {
+    /// Uses Redis if `REDIS_URL` is set, otherwise the database.
+    pub fn from_env(db: &DatabaseRef) -> Self {
+        let backend = match std::env::var("REDIS_URL") {
+            Ok(url) => Backend::Redis(redis::Client::open(url).expect("invalid REDIS_URL")),
+            Err(_) => Backend::Database(db.clone()),
+        };
+        Self { backend }
+    }
+
+    /// Tries to take the lock, returns `None` if another instance holds it.
+    pub async fn try_acquire(&self, name: &str) -> Result<Option<JobLockGuard>, String> {
+        let token = lock_token();
+        if !self.backend.try_acquire(name, &token).await? {
+            return Ok(None);
+        }
+
+        let progress: Arc<Mutex<Weak<BackgroundOperationProgress>>> = Arc::new(Mutex::new(Weak::new()));
+        let refresh_task = {
+            let (backend, name, token, progress) = (
+                self.backend.clone(),
+                String::from(name),
+                token.clone(),
+                progress.clone(),
+            );
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(REFRESH_INTERVAL).await;
+                    let log = progress
+                        .lock()
+                        .unwrap()
+                        .upgrade()
+                        .map(|progress| progress.get_log())
+                        .unwrap_or_default();
+                    if let Err(err) = backend.refresh(&name, &token, &log).await {
+                        log::error!("failed to refresh job lock {}: {}", name, err);
+                    }
+                }
+            })
+        };
+
+        Ok(Some(JobLockGuard {
+            backend: self.backend.clone(),
+            name: String::from(name),
+            token,
+            progress,
+            refresh_task,
+        }))
+    }
+
+    /// Waits until no other job holds the lock and takes it.
+    pub async fn acquire(&self, name: &str) -> Result<JobLockGuard, String> {
+        loop {
+            if let Some(guard) = self.try_acquire(name).await? {
+                return Ok(guard);
+            }
+            tokio::time::sleep(WAIT_INTERVAL).await;
+        }
+    }
+
+    /// The log of the job holding the lock, on whichever instance it runs.
+    pub async fn running_job_log(&self, name: &str) -> Result<Option<Vec<String>>, String> {
+        self.backend.running_job_log(name).await
+    }
+}
+
+impl JobLockGuard {
+    /// Publishes the log of the given job along with the lock.
+    pub fn track(&self, progress: &Weak<BackgroundOperationProgress>) {
+        *self.progress.lock().unwrap() = progress.clone();
+    }
+}
+
+impl Drop for JobLockGuard {
+    fn drop(&mut self) {
+        self.refresh_task.abort();
+        let (backend, name, token) = (self.backend.clone(), self.name.clone(), self.token.clone());
+        tokio::spawn(async move {
+            if let Err(err) = backend.release(&name, &token).await {
+                log::error!("failed to release job lock {}: {}", name, err);
+            }
+        });
+    }
+}
@@ -0,0 +1,28 @@
+//! Models, data layer, scrapers and statistics of the ITSF player database, shared by the server and other tools
+//! working on the same database, e.g. command line tools, bots or batch jobs.
+
+pub mod background;
+pub mod bench;
+pub mod coverage;
+pub mod data;
+pub mod export;
+pub mod filter;
+pub mod freshness;
+pub mod geo;
+pub mod ics;
+pub mod import;
+pub mod joblock;
+pub mod maintenance;
+pub mod notify;
+pub mod projection;
+pub mod replication;
+pub mod retention;
+mod schema;
+pub mod scraping;
+pub mod search;
+pub mod seed;
+pub mod stats;
+pub mod sync;
+pub mod transitions;
+pub mod undo;
+pub mod warmup;
@@ -0,0 +1,139 @@
+//! Keeps the query planner statistics of the database current after large scrapes by running `ANALYZE` on
+//! the biggest tables, and optionally `VACUUM`, once `MAINTENANCE_WRITE_THRESHOLD` players were written
+//! since the last run. Also deletes orphaned player images once a day. Every run is recorded in the job
+//! history.
+
+use std::time::Duration;
+
+use crate::data::images::GcReport;
+use crate::data::jobs::JobRun;
+use crate::data::DatabaseRef;
+
+/// How often the number of player writes is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub const JOB_NAME: &str = "maintenance";
+pub const IMAGE_GC_JOB_NAME: &str = "image_gc";
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct MaintenanceSettings {
+    /// Player writes after which the job runs, 0 disables the scheduled runs.
+    pub write_threshold: u64,
+    /// Number of tables, largest first, that are analyzed.
+    pub tables: usize,
+    pub vacuum: bool,
+}
+
+impl MaintenanceSettings {
+    pub fn from_env() -> Self {
+        let write_threshold = match std::env::var("MAINTENANCE_WRITE_THRESHOLD") {
+            Ok(writes) => writes.parse::<u64>().expect("invalid MAINTENANCE_WRITE_THRESHOLD"),
+            Err(_) => 1000,
+        };
+        let tables = match std::env::var("MAINTENANCE_TABLES") {
+            Ok(tables) => tables
+                .parse::<usize>()
+                .ok()
+                .filter(|tables| *tables > 0)
+                .expect("invalid MAINTENANCE_TABLES"),
+            Err(_) => 3,
+        };
+        MaintenanceSettings {
+            write_threshold,
+            tables,
+            vacuum: std::env::var("MAINTENANCE_VACUUM").is_ok_and(|vacuum| vacuum == "true"),
+        }
+    }
+}
+
+/// Analyzes the biggest tables, vacuums if configured and records the run.
+pub fn run(db: &DatabaseRef, settings: &MaintenanceSettings) -> JobRun {
+    let started_at = chrono::Utc::now().timestamp();
+    let mut log = Vec::new();
+
+    for (table, rows) in db.get_table_sizes().into_iter().take(settings.tables) {
+        let start = std::time::Instant::now();
+        db.analyze_table(&table);
+        log.push(format!(
+            "[Maintenance] analyzed {} ({} rows) in {} ms",
+            table,
+            rows,
+            start.elapsed().as_millis()
+        ));
+    }
+    if settings.vacuum {
+        let start = std::time::Instant::now();
+        db.vacuum();
+        log.push(format!("[Maintenance] vacuumed in {} ms", start.elapsed().as_millis()));
+    }
+
+    let run = JobRun {
+        job: String::from(JOB_NAME),
+        id: None,
+        started_at,
+        finished_at: chrono::Utc::now().timestamp(),
+        log,
+    };
+    for line in &run.log {
+        log::info!("{}", line);
+    }
+    db.add_job_run(&run);
+    run
+}
+
+/// Checks every hour whether enough players were written since the last run, i.e. after a large scrape.
+pub fn start_maintenance_task(db: &DatabaseRef, settings: MaintenanceSettings) {
+    if settings.write_threshold == 0 {
+        return;
+    }
+    let db = db.clone();
+    tokio::spawn(async move {
+        let mut last_generation = db.get_player_generation();
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let generation = db.get_player_generation();
+            if generation - last_generation >= settings.write_threshold {
+                run(&db, &settings);
+                last_generation = generation;
+            }
+        }
+    });
+}
+
+/// Deletes orphaned player images, with `dry_run` only reports them. Deletions are recorded as a job run.
+pub fn collect_image_garbage(db: &DatabaseRef, dry_run: bool) -> Result<GcReport, String> {
+    let started_at = chrono::Utc::now().timestamp();
+    let report = db.collect_image_garbage(dry_run)?;
+    if !dry_run {
+        let mut log = vec![format!(
+            "[Images] deleted {} of {} images, reclaimed {} bytes",
+            report.orphaned.len(),
+            report.scanned,
+            report.reclaimed_bytes
+        )];
+        log.extend(report.orphaned.iter().map(|file| format!("[Images] deleted {}", file)));
+        log::info!("{}", log[0]);
+        db.add_job_run(&JobRun {
+            job: String::from(IMAGE_GC_JOB_NAME),
+            id: None,
+            started_at,
+            finished_at: chrono::Utc::now().timestamp(),
+            log,
+        });
+    }
+    Ok(report)
+}
+
+/// Collects image garbage once a day, starting a day after startup.
+pub fn start_image_gc_task(db: &DatabaseRef) {
+    const INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+    let db = db.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(INTERVAL).await;
+            if let Err(err) = collect_image_garbage(&db, false) {
+                log::error!("[Images] garbage collection failed: {}", err);
+            }
+        }
+    });
+}
@@ -0,0 +1,107 @@
+use futures_util::future::BoxFuture;
+use lazy_static::lazy_static;
+use serde_json::json;
+
+use super::{email, webhook, Message};
+
+/// A broadcast target for job summaries and ranking highlights.
+pub trait Channel: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn post<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<(), String>>;
+}
+
+fn truncate(text: String, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars.saturating_sub(1)) {
+        Some((pos, _)) => format!("{}…", &text[..pos]),
+        None => text,
+    }
+}
+
+struct EmailChannel {
+    address: String,
+}
+
+impl Channel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn post<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(email::send(&self.address, message.clone()))
+    }
+}
+
+struct TelegramChannel {
+    bot_token: String,
+    chat_id: String,
+}
+
+impl Channel for TelegramChannel {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn post<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<(), String>> {
+        const MAX_MESSAGE_LENGTH: usize = 4096;
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+        let payload = json!({
+            "chat_id": self.chat_id,
+            "text": truncate(format!("{}\n\n{}", message.subject, message.body), MAX_MESSAGE_LENGTH),
+        });
+        Box::pin(async move { webhook::post_json(&url, &payload).await })
+    }
+}
+
+struct DiscordChannel {
+    webhook_url: String,
+}
+
+impl Channel for DiscordChannel {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn post<'a>(&'a self, message: &'a Message) -> BoxFuture<'a, Result<(), String>> {
+        const MAX_MESSAGE_LENGTH: usize = 2000;
+        let payload = json!({
+            "content": truncate(format!("**{}**\n{}", message.subject, message.body), MAX_MESSAGE_LENGTH),
+        });
+        Box::pin(async move { webhook::post_json(&self.webhook_url, &payload).await })
+    }
+}
+
+fn load_channels() -> Vec<Box<dyn Channel>> {
+    let mut channels: Vec<Box<dyn Channel>> = Vec::new();
+
+    if let Some(address) = email::admin_address() {
+        channels.push(Box::new(EmailChannel { address }));
+    }
+    if let Ok(bot_token) = std::env::var("TELEGRAM_BOT_TOKEN") {
+        let chat_id = std::env::var("TELEGRAM_CHAT_ID").expect("TELEGRAM_CHAT_ID missing from environment");
+        channels.push(Box::new(TelegramChannel { bot_token, chat_id }));
+    }
+    if let Ok(webhook_url) = std::env::var("DISCORD_WEBHOOK_URL") {
+        channels.push(Box::new(DiscordChannel { webhook_url }));
+    }
+
+    channels
+}
+
+lazy_static! {
+    static ref CHANNELS: Vec<Box<dyn Channel>> = load_channels();
+}
+
+pub fn is_configured() -> bool {
+    !CHANNELS.is_empty()
+}
+
+/// Posts the message to every configured channel, returning the failures.
+pub async fn broadcast(message: &Message) -> Vec<String> {
+    let mut errors = Vec::new();
+    for channel in CHANNELS.iter() {
+        if let Err(err) = channel.post(message).await {
+            errors.push(format!("{}: {}", channel.name(), err));
+        }
+    }
+    errors
+}
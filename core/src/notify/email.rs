@@ -0,0 +1,132 @@
+use lazy_static::lazy_static;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::Message;
+
+/// Failed mails are retried this many times before being dropped.
+const MAX_ATTEMPTS: u32 = 5;
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+struct QueuedMail {
+    to: String,
+    message: Message,
+    attempts: u32,
+}
+
+fn load_mailer() -> Option<Mailer> {
+    let host = std::env::var("SMTP_HOST").ok()?;
+    let port = match std::env::var("SMTP_PORT") {
+        Ok(port) => port.parse::<u16>().expect("invalid SMTP_PORT"),
+        Err(_) => 587,
+    };
+    let from = std::env::var("SMTP_FROM").expect("SMTP_FROM missing from environment");
+
+    let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+        .expect("invalid SMTP_HOST")
+        .port(port);
+    if let Ok(user) = std::env::var("SMTP_USER") {
+        let password = std::env::var("SMTP_PASSWORD").expect("SMTP_PASSWORD missing from environment");
+        builder = builder.credentials(Credentials::new(user, password));
+    }
+
+    Some(Mailer {
+        transport: builder.build(),
+        from,
+    })
+}
+
+lazy_static! {
+    static ref MAILER: Option<Mailer> = load_mailer();
+    static ref RETRY_QUEUE: Mutex<Vec<QueuedMail>> = Mutex::new(Vec::new());
+}
+
+pub fn is_configured() -> bool {
+    MAILER.is_some()
+}
+
+/// Address receiving job completion mails, if any.
+pub fn admin_address() -> Option<String> {
+    std::env::var("NOTIFY_EMAIL").ok().filter(|_| is_configured())
+}
+
+async fn try_send(mailer: &Mailer, to: &str, message: &Message) -> Result<(), String> {
+    let mail = lettre::Message::builder()
+        .from(
+            mailer
+                .from
+                .parse()
+                .map_err(|_| format!("invalid sender: {}", mailer.from))?,
+        )
+        .to(to.parse().map_err(|_| format!("invalid recipient: {}", to))?)
+        .subject(&message.subject)
+        .body(message.body.clone())
+        .map_err(|err| err.to_string())?;
+
+    mailer.transport.send(mail).await.map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Sends the mail, failed deliveries are queued for a later retry.
+pub async fn send(to: &str, message: Message) -> Result<(), String> {
+    let mailer = MAILER.as_ref().ok_or("SMTP is not configured")?;
+    match try_send(mailer, to, &message).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            RETRY_QUEUE.lock().unwrap().push(QueuedMail {
+                to: String::from(to),
+                message,
+                attempts: 1,
+            });
+            Err(err)
+        }
+    }
+}
+
+async fn retry_queued() {
+    let mailer = match MAILER.as_ref() {
+        Some(mailer) => mailer,
+        None => return,
+    };
+
+    let queued: Vec<QueuedMail> = std::mem::take(&mut *RETRY_QUEUE.lock().unwrap());
+    for mut mail in queued {
+        match try_send(mailer, &mail.to, &mail.message).await {
+            Ok(()) => log::info!("[Email] delivered queued mail to {}", mail.to),
+            Err(err) => {
+                mail.attempts += 1;
+                if mail.attempts >= MAX_ATTEMPTS {
+                    log::error!(
+                        "[Email] dropping mail to {} after {} attempts: {}",
+                        mail.to,
+                        mail.attempts,
+                        err
+                    );
+                } else {
+                    RETRY_QUEUE.lock().unwrap().push(mail);
+                }
+            }
+        }
+    }
+}
+
+/// Periodically retries mails whose delivery failed.
+pub fn start_retry_task() {
+    if !is_configured() {
+        return;
+    }
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(RETRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            retry_queued().await;
+        }
+    });
+}
@@ -0,0 +1,310 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+
+use crate::background::BackgroundOperationProgress;
+use crate::data::{itsf, DatabaseRef, Player, PlayerSection};
+
+mod channels;
+pub mod email;
+pub mod webhook;
+
+/// A rendered notification, independent of the channel it is delivered through.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PlayerChanges {
+    pub itsf_lic: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub changes: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+struct SubscriptionNotification<'a> {
+    user_id: &'a str,
+    players: Vec<&'a PlayerChanges>,
+}
+
+/// Describes the ranking, team and championship changes between two versions of a player.
+fn player_changes(before: &Player, after: &Player) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    for ranking in &after.itsf_rankings {
+        match before.itsf_rankings.iter().find(|r| r.matches(ranking)) {
+            Some(old) if old.place != ranking.place => changes.push(format!(
+                "ITSF ranking {} {:?} {:?}: place {} -> {}",
+                ranking.year, ranking.category, ranking.class, old.place, ranking.place
+            )),
+            Some(_) => {}
+            None => changes.push(format!(
+                "ITSF ranking {} {:?} {:?}: place {}",
+                ranking.year, ranking.category, ranking.class, ranking.place
+            )),
+        }
+    }
+
+    for ranking in &after.dtfb_national_rankings {
+        match before.dtfb_national_rankings.iter().find(|r| r.matches(ranking)) {
+            Some(old) if old.place != ranking.place => changes.push(format!(
+                "DTFB ranking {} {:?}: place {} -> {}",
+                ranking.year, ranking.category, old.place, ranking.place
+            )),
+            Some(_) => {}
+            None => changes.push(format!(
+                "DTFB ranking {} {:?}: place {}",
+                ranking.year, ranking.category, ranking.place
+            )),
+        }
+    }
+
+    for result in &after.dtfb_championship_results {
+        if !before.dtfb_championship_results.contains(result) {
+            changes.push(format!(
+                "German championship {} {:?} {:?}: place {}",
+                result.year, result.category, result.class, result.place
+            ));
+        }
+    }
+
+    for team in &after.dtfb_league_teams {
+        if !before.dtfb_league_teams.contains(team) {
+            changes.push(format!("DTFL team {}: {}", team.year, team.name));
+        }
+    }
+
+    changes
+}
+
+fn subscription_message(players: &[&PlayerChanges]) -> Message {
+    let mut body = String::new();
+    for player in players {
+        body += &format!("{} {} ({:08}):\n", player.first_name, player.last_name, player.itsf_lic);
+        for change in &player.changes {
+            body += &format!("  - {}\n", change);
+        }
+        body += "\n";
+    }
+    Message {
+        subject: format!("ITSF Player DB: {} of your players changed", players.len()),
+        body,
+    }
+}
+
+fn job_message(progress: &BackgroundOperationProgress) -> Message {
+    Message {
+        subject: format!("ITSF Player DB: {} finished", progress.get_title()),
+        body: progress.get_log().join("\n"),
+    }
+}
+
+/// What the changes after a download are compared against: copies of the rankings, results and teams of
+/// the players somebody is subscribed to and, if broadcast channels are configured, the top places of every
+/// player's ITSF rankings. Other players and sections aren't copied.
+pub struct Snapshot {
+    subscribed: HashMap<i32, Player>,
+    top_places: HashMap<i32, Vec<itsf::Ranking>>,
+}
+
+/// The parts of a player `player_changes` compares.
+const SECTIONS: [PlayerSection; 3] = [PlayerSection::Rankings, PlayerSection::Results, PlayerSection::Teams];
+
+pub fn snapshot_players(db: &DatabaseRef) -> Snapshot {
+    let subscribed = db
+        .get_subscriptions()
+        .iter()
+        .flat_map(|subscription| subscription.players.iter().copied())
+        .collect::<HashSet<i32>>()
+        .into_iter()
+        .filter_map(|itsf_id| {
+            db.get_player_sections(itsf_id, &SECTIONS)
+                .map(|player| (itsf_id, player))
+        })
+        .collect();
+    let top_places = if channels::is_configured() {
+        db.aggregate_players(|players| players.filter_map(top_place_rankings).collect())
+    } else {
+        HashMap::new()
+    };
+    Snapshot { subscribed, top_places }
+}
+
+lazy_static! {
+    static ref TOP_PLACES: Result<i32, String> = match std::env::var("NOTIFY_TOP_PLACES") {
+        Ok(places) => places
+            .parse::<i32>()
+            .ok()
+            .filter(|places| *places > 0)
+            .ok_or_else(|| format!("invalid NOTIFY_TOP_PLACES: '{}'", places)),
+        Err(_) => Ok(10),
+    };
+}
+
+/// Reads the notification settings at startup, so invalid ones are reported before the first download.
+pub fn init() -> Result<(), String> {
+    TOP_PLACES.clone().map(|_| ())
+}
+
+fn top_places() -> i32 {
+    TOP_PLACES.clone().unwrap_or(10)
+}
+
+/// The ITSF rankings a visible player is in the top places of, if any.
+fn top_place_rankings(player: &Player) -> Option<(i32, Vec<itsf::Ranking>)> {
+    let rankings: Vec<itsf::Ranking> = player
+        .itsf_rankings
+        .iter()
+        .filter(|ranking| ranking.place <= top_places())
+        .cloned()
+        .collect();
+    if player.hidden || rankings.is_empty() {
+        return None;
+    }
+    Some((player.itsf_id, rankings))
+}
+
+/// Players who newly entered the top places of an ITSF ranking.
+fn top_place_entries(db: &DatabaseRef, before: &Snapshot) -> Vec<String> {
+    let mut entries = db.aggregate_players(|players| {
+        let mut entries = Vec::new();
+        for player in players {
+            let rankings = match top_place_rankings(player) {
+                Some((_, rankings)) => rankings,
+                None => continue,
+            };
+            let old_rankings = before
+                .top_places
+                .get(&player.itsf_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for ranking in rankings
+                .iter()
+                .filter(|ranking| !old_rankings.iter().any(|r| r.matches(ranking)))
+            {
+                entries.push(format!(
+                    "{} {}: place {} in ITSF ranking {} {:?} {:?}",
+                    player.first_name, player.last_name, ranking.place, ranking.year, ranking.category, ranking.class
+                ));
+            }
+        }
+        entries
+    });
+
+    entries.sort();
+    entries
+}
+
+/// Posts players entering the top places of a ranking to the broadcast channels.
+pub async fn notify_big_changes(db: &DatabaseRef, before: &Snapshot, progress: Arc<BackgroundOperationProgress>) {
+    if !channels::is_configured() {
+        return;
+    }
+    let entries = top_place_entries(db, before);
+    if entries.is_empty() {
+        return;
+    }
+
+    let message = Message {
+        subject: format!("ITSF Player DB: {} new top {} placements", entries.len(), top_places()),
+        body: entries.join("\n"),
+    };
+    for err in channels::broadcast(&message).await {
+        progress.log(format!("[Notify] failed to post ranking changes: {}", err));
+    }
+}
+
+/// Notifies subscribers about changes of their players since `before` was taken.
+pub async fn notify_subscribers(db: &DatabaseRef, before: &Snapshot, progress: Arc<BackgroundOperationProgress>) {
+    let subscriptions = db.get_subscriptions();
+    let subscribed: HashSet<i32> = subscriptions
+        .iter()
+        .flat_map(|subscription| subscription.players.iter().copied())
+        .collect();
+
+    let changes: HashMap<i32, PlayerChanges> = subscribed
+        .iter()
+        .filter_map(|itsf_id| {
+            let before = before.subscribed.get(itsf_id)?;
+            let after = db
+                .get_player_sections(*itsf_id, &SECTIONS)
+                .filter(|player| !player.hidden)?;
+            let changes = player_changes(before, &after);
+            if changes.is_empty() {
+                return None;
+            }
+            Some((
+                *itsf_id,
+                PlayerChanges {
+                    itsf_lic: *itsf_id,
+                    first_name: after.first_name,
+                    last_name: after.last_name,
+                    changes,
+                },
+            ))
+        })
+        .collect();
+
+    if changes.is_empty() {
+        return;
+    }
+
+    // the job log is public, so it only gets counts, never user ids or webhook URLs
+    let (mut sent, mut failed) = (0, 0);
+    for subscription in subscriptions {
+        let players: Vec<&PlayerChanges> = subscription
+            .players
+            .iter()
+            .filter_map(|itsf_id| changes.get(itsf_id))
+            .collect();
+        if players.is_empty() {
+            continue;
+        }
+
+        if let Some(url) = &subscription.webhook_url {
+            let notification = SubscriptionNotification {
+                user_id: &subscription.user_id,
+                players: players.clone(),
+            };
+            match webhook::post_json_to_public_host(url, &notification).await {
+                Ok(()) => sent += 1,
+                Err(err) => {
+                    log::warn!("[Notify] failed to notify {}: {}", subscription.user_id, err);
+                    failed += 1;
+                }
+            }
+        }
+
+        if let Some(address) = &subscription.email {
+            match email::send(address, subscription_message(&players)).await {
+                Ok(()) => sent += 1,
+                Err(err) => {
+                    log::warn!(
+                        "[Notify] failed to mail {}, queued for retry: {}",
+                        subscription.user_id,
+                        err
+                    );
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if sent > 0 {
+        progress.log(format!("[Notify] sent {} subscriber notifications", sent));
+    }
+    if failed > 0 {
+        progress.log(format!("[Notify] {} subscriber notifications failed", failed));
+    }
+}
+
+/// Posts the job log to the broadcast channels.
+pub async fn notify_job_finished(progress: &BackgroundOperationProgress) {
+    for err in channels::broadcast(&job_message(progress)).await {
+        log::error!("[Notify] failed to post job summary: {}", err);
+    }
+}
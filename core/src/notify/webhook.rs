@@ -0,0 +1,110 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use serde::Serialize;
+
+/// Whether `ip` is reachable on the internet, i.e. not loopback, private, link-local (which includes
+/// the cloud metadata endpoints), shared, multicast or otherwise reserved.
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || a >= 240
+        || (a == 100 && (64..128).contains(&b)))
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        return is_public_v4(ip);
+    }
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || first == 0x2001 && ip.segments()[1] == 0x0db8)
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+/// Resolves the host of a webhook URL, refusing anything but http(s) URLs of public hosts, so
+/// subscribers can't make the server send requests into its own network.
+pub async fn resolve(url: &str) -> Result<(reqwest::Url, Vec<SocketAddr>), String> {
+    let url = reqwest::Url::parse(url).map_err(|_| String::from("invalid webhook url"))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(String::from("invalid webhook url"));
+    }
+    let host = url.host_str().ok_or_else(|| String::from("invalid webhook url"))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| format!("unknown webhook host {}", host))?
+        .collect();
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public(addr.ip())) {
+        return Err(String::from("webhook url must point to a public host"));
+    }
+    Ok((url, addrs))
+}
+
+/// Posts to a webhook configured by an administrator.
+pub async fn post_json<T: Serialize>(url: &str, payload: &T) -> Result<(), String> {
+    send(reqwest::Client::new().post(url), payload).await
+}
+
+/// Posts to a webhook given by a user, which must resolve to a public host. The connection is pinned
+/// to the checked addresses and redirects aren't followed, so neither can lead elsewhere.
+pub async fn post_json_to_public_host<T: Serialize>(url: &str, payload: &T) -> Result<(), String> {
+    let (url, addrs) = resolve(url).await?;
+    let mut client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+    if let Some(domain) = url.domain() {
+        client = client.resolve_to_addrs(domain, &addrs);
+    }
+    let client = client.build().map_err(|err| err.to_string())?;
+    send(client.post(url), payload).await
+}
+
+/// Errors leave out the URL, which may contain a secret like a bot token and ends up in the public job log.
+async fn send<T: Serialize>(request: reqwest::RequestBuilder, payload: &T) -> Result<(), String> {
+    let response = request
+        .json(payload)
+        .send()
+        .await
+        .map_err(|err| err.without_url().to_string())?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("webhook returned {}", response.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_leave_out_the_url() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        // nothing listens on port 1
+        let url = "http://127.0.0.1:1/bot123456:secret-token/sendMessage";
+        let err = runtime.block_on(post_json(url, &"message")).unwrap_err();
+        assert!(!err.contains("secret-token"), "{}", err);
+        assert!(!err.contains("127.0.0.1"), "{}", err);
+    }
+}
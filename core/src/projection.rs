@@ -0,0 +1,137 @@
+//! Projects a player's ITSF ranking points at a future date under the rolling ranking rules: the points of a
+//! result count for 12 months after they were earned, then drop out. The results are the stored season
+//! rankings, each worth the points of its place on the scale of the country ranking. A placement counts as
+//! earned when it was downloaded, but no later than the end of its season, so final rankings downloaded after
+//! the season don't count longer than the season's results did.
+
+use chrono::{Months, NaiveDate};
+
+use crate::data::itsf::{Ranking, RankingCategory, RankingClass};
+use crate::data::Player;
+use crate::stats;
+
+/// Months the points of a result count.
+pub const VALIDITY_MONTHS: u32 = 12;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProjectedResult {
+    pub year: i32,
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    pub place: i32,
+    pub points: i32,
+    pub earned_on: NaiveDate,
+    /// First day the points no longer count.
+    pub expires_on: NaiveDate,
+}
+
+impl ProjectedResult {
+    fn new(ranking: &Ranking) -> Option<Self> {
+        let season_end = NaiveDate::from_ymd_opt(ranking.year, 12, 31)?;
+        let downloaded = ranking
+            .provenance
+            .as_ref()
+            .and_then(|provenance| chrono::DateTime::from_timestamp(provenance.scraped_at, 0))
+            .map(|scraped_at| scraped_at.date_naive());
+        let earned_on = downloaded.map_or(season_end, |downloaded| downloaded.min(season_end));
+        Some(ProjectedResult {
+            year: ranking.year,
+            category: ranking.category,
+            class: ranking.class,
+            place: ranking.place,
+            points: stats::place_points(ranking.place),
+            earned_on,
+            expires_on: earned_on.checked_add_months(Months::new(VALIDITY_MONTHS))?,
+        })
+    }
+
+    fn counts_on(&self, date: NaiveDate) -> bool {
+        self.earned_on <= date && date < self.expires_on
+    }
+}
+
+/// Points of a ranking today and at the projected date.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RankingProjection {
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    pub points_today: i32,
+    pub points: i32,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Projection {
+    pub itsf_lic: i32,
+    pub date: NaiveDate,
+    pub points_today: i32,
+    pub points: i32,
+    /// Rankings with points today or at the date.
+    pub rankings: Vec<RankingProjection>,
+    /// Results still counting at the date.
+    pub counted: Vec<ProjectedResult>,
+    /// Results counting today that drop out until the date, the earliest expiry first.
+    pub expiring: Vec<ProjectedResult>,
+}
+
+/// Projects the player's points at `date`, which must not be before `today`. Combined rankings are left out,
+/// they are made of the singles and doubles results and would count them twice.
+pub fn project(player: &Player, today: NaiveDate, date: NaiveDate) -> Result<Projection, String> {
+    if date < today {
+        return Err(String::from("date must not be in the past"));
+    }
+    let results: Vec<ProjectedResult> = player
+        .itsf_rankings
+        .iter()
+        .filter(|ranking| ranking.class != RankingClass::Combined)
+        .filter_map(ProjectedResult::new)
+        .filter(|result| result.points > 0)
+        .collect();
+
+    let mut rankings: Vec<RankingProjection> = Vec::new();
+    for result in &results {
+        let (today, at_date) = (result.counts_on(today), result.counts_on(date));
+        if !today && !at_date {
+            continue;
+        }
+        let index = match rankings
+            .iter()
+            .position(|ranking| (ranking.category, ranking.class) == (result.category, result.class))
+        {
+            Some(index) => index,
+            None => {
+                rankings.push(RankingProjection {
+                    category: result.category,
+                    class: result.class,
+                    points_today: 0,
+                    points: 0,
+                });
+                rankings.len() - 1
+            }
+        };
+        if today {
+            rankings[index].points_today += result.points;
+        }
+        if at_date {
+            rankings[index].points += result.points;
+        }
+    }
+    rankings.sort_by_key(|ranking| (ranking.category as i8, ranking.class as i8));
+
+    let mut expiring: Vec<ProjectedResult> = results
+        .iter()
+        .filter(|result| result.counts_on(today) && !result.counts_on(date))
+        .cloned()
+        .collect();
+    expiring.sort_by_key(|result| result.expires_on);
+    let counted: Vec<ProjectedResult> = results.into_iter().filter(|result| result.counts_on(date)).collect();
+
+    Ok(Projection {
+        itsf_lic: player.itsf_id,
+        date,
+        points_today: rankings.iter().map(|ranking| ranking.points_today).sum(),
+        points: rankings.iter().map(|ranking| ranking.points).sum(),
+        rankings,
+        counted,
+        expiring,
+    })
+}
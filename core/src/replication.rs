@@ -0,0 +1,199 @@
+//! Pull-based replication, for clubs keeping a local mirror of a central database: a secondary instance polls
+//! `/sync?format=documents` of its primary and stores the changed players as they are there, with their comments
+//! and images. Changes made on the secondary are replaced with the next change of the player on the primary.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::data::documents::PlayerDocument;
+use crate::data::jobs::JobRun;
+use crate::data::replication::ReplicationState;
+use crate::data::{DatabaseRef, PlayerImage};
+
+pub const JOB_NAME: &str = "replication";
+
+/// Players requested per sync request.
+const PAGE_SIZE: usize = 500;
+
+/// A changed player of `/sync?format=documents`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SyncDocument {
+    pub revision: u64,
+    /// Of `/image/{ITSF-ID}-{hash}.jpg`, none without image.
+    pub image_hash: Option<String>,
+    pub document: PlayerDocument,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SyncDocuments {
+    /// Pass as `since_revision` next time.
+    pub revision: u64,
+    /// False if more changes follow after `revision`.
+    pub complete: bool,
+    pub players: Vec<SyncDocument>,
+}
+
+#[derive(serde::Deserialize)]
+struct SyncResponse {
+    data: SyncDocuments,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplicationSettings {
+    /// Base URL of the primary, without trailing slash.
+    pub primary_url: String,
+    /// Login on the primary.
+    pub user: String,
+    pub password: String,
+    pub interval: Duration,
+}
+
+impl ReplicationSettings {
+    /// Configured via `REPLICATE_FROM`, `REPLICATE_USER`, `REPLICATE_PASSWORD` and `REPLICATE_INTERVAL` (seconds,
+    /// default 300), none without `REPLICATE_FROM`.
+    pub fn from_env() -> Option<Self> {
+        let primary_url = std::env::var("REPLICATE_FROM").ok()?;
+        let interval = match std::env::var("REPLICATE_INTERVAL") {
+            Ok(secs) => secs
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .expect("invalid REPLICATE_INTERVAL"),
+            Err(_) => 300,
+        };
+        Some(ReplicationSettings {
+            primary_url: String::from(primary_url.trim_end_matches('/')),
+            user: std::env::var("REPLICATE_USER").expect("REPLICATE_USER missing from environment"),
+            password: std::env::var("REPLICATE_PASSWORD").expect("REPLICATE_PASSWORD missing from environment"),
+            interval: Duration::from_secs(interval),
+        })
+    }
+}
+
+struct Replicator {
+    db: DatabaseRef,
+    settings: ReplicationSettings,
+    client: reqwest::Client,
+    /// Primary's hash of the image replicated per player. Images are re-encoded when they're stored, so the local
+    /// hash differs. Not kept across restarts, the image of a player is then downloaded again with its next change.
+    image_hashes: HashMap<i32, String>,
+}
+
+impl Replicator {
+    async fn get(&self, url: &str) -> Result<reqwest::Response, String> {
+        self.client
+            .get(url)
+            .basic_auth(&self.settings.user, Some(&self.settings.password))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| err.to_string())
+    }
+
+    async fn replicate_image(&mut self, itsf_id: i32, hash: &str) -> Result<(), String> {
+        if self.image_hashes.get(&itsf_id).map(String::as_str) == Some(hash) {
+            return Ok(());
+        }
+        let url = format!("{}/image/{}-{}.jpg", self.settings.primary_url, itsf_id, hash);
+        let image_data = self.get(&url).await?.bytes().await.map_err(|err| err.to_string())?;
+        self.db.set_player_image(PlayerImage {
+            itsf_id,
+            image_data: image_data.to_vec(),
+            image_format: String::from("jpg"),
+        })?;
+        self.image_hashes.insert(itsf_id, String::from(hash));
+        Ok(())
+    }
+
+    /// Replicates the changes since the last sync, page by page, logging to `log`. The position is kept after
+    /// every page, so a failed sync continues where it stopped.
+    async fn sync(&mut self, log: &mut Vec<String>) -> Result<(), String> {
+        let primary_url = self.settings.primary_url.clone();
+        let mut state = self.db.get_replication_state(&primary_url).unwrap_or_default();
+        loop {
+            let url = format!(
+                "{}/sync?format=documents&since_revision={}&limit={}",
+                primary_url, state.revision, PAGE_SIZE
+            );
+            let sync = self
+                .get(&url)
+                .await?
+                .json::<SyncResponse>()
+                .await
+                .map_err(|err| err.to_string())?
+                .data;
+            if !sync.players.is_empty() {
+                log.push(format!("[Replication] replicating {} players", sync.players.len()));
+            }
+            for player in sync.players {
+                let player_data = player.document.into_player();
+                let itsf_id = player_data.itsf_id;
+                let anonymized = player_data.anonymized;
+                self.db.replicate_player(player_data);
+                match player.image_hash.filter(|_| !anonymized) {
+                    Some(hash) => {
+                        if let Err(err) = self.replicate_image(itsf_id, &hash).await {
+                            log.push(format!(
+                                "[Replication] failed to replicate image of {}: {}",
+                                itsf_id, err
+                            ));
+                        }
+                    }
+                    None => {
+                        self.image_hashes.remove(&itsf_id);
+                        self.db.delete_player_image(itsf_id);
+                    }
+                }
+            }
+            state = ReplicationState {
+                revision: sync.revision,
+                synced_at: Some(chrono::Utc::now().timestamp()),
+            };
+            self.db.set_replication_state(&primary_url, &state);
+            if sync.complete {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Replicates from the primary right away and then every `interval`. Syncs that replicated players or failed
+/// are recorded in the job history.
+pub fn start_replication_task(db: &DatabaseRef, settings: ReplicationSettings) {
+    const TIMEOUT: Duration = Duration::from_secs(60);
+    let client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .expect("failed to set up the HTTP client");
+    let mut replicator = Replicator {
+        db: db.clone(),
+        settings,
+        client,
+        image_hashes: HashMap::new(),
+    };
+    tokio::spawn(async move {
+        loop {
+            let started_at = chrono::Utc::now().timestamp();
+            let mut log = Vec::new();
+            if let Err(err) = replicator.sync(&mut log).await {
+                log.push(format!(
+                    "[Replication] sync from {} failed: {}",
+                    replicator.settings.primary_url, err
+                ));
+            }
+            if !log.is_empty() {
+                for line in &log {
+                    log::info!("{}", line);
+                }
+                replicator.db.add_job_run(&JobRun {
+                    job: String::from(JOB_NAME),
+                    id: None,
+                    started_at,
+                    finished_at: chrono::Utc::now().timestamp(),
+                    log,
+                });
+            }
+            tokio::time::sleep(replicator.settings.interval).await;
+        }
+    });
+}
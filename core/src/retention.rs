@@ -0,0 +1,132 @@
+//! Deletes data older than its retention period in a daily background job.
+//! Periods are configured in days via `JOB_LOG_RETENTION`, `REQUEST_SAMPLE_RETENTION` and `EVENT_RETENTION`.
+
+use std::time::Duration;
+
+use crate::data::DatabaseRef;
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// Days each kind of data is kept.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct RetentionPolicy {
+    /// Logs of jobs that never released their lock, counted from the lock's expiry, and the job history.
+    pub job_logs: i64,
+    pub request_samples: i64,
+    /// Tournaments, counted from their last day.
+    pub events: i64,
+}
+
+fn days_from_env(name: &str, default: i64) -> i64 {
+    match std::env::var(name) {
+        Ok(days) => days
+            .parse::<i64>()
+            .ok()
+            .filter(|days| *days >= 0)
+            .unwrap_or_else(|| panic!("invalid {}", name)),
+        Err(_) => default,
+    }
+}
+
+impl RetentionPolicy {
+    pub fn from_env() -> Self {
+        RetentionPolicy {
+            job_logs: days_from_env("JOB_LOG_RETENTION", 90),
+            request_samples: days_from_env("REQUEST_SAMPLE_RETENTION", 30),
+            events: days_from_env("EVENT_RETENTION", 365),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RuleReport {
+    pub rule: &'static str,
+    pub retention_days: i64,
+    /// Unix timestamp, older data is deleted.
+    pub cutoff: i64,
+    /// Deleted items, or those that would be deleted in a dry run.
+    pub items: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PruneReport {
+    pub dry_run: bool,
+    pub rules: Vec<RuleReport>,
+}
+
+/// Applies every rule of the policy, with `dry_run` only counts what would be deleted. The counts read the
+/// primary like the deletes, so a dry run reports exactly what a real run would delete despite replication lag.
+pub fn prune(db: &DatabaseRef, policy: &RetentionPolicy, dry_run: bool) -> PruneReport {
+    let now = chrono::Utc::now().timestamp();
+    let mut rules = Vec::new();
+
+    let cutoff = now - policy.job_logs * DAY;
+    let items = match dry_run {
+        true => db.count_job_locks_expired_before(cutoff),
+        false => db.prune_job_locks(cutoff),
+    };
+    rules.push(RuleReport {
+        rule: "job_logs",
+        retention_days: policy.job_logs,
+        cutoff,
+        items,
+    });
+
+    let cutoff = now - policy.job_logs * DAY;
+    let items = match dry_run {
+        true => db.count_job_runs_before(cutoff),
+        false => db.prune_job_runs(cutoff),
+    };
+    rules.push(RuleReport {
+        rule: "job_history",
+        retention_days: policy.job_logs,
+        cutoff,
+        items,
+    });
+
+    let cutoff = now - policy.request_samples * DAY;
+    let items = match dry_run {
+        true => db.count_request_samples_before(cutoff),
+        false => db.prune_request_samples(cutoff),
+    };
+    rules.push(RuleReport {
+        rule: "request_samples",
+        retention_days: policy.request_samples,
+        cutoff,
+        items,
+    });
+
+    let cutoff = now - policy.events * DAY;
+    let cutoff_date = chrono::Utc::now().date_naive() - chrono::Duration::days(policy.events);
+    let event_ids: Vec<i32> = db
+        .get_events_before(cutoff_date)
+        .iter()
+        .map(|event| event.event_id)
+        .collect();
+    if !dry_run {
+        db.delete_events(&event_ids);
+    }
+    rules.push(RuleReport {
+        rule: "events",
+        retention_days: policy.events,
+        cutoff,
+        items: event_ids.len(),
+    });
+
+    PruneReport { dry_run, rules }
+}
+
+/// Prunes once a day, starting right away.
+pub fn start_pruning_task(db: &DatabaseRef, policy: RetentionPolicy) {
+    const INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+    let db = db.clone();
+    tokio::spawn(async move {
+        loop {
+            let report = prune(&db, &policy, false);
+            for rule in report.rules.iter().filter(|rule| rule.items > 0) {
+                log::info!("[Retention] deleted {} {}", rule.items, rule.rule);
+            }
+            tokio::time::sleep(INTERVAL).await;
+        }
+    });
+}
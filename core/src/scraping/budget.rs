@@ -0,0 +1,86 @@
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Limits of all downloads together, across all running jobs, configured via
+/// `SCRAPE_REQUESTS_PER_MINUTE` and `SCRAPE_BANDWIDTH` (KB per second). 0 means unlimited.
+#[derive(Debug, Clone)]
+pub struct Budget {
+    pub requests_per_minute: u64,
+    pub bytes_per_second: u64,
+}
+
+fn limit_from_env(name: &str) -> u64 {
+    match std::env::var(name) {
+        Ok(limit) => limit.parse::<u64>().unwrap_or_else(|_| panic!("invalid {}", name)),
+        Err(_) => 0,
+    }
+}
+
+impl Budget {
+    pub fn from_env() -> Self {
+        Budget {
+            requests_per_minute: limit_from_env("SCRAPE_REQUESTS_PER_MINUTE"),
+            bytes_per_second: limit_from_env("SCRAPE_BANDWIDTH") * 1024,
+        }
+    }
+}
+
+/// When the next request may start, advanced by every request and every downloaded byte.
+struct Schedule {
+    next_request: Instant,
+    bandwidth_free: Instant,
+}
+
+lazy_static! {
+    static ref BUDGET: Budget = Budget::from_env();
+    static ref SCHEDULE: Mutex<Schedule> = Mutex::new(Schedule {
+        next_request: Instant::now(),
+        bandwidth_free: Instant::now(),
+    });
+}
+
+/// Responses and their bytes downloaded since the start, for the average response size.
+static RESPONSES: AtomicU64 = AtomicU64::new(0);
+static RESPONSE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn limits() -> &'static Budget {
+    &BUDGET
+}
+
+/// Average size of the responses downloaded so far, none before the first.
+pub(crate) fn average_response_bytes() -> Option<u64> {
+    let responses = RESPONSES.load(Ordering::Relaxed);
+    (responses > 0).then(|| RESPONSE_BYTES.load(Ordering::Relaxed) / responses)
+}
+
+/// Waits until the budget allows another request, reserving its slot.
+pub async fn acquire() {
+    if BUDGET.requests_per_minute == 0 && BUDGET.bytes_per_second == 0 {
+        return;
+    }
+    let start = {
+        let mut schedule = SCHEDULE.lock().await;
+        let start = Instant::now().max(schedule.next_request).max(schedule.bandwidth_free);
+        if BUDGET.requests_per_minute > 0 {
+            schedule.next_request = start + Duration::from_secs(60) / BUDGET.requests_per_minute as u32;
+        }
+        start
+    };
+    tokio::time::sleep_until(start).await;
+}
+
+/// Accounts for a downloaded response, delaying the following requests until the bandwidth it used
+/// has been made up for.
+pub async fn charge(bytes: usize) {
+    RESPONSES.fetch_add(1, Ordering::Relaxed);
+    RESPONSE_BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+    if BUDGET.bytes_per_second == 0 {
+        return;
+    }
+    let mut schedule = SCHEDULE.lock().await;
+    let used = Duration::from_secs_f64(bytes as f64 / BUDGET.bytes_per_second as f64);
+    schedule.bandwidth_free = Instant::now().max(schedule.bandwidth_free) + used;
+}
@@ -0,0 +1,108 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use reqwest::{Client, StatusCode};
+
+use super::budget;
+
+/// Tuning of the HTTP client shared by all scrapers, configured via `SCRAPE_TIMEOUT`,
+/// `SCRAPE_CONNECT_TIMEOUT`, `SCRAPE_POOL_IDLE_TIMEOUT` and `SCRAPE_POOL_MAX_IDLE`.
+#[derive(Debug, Clone)]
+pub struct ClientSettings {
+    /// Whole request including the response body.
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    /// How long an unused connection is kept open for the next request to the same host.
+    pub pool_idle_timeout: Duration,
+    /// Idle connections kept open per host.
+    pub pool_max_idle: usize,
+}
+
+fn secs_from_env(name: &str, default: u64) -> Duration {
+    match std::env::var(name) {
+        Ok(secs) => Duration::from_secs(secs.parse::<u64>().unwrap_or_else(|_| panic!("invalid {}", name))),
+        Err(_) => Duration::from_secs(default),
+    }
+}
+
+impl ClientSettings {
+    pub fn from_env() -> Self {
+        ClientSettings {
+            timeout: secs_from_env("SCRAPE_TIMEOUT", 60),
+            connect_timeout: secs_from_env("SCRAPE_CONNECT_TIMEOUT", 10),
+            pool_idle_timeout: secs_from_env("SCRAPE_POOL_IDLE_TIMEOUT", 90),
+            pool_max_idle: match std::env::var("SCRAPE_POOL_MAX_IDLE") {
+                Ok(max_idle) => max_idle.parse::<usize>().expect("invalid SCRAPE_POOL_MAX_IDLE"),
+                Err(_) => 16,
+            },
+        }
+    }
+
+    /// Uses HTTP/2 where the host offers it, and keeps connections alive between requests. There is no
+    /// cookie store, cookies like the DTFB season filter are passed per request so they don't leak
+    /// into the next one.
+    fn build(&self) -> Result<Client, reqwest::Error> {
+        Client::builder()
+            .danger_accept_invalid_certs(true)
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle)
+            .tcp_keepalive(self.pool_idle_timeout)
+            .http2_adaptive_window(true)
+            .build()
+    }
+}
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// The client all downloads go through, so connections to the scraped hosts are reused.
+fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        ClientSettings::from_env()
+            .build()
+            .expect("failed to set up the HTTP client")
+    })
+}
+
+async fn get(url: &str, headers: &[(&str, &str)]) -> Result<String, reqwest::Error> {
+    let mut request = client().get(url);
+    for header in headers {
+        request = request.header(header.0, header.1);
+    }
+
+    budget::acquire().await;
+    let body = request.send().await?.text().await?;
+    budget::charge(body.len()).await;
+    Ok(body)
+}
+
+pub async fn download(url: &str, headers: &[(&str, &str)]) -> Result<String, String> {
+    get(url, headers).await.map_err(|err| err.to_string())
+}
+
+/// The response body, `None` if the server answers 404.
+pub async fn download_bytes(url: &str) -> Result<Option<Vec<u8>>, String> {
+    budget::acquire().await;
+    let response = match client().get(url).send().await {
+        Ok(response) if response.status() == StatusCode::NOT_FOUND => return Ok(None),
+        Ok(response) => response,
+        Err(err) if err.status() == Some(StatusCode::NOT_FOUND) => return Ok(None),
+        Err(err) => return Err(err.to_string()),
+    };
+    let bytes = response.bytes().await.map_err(|err| err.to_string())?;
+    budget::charge(bytes.len()).await;
+    Ok(Some(bytes.to_vec()))
+}
+
+/// Status of a HEAD request, any response counts as the host being reachable.
+pub async fn head(url: &str) -> Result<u16, String> {
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    let response = client()
+        .head(url)
+        .timeout(TIMEOUT)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(response.status().as_u16())
+}
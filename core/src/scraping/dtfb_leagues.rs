@@ -0,0 +1,131 @@
+use scraper::{ElementRef, Html, Selector};
+
+use crate::background::BackgroundOperationProgress;
+use crate::data::leagues::*;
+use crate::data::quality::Parser;
+use crate::data::season::Season;
+use crate::data::DatabaseRef;
+
+use super::pipeline::{Page, Scraper};
+use super::{sources, SourceHost};
+
+fn text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Parses a standings table, rows that don't start with a position are skipped.
+/// The team is in the second column and the points are in the last one.
+fn parse_standings(table: ElementRef) -> Vec<LeagueStanding> {
+    let mut standings = Vec::new();
+
+    for row in table.select(&Selector::parse("tr").unwrap()) {
+        let cells: Vec<String> = row.select(&Selector::parse("td").unwrap()).map(text).collect();
+        if cells.len() < 3 {
+            continue;
+        }
+        let position = cells[0].trim_end_matches('.').parse::<i32>();
+        let points = cells[cells.len() - 1].parse::<i32>();
+        match (position, points) {
+            (Ok(position), Ok(points)) => standings.push(LeagueStanding {
+                position,
+                team: cells[1].clone(),
+                points,
+            }),
+            _ => log::debug!("skipping league table row: {:?}", cells),
+        }
+    }
+
+    standings
+}
+
+/// Parses the tables of all divisions, every table belongs to the heading before it.
+fn parse_league_tables(html: &Html) -> Vec<LeagueTable> {
+    let mut tables = Vec::new();
+    let mut division = None;
+
+    for element in html.select(&Selector::parse("h2, h3, table").unwrap()) {
+        if element.value().name() != "table" {
+            division = Some(text(element));
+            continue;
+        }
+        let standings = parse_standings(element);
+        if standings.is_empty() {
+            continue;
+        }
+        tables.push(LeagueTable {
+            division: division.clone().unwrap_or(format!("Division {}", tables.len() + 1)),
+            standings,
+        });
+    }
+
+    tables
+}
+
+/// The Bundesliga tables of all divisions of a season.
+pub struct LeagueTables;
+
+impl Scraper for LeagueTables {
+    type Request = Season;
+    type Output = Vec<LeagueTable>;
+
+    fn host(&self) -> SourceHost {
+        SourceHost::Dtfb
+    }
+
+    fn parser(&self) -> Parser {
+        Parser::DtfbLeagues
+    }
+
+    fn request(&self, request: &str) -> Result<Season, String> {
+        Season::parse_dtfb(request)
+    }
+
+    fn url(&self, _season: &Season) -> String {
+        format!("{}/wettbewerbe/bundesliga/tabelle", sources::get().dtfb)
+    }
+
+    fn cookies(&self, season: &Season) -> Option<String> {
+        Some(format!("sportsmanager_filter_saison_id={}", season.year()))
+    }
+
+    fn parse(&self, _season: &Season, body: &str) -> Result<Vec<LeagueTable>, String> {
+        Ok(parse_league_tables(&Html::parse_document(body)))
+    }
+
+    fn records(&self, tables: &Vec<LeagueTable>) -> Vec<Vec<(&'static str, bool)>> {
+        tables
+            .iter()
+            .map(|table| {
+                vec![
+                    ("division", !table.division.is_empty()),
+                    ("standings", !table.standings.is_empty()),
+                ]
+            })
+            .collect()
+    }
+
+    async fn persist(
+        &self,
+        db: &DatabaseRef,
+        progress: &BackgroundOperationProgress,
+        season: Season,
+        page: Page<Vec<LeagueTable>>,
+    ) {
+        if page.data.is_empty() {
+            progress.log(format!("[DTFB] No league tables for season {}", season));
+            return;
+        }
+        progress.log(format!(
+            "[DTFB] Downloaded {} league tables for season {}",
+            page.data.len(),
+            season
+        ));
+        db.set_league_tables(season, page.data);
+    }
+}
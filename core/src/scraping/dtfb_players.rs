@@ -1,15 +1,22 @@
 use scraper::{Html, Selector};
 
+use crate::background::BackgroundOperationProgress;
 use crate::data::dtfb::*;
+use crate::data::license::LicenseNumber;
+use crate::data::quality::Parser;
+use crate::data::season::Season;
+use crate::data::DatabaseRef;
 
-use super::download;
+use super::pipeline::{self, Page, Scraper};
+use super::{provenance, sources, SourceHost};
 
 pub async fn collect_dtfb_ids_from_rankings(ranking_id: i32, max_rank: usize) -> Result<Vec<i32>, String> {
     let url = format!(
-        "https://dtfb.de/wettbewerbe/turnierserie/rangliste?task=rangliste&id={}",
+        "{}/wettbewerbe/turnierserie/rangliste?task=rangliste&id={}",
+        sources::get().dtfb,
         ranking_id
     );
-    let html = download::download_html(&url).await?;
+    let html = Html::parse_document(&pipeline::fetch_url(&url, None).await?);
 
     let mut ret = Vec::new();
 
@@ -28,11 +35,10 @@ pub async fn collect_dtfb_ids_from_rankings(ranking_id: i32, max_rank: usize) ->
     Ok(ret)
 }
 
-pub async fn collect_dtfb_rankings_for_season(season: i32) -> Result<Vec<i32>, String> {
-    let url = "https://dtfb.de/wettbewerbe/turnierserie/rangliste";
-    let cookies = format!("sportsmanager_filter_saison_id={}", season);
-    let html = download::download(url, &[("Cookie", &cookies)]).await?;
-    let html = Html::parse_document(&html);
+pub async fn collect_dtfb_rankings_for_season(season: Season) -> Result<Vec<i32>, String> {
+    let url = format!("{}/wettbewerbe/turnierserie/rangliste", sources::get().dtfb);
+    let cookies = format!("sportsmanager_filter_saison_id={}", season.year());
+    let html = Html::parse_document(&pipeline::fetch_url(&url, Some(&cookies)).await?);
 
     let mut ret = Vec::new();
 
@@ -83,18 +89,13 @@ fn array<'a>(json: &'a serde_json::Value, name: &str) -> Result<&'a Vec<serde_js
 }
 
 impl DtfbPlayerInfo {
-    async fn try_download(dtfb_id: i32) -> Result<Self, String> {
-        let url = format!(
-            "https://dtfb.de/component/sportsmanager?task=spieler_details&id={}&format=json",
-            dtfb_id
-        );
-        let json = download::download(&url, &[]).await?;
-        let json: serde_json::Value = serde_json::from_str(&json).map_err(|err| err.to_string())?;
+    fn parse(dtfb_id: i32, json: &str) -> Result<Self, String> {
+        let json: serde_json::Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
 
         let data = value(&json, "data")?;
         let spieler = value(data, "spieler")?;
         let spieler_id = int(spieler, "spieler_id")?;
-        let lizenznr = int(spieler, "lizenznr")?;
+        let lizenznr = LicenseNumber::new(int(spieler, "lizenznr")?)?.get();
         let teams = array(data, "teams")?;
         let turnier_platzierungen = array(data, "turnier_platzierungen")?;
         let ranglisten_platzierungen = array(data, "ranglisten_platzierungen")?;
@@ -145,6 +146,7 @@ impl DtfbPlayerInfo {
                         year: saisonbezeichnung as _,
                         class,
                         category,
+                        provenance: None,
                     })
                 }
             }
@@ -167,6 +169,7 @@ impl DtfbPlayerInfo {
                     year: saisonbezeichnung as _,
                     place: platz as _,
                     category,
+                    provenance: None,
                 });
             }
         }
@@ -179,10 +182,87 @@ impl DtfbPlayerInfo {
             teams: player_teams,
         })
     }
+}
+
+/// The profile of a DTFB player, with national championship results, rankings and league teams. They're stored
+/// with the player of the ITSF licence in the profile, if that is stored.
+pub struct Profiles;
+
+impl Scraper for Profiles {
+    /// The DTFB player id.
+    type Request = i32;
+    type Output = DtfbPlayerInfo;
+
+    fn host(&self) -> SourceHost {
+        SourceHost::Dtfb
+    }
 
-    pub async fn download(dtfb_id: i32) -> Result<Self, String> {
-        Self::try_download(dtfb_id)
-            .await
-            .map_err(|err| format!("DTFB={}: {}", dtfb_id, err))
+    fn parser(&self) -> Parser {
+        Parser::DtfbPlayers
+    }
+
+    fn request(&self, request: &str) -> Result<i32, String> {
+        request
+            .parse::<i32>()
+            .map_err(|_| format!("invalid DTFB player id: '{}'", request))
+    }
+
+    fn url(&self, dtfb_id: &i32) -> String {
+        format!(
+            "{}/component/sportsmanager?task=spieler_details&id={}&format=json",
+            sources::get().dtfb,
+            dtfb_id
+        )
+    }
+
+    fn parse(&self, dtfb_id: &i32, body: &str) -> Result<DtfbPlayerInfo, String> {
+        DtfbPlayerInfo::parse(*dtfb_id, body)
+    }
+
+    fn records(&self, _info: &DtfbPlayerInfo) -> Vec<Vec<(&'static str, bool)>> {
+        vec![Vec::new()]
+    }
+
+    async fn persist(
+        &self,
+        db: &DatabaseRef,
+        progress: &BackgroundOperationProgress,
+        _dtfb_id: i32,
+        page: Page<DtfbPlayerInfo>,
+    ) {
+        let provenance = || provenance(progress, &page.url, page.scraped_at);
+        let info = page.data;
+        db.set_player_dtfb_id(info.itsf_id, info.dtfb_id);
+
+        for result in info.championship_results {
+            db.add_player_dtfb_championship_result(
+                info.itsf_id,
+                NationalChampionshipResult {
+                    provenance: provenance(),
+                    ..result
+                },
+            );
+        }
+
+        for ranking in info.national_rankings {
+            db.add_player_dtfb_ranking(
+                info.itsf_id,
+                NationalRanking {
+                    provenance: provenance(),
+                    ..ranking
+                },
+            );
+        }
+
+        for team in info.teams {
+            db.add_player_dtfb_team(
+                info.itsf_id,
+                NationalTeam {
+                    year: team.0,
+                    name: team.1,
+                    provenance: provenance(),
+                },
+            );
+        }
     }
 }
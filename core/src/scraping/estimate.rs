@@ -0,0 +1,172 @@
+//! Dry run of a download job: the requests it would send, counted from the stored data, and how long the
+//! download budget lets them take. The counts are upper bounds, e.g. a ranking unchanged since its last download
+//! is skipped after its first request.
+
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::Datelike;
+
+use crate::data::quality::Parser;
+use crate::data::{itsf, season::Season, DatabaseRef, Player};
+
+use super::{budget, MaxRanks};
+
+/// Ranking pages DTFB lists per season, one per category.
+const DTFB_RANKINGS_PER_SEASON: usize = 4;
+
+/// Counted under `pages` for the player images.
+const IMAGES: &str = "itsf_images";
+
+/// The budget limit the requests wait for most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    Requests,
+    Bandwidth,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct Estimate {
+    /// Pages by scraper, e.g. `itsf_players`, player images as `itsf_images`.
+    pub pages: BTreeMap<&'static str, usize>,
+    pub requests: usize,
+    /// What the job would leave out, e.g. closed rankings.
+    pub skipped: Vec<String>,
+    /// Time the requests take under `SCRAPE_REQUESTS_PER_MINUTE` and `SCRAPE_BANDWIDTH`, none without limits.
+    /// The bandwidth is only accounted for once a download measured the size of the responses.
+    pub seconds: Option<u64>,
+    pub limited_by: Option<Limit>,
+}
+
+impl Estimate {
+    fn add(&mut self, page: &'static str, count: usize) {
+        *self.pages.entry(page).or_default() += count;
+        self.requests += count;
+    }
+
+    /// Adds a profile and an image request per player.
+    fn add_players(&mut self, count: usize) {
+        self.add(Parser::ItsfPlayers.name(), count);
+        self.add(IMAGES, count);
+    }
+
+    fn finish(mut self) -> Self {
+        let limits = budget::limits();
+        let by_requests =
+            (limits.requests_per_minute > 0).then(|| (self.requests as u64 * 60).div_ceil(limits.requests_per_minute));
+        let by_bandwidth = budget::average_response_bytes()
+            .filter(|_| limits.bytes_per_second > 0)
+            .map(|bytes| (self.requests as u64 * bytes).div_ceil(limits.bytes_per_second));
+        (self.seconds, self.limited_by) = match (by_requests, by_bandwidth) {
+            (Some(requests), Some(bandwidth)) if bandwidth > requests => (Some(bandwidth), Some(Limit::Bandwidth)),
+            (Some(requests), _) => (Some(requests), Some(Limit::Requests)),
+            (None, Some(bandwidth)) => (Some(bandwidth), Some(Limit::Bandwidth)),
+            (None, None) => (None, None),
+        };
+        self
+    }
+}
+
+/// Whether the rankings job would download the profile of the stored player.
+fn needs_download(player: &Player, force: bool) -> bool {
+    !player.anonymized && (force || player.is_stale())
+}
+
+/// The ITSF rankings job with the arguments of `start_itsf_rankings_download`. The stored players of a ranking
+/// are the ones it listed at its last download, places beyond those are counted as players not stored yet.
+pub fn itsf_rankings(
+    db: &DatabaseRef,
+    rankings: &[(Season, itsf::RankingCategory, itsf::RankingClass)],
+    max_ranks: &MaxRanks,
+    force: bool,
+    force_closed: bool,
+) -> Estimate {
+    let mut estimate = Estimate::default();
+    let mut players = HashSet::new();
+    let mut new_players = 0;
+    for (season, category, class) in rankings.iter().copied() {
+        let year = season.year();
+        let max_rank = max_ranks.get(category);
+        let previous = db.get_ranking_download(year, category, class);
+        if previous.as_ref().is_some_and(|previous| previous.closed.is_some()) && !force_closed {
+            estimate
+                .skipped
+                .push(format!("{}, {:?}, {:?}: closed", year, category, class));
+            continue;
+        }
+        // the "last update" check before the ranking
+        let checked = usize::from(previous.is_some() && !force);
+        estimate.add(Parser::ItsfRankings.name(), checked + 1);
+
+        let listed: Vec<(i32, bool)> = db.aggregate_players(|all| {
+            all.filter(|player| {
+                player.itsf_rankings.iter().any(|ranking| {
+                    (ranking.year, ranking.category, ranking.class) == (year, category, class)
+                        && ranking.place as usize <= max_rank
+                })
+            })
+            .map(|player| (player.itsf_id, needs_download(player, force)))
+            .collect()
+        });
+        let entries = match &previous {
+            Some(previous) if previous.max_rank.is_some_and(|previous_max| previous_max >= max_rank) => {
+                previous.entries.min(max_rank)
+            }
+            _ => max_rank,
+        };
+        new_players += entries.saturating_sub(listed.len());
+        players.extend(
+            listed
+                .into_iter()
+                .filter(|(_, download)| *download)
+                .map(|(itsf_id, _)| itsf_id),
+        );
+    }
+    estimate.add_players(players.len() + new_players);
+    estimate.finish()
+}
+
+/// The DTFB rankings job with the arguments of `start_dtfb_rankings_download`. The players are the stored ones
+/// with a DTFB id, or `max_rank` per ranking while there are none.
+pub fn dtfb_rankings(db: &DatabaseRef, seasons: &[Season], max_rank: usize, force: bool) -> Estimate {
+    let mut estimate = Estimate::default();
+    estimate.add(Parser::DtfbLeagues.name(), seasons.len());
+    // the list of a season's rankings, then the rankings
+    estimate.add(
+        Parser::DtfbPlayers.name(),
+        seasons.len() * (1 + DTFB_RANKINGS_PER_SEASON),
+    );
+
+    let (stored, stale) = db.aggregate_players(|players| {
+        players
+            .filter(|player| player.dtfb_id.is_some())
+            .fold((0, 0), |(stored, stale), player| {
+                (stored + 1, stale + usize::from(needs_download(player, force)))
+            })
+    });
+    let (profiles, itsf_profiles) = match stored {
+        0 => {
+            let listed = max_rank * DTFB_RANKINGS_PER_SEASON;
+            (listed, listed)
+        }
+        stored => (stored, stale),
+    };
+    estimate.add(Parser::DtfbPlayers.name(), profiles);
+    estimate.add_players(itsf_profiles);
+    estimate.finish()
+}
+
+/// The events job with the arguments of `start_itsf_events_download`, with the team results of the stored World
+/// Championships that are over. Geocoding the locations isn't counted, it doesn't use the download budget.
+pub fn itsf_events(db: &DatabaseRef, years: &[i32]) -> Estimate {
+    let mut estimate = Estimate::default();
+    estimate.add(Parser::ItsfEvents.name(), years.len());
+    let today = chrono::Utc::now().date_naive();
+    let world_championships = db
+        .get_events_before(today)
+        .iter()
+        .filter(|event| event.is_world_championship() && years.contains(&event.start_date.year()))
+        .count();
+    estimate.add(Parser::ItsfTeams.name(), world_championships);
+    estimate.finish()
+}
@@ -0,0 +1,144 @@
+use chrono::NaiveDate;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::background::BackgroundOperationProgress;
+use crate::data::events::Event;
+use crate::data::quality::Parser;
+use crate::data::DatabaseRef;
+
+use super::pipeline::{Page, Scraper};
+use super::{sources, SourceHost};
+
+fn text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%d/%m/%Y").ok()
+}
+
+/// `15/03/2023 - 17/03/2023`, or a single date for one-day events.
+fn parse_dates(dates: &str) -> Option<(NaiveDate, NaiveDate)> {
+    match dates.split_once('-') {
+        Some((start, end)) => Some((parse_date(start)?, parse_date(end)?)),
+        None => parse_date(dates).map(|date| (date, date)),
+    }
+}
+
+/// `Paris (FRA)`, the country is missing for online events.
+fn parse_location(location: &str) -> (String, Option<String>) {
+    match location.rsplit_once('(') {
+        Some((city, country)) if country.ends_with(')') => (
+            String::from(city.trim()),
+            Some(String::from(country.trim_end_matches(')').trim())),
+        ),
+        _ => (String::from(location), None),
+    }
+}
+
+/// The event id from a link like `?page=event&id=123`.
+fn parse_event_id(href: &str) -> Option<i32> {
+    href.split(['?', '&'])
+        .find_map(|param| param.strip_prefix("id="))
+        .and_then(|id| id.parse::<i32>().ok())
+}
+
+/// Parses the calendar table, whose rows have the dates, the linked name, the location and the category.
+/// Rows that don't fit, e.g. headings, are skipped.
+fn parse_calendar(html: &Html, base_url: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+
+    for row in html.select(&Selector::parse("tr").unwrap()) {
+        let cells: Vec<ElementRef> = row.select(&Selector::parse("td").unwrap()).collect();
+        if cells.len() < 4 {
+            continue;
+        }
+        let link = cells[1].select(&Selector::parse("a").unwrap()).next();
+        let href = link.and_then(|link| link.value().attr("href"));
+        let event_id = href.and_then(parse_event_id);
+        let dates = parse_dates(&text(cells[0]));
+        match (event_id, dates) {
+            (Some(event_id), Some((start_date, end_date))) => {
+                let (location, country_code) = parse_location(&text(cells[2]));
+                events.push(Event {
+                    event_id,
+                    name: text(cells[1]),
+                    start_date,
+                    end_date,
+                    location,
+                    country_code,
+                    category: text(cells[3]),
+                    url: format!("{}/page/event&id={}", base_url, event_id),
+                    coordinates: None,
+                });
+            }
+            _ => log::debug!("skipping calendar row: {}", text(row)),
+        }
+    }
+
+    events
+}
+
+/// The ITSF tournament calendar of a year, replacing the stored events of the year.
+pub struct Calendar;
+
+impl Scraper for Calendar {
+    type Request = i32;
+    type Output = Vec<Event>;
+
+    fn host(&self) -> SourceHost {
+        SourceHost::Itsf
+    }
+
+    fn parser(&self) -> Parser {
+        Parser::ItsfEvents
+    }
+
+    fn request(&self, request: &str) -> Result<i32, String> {
+        request
+            .parse::<i32>()
+            .map_err(|_| format!("invalid year: '{}'", request))
+    }
+
+    fn url(&self, year: &i32) -> String {
+        format!("{}/page/calendar&year={}", sources::get().itsf, year)
+    }
+
+    fn parse(&self, _year: &i32, body: &str) -> Result<Vec<Event>, String> {
+        Ok(parse_calendar(&Html::parse_document(body), &sources::get().itsf))
+    }
+
+    fn records(&self, events: &Vec<Event>) -> Vec<Vec<(&'static str, bool)>> {
+        events
+            .iter()
+            .map(|event| {
+                vec![
+                    ("name", !event.name.is_empty()),
+                    ("location", !event.location.is_empty()),
+                    ("country_code", event.country_code.is_some()),
+                    ("category", !event.category.is_empty()),
+                ]
+            })
+            .collect()
+    }
+
+    async fn persist(
+        &self,
+        db: &DatabaseRef,
+        progress: &BackgroundOperationProgress,
+        year: i32,
+        page: Page<Vec<Event>>,
+    ) {
+        progress.log(format!("[ITSF] Downloaded {} events of {}", page.data.len(), year));
+        let removed = db.replace_events_of_year(year, page.data);
+        if removed > 0 {
+            progress.log(format!("[ITSF] Removed {} events no longer in the calendar", removed));
+        }
+    }
+}
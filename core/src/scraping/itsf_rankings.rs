@@ -0,0 +1,270 @@
+use super::pipeline::{Page, Scraper};
+use super::{download_itsf_players, provenance, sources, SourceHost};
+use crate::background::BackgroundOperationProgress;
+use crate::data::itsf::*;
+use crate::data::license::LicenseNumber;
+use crate::data::quality::Parser;
+use crate::data::snapshots::{self, Placement, RankingSnapshot};
+use crate::data::DatabaseRef;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashSet;
+
+fn get_player_from_div(div: &ElementRef) -> Result<(i32, i32), &'static str> {
+    let id = div.value().attr("id").ok_or("no id attr")?;
+    let onclick = div.value().attr("onclick").ok_or("no onclick attr")?;
+
+    let place = if let Some(striped_place) = id.strip_prefix("place") {
+        striped_place.parse::<i32>().map_err(|_| "can't parse place attr")?
+    } else {
+        Err("id attr has no place")?
+    };
+
+    let license = if onclick.contains("&numlic=") {
+        let mut parts = onclick.split("&numlic=");
+        parts.next().ok_or("onclick doesn't contain player link")?;
+        let license = parts.next().ok_or("onclick doesn't contain player link")?;
+        license
+            .split('&')
+            .next()
+            .ok_or("doesn't contain player link")?
+            .parse::<LicenseNumber>()
+            .map_err(|_| "can't parse player license")?
+            .get()
+    } else {
+        Err("onclick doesn't contain player link")?
+    };
+
+    Ok((place, license))
+}
+
+/// Date following the page's "Last update" label, e.g. `Last update: 15/03/2023`.
+fn parse_last_updated(html: &Html) -> Option<chrono::NaiveDate> {
+    const FORMATS: [&str; 3] = ["%d/%m/%Y", "%d.%m.%Y", "%Y-%m-%d"];
+    let text = html.root_element().text().collect::<Vec<_>>().join(" ");
+    let start = text.to_lowercase().find("last update")?;
+    text[start..]
+        .split(|c: char| c.is_whitespace() || c == ':' || c == ',')
+        .take(8)
+        .find_map(|word| {
+            FORMATS
+                .iter()
+                .find_map(|format| chrono::NaiveDate::parse_from_str(word, format).ok())
+        })
+}
+
+pub struct RankingPage {
+    /// (place, ITSF ID) of every listed player.
+    pub placements: Vec<(i32, i32)>,
+    pub last_updated: Option<chrono::NaiveDate>,
+}
+
+impl RankingPage {
+    /// Sanity checks of the parsed table, so a changed or half-loaded page isn't stored as the
+    /// ranking. `previous_entries` is the size of the last download of the same ranking, if it
+    /// requested at least as many places.
+    pub fn check(&self, max_rank: usize, previous_entries: Option<usize>) -> Result<(), String> {
+        if self.placements.is_empty() {
+            return Err(String::from("no players found"));
+        }
+        if self.placements.len() > max_rank {
+            return Err(format!(
+                "{} players found, but only {} were requested",
+                self.placements.len(),
+                max_rank
+            ));
+        }
+        // rankings only grow during a season, a much shorter table means it was cut off
+        if let Some(previous) = previous_entries.map(|entries| entries.min(max_rank)) {
+            if self.placements.len() < previous / 2 {
+                return Err(format!(
+                    "only {} players found, {} in the previous download",
+                    self.placements.len(),
+                    previous
+                ));
+            }
+        }
+        let mut places = HashSet::new();
+        let mut players = HashSet::new();
+        for (place, itsf_id) in &self.placements {
+            if !places.insert(place) {
+                return Err(format!("place {} is listed twice", place));
+            }
+            if !players.insert(itsf_id) {
+                return Err(format!("player {} is listed twice", itsf_id));
+            }
+        }
+        if !places.contains(&1) {
+            return Err(String::from("no player in first place"));
+        }
+        Ok(())
+    }
+}
+
+/// A ranking to download, i.e. its first `max_rank` places.
+#[derive(Debug, Clone)]
+pub struct RankingRequest {
+    pub year: i32,
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    pub max_rank: usize,
+    /// Also download the profiles of players that were downloaded recently.
+    pub force: bool,
+    /// Replace the ranking even if it was closed.
+    pub force_closed: bool,
+}
+
+/// An ITSF ranking, stored as snapshot and as placements of the players, whose profiles are downloaded first.
+/// The page is rejected if it fails the sanity checks of `RankingPage::check`.
+pub struct Rankings;
+
+impl Scraper for Rankings {
+    type Request = RankingRequest;
+    type Output = RankingPage;
+
+    fn host(&self) -> SourceHost {
+        SourceHost::Itsf
+    }
+
+    fn parser(&self) -> Parser {
+        Parser::ItsfRankings
+    }
+
+    /// `2023:open:singles:100` for the first 100 places of the open singles ranking of 2023.
+    fn request(&self, request: &str) -> Result<RankingRequest, String> {
+        let invalid = || format!("invalid ranking: '{}', expected year:category:class:max_rank", request);
+        let parts: Vec<&str> = request.split(':').collect();
+        if parts.len() != 4 {
+            return Err(invalid());
+        }
+        Ok(RankingRequest {
+            year: parts[0].parse::<i32>().map_err(|_| invalid())?,
+            category: RankingCategory::try_from_str(parts[1])?,
+            class: RankingClass::try_from_str(parts[2])?,
+            max_rank: parts[3].parse::<usize>().map_err(|_| invalid())?,
+            force: false,
+            force_closed: false,
+        })
+    }
+
+    fn url(&self, request: &RankingRequest) -> String {
+        let category = match request.category {
+            RankingCategory::Open => "o",
+            RankingCategory::Women => "w",
+            RankingCategory::Junior => "j",
+            RankingCategory::Senior => "s",
+        };
+        let class = match request.class {
+            RankingClass::Singles => "s",
+            RankingClass::Doubles => "d",
+            RankingClass::Combined => "c",
+        };
+        format!(
+            "{}/page/rankings?category={}{}&system=1&Ranking+Rules=Select+Category&tour={}&vues={}",
+            sources::get().itsf,
+            category,
+            class,
+            request.year,
+            request.max_rank
+        )
+    }
+
+    fn parse(&self, _request: &RankingRequest, body: &str) -> Result<RankingPage, String> {
+        let itsf = Html::parse_document(body);
+        let placements = itsf
+            .select(&Selector::parse("div").unwrap())
+            .filter_map(|div| get_player_from_div(&div).ok())
+            .collect();
+        Ok(RankingPage {
+            placements,
+            last_updated: parse_last_updated(&itsf),
+        })
+    }
+
+    fn records(&self, page: &RankingPage) -> Vec<Vec<(&'static str, bool)>> {
+        vec![Vec::new(); page.placements.len()]
+    }
+
+    async fn persist(
+        &self,
+        db: &DatabaseRef,
+        progress: &BackgroundOperationProgress,
+        request: RankingRequest,
+        page: Page<RankingPage>,
+    ) {
+        let RankingRequest {
+            year,
+            category,
+            class,
+            max_rank,
+            ..
+        } = request;
+        let previous = db.get_ranking_download(year, category, class);
+        let closed = previous.as_ref().and_then(|previous| previous.closed.clone());
+        if let Some(closed) = closed.as_ref().filter(|_| !request.force_closed) {
+            progress.log(format!(
+                "[ITSF] Skipping {}, {:?}, {:?}: closed by {}",
+                year, category, class, closed.closed_by
+            ));
+            return;
+        }
+        let previous_entries = previous
+            .filter(|previous| previous.max_rank.is_some_and(|previous_max| previous_max >= max_rank))
+            .map(|previous| previous.entries);
+        if let Err(err) = page.data.check(max_rank, previous_entries) {
+            progress.log(format!(
+                "[ITSF] Rejected {}, {:?}, {:?}, keeping the previous download: {} ({})",
+                year, category, class, err, page.url
+            ));
+            return;
+        }
+        let rankings = page.data.placements;
+        let scraped_at = page.scraped_at;
+        let placements: Vec<Placement> = rankings
+            .iter()
+            .map(|(place, itsf_id)| Placement {
+                place: *place,
+                itsf_id: *itsf_id,
+            })
+            .collect();
+        let download = RankingDownload {
+            year,
+            category,
+            class,
+            scraped_at,
+            entries: rankings.len(),
+            max_rank: Some(max_rank),
+            source_updated: page.data.last_updated,
+            // a forced download replaces the closed snapshot, which stays closed with the new checksum
+            closed: closed.map(|closed| RankingClosure {
+                scraped_at,
+                checksum: snapshots::checksum(&placements),
+                ..closed
+            }),
+        };
+
+        let itsf_player_ids: Vec<i32> = rankings.iter().map(|entry| entry.1).collect();
+        download_itsf_players(db, &itsf_player_ids, progress, request.force).await;
+
+        db.record_ranking_snapshot(&RankingSnapshot {
+            year,
+            category,
+            class,
+            scraped_at,
+            placements,
+        });
+        for (place, itsf_id) in rankings {
+            db.add_player_itsf_ranking(
+                itsf_id,
+                Ranking {
+                    year,
+                    category,
+                    class,
+                    place,
+                    percentile: percentile(place, download.entries),
+                    provenance: provenance(progress, &page.url, scraped_at),
+                },
+            );
+        }
+        db.record_ranking_download(download);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+
+use chrono::Datelike;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::background::BackgroundOperationProgress;
+use crate::data::itsf::NationalTeamAppearance;
+use crate::data::license::LicenseNumber;
+use crate::data::quality::Parser;
+use crate::data::DatabaseRef;
+
+use super::pipeline::{Page, Scraper};
+use super::{provenance, sources, SourceHost};
+
+/// A national team's result in one team competition of an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamResult {
+    /// e.g. `Men's Teams`
+    pub competition: String,
+    pub place: Option<i32>,
+    pub country_code: String,
+    /// ITSF IDs of the players listed for the team.
+    pub players: Vec<i32>,
+}
+
+fn text(element: ElementRef) -> String {
+    element.text().collect::<Vec<&str>>().join(" ").trim().to_string()
+}
+
+/// The license from a player link like `?page=player&numlic=84000895&`.
+fn parse_license(href: &str) -> Option<i32> {
+    href.split('&')
+        .find_map(|param| param.strip_prefix("numlic="))
+        .and_then(|license| license.parse::<LicenseNumber>().ok())
+        .map(|license| license.get())
+}
+
+/// Parses the result tables of the team competitions, i.e. those with "Teams" in their heading. Their rows
+/// have the place, the country code and links to the players.
+fn parse_team_results(html: &Html) -> Vec<TeamResult> {
+    let mut results = Vec::new();
+
+    for competition in html.select(&Selector::parse("div.competition").unwrap()) {
+        let name = match competition.select(&Selector::parse("h3").unwrap()).next() {
+            Some(heading) => text(heading),
+            None => continue,
+        };
+        if !name.to_lowercase().contains("teams") {
+            continue;
+        }
+        for row in competition.select(&Selector::parse("tr").unwrap()) {
+            let cells: Vec<ElementRef> = row.select(&Selector::parse("td").unwrap()).collect();
+            if cells.len() < 3 {
+                continue;
+            }
+            let players: Vec<i32> = cells[2]
+                .select(&Selector::parse("a").unwrap())
+                .filter_map(|link| link.value().attr("href").and_then(parse_license))
+                .collect();
+            let country_code = text(cells[1]);
+            if players.is_empty() || country_code.is_empty() {
+                log::debug!("skipping team result row: {}", text(row));
+                continue;
+            }
+            results.push(TeamResult {
+                competition: name.clone(),
+                place: text(cells[0]).trim_end_matches('.').parse::<i32>().ok(),
+                country_code,
+                players,
+            });
+        }
+    }
+
+    results
+}
+
+/// The national team results of an ITSF event, recorded as appearances of the stored players. The event has to be
+/// stored already.
+pub struct TeamResults;
+
+impl Scraper for TeamResults {
+    /// The event id.
+    type Request = i32;
+    type Output = Vec<TeamResult>;
+
+    fn host(&self) -> SourceHost {
+        SourceHost::Itsf
+    }
+
+    fn parser(&self) -> Parser {
+        Parser::ItsfTeams
+    }
+
+    fn request(&self, request: &str) -> Result<i32, String> {
+        request
+            .parse::<i32>()
+            .map_err(|_| format!("invalid event id: '{}'", request))
+    }
+
+    fn url(&self, event_id: &i32) -> String {
+        format!("{}/page/event&id={}", sources::get().itsf, event_id)
+    }
+
+    fn parse(&self, _event_id: &i32, body: &str) -> Result<Vec<TeamResult>, String> {
+        Ok(parse_team_results(&Html::parse_document(body)))
+    }
+
+    fn records(&self, results: &Vec<TeamResult>) -> Vec<Vec<(&'static str, bool)>> {
+        results
+            .iter()
+            .map(|result| {
+                vec![
+                    ("place", result.place.is_some()),
+                    ("country_code", !result.country_code.is_empty()),
+                    ("players", !result.players.is_empty()),
+                ]
+            })
+            .collect()
+    }
+
+    async fn persist(
+        &self,
+        db: &DatabaseRef,
+        progress: &BackgroundOperationProgress,
+        event_id: i32,
+        page: Page<Vec<TeamResult>>,
+    ) {
+        let event = match db.get_event(event_id) {
+            Some(event) => event,
+            None => {
+                progress.log(format!(
+                    "[ITSF] Skipping the team results of unknown event {}",
+                    event_id
+                ));
+                return;
+            }
+        };
+        let appearances: Vec<(i32, NationalTeamAppearance)> = page
+            .data
+            .iter()
+            .flat_map(|result| {
+                result.players.iter().map(|itsf_id| {
+                    (
+                        *itsf_id,
+                        NationalTeamAppearance {
+                            year: event.start_date.year(),
+                            event_id,
+                            event: event.name.clone(),
+                            competition: result.competition.clone(),
+                            country_code: result.country_code.clone(),
+                            place: result.place,
+                            provenance: provenance(progress, &page.url, page.scraped_at),
+                        },
+                    )
+                })
+            })
+            .collect();
+        let listed: HashSet<i32> = appearances.iter().map(|(itsf_id, _)| *itsf_id).collect();
+        let listed = listed.len();
+        let stored = db.set_national_team_appearances(event_id, appearances);
+        progress.log(format!(
+            "[ITSF] Recorded {} national team players of {} ({} not in the database)",
+            stored,
+            event.name,
+            listed - stored
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use super::players;
+
+/// Live checks are answered from the cache for this long.
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+/// Minimum time between two requests to the ITSF site.
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Result of looking up a licence on the ITSF site.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LicenceCheck {
+    pub itsf_lic: i32,
+    pub exists: bool,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub country_code: Option<String>,
+    /// Code of the player category, e.g. `junior_male`.
+    pub category: Option<&'static str>,
+    /// Unix timestamp of the live check, older than now if the result came from the cache.
+    pub checked: i64,
+}
+
+lazy_static! {
+    static ref CACHE: std::sync::Mutex<HashMap<i32, (Instant, LicenceCheck)>> = std::sync::Mutex::new(HashMap::new());
+    static ref LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+async fn check_live(itsf_id: i32) -> Result<LicenceCheck, String> {
+    {
+        let mut last_request = LAST_REQUEST.lock().await;
+        if let Some(wait) = last_request.and_then(|last| MIN_INTERVAL.checked_sub(last.elapsed())) {
+            tokio::time::sleep(wait).await;
+        }
+        *last_request = Some(Instant::now());
+    }
+
+    let player = players::lookup_player_info(itsf_id).await?;
+    Ok(LicenceCheck {
+        itsf_lic: itsf_id,
+        exists: player.is_some(),
+        first_name: player.as_ref().map(|player| player.first_name.clone()),
+        last_name: player.as_ref().map(|player| player.last_name.clone()),
+        country_code: player.as_ref().and_then(|player| player.country_code.clone()),
+        category: player.as_ref().map(|player| player.category.code()),
+        checked: chrono::Utc::now().timestamp(),
+    })
+}
+
+/// Checks whether ITSF knows the licence, independently of the stored players.
+/// Requests to ITSF are rate limited and results are cached for an hour.
+pub async fn check_licence(itsf_id: i32) -> Result<LicenceCheck, String> {
+    if let Some((time, check)) = CACHE.lock().unwrap().get(&itsf_id) {
+        if time.elapsed() < CACHE_TTL {
+            return Ok(check.clone());
+        }
+    }
+
+    let check = check_live(itsf_id).await?;
+    let mut cache = CACHE.lock().unwrap();
+    cache.retain(|_, (time, _)| time.elapsed() < CACHE_TTL);
+    cache.insert(itsf_id, (Instant::now(), check.clone()));
+    Ok(check)
+}
@@ -0,0 +1,429 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    background::BackgroundOperationProgress,
+    data::jobs::JobRun,
+    data::{dtfb, itsf, season::Season},
+    data::{DatabaseRef, Provenance, RefreshTarget},
+    freshness, geo,
+    joblock::{JobLock, JobLockGuard},
+    notify, warmup,
+};
+use futures_util::future::join_all;
+use pipeline::Scraper;
+
+mod budget;
+mod download;
+mod dtfb_leagues;
+mod dtfb_players;
+pub mod estimate;
+mod itsf_events;
+mod itsf_rankings;
+mod itsf_teams;
+pub mod licence;
+pub mod pipeline;
+mod players;
+pub mod quality;
+pub mod sources;
+
+/// Site a download job scrapes. Jobs of different hosts run in parallel, but only one job per host runs
+/// at a time, across all instances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+pub enum SourceHost {
+    #[serde(rename = "itsf")]
+    Itsf,
+    #[serde(rename = "dtfb")]
+    Dtfb,
+}
+
+impl SourceHost {
+    pub const ALL: [Self; 2] = [Self::Itsf, Self::Dtfb];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SourceHost::Itsf => "ITSF",
+            SourceHost::Dtfb => "DTFB",
+        }
+    }
+
+    /// The job lock held while a job scrapes the host.
+    pub fn lock_name(self) -> &'static str {
+        match self {
+            SourceHost::Itsf => "download:itsf",
+            SourceHost::Dtfb => "download:dtfb",
+        }
+    }
+}
+
+/// How far down the ITSF rankings are downloaded. Can differ per category, since the long tail
+/// of the open ranking is much longer than that of the junior one.
+#[derive(Debug, Clone)]
+pub struct MaxRanks {
+    pub default: usize,
+    pub per_category: HashMap<itsf::RankingCategory, usize>,
+}
+
+impl MaxRanks {
+    pub fn get(&self, category: itsf::RankingCategory) -> usize {
+        *self.per_category.get(&category).unwrap_or(&self.default)
+    }
+}
+
+/// Sends a HEAD request to every scraped host, returning the HTTP status or the error per host.
+pub async fn check_hosts() -> Vec<(&'static str, Result<u16, String>)> {
+    let checks = sources::get()
+        .hosts()
+        .into_iter()
+        .map(|host| async move { (host, download::head(host).await) });
+    join_all(checks).await
+}
+
+async fn download_itsf_players(
+    db: &DatabaseRef,
+    player_itsf_ids: &[i32],
+    progress: &BackgroundOperationProgress,
+    force: bool,
+) {
+    let missing_players: Vec<i32> = player_itsf_ids
+        .iter()
+        .filter_map(|itsf_lic| match db.get_player(*itsf_lic) {
+            None => Some(*itsf_lic),
+            Some(player) if player.anonymized => None,
+            Some(player) if force || player.is_stale() => Some(*itsf_lic),
+            Some(_) => None,
+        })
+        .collect();
+    if !missing_players.is_empty() {
+        download_player_profiles(db, missing_players, progress, force).await;
+    }
+}
+
+/// Downloads the profiles and images of the players in order, with `replace` the stored profiles are replaced
+/// instead of refreshed.
+async fn download_player_profiles(
+    db: &DatabaseRef,
+    itsf_ids: Vec<i32>,
+    progress: &BackgroundOperationProgress,
+    replace: bool,
+) {
+    progress.log(format!("[ITSF] Downloading {} ITSF player profiles", itsf_ids.len()));
+    pipeline::run(&players::Profiles { replace }, db, progress, itsf_ids.clone()).await;
+
+    for itsf_ids in itsf_ids.chunks(pipeline::MAX_CONCURRENT) {
+        let images = join_all(itsf_ids.iter().map(|itsf_id| players::download_player_image(*itsf_id))).await;
+        for (itsf_id, image) in itsf_ids.iter().zip(images) {
+            let stored = match image {
+                Ok(Some(image)) => db.set_player_image(image),
+                Ok(None) => Ok(()),
+                Err(err) => Err(format!("Image of player {}: {}", itsf_id, err)),
+            };
+            if let Err(err) = stored {
+                progress.log(format!("[ITSF] Failed to store player image: {}", err));
+                db.record_refresh_error(*itsf_id, RefreshTarget::Image, err);
+            }
+        }
+    }
+
+    progress.log("[ITSF] Done".to_string());
+}
+
+/// Unless `force` is set, a ranking that was downloaded before is skipped if its page still shows the
+/// same "last update" date and at least as many places were downloaded then. Closed rankings are only
+/// downloaded again with `force_closed`.
+async fn do_itsf_rankings_downloads(
+    db: &DatabaseRef,
+    rankings: Vec<(Season, itsf::RankingCategory, itsf::RankingClass)>,
+    progress: Arc<BackgroundOperationProgress>,
+    max_ranks: MaxRanks,
+    force: bool,
+    force_closed: bool,
+) -> Result<(), String> {
+    let scraper = itsf_rankings::Rankings;
+    for (season, category, class) in rankings {
+        let year = season.year();
+        progress.log(format!(
+            "[ITSF] Scraping ITSF rankings for {}, {:?}, {:?}",
+            year, category, class
+        ));
+        let request = itsf_rankings::RankingRequest {
+            year,
+            category,
+            class,
+            max_rank: max_ranks.get(category),
+            force,
+            force_closed,
+        };
+        let previous = db.get_ranking_download(year, category, class);
+        let closed = previous.as_ref().and_then(|previous| previous.closed.as_ref());
+        if let Some(closed) = closed.filter(|_| !force_closed) {
+            progress.log(format!(
+                "[ITSF] Skipping {}, {:?}, {:?}: closed by {}",
+                year, category, class, closed.closed_by
+            ));
+            continue;
+        }
+        if !force {
+            if let Some(previous) = &previous {
+                // a single place is enough to read the page's "last update" date
+                let first_place = itsf_rankings::RankingRequest {
+                    max_rank: 1,
+                    ..request.clone()
+                };
+                let last_updated = pipeline::scrape(&scraper, &first_place).await?.data.last_updated;
+                if let Some(last_updated) = last_updated.filter(|date| previous.is_up_to_date(request.max_rank, *date))
+                {
+                    progress.log(format!(
+                        "[ITSF] Skipping {}, {:?}, {:?}: unchanged since last update on {}",
+                        year, category, class, last_updated
+                    ));
+                    continue;
+                }
+            }
+        }
+        let page = pipeline::scrape(&scraper, &request).await?;
+        scraper.persist(db, &progress, request, page).await;
+    }
+    Ok(())
+}
+
+/// Keeps the log of a finished download in the job history, under the id its provenance refers to, and
+/// stores the parse quality counted meanwhile.
+/// Records the finished job and has the leaderboards recomputed with the downloaded data.
+fn record_job_run(db: &DatabaseRef, progress: &BackgroundOperationProgress, job: &str) {
+    quality::flush(db);
+    crate::stats::request_refresh();
+    db.add_job_run(&JobRun {
+        job: String::from(job),
+        id: Some(String::from(progress.get_id())),
+        started_at: progress.get_started_at(),
+        finished_at: chrono::Utc::now().timestamp(),
+        log: progress.get_log(),
+    });
+}
+
+fn provenance(progress: &BackgroundOperationProgress, source_url: &str, scraped_at: i64) -> Option<Provenance> {
+    Some(Provenance {
+        source_url: String::from(source_url),
+        scraped_at,
+        job_id: String::from(progress.get_id()),
+    })
+}
+
+/// Downloads the given rankings, i.e. combinations of season, category and class.
+pub fn start_itsf_rankings_download(
+    db: DatabaseRef,
+    rankings: Vec<(Season, itsf::RankingCategory, itsf::RankingClass)>,
+    max_ranks: MaxRanks,
+    force: bool,
+    force_closed: bool,
+    lock: JobLockGuard,
+) -> Weak<BackgroundOperationProgress> {
+    let (arc, weak) = BackgroundOperationProgress::new("ITSF Rankings Download", 1);
+    lock.track(&weak);
+    tokio::spawn(async move {
+        let players_before = notify::snapshot_players(&db);
+        match do_itsf_rankings_downloads(&db, rankings, arc.clone(), max_ranks, force, force_closed).await {
+            Ok(_) => {}
+            Err(err) => log::error!("failed to download ITSF rankings: {}", err),
+        };
+        notify::notify_subscribers(&db, &players_before, arc.clone()).await;
+        notify::notify_big_changes(&db, &players_before, arc.clone()).await;
+        warmup::warm_caches(&db, &arc).await;
+        record_job_run(&db, &arc, "itsf_rankings");
+        notify::notify_job_finished(&arc).await;
+        arc.set_progress(1, 1);
+        drop(lock);
+    });
+    weak
+}
+
+/// Downloads the profiles of the players again, e.g. the ones `freshness` picked, keeping their rankings.
+pub fn start_player_refresh(
+    db: DatabaseRef,
+    itsf_ids: Vec<i32>,
+    lock: JobLockGuard,
+) -> Weak<BackgroundOperationProgress> {
+    let (arc, weak) = BackgroundOperationProgress::new("ITSF Player Refresh", 1);
+    lock.track(&weak);
+    tokio::spawn(async move {
+        if !itsf_ids.is_empty() {
+            download_player_profiles(&db, itsf_ids, &arc, false).await;
+        }
+        record_job_run(&db, &arc, freshness::JOB_NAME);
+        arc.set_progress(1, 1);
+        drop(lock);
+    });
+    weak
+}
+
+/// Downloads the calendars, then the national teams of the World Championships that are over.
+async fn do_itsf_events_download(db: &DatabaseRef, years: Vec<i32>, progress: Arc<BackgroundOperationProgress>) {
+    use chrono::Datelike;
+    pipeline::run(&itsf_events::Calendar, db, &progress, years.clone()).await;
+    let today = chrono::Utc::now().date_naive();
+    let world_championships: Vec<i32> = db
+        .get_events_before(today)
+        .into_iter()
+        .filter(|event| event.is_world_championship() && years.contains(&event.start_date.year()))
+        .map(|event| event.event_id)
+        .collect();
+    pipeline::run(&itsf_teams::TeamResults, db, &progress, world_championships).await;
+    geo::locate_events(db, &progress).await;
+}
+
+/// Downloads the ITSF tournament calendars of the given years.
+pub fn start_itsf_events_download(
+    db: DatabaseRef,
+    years: Vec<i32>,
+    lock: JobLockGuard,
+) -> Weak<BackgroundOperationProgress> {
+    let (arc, weak) = BackgroundOperationProgress::new("ITSF Events Download", 1);
+    lock.track(&weak);
+    tokio::spawn(async move {
+        do_itsf_events_download(&db, years, arc.clone()).await;
+        record_job_run(&db, &arc, "itsf_events");
+        notify::notify_job_finished(&arc).await;
+        arc.set_progress(1, 1);
+        drop(lock);
+    });
+    weak
+}
+
+/// Runs the scraper registered as `source` on the requests, given as text. `lock` is the lock of the scraper's
+/// host.
+pub fn start_source_download(
+    db: DatabaseRef,
+    source: &'static str,
+    requests: Vec<String>,
+    lock: JobLockGuard,
+) -> Weak<BackgroundOperationProgress> {
+    let (arc, weak) = BackgroundOperationProgress::new("Source Download", 1);
+    lock.track(&weak);
+    tokio::spawn(async move {
+        let players_before = notify::snapshot_players(&db);
+        match pipeline::registry().get(source) {
+            Some(scraper) => {
+                if let Err(err) = scraper.run(&db, &arc, &requests).await {
+                    arc.log(format!(
+                        "[{}] Failed to download {}: {}",
+                        scraper.host().name(),
+                        source,
+                        err
+                    ));
+                }
+            }
+            None => log::error!("unknown scraper: {}", source),
+        }
+        notify::notify_subscribers(&db, &players_before, arc.clone()).await;
+        notify::notify_big_changes(&db, &players_before, arc.clone()).await;
+        warmup::warm_caches(&db, &arc).await;
+        record_job_run(&db, &arc, source);
+        notify::notify_job_finished(&arc).await;
+        arc.set_progress(1, 1);
+        drop(lock);
+    });
+    weak
+}
+
+async fn do_dtfb_rankings_download(
+    db: &DatabaseRef,
+    job_lock: &JobLock,
+    seasons: Vec<Season>,
+    progress: Arc<BackgroundOperationProgress>,
+    max_rank: usize,
+    force: bool,
+) -> Result<(), String> {
+    progress.log(format!(
+        "[DTFB] starting download of DTFB rankings for seasons {}",
+        seasons
+            .iter()
+            .map(Season::to_string)
+            .collect::<Vec<String>>()
+            .join(", ")
+    ));
+
+    pipeline::run(&dtfb_leagues::LeagueTables, db, &progress, seasons.clone()).await;
+
+    let mut dtfb_player_ids = HashSet::new();
+    for season in seasons.iter().copied() {
+        let ranking_ids = dtfb_players::collect_dtfb_rankings_for_season(season).await?;
+        for ranking_id in ranking_ids {
+            let rankings = dtfb_players::collect_dtfb_ids_from_rankings(ranking_id, max_rank).await?;
+            for id in rankings {
+                dtfb_player_ids.insert(id);
+            }
+        }
+    }
+
+    progress.log(format!("[DTFB] Downloading {} players", dtfb_player_ids.len()));
+
+    let scraper = dtfb_players::Profiles;
+    let dtfb_players = pipeline::scrape_all(&scraper, db, &progress, dtfb_player_ids.into_iter().collect()).await;
+    for (_, page) in &dtfb_players {
+        progress.log(format!(
+            "[DTFB] .. downloaded player info for DTFB={}, ITSF={}",
+            page.data.dtfb_id, page.data.itsf_id,
+        ));
+    }
+
+    // the ITSF profiles are scraped while no ITSF job runs, the ITSF host is only taken for that part
+    let itsf_player_ids: Vec<i32> = dtfb_players.iter().map(|(_, page)| page.data.itsf_id).collect();
+    let itsf_lock = match job_lock.try_acquire(SourceHost::Itsf.lock_name()).await? {
+        Some(itsf_lock) => itsf_lock,
+        None => {
+            progress.log(String::from("[DTFB] Waiting for the running ITSF download to finish"));
+            job_lock.acquire(SourceHost::Itsf.lock_name()).await?
+        }
+    };
+    itsf_lock.track(&Arc::downgrade(&progress));
+    download_itsf_players(db, &itsf_player_ids, &progress, force).await;
+    drop(itsf_lock);
+
+    for (dtfb_id, page) in dtfb_players {
+        scraper.persist(db, &progress, dtfb_id, page).await;
+    }
+
+    let scraped_at = chrono::Utc::now().timestamp();
+    for season in seasons {
+        db.record_dtfb_download(dtfb::SeasonDownload {
+            year: season.year(),
+            scraped_at,
+        });
+    }
+
+    progress.log("[DTFB] done".to_string());
+
+    Ok(())
+}
+
+/// Downloads the DTFB rankings of the seasons, `lock` is the DTFB host lock, the ITSF one is taken from
+/// `job_lock` while the ITSF profiles of the players are downloaded.
+pub fn start_dtfb_rankings_download(
+    db: DatabaseRef,
+    seasons: Vec<Season>,
+    max_rank: usize,
+    force: bool,
+    lock: JobLockGuard,
+    job_lock: JobLock,
+) -> Weak<BackgroundOperationProgress> {
+    let (arc, weak) = BackgroundOperationProgress::new("DTFB Rankings Download", 1);
+    lock.track(&weak);
+    tokio::spawn(async move {
+        let players_before = notify::snapshot_players(&db);
+        match do_dtfb_rankings_download(&db, &job_lock, seasons, arc.clone(), max_rank, force).await {
+            Ok(_) => {}
+            Err(err) => log::error!("failed to download DTFB rankings: {}", err),
+        };
+        notify::notify_subscribers(&db, &players_before, arc.clone()).await;
+        notify::notify_big_changes(&db, &players_before, arc.clone()).await;
+        warmup::warm_caches(&db, &arc).await;
+        record_job_run(&db, &arc, "dtfb_rankings");
+        notify::notify_job_finished(&arc).await;
+        arc.set_progress(1, 1);
+        drop(lock);
+    });
+    weak
+}
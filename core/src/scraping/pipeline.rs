@@ -0,0 +1,249 @@
+//! The steps every scraped page goes through: a `Scraper` names the page of a request, parses the downloaded
+//! body and persists what it parsed. Fetching with retries, counting the parse quality and running a batch of
+//! requests are shared, so a new source is a module implementing `Scraper` plus its line in `registry`.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use futures_util::future::{join_all, BoxFuture};
+use lazy_static::lazy_static;
+
+use crate::background::BackgroundOperationProgress;
+use crate::data::quality::Parser;
+use crate::data::DatabaseRef;
+
+use super::{
+    download, dtfb_leagues, dtfb_players, itsf_events, itsf_rankings, itsf_teams, players, quality, SourceHost,
+};
+
+/// Requests scraped at the same time, to hide the latency of the hosts.
+pub(crate) const MAX_CONCURRENT: usize = 5;
+
+/// Wait before the first retry of a failed download, every further retry waits once more as long.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    /// How often a failed download is retried, configured via `SCRAPE_RETRIES`.
+    static ref RETRIES: u32 = match std::env::var("SCRAPE_RETRIES") {
+        Ok(retries) => retries.parse::<u32>().expect("invalid SCRAPE_RETRIES"),
+        Err(_) => 2,
+    };
+}
+
+/// A downloaded page, or what was parsed from it.
+pub struct Page<T> {
+    pub url: String,
+    /// Unix timestamp of the download.
+    pub scraped_at: i64,
+    pub data: T,
+}
+
+pub trait Scraper: Send + Sync {
+    /// What is scraped, e.g. the year of a calendar.
+    type Request: Send + Sync;
+    type Output: Send;
+
+    /// The host the pages are downloaded from, its job lock is held while the scraper runs.
+    fn host(&self) -> SourceHost;
+
+    /// The parser the quality of the pages is counted for, its name is the scraper's name in `registry`.
+    fn parser(&self) -> Parser;
+
+    /// Parses a request given as text, e.g. `2023` for the calendar of the year.
+    fn request(&self, request: &str) -> Result<Self::Request, String>;
+
+    fn url(&self, request: &Self::Request) -> String;
+
+    /// Sent with the request, e.g. the DTFB season filter.
+    fn cookies(&self, _request: &Self::Request) -> Option<String> {
+        None
+    }
+
+    fn parse(&self, request: &Self::Request, body: &str) -> Result<Self::Output, String>;
+
+    /// The fields every parsed record has or lacks, one entry per record.
+    fn records(&self, output: &Self::Output) -> Vec<Vec<(&'static str, bool)>>;
+
+    /// Stores a parsed page, logging what was stored to `progress`.
+    fn persist(
+        &self,
+        db: &DatabaseRef,
+        progress: &BackgroundOperationProgress,
+        request: Self::Request,
+        page: Page<Self::Output>,
+    ) -> impl Future<Output = ()> + Send;
+
+    /// Called with a request that couldn't be scraped, after the error was logged.
+    fn failed(&self, _db: &DatabaseRef, _request: &Self::Request, _err: &str) {}
+}
+
+/// Downloads the page, retrying after network errors.
+pub(crate) async fn fetch_url(url: &str, cookies: Option<&str>) -> Result<String, String> {
+    let headers: Vec<(&str, &str)> = cookies.map(|cookies| ("Cookie", cookies)).into_iter().collect();
+    let mut attempt = 0;
+    loop {
+        match download::download(url, &headers).await {
+            Ok(body) => return Ok(body),
+            Err(err) if attempt < *RETRIES => {
+                attempt += 1;
+                log::warn!("retrying download of {} ({}): {}", url, attempt, err);
+                tokio::time::sleep(RETRY_DELAY * attempt).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Downloads the page of the request.
+pub async fn fetch<S: Scraper + ?Sized>(scraper: &S, request: &S::Request) -> Result<Page<String>, String> {
+    let url = scraper.url(request);
+    let body = fetch_url(&url, scraper.cookies(request).as_deref())
+        .await
+        .map_err(|err| format!("{}: {}", url, err))?;
+    Ok(Page {
+        url,
+        scraped_at: chrono::Utc::now().timestamp(),
+        data: body,
+    })
+}
+
+/// Downloads and parses the page of the request, counting the parse quality.
+pub async fn scrape<S: Scraper + ?Sized>(scraper: &S, request: &S::Request) -> Result<Page<S::Output>, String> {
+    let page = fetch(scraper, request).await?;
+    let data = match scraper.parse(request, &page.data) {
+        Ok(data) => {
+            quality::record_page(scraper.parser(), &scraper.records(&data), Clone::clone);
+            data
+        }
+        Err(err) => {
+            quality::record_failure(scraper.parser());
+            return Err(format!("{}: {}", page.url, err));
+        }
+    };
+    Ok(Page {
+        url: page.url,
+        scraped_at: page.scraped_at,
+        data,
+    })
+}
+
+/// Scrapes the requests, `MAX_CONCURRENT` at a time, keeping their order. Failed requests are logged and left out.
+pub async fn scrape_all<S: Scraper + ?Sized>(
+    scraper: &S,
+    db: &DatabaseRef,
+    progress: &BackgroundOperationProgress,
+    mut requests: Vec<S::Request>,
+) -> Vec<(S::Request, Page<S::Output>)> {
+    let mut pages = Vec::new();
+    while !requests.is_empty() {
+        let rest = requests.split_off(requests.len().min(MAX_CONCURRENT));
+        let batch = std::mem::replace(&mut requests, rest);
+        pages.extend(scrape_batch(scraper, db, progress, batch).await);
+    }
+    pages
+}
+
+async fn scrape_batch<S: Scraper + ?Sized>(
+    scraper: &S,
+    db: &DatabaseRef,
+    progress: &BackgroundOperationProgress,
+    batch: Vec<S::Request>,
+) -> Vec<(S::Request, Page<S::Output>)> {
+    let results = join_all(batch.iter().map(|request| scrape(scraper, request))).await;
+    let mut pages = Vec::new();
+    for (request, result) in batch.into_iter().zip(results) {
+        match result {
+            Ok(page) => pages.push((request, page)),
+            Err(err) => {
+                progress.log(format!(
+                    "[{}] Failed to scrape {}: {}",
+                    Scraper::host(scraper).name(),
+                    scraper.parser().name(),
+                    err
+                ));
+                scraper.failed(db, &request, &err);
+            }
+        }
+    }
+    pages
+}
+
+/// Scrapes and persists the requests, `MAX_CONCURRENT` are downloaded at a time and persisted in order.
+pub async fn run<S: Scraper + ?Sized>(
+    scraper: &S,
+    db: &DatabaseRef,
+    progress: &BackgroundOperationProgress,
+    mut requests: Vec<S::Request>,
+) {
+    let total = requests.len();
+    while !requests.is_empty() {
+        progress.set_progress(total - requests.len(), total);
+        let rest = requests.split_off(requests.len().min(MAX_CONCURRENT));
+        let batch = std::mem::replace(&mut requests, rest);
+        for (request, page) in scrape_batch(scraper, db, progress, batch).await {
+            scraper.persist(db, progress, request, page).await;
+        }
+    }
+}
+
+/// A registered scraper, taking its requests as text.
+pub trait Source: Send + Sync {
+    fn host(&self) -> SourceHost;
+
+    fn validate(&self, request: &str) -> Result<(), String>;
+
+    /// Scrapes and persists the requests. Fails before anything is downloaded if a request is invalid.
+    fn run<'a>(
+        &'a self,
+        db: &'a DatabaseRef,
+        progress: &'a BackgroundOperationProgress,
+        requests: &'a [String],
+    ) -> BoxFuture<'a, Result<(), String>>;
+}
+
+impl<S: Scraper> Source for S {
+    fn host(&self) -> SourceHost {
+        Scraper::host(self)
+    }
+
+    fn validate(&self, request: &str) -> Result<(), String> {
+        self.request(request).map(|_| ())
+    }
+
+    fn run<'a>(
+        &'a self,
+        db: &'a DatabaseRef,
+        progress: &'a BackgroundOperationProgress,
+        requests: &'a [String],
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let requests = requests
+                .iter()
+                .map(|request| self.request(request))
+                .collect::<Result<Vec<S::Request>, String>>()?;
+            run(self, db, progress, requests).await;
+            Ok(())
+        })
+    }
+}
+
+fn register(registry: &mut BTreeMap<&'static str, Box<dyn Source>>, scraper: impl Scraper + 'static) {
+    registry.insert(scraper.parser().name(), Box::new(scraper));
+}
+
+/// The scrapers by name.
+pub fn registry() -> &'static BTreeMap<&'static str, Box<dyn Source>> {
+    static REGISTRY: OnceLock<BTreeMap<&'static str, Box<dyn Source>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = BTreeMap::new();
+        register(&mut registry, players::Profiles { replace: false });
+        register(&mut registry, itsf_rankings::Rankings);
+        register(&mut registry, itsf_events::Calendar);
+        register(&mut registry, itsf_teams::TeamResults);
+        register(&mut registry, dtfb_players::Profiles);
+        register(&mut registry, dtfb_leagues::LeagueTables);
+        registry
+    })
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use crate::background::BackgroundOperationProgress;
+use crate::data::license::LicenseNumber;
+use crate::data::{itsf::PlayerCategory, quality::Parser, DatabaseRef, Player, PlayerImage, RefreshTarget};
+
+use super::pipeline::{self, Page, Scraper};
+use super::{download, sources, SourceHost};
+use scraper::{ElementRef, Html, Selector};
+
+fn get_div_with_class<'a>(root: &'a Html, class: &'static str) -> Vec<ElementRef<'a>> {
+    let div_selector = Selector::parse("div").unwrap();
+    root.select(&div_selector)
+        .filter(|div| div.value().attr("class") == Some(class))
+        .collect()
+}
+
+fn is_uppercase(word: &str) -> bool {
+    word.chars().all(|c| !c.is_lowercase())
+}
+
+fn to_normalcase(word: &str) -> String {
+    let mut result = String::new();
+
+    for ch in word.chars().enumerate() {
+        if ch.0 == 0 {
+            result.push(ch.1);
+        } else {
+            for ch in ch.1.to_lowercase() {
+                result.push(ch);
+            }
+        }
+    }
+
+    result
+}
+
+fn parse_player_info_from(itsf_id: i32, html: &Html) -> Result<Player, String> {
+    let nomdujoueur = get_div_with_class(html, "nomdujoueur");
+    let nomdujoueur = nomdujoueur.first().ok_or("can't find div nomdujoueur")?;
+    let name = nomdujoueur.text().next().ok_or("can't find text in nomdujoueur div")?;
+
+    let last_name = name
+        .split(' ')
+        .filter(|word| !word.is_empty() && is_uppercase(word))
+        .map(to_normalcase)
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    let first_name = name
+        .split(' ')
+        .filter(|word| !word.is_empty() && !is_uppercase(word))
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    let span_selector = Selector::parse("span").unwrap();
+    let country_code = nomdujoueur
+        .select(&span_selector)
+        .next()
+        .ok_or("can't find country code")?;
+    let country_code = country_code.text().next().ok_or("can't find country code text")?;
+    if !country_code.starts_with('(') || !country_code.ends_with(')') {
+        return Err(format!("invalid country code ({:?})", country_code));
+    }
+    let country_code = country_code[1..]
+        .split(' ')
+        .next()
+        .ok_or(format!("invalid country code ({:?})", country_code))?;
+
+    let contenu_typeinfojoueur = get_div_with_class(html, "contenu_typeinfojoueur");
+    if contenu_typeinfojoueur.len() < 2 {
+        return Err(format!(
+            "invalid number of contenu_typeinfojoueur ({})",
+            contenu_typeinfojoueur.len()
+        ));
+    }
+
+    let contenu_typeinfojoueur_even = get_div_with_class(html, "contenu_typeinfojoueur even");
+    if contenu_typeinfojoueur_even.is_empty() {
+        return Err(format!(
+            "invalid number of contenu_typeinfojoueur ({})",
+            contenu_typeinfojoueur_even.len()
+        ));
+    }
+
+    let category = contenu_typeinfojoueur_even[0]
+        .text()
+        .next()
+        .ok_or("can't find category text")?
+        .trim();
+    let category = PlayerCategory::try_from_str(category)?;
+
+    let birth_year = contenu_typeinfojoueur[1].text().next().ok_or("can't find birth year")?;
+    let birth_year = birth_year.parse::<i32>().unwrap_or(0);
+
+    Ok(Player {
+        itsf_id,
+        first_name,
+        last_name,
+        birth_year,
+        country_code: Some(country_code.into()),
+        category,
+        itsf_rankings: Vec::new(),
+        dtfb_id: None,
+        dtfb_championship_results: Vec::new(),
+        dtfb_national_rankings: Vec::new(),
+        dtfb_league_teams: Vec::new(),
+        comments: Vec::new(),
+        tags: Vec::new(),
+        former_names: Vec::new(),
+        country_changes: Vec::new(),
+        national_team_appearances: Vec::new(),
+        hidden: false,
+        scraped_at: Some(chrono::Utc::now().timestamp()),
+        refresh_errors: Vec::new(),
+        anonymized: false,
+        revision: 0,
+        overridden: BTreeMap::new(),
+    })
+}
+
+fn player_fields(player: &Player) -> Vec<(&'static str, bool)> {
+    vec![
+        ("first_name", !player.first_name.is_empty()),
+        ("last_name", !player.last_name.is_empty()),
+        ("birth_year", player.birth_year != 0),
+    ]
+}
+
+fn profile_url(itsf_id: i32) -> String {
+    format!("{}/page/player&numlic={:08}", sources::get().itsf, itsf_id)
+}
+
+/// The profile of an ITSF player. With `replace` the stored profile is replaced, otherwise only the data of the
+/// profile page is refreshed, keeping e.g. the rankings.
+pub struct Profiles {
+    pub replace: bool,
+}
+
+impl Scraper for Profiles {
+    /// The ITSF licence.
+    type Request = i32;
+    type Output = Player;
+
+    fn host(&self) -> SourceHost {
+        SourceHost::Itsf
+    }
+
+    fn parser(&self) -> Parser {
+        Parser::ItsfPlayers
+    }
+
+    fn request(&self, request: &str) -> Result<i32, String> {
+        request
+            .parse::<LicenseNumber>()
+            .map(|license| license.get())
+            .map_err(|_| format!("invalid ITSF licence: '{}'", request))
+    }
+
+    fn url(&self, itsf_id: &i32) -> String {
+        profile_url(*itsf_id)
+    }
+
+    fn parse(&self, itsf_id: &i32, body: &str) -> Result<Player, String> {
+        parse_player_info_from(*itsf_id, &Html::parse_document(body))
+    }
+
+    fn records(&self, player: &Player) -> Vec<Vec<(&'static str, bool)>> {
+        vec![player_fields(player)]
+    }
+
+    async fn persist(
+        &self,
+        db: &DatabaseRef,
+        progress: &BackgroundOperationProgress,
+        _itsf_id: i32,
+        page: Page<Player>,
+    ) {
+        let player = page.data;
+        progress.log(format!(
+            "[ITSF] .. downloaded player info for ID={}: {} {} ({:?}, {:?})",
+            player.itsf_id, player.first_name, player.last_name, player.category, player.country_code
+        ));
+        if self.replace {
+            db.add_player(player);
+        } else {
+            db.refresh_player_info(player);
+        }
+    }
+
+    fn failed(&self, db: &DatabaseRef, itsf_id: &i32, err: &str) {
+        db.record_refresh_error(*itsf_id, RefreshTarget::Profile, String::from(err));
+    }
+}
+
+/// Fetches the live player page, `None` if ITSF doesn't know the licence.
+pub async fn lookup_player_info(itsf_id: i32) -> Result<Option<Player>, String> {
+    let url = profile_url(itsf_id);
+    let body = pipeline::fetch_url(&url, None).await?;
+    let html = Html::parse_document(&body);
+    if get_div_with_class(&html, "nomdujoueur").is_empty() {
+        return Ok(None);
+    }
+    parse_player_info_from(itsf_id, &html)
+        .map(Some)
+        .map_err(|msg| format!("Player[{}]: {}", url, msg))
+}
+
+pub async fn download_player_image(itsf_id: i32) -> Result<Option<PlayerImage>, String> {
+    let url = format!("{}/photos/players/{:08}.jpg", sources::get().itsf_media, itsf_id);
+
+    let image_data = match download::download_bytes(&url).await? {
+        Some(image_data) => image_data,
+        None => return Ok(None),
+    };
+
+    Ok(Some(PlayerImage {
+        itsf_id,
+        image_data,
+        image_format: String::from("jpg"),
+    }))
+}
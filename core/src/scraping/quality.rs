@@ -0,0 +1,148 @@
+//! Counts what the parsers make of the downloaded pages, so a change of an upstream site's markup shows up as
+//! failing or empty pages and missing fields in `GET /admin/quality`. Counts are collected in memory and
+//! added to the database per parser and day when a download job finishes.
+
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::data::quality::{ParseCounts, Parser};
+use crate::data::DatabaseRef;
+
+const DAY: i64 = 24 * 60 * 60;
+
+lazy_static! {
+    static ref PENDING: Mutex<HashMap<(Parser, i64), ParseCounts>> = Mutex::new(HashMap::new());
+}
+
+/// Start of the current day, UTC.
+fn today() -> i64 {
+    let now = chrono::Utc::now().timestamp();
+    now - now.rem_euclid(DAY)
+}
+
+fn update(parser: Parser, update: impl FnOnce(&mut ParseCounts)) {
+    let mut pending = PENDING.lock().unwrap();
+    update(pending.entry((parser, today())).or_default());
+}
+
+/// Records a page the parser rejected.
+pub(crate) fn record_failure(parser: Parser) {
+    update(parser, |counts| {
+        counts.pages += 1;
+        counts.failed += 1;
+    });
+}
+
+/// Records a parsed page with the fields every record has or lacks.
+pub(crate) fn record_page<T>(parser: Parser, records: &[T], fields: impl Fn(&T) -> Vec<(&'static str, bool)>) {
+    update(parser, |counts| {
+        counts.pages += 1;
+        if records.is_empty() {
+            counts.empty += 1;
+        }
+        counts.records += records.len() as u64;
+        for record in records {
+            for (field, present) in fields(record) {
+                let count = counts.present.entry(String::from(field)).or_default();
+                if present {
+                    *count += 1;
+                }
+            }
+        }
+    });
+}
+
+/// Adds the counts collected since the last call to the database.
+pub fn flush(db: &DatabaseRef) {
+    let pending: Vec<((Parser, i64), ParseCounts)> = PENDING.lock().unwrap().drain().collect();
+    for ((parser, day), counts) in pending {
+        db.add_parse_quality(parser, day, &counts);
+    }
+}
+
+/// Rates derived from counts.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QualitySummary {
+    pub pages: u64,
+    pub failed: u64,
+    pub empty: u64,
+    pub records: u64,
+    /// Share of the pages that parsed into at least one record, unknown without pages.
+    pub success_rate: Option<f64>,
+    /// Share of the records that had the field, by field name.
+    pub completeness: BTreeMap<String, f64>,
+}
+
+impl QualitySummary {
+    fn new(counts: &ParseCounts) -> Self {
+        QualitySummary {
+            pages: counts.pages,
+            failed: counts.failed,
+            empty: counts.empty,
+            records: counts.records,
+            success_rate: (counts.pages > 0)
+                .then(|| (counts.pages - counts.failed - counts.empty) as f64 / counts.pages as f64),
+            completeness: counts
+                .present
+                .iter()
+                .filter(|_| counts.records > 0)
+                .map(|(field, present)| (field.clone(), *present as f64 / counts.records as f64))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DayQuality {
+    /// Unix timestamp of the day's start, UTC.
+    pub day: i64,
+    #[serde(flatten)]
+    pub summary: QualitySummary,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ParserQuality {
+    pub parser: Parser,
+    /// Of all days of the report.
+    #[serde(flatten)]
+    pub total: QualitySummary,
+    /// Days the parser ran, oldest first.
+    pub days: Vec<DayQuality>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QualityReport {
+    pub since: i64,
+    pub parsers: Vec<ParserQuality>,
+}
+
+/// Quality of every parser over the last `days` days, including today.
+pub fn report(db: &DatabaseRef, days: i64) -> QualityReport {
+    flush(db);
+    let since = today() - (days.max(1) - 1) * DAY;
+    let stored = db.get_parse_quality_since(since);
+    let parsers = Parser::ALL
+        .into_iter()
+        .map(|parser| {
+            let mut total = ParseCounts::default();
+            let days = stored
+                .iter()
+                .filter(|quality| quality.parser == parser)
+                .map(|quality| {
+                    total.add(&quality.counts);
+                    DayQuality {
+                        day: quality.day,
+                        summary: QualitySummary::new(&quality.counts),
+                    }
+                })
+                .collect();
+            ParserQuality {
+                parser,
+                total: QualitySummary::new(&total),
+                days,
+            }
+        })
+        .collect();
+    QualityReport { since, parsers }
+}
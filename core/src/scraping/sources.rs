@@ -0,0 +1,48 @@
+use std::sync::OnceLock;
+
+/// Base URLs of the scraped sites, without trailing slash.
+#[derive(Debug, Clone)]
+pub struct Sources {
+    pub itsf: String,
+    pub itsf_media: String,
+    pub dtfb: String,
+}
+
+impl Sources {
+    fn live() -> Self {
+        Sources {
+            itsf: String::from("https://www.tablesoccer.org"),
+            itsf_media: String::from("https://media.fast4foos.org"),
+            dtfb: String::from("https://dtfb.de"),
+        }
+    }
+
+    /// The sites as served by a mock source at `base_url`, see `use_mock_source`.
+    pub fn mock(base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        Sources {
+            itsf: format!("{}/itsf", base_url),
+            itsf_media: format!("{}/media", base_url),
+            dtfb: format!("{}/dtfb", base_url),
+        }
+    }
+
+    pub fn hosts(&self) -> [&str; 3] {
+        [&self.itsf, &self.itsf_media, &self.dtfb]
+    }
+}
+
+static SOURCES: OnceLock<Sources> = OnceLock::new();
+
+/// The sites all scrapers download from, the real federation sites unless a mock source was set up.
+pub fn get() -> &'static Sources {
+    SOURCES.get_or_init(Sources::live)
+}
+
+/// Scrapes from a mock server serving the ITSF site under `/itsf`, the ITSF images under `/media`
+/// and the DTFB site under `/dtfb`. Has to be called before the first download.
+pub fn use_mock_source(base_url: &str) -> Result<(), String> {
+    SOURCES
+        .set(Sources::mock(base_url))
+        .map_err(|_| String::from("scrape sources are already in use"))
+}
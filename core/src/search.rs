@@ -0,0 +1,334 @@
+use lazy_static::lazy_static;
+use serde_json::json;
+
+use crate::data::{DatabaseRef, Player};
+
+/// Players are mirrored into the index in batches of this size.
+const BATCH_SIZE: usize = 500;
+
+/// Player fields mirrored into the external search index.
+#[derive(Debug, Clone, serde::Serialize)]
+struct SearchDocument {
+    itsf_lic: i32,
+    first_name: String,
+    last_name: String,
+    country_code: Option<String>,
+    dtfb_id: Option<i32>,
+    tags: Vec<String>,
+    former_names: Vec<String>,
+}
+
+impl SearchDocument {
+    fn new(player: &Player) -> Self {
+        SearchDocument {
+            itsf_lic: player.itsf_id,
+            first_name: player.first_name.clone(),
+            last_name: player.last_name.clone(),
+            country_code: player.country_code.clone(),
+            dtfb_id: player.dtfb_id,
+            tags: player.tags.clone(),
+            former_names: player.former_names.iter().map(|name| name.full_name()).collect(),
+        }
+    }
+}
+
+/// Connection to a Meilisearch index, configured via `MEILISEARCH_URL`, `MEILISEARCH_KEY` and `MEILISEARCH_INDEX`.
+struct SearchIndex {
+    url: String,
+    key: Option<String>,
+    index: String,
+}
+
+impl SearchIndex {
+    fn from_env() -> Option<Self> {
+        let url = std::env::var("MEILISEARCH_URL").ok()?;
+        Some(SearchIndex {
+            url: String::from(url.trim_end_matches('/')),
+            key: std::env::var("MEILISEARCH_KEY").ok(),
+            index: std::env::var("MEILISEARCH_INDEX").unwrap_or(String::from("players")),
+        })
+    }
+
+    fn request(&self, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/indexes/{}/{}", self.url, self.index, path);
+        let request = reqwest::Client::new().post(url);
+        match &self.key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    async fn add_documents(&self, documents: &[SearchDocument]) -> Result<(), String> {
+        let response = self
+            .request("documents?primaryKey=itsf_lic")
+            .json(documents)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("search index returned {}", response.status()))
+        }
+    }
+
+    async fn delete_documents(&self, itsf_ids: &[i32]) -> Result<(), String> {
+        let response = self
+            .request("documents/delete-batch")
+            .json(itsf_ids)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("search index returned {}", response.status()))
+        }
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<i32>, String> {
+        #[derive(serde::Deserialize)]
+        struct Hit {
+            itsf_lic: i32,
+        }
+        #[derive(serde::Deserialize)]
+        struct SearchResult {
+            hits: Vec<Hit>,
+        }
+
+        let response = self
+            .request("search")
+            .json(&json!({ "q": query, "limit": limit, "attributesToRetrieve": ["itsf_lic"] }))
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("search index returned {}", response.status()));
+        }
+        let result: SearchResult = response.json().await.map_err(|err| err.to_string())?;
+        Ok(result.hits.into_iter().map(|hit| hit.itsf_lic).collect())
+    }
+}
+
+lazy_static! {
+    static ref SEARCH_INDEX: Option<SearchIndex> = SearchIndex::from_env();
+}
+
+/// Mirrors all visible players into the search index and keeps it updated after every player write.
+pub fn start_index_sync(db: &DatabaseRef) {
+    let index = match SEARCH_INDEX.as_ref() {
+        Some(index) => index,
+        None => return,
+    };
+
+    let mut writes = db.subscribe_player_writes();
+    let players: Vec<SearchDocument> = db
+        .get_player_ids()
+        .into_iter()
+        .filter_map(|itsf_id| db.get_player(itsf_id))
+        .filter(|player| !player.hidden)
+        .map(|player| SearchDocument::new(&player))
+        .collect();
+
+    tokio::spawn(async move {
+        for batch in players.chunks(BATCH_SIZE) {
+            if let Err(err) = index.add_documents(batch).await {
+                log::error!("[Search] failed to sync players: {}", err);
+            }
+        }
+        log::info!("[Search] synced {} players", players.len());
+
+        while let Some(player) = writes.recv().await {
+            let mut players = vec![player];
+            while players.len() < BATCH_SIZE {
+                match writes.try_recv() {
+                    Ok(player) => players.push(player),
+                    Err(_) => break,
+                }
+            }
+
+            let (hidden, visible): (Vec<Player>, Vec<Player>) = players.into_iter().partition(|player| player.hidden);
+            let batch: Vec<SearchDocument> = visible.iter().map(SearchDocument::new).collect();
+            if !batch.is_empty() {
+                if let Err(err) = index.add_documents(&batch).await {
+                    log::error!("[Search] failed to sync {} players: {}", batch.len(), err);
+                }
+            }
+            let hidden: Vec<i32> = hidden.iter().map(|player| player.itsf_id).collect();
+            if !hidden.is_empty() {
+                if let Err(err) = index.delete_documents(&hidden).await {
+                    log::error!("[Search] failed to remove {} hidden players: {}", hidden.len(), err);
+                }
+            }
+        }
+    });
+}
+
+fn matches_locally(player: &Player, words: &[String]) -> bool {
+    let former_names: Vec<String> = player.former_names.iter().map(|name| name.full_name()).collect();
+    let text = format!(
+        "{} {} {} {} {} {} {}",
+        player.first_name,
+        player.last_name,
+        player.country_code.as_deref().unwrap_or_default(),
+        player.itsf_id,
+        player.dtfb_id.map(|dtfb_id| dtfb_id.to_string()).unwrap_or_default(),
+        player.tags.join(" "),
+        former_names.join(" ")
+    )
+    .to_lowercase();
+    words.iter().all(|word| text.contains(word.as_str()))
+}
+
+/// Finds players by current or former name, ITSF or DTFB license, country or tag, using the search index if one is configured.
+/// The search index only contains visible players, so hidden players are only found by the local search.
+pub async fn search_players(db: &DatabaseRef, query: &str, limit: usize, include_hidden: bool) -> Vec<Player> {
+    if let Some(index) = SEARCH_INDEX.as_ref().filter(|_| !include_hidden) {
+        match index.search(query, limit).await {
+            Ok(ids) => {
+                return ids
+                    .into_iter()
+                    .filter_map(|itsf_id| db.get_player(itsf_id))
+                    .filter(|player| !player.hidden)
+                    .collect()
+            }
+            Err(err) => log::error!("[Search] falling back to local search: {}", err),
+        }
+    }
+
+    let words: Vec<String> = query.split_whitespace().map(|word| word.to_lowercase()).collect();
+    let mut players: Vec<Player> = db
+        .get_player_ids()
+        .into_iter()
+        .filter_map(|itsf_id| db.get_player(itsf_id))
+        .filter(|player| include_hidden || !player.hidden)
+        .filter(|player| matches_locally(player, &words))
+        .collect();
+    players.sort_by(|a, b| (&a.last_name, &a.first_name).cmp(&(&b.last_name, &b.first_name)));
+    players.truncate(limit);
+    players
+}
+
+/// Characters of comment text shown before and after the first match.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A comment containing all words of a search query.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CommentMatch {
+    pub timestamp: u32,
+    /// HTML-escaped excerpt around the first match, with matched words wrapped in `<em>`.
+    pub snippet: String,
+}
+
+fn lowercase_chars(text: &str) -> Vec<char> {
+    text.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect()
+}
+
+fn push_escaped(snippet: &mut String, c: char) {
+    match c {
+        '&' => snippet.push_str("&amp;"),
+        '<' => snippet.push_str("&lt;"),
+        '>' => snippet.push_str("&gt;"),
+        c => snippet.push(c),
+    }
+}
+
+/// Snippet of the text with all words highlighted, or None if any word is missing.
+fn highlight(text: &str, words: &[String]) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower = lowercase_chars(text);
+
+    // character ranges of all occurrences of all words
+    let mut ranges = Vec::new();
+    for word in words {
+        let word = lowercase_chars(word);
+        let occurrences: Vec<(usize, usize)> = (0..lower.len().saturating_sub(word.len() - 1))
+            .filter(|start| lower[*start..].starts_with(&word))
+            .map(|start| (start, start + word.len()))
+            .collect();
+        if occurrences.is_empty() {
+            return None;
+        }
+        ranges.extend(occurrences);
+    }
+    ranges.sort();
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let from = merged[0].0.saturating_sub(SNIPPET_CONTEXT);
+    let to = (merged[0].1 + SNIPPET_CONTEXT).min(chars.len());
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    let mut highlighted = false;
+    for (i, c) in chars.iter().enumerate().take(to).skip(from) {
+        if !highlighted
+            && merged
+                .iter()
+                .any(|(start, end)| *start == i || (i == from && *start < i && i < *end))
+        {
+            snippet.push_str("<em>");
+            highlighted = true;
+        }
+        push_escaped(&mut snippet, *c);
+        if highlighted && merged.iter().any(|(_, end)| *end == i + 1) {
+            snippet.push_str("</em>");
+            highlighted = false;
+        }
+    }
+    if highlighted {
+        snippet.push_str("</em>");
+    }
+    if to < chars.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+/// Like `search_players`, but also finds players by the text of their comments, including internal ones and
+/// hidden players, so only meant for authenticated users. Searches the comments of the workspace instead of the
+/// shared ones if there is one. Always searches locally, comments are never indexed.
+pub fn search_players_and_comments(
+    db: &DatabaseRef,
+    workspace: Option<&str>,
+    query: &str,
+    limit: usize,
+) -> Vec<(Player, Vec<CommentMatch>)> {
+    let words: Vec<String> = query.split_whitespace().map(|word| word.to_lowercase()).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut results: Vec<(Player, Vec<CommentMatch>)> = db
+        .get_player_ids()
+        .into_iter()
+        .filter_map(|itsf_id| db.get_player(itsf_id))
+        .map(|player| db.with_workspace_notes(workspace, player))
+        .filter_map(|player| {
+            let comments: Vec<CommentMatch> = player
+                .comments
+                .iter()
+                .filter_map(|comment| {
+                    Some(CommentMatch {
+                        timestamp: comment.timestamp,
+                        snippet: highlight(&comment.text, &words)?,
+                    })
+                })
+                .collect();
+            if comments.is_empty() && !matches_locally(&player, &words) {
+                return None;
+            }
+            Some((player, comments))
+        })
+        .collect();
+    results.sort_by(|(a, _), (b, _)| (&a.last_name, &a.first_name).cmp(&(&b.last_name, &b.first_name)));
+    results.truncate(limit);
+    results
+}
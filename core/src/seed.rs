@@ -0,0 +1,370 @@
+//! Generates fake players with rankings, images and comments, for load testing and frontend development.
+
+use chrono::Datelike;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Cursor;
+
+use crate::data::{
+    dtfb, itsf,
+    season::{self, Season},
+    CommentVisibility, DatabaseRef, Player, PlayerComment, PlayerImage,
+};
+
+/// Licenses of generated players start here, far above the licenses ITSF hands out.
+pub const FIRST_LICENSE: i32 = 99000000;
+/// Generated DTFB ids are the ITSF license minus this offset.
+const DTFB_ID_OFFSET: i32 = FIRST_LICENSE - 900000;
+
+const MALE_FIRST_NAMES: [&str; 16] = [
+    "Alexander",
+    "Ben",
+    "Carlos",
+    "Daniel",
+    "Elias",
+    "Felix",
+    "Jan",
+    "Jonas",
+    "Lukas",
+    "Marco",
+    "Niklas",
+    "Paul",
+    "Pierre",
+    "Sebastian",
+    "Thomas",
+    "Tobias",
+];
+const FEMALE_FIRST_NAMES: [&str; 12] = [
+    "Anna",
+    "Clara",
+    "Emma",
+    "Hannah",
+    "Julia",
+    "Laura",
+    "Lea",
+    "Maria",
+    "Nina",
+    "Sarah",
+    "Sophie",
+    "Valentina",
+];
+const LAST_NAMES: [&str; 20] = [
+    "Bauer", "Becker", "Dubois", "Fischer", "Garcia", "Hoffmann", "Keller", "Klein", "Lambert", "Meyer", "Müller",
+    "Peeters", "Richter", "Rossi", "Schäfer", "Schmidt", "Schulz", "Wagner", "Weber", "Wolf",
+];
+/// Countries with their weight, Germany is overrepresented like in the real database.
+const COUNTRIES: [(&str, u64); 8] = [
+    ("GER", 8),
+    ("AUT", 2),
+    ("BEL", 2),
+    ("FRA", 3),
+    ("ITA", 2),
+    ("ESP", 1),
+    ("USA", 2),
+    ("DEN", 1),
+];
+const TEAMS: [&str; 8] = [
+    "Kickerfreunde Nord",
+    "TFC Süd",
+    "Stangenzauber",
+    "Tischkicker Team West",
+    "Bandenkönige",
+    "KC Ost",
+    "Flipperfüße",
+    "Die Torjäger",
+];
+const TAGS: [&str; 5] = ["goalie", "forward", "rookie", "veteran", "trainer"];
+const COMMENTS: [&str; 8] = [
+    "Very strong pull shot",
+    "Plays mostly doubles in the back",
+    "Great passing from the five bar",
+    "Came back after a long break",
+    "Reliable partner, good at reading the game",
+    "Needs to work on the defence",
+    "Plays well with #{}",
+    "Won the local league last season",
+];
+
+/// Small xorshift generator, enough for fake data and without pulling in a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    fn weighted<'a, T>(&mut self, items: &'a [(T, u64)]) -> &'a T {
+        let mut choice = self.below(items.iter().map(|item| item.1).sum());
+        for (item, weight) in items {
+            if choice < *weight {
+                return item;
+            }
+            choice -= weight;
+        }
+        &items[0].0
+    }
+}
+
+/// A plain coloured JPEG portrait, distinct per player.
+pub fn portrait(itsf_id: i32) -> Vec<u8> {
+    let shade = (itsf_id % 8) as u8 * 16;
+    let image = image::RgbImage::from_pixel(120, 160, image::Rgb([60 + shade, 120, 200 - shade]));
+    let mut jpeg = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+        .expect("failed to encode portrait");
+    jpeg
+}
+
+struct SeedPlayer {
+    player: Player,
+    female: bool,
+    strength: u64,
+    first_year: i32,
+    last_year: i32,
+    team: Option<&'static str>,
+}
+
+impl SeedPlayer {
+    fn active_in(&self, year: i32) -> bool {
+        self.first_year <= year && year <= self.last_year
+    }
+
+    fn fits(&self, category: itsf::RankingCategory, year: i32) -> bool {
+        let age = year - self.player.birth_year;
+        match category {
+            itsf::RankingCategory::Open => true,
+            itsf::RankingCategory::Women => self.female,
+            itsf::RankingCategory::Junior => age <= 18,
+            itsf::RankingCategory::Senior => age >= 50,
+        }
+    }
+}
+
+fn generate_player(rng: &mut Rng, itsf_id: i32, now: i64, current_year: i32) -> SeedPlayer {
+    let female = rng.chance(30);
+    let birth_year = current_year - 12 - rng.below(55) as i32;
+    let age = current_year - birth_year;
+    let category = match (female, age) {
+        (false, ..=18) => itsf::PlayerCategory::JuniorMale,
+        (true, ..=18) => itsf::PlayerCategory::JuniorFemale,
+        (false, 50..) => itsf::PlayerCategory::SeniorMale,
+        (true, 50..) => itsf::PlayerCategory::SeniorFemale,
+        (false, _) => itsf::PlayerCategory::Men,
+        (true, _) => itsf::PlayerCategory::Women,
+    };
+    let first_name = match female {
+        true => rng.pick(&FEMALE_FIRST_NAMES),
+        false => rng.pick(&MALE_FIRST_NAMES),
+    };
+    let country_code = *rng.weighted(&COUNTRIES);
+    let first_year = (season::FIRST_YEAR + rng.below(15) as i32).max(birth_year + 10);
+    let last_year = (first_year + 2 + rng.below(15) as i32).min(current_year);
+    // some profiles are older than the default DATA_STALE_AFTER, to see stale players in the frontend
+    let scraped_at = now - rng.below(250 * 24 * 60 * 60) as i64;
+
+    SeedPlayer {
+        player: Player {
+            itsf_id,
+            first_name: String::from(*first_name),
+            last_name: String::from(*rng.pick(&LAST_NAMES)),
+            birth_year,
+            country_code: Some(String::from(country_code)),
+            category,
+            itsf_rankings: Vec::new(),
+            dtfb_id: (country_code == "GER").then_some(itsf_id - DTFB_ID_OFFSET),
+            dtfb_national_rankings: Vec::new(),
+            dtfb_championship_results: Vec::new(),
+            dtfb_league_teams: Vec::new(),
+            comments: Vec::new(),
+            tags: Vec::new(),
+            former_names: Vec::new(),
+            country_changes: Vec::new(),
+            national_team_appearances: Vec::new(),
+            hidden: false,
+            scraped_at: Some(scraped_at),
+            refresh_errors: Vec::new(),
+            anonymized: false,
+            revision: 0,
+            overridden: BTreeMap::new(),
+        },
+        female,
+        strength: rng.below(1000),
+        first_year,
+        last_year,
+        team: (country_code == "GER" && rng.chance(60)).then(|| *rng.pick(&TEAMS)),
+    }
+}
+
+/// Orders the players by strength with some luck, the first one is placed first.
+fn placements(rng: &mut Rng, players: &[usize], seeds: &[SeedPlayer]) -> Vec<(usize, i32)> {
+    let mut scored: Vec<(u64, usize)> = players
+        .iter()
+        .map(|index| (seeds[*index].strength + rng.below(300), *index))
+        .collect();
+    scored.sort_by(|a, b| b.cmp(a));
+    scored
+        .into_iter()
+        .enumerate()
+        .map(|(place, (_, index))| (index, place as i32 + 1))
+        .collect()
+}
+
+fn add_itsf_rankings(rng: &mut Rng, seeds: &mut [SeedPlayer], years: &[i32]) {
+    for year in years {
+        for category in itsf::RankingCategory::ALL {
+            let eligible: Vec<usize> = (0..seeds.len())
+                .filter(|index| seeds[*index].active_in(*year) && seeds[*index].fits(category, *year))
+                .collect();
+            for class in itsf::RankingClass::ALL {
+                for (index, place) in placements(rng, &eligible, seeds) {
+                    seeds[index].player.itsf_rankings.push(itsf::Ranking {
+                        year: *year,
+                        place,
+                        category,
+                        class,
+                        percentile: itsf::percentile(place, eligible.len()),
+                        provenance: None,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn add_dtfb_results(rng: &mut Rng, seeds: &mut [SeedPlayer], years: &[i32]) {
+    const CHAMPIONSHIP_PLACES: i32 = 16;
+    for year in years {
+        for female in [false, true] {
+            let category = match female {
+                true => dtfb::ChampionshipCategory::Women,
+                false => dtfb::ChampionshipCategory::Men,
+            };
+            let eligible: Vec<usize> = (0..seeds.len())
+                .filter(|index| {
+                    let seed = &seeds[*index];
+                    seed.player.dtfb_id.is_some() && seed.female == female && seed.active_in(*year)
+                })
+                .collect();
+            for (index, place) in placements(rng, &eligible, seeds) {
+                let player = &mut seeds[index].player;
+                player.dtfb_national_rankings.push(dtfb::NationalRanking {
+                    year: *year,
+                    place,
+                    category,
+                    provenance: None,
+                });
+            }
+            for class in [dtfb::ChampionshipClass::Singles, dtfb::ChampionshipClass::Doubles] {
+                for (index, place) in placements(rng, &eligible, seeds) {
+                    if place <= CHAMPIONSHIP_PLACES {
+                        seeds[index]
+                            .player
+                            .dtfb_championship_results
+                            .push(dtfb::NationalChampionshipResult {
+                                year: *year,
+                                place,
+                                category,
+                                class,
+                                provenance: None,
+                            });
+                    }
+                }
+            }
+        }
+    }
+    for seed in seeds.iter_mut() {
+        if let Some(team) = seed.team {
+            seed.player.dtfb_league_teams = years
+                .iter()
+                .filter(|year| seed.active_in(**year))
+                .map(|year| dtfb::NationalTeam {
+                    year: *year,
+                    name: String::from(team),
+                    provenance: None,
+                })
+                .collect();
+        }
+    }
+}
+
+fn add_comments_and_tags(rng: &mut Rng, seeds: &mut [SeedPlayer], now: i64) {
+    let itsf_ids: Vec<i32> = seeds.iter().map(|seed| seed.player.itsf_id).collect();
+    for seed in seeds.iter_mut() {
+        for _ in 0..rng.below(4) {
+            let text = rng.pick(&COMMENTS).replace("{}", &rng.pick(&itsf_ids).to_string());
+            seed.player.comments.push(PlayerComment {
+                timestamp: (now - rng.below(3 * 365 * 24 * 60 * 60) as i64) as u32,
+                text,
+                visibility: match rng.chance(25) {
+                    true => CommentVisibility::Internal,
+                    false => CommentVisibility::Public,
+                },
+                author: None,
+                status: None,
+                resolved_by: None,
+                resolved_at: None,
+                thumbs_up: Vec::new(),
+            });
+        }
+        seed.player.comments.sort_by_key(|comment| comment.timestamp);
+        if rng.chance(20) {
+            seed.player.tags.push(String::from(*rng.pick(&TAGS)));
+        }
+    }
+}
+
+/// Adds `count` fake players with licenses from `FIRST_LICENSE` on, skipping licenses already in use,
+/// and returns their licenses. The same `random_seed` generates the same players.
+pub fn seed_players(db: &DatabaseRef, count: usize, random_seed: u64) -> Vec<i32> {
+    let mut rng = Rng::new(random_seed);
+    let now = chrono::Utc::now().timestamp();
+    let current_year = chrono::Utc::now().year();
+
+    let existing: HashSet<i32> = db.get_player_ids().into_iter().collect();
+    let itsf_ids: Vec<i32> = (FIRST_LICENSE..)
+        .filter(|itsf_id| !existing.contains(itsf_id))
+        .take(count)
+        .collect();
+    let mut seeds: Vec<SeedPlayer> = itsf_ids
+        .iter()
+        .map(|itsf_id| generate_player(&mut rng, *itsf_id, now, current_year))
+        .collect();
+
+    let years: Vec<i32> = Season::all_itsf().into_iter().map(Season::year).collect();
+    add_itsf_rankings(&mut rng, &mut seeds, &years);
+    add_dtfb_results(&mut rng, &mut seeds, &years);
+    add_comments_and_tags(&mut rng, &mut seeds, now);
+
+    for seed in seeds {
+        let itsf_id = seed.player.itsf_id;
+        db.add_player(seed.player);
+        if rng.chance(80) {
+            db.set_player_image(PlayerImage {
+                itsf_id,
+                image_data: portrait(itsf_id),
+                image_format: String::from("jpg"),
+            })
+            .expect("failed to store portrait");
+        }
+    }
+    itsf_ids
+}
@@ -0,0 +1,410 @@
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::data::itsf::{RankingCategory, RankingClass};
+use crate::data::{DatabaseRef, Player};
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RecordHolder {
+    pub itsf_lic: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub year: i32,
+}
+
+/// The best ITSF ranking place ever reached in a category and class, with everybody who reached it.
+/// Includes the combined class, whose records are kept apart from the singles and doubles ones.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Record {
+    pub category: RankingCategory,
+    pub class: RankingClass,
+    pub place: i32,
+    pub holders: Vec<RecordHolder>,
+}
+
+/// Country used for `/records` unless requested otherwise, configured via `RECORDS_COUNTRY`.
+pub fn default_country() -> String {
+    std::env::var("RECORDS_COUNTRY").unwrap_or(String::from("GER"))
+}
+
+/// Best-ever ITSF placements of the players of a country, per category and class.
+#[derive(Default)]
+struct RecordsBuilder(HashMap<(RankingCategory, RankingClass), Record>);
+
+impl RecordsBuilder {
+    fn add(&mut self, player: &Player) {
+        for ranking in &player.itsf_rankings {
+            let record = self.0.entry((ranking.category, ranking.class)).or_insert(Record {
+                category: ranking.category,
+                class: ranking.class,
+                place: ranking.place,
+                holders: Vec::new(),
+            });
+            if ranking.place < record.place {
+                record.place = ranking.place;
+                record.holders.clear();
+            }
+            if ranking.place == record.place {
+                record.holders.push(RecordHolder {
+                    itsf_lic: player.itsf_id,
+                    first_name: player.first_name.clone(),
+                    last_name: player.last_name.clone(),
+                    year: ranking.year,
+                });
+            }
+        }
+    }
+
+    fn build(self) -> Vec<Record> {
+        let mut records: Vec<Record> = self.0.into_values().collect();
+        for record in &mut records {
+            record.holders.sort_by_key(|holder| (holder.year, holder.itsf_lic));
+        }
+        records.sort_by_key(|record| (record.category as i8, record.class as i8));
+        records
+    }
+}
+
+/// Places beyond this don't earn points in the country ranking.
+const MAX_SCORING_PLACE: i32 = 100;
+
+/// Points a ranking place earns for the country ranking: 100 for 1st place down to 1 for 100th.
+pub(crate) fn place_points(place: i32) -> i32 {
+    (MAX_SCORING_PLACE + 1 - place).max(0)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CountryRanking {
+    pub country_code: String,
+    pub points: i32,
+    pub players: usize,
+    pub best_place: i32,
+}
+
+/// Countries of a year by the points of their players.
+#[derive(Default)]
+struct CountryRankingBuilder(HashMap<String, (CountryRanking, HashSet<i32>)>);
+
+impl CountryRankingBuilder {
+    fn add(&mut self, country_code: &str, itsf_id: i32, place: i32) {
+        let (country, players) = self.0.entry(String::from(country_code)).or_insert((
+            CountryRanking {
+                country_code: String::from(country_code),
+                points: 0,
+                players: 0,
+                best_place: place,
+            },
+            HashSet::new(),
+        ));
+        country.points += place_points(place);
+        country.best_place = country.best_place.min(place);
+        players.insert(itsf_id);
+    }
+
+    fn build(self) -> Vec<CountryRanking> {
+        let mut ranking: Vec<CountryRanking> = self
+            .0
+            .into_values()
+            .map(|(mut country, players)| {
+                country.players = players.len();
+                country
+            })
+            .collect();
+        ranking.sort_by(|a, b| {
+            b.points
+                .cmp(&a.points)
+                .then_with(|| a.country_code.cmp(&b.country_code))
+        });
+        ranking
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+pub enum TimeseriesMetric {
+    /// Players with at least one ITSF ranking in the year.
+    #[serde(rename = "players_ranked")]
+    PlayersRanked,
+    /// Mean place over all ITSF rankings of the year.
+    #[serde(rename = "average_rank")]
+    AverageRank,
+    /// ITSF rankings of the year with a place in the top three.
+    #[serde(rename = "podiums")]
+    Podiums,
+}
+
+impl TimeseriesMetric {
+    const ALL: [TimeseriesMetric; 3] = [Self::PlayersRanked, Self::AverageRank, Self::Podiums];
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TimeseriesPoint {
+    pub year: i32,
+    pub value: f64,
+}
+
+/// Ranking figures of a year, for the timeseries.
+#[derive(Default)]
+struct YearStats {
+    ranked: HashSet<i32>,
+    rankings: usize,
+    place_sum: i64,
+    podiums: usize,
+}
+
+impl YearStats {
+    fn add(&mut self, itsf_id: i32, place: i32) {
+        self.ranked.insert(itsf_id);
+        self.rankings += 1;
+        self.place_sum += place as i64;
+        if place <= 3 {
+            self.podiums += 1;
+        }
+    }
+
+    fn value(&self, metric: TimeseriesMetric) -> f64 {
+        match metric {
+            TimeseriesMetric::PlayersRanked => self.ranked.len() as f64,
+            TimeseriesMetric::AverageRank => self.place_sum as f64 / self.rankings as f64,
+            TimeseriesMetric::Podiums => self.podiums as f64,
+        }
+    }
+}
+
+/// Year-by-year values of the metric.
+fn timeseries_of(years: &HashMap<i32, YearStats>, metric: TimeseriesMetric) -> Vec<TimeseriesPoint> {
+    let mut series: Vec<TimeseriesPoint> = years
+        .iter()
+        .map(|(year, stats)| TimeseriesPoint {
+            year: *year,
+            value: stats.value(metric),
+        })
+        .collect();
+    series.sort_by_key(|point| point.year);
+    series
+}
+
+/// Doubles places of a season count half as much as those of the following one.
+const DOUBLES_DECAY_PER_YEAR: f64 = 0.5;
+
+/// How well a player does in the doubles rankings of a category.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct DoublesStrength {
+    /// Average of the points of the doubles places on the scale of the country ranking, weighting every season
+    /// half as much as the next one up to the latest ranked season, 0 without doubles places.
+    pub strength: f64,
+    pub seasons: usize,
+    pub best_place: Option<i32>,
+}
+
+/// Doubles places per player and category, as (year, place).
+type DoublesPlaces = HashMap<(i32, RankingCategory), Vec<(i32, i32)>>;
+
+fn doubles_strengths(places: DoublesPlaces, latest_year: i32) -> HashMap<(i32, RankingCategory), DoublesStrength> {
+    places
+        .into_iter()
+        .map(|(key, places)| {
+            let mut strength = DoublesStrength::default();
+            let (mut points, mut weights) = (0.0, 0.0);
+            for (year, place) in places {
+                let weight = DOUBLES_DECAY_PER_YEAR.powi((latest_year - year).max(0));
+                points += weight * place_points(place) as f64;
+                weights += weight;
+                strength.seasons += 1;
+                strength.best_place = Some(strength.best_place.map_or(place, |best| best.min(place)));
+            }
+            strength.strength = (points / weights * 100.0).round() / 100.0;
+            (key, strength)
+        })
+        .collect()
+}
+
+/// All aggregates served by the stats endpoints, computed in the background so requests never wait for them.
+#[derive(Default)]
+struct Leaderboards {
+    /// Records per country, with and without hidden players.
+    records: HashMap<(String, bool), Vec<Record>>,
+    country_rankings: HashMap<i32, Vec<CountryRanking>>,
+    latest_year: Option<i32>,
+    timeseries: HashMap<(TimeseriesMetric, Option<String>), Vec<TimeseriesPoint>>,
+    doubles_strengths: HashMap<(i32, RankingCategory), DoublesStrength>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct RefreshStatus {
+    pub running: bool,
+    /// Unix timestamp of the last finished refresh.
+    pub last_finished: Option<i64>,
+    pub log: Vec<String>,
+}
+
+lazy_static! {
+    static ref LEADERBOARDS: Mutex<Arc<Leaderboards>> = Mutex::new(Arc::new(Leaderboards::default()));
+    static ref REFRESH_STATUS: Mutex<RefreshStatus> = Mutex::new(RefreshStatus::default());
+    static ref REFRESH_REQUESTED: Notify = Notify::new();
+}
+
+fn leaderboards() -> Arc<Leaderboards> {
+    LEADERBOARDS.lock().unwrap().clone()
+}
+
+/// Computes every aggregate in a single pass over the players, as it blocks all other database access.
+fn compute_leaderboards(db: &DatabaseRef) -> Leaderboards {
+    let mut records: HashMap<(String, bool), RecordsBuilder> = HashMap::new();
+    let mut country_rankings: HashMap<i32, CountryRankingBuilder> = HashMap::new();
+    let mut years: HashMap<Option<String>, HashMap<i32, YearStats>> = HashMap::new();
+    let mut doubles: DoublesPlaces = HashMap::new();
+
+    db.aggregate_players(|players| {
+        for player in players {
+            if let Some(country_code) = &player.country_code {
+                records.entry((country_code.clone(), true)).or_default().add(player);
+                let visible_records = records.entry((country_code.clone(), false)).or_default();
+                if !player.hidden {
+                    visible_records.add(player);
+                }
+                years.entry(Some(country_code.clone())).or_default();
+            }
+
+            for ranking in &player.itsf_rankings {
+                if ranking.class == RankingClass::Doubles {
+                    doubles
+                        .entry((player.itsf_id, ranking.category))
+                        .or_default()
+                        .push((ranking.year, ranking.place));
+                }
+                let country_ranking = country_rankings.entry(ranking.year).or_default();
+                // combined rankings are made of the singles and doubles results, counting them would score those twice
+                if ranking.class == RankingClass::Combined {
+                    continue;
+                }
+                if let Some(country_code) = &player.country_code {
+                    country_ranking.add(country_code, player.itsf_id, ranking.place);
+                    years
+                        .entry(Some(country_code.clone()))
+                        .or_default()
+                        .entry(ranking.year)
+                        .or_default()
+                        .add(player.itsf_id, ranking.place);
+                }
+                years
+                    .entry(None)
+                    .or_default()
+                    .entry(ranking.year)
+                    .or_default()
+                    .add(player.itsf_id, ranking.place);
+            }
+        }
+    });
+
+    let latest_year = country_rankings.keys().copied().max();
+    let mut timeseries = HashMap::new();
+    for (country_code, years) in &years {
+        for metric in TimeseriesMetric::ALL {
+            timeseries.insert((metric, country_code.clone()), timeseries_of(years, metric));
+        }
+    }
+    Leaderboards {
+        records: records
+            .into_iter()
+            .map(|(key, records)| (key, records.build()))
+            .collect(),
+        country_rankings: country_rankings
+            .into_iter()
+            .map(|(year, ranking)| (year, ranking.build()))
+            .collect(),
+        latest_year,
+        timeseries,
+        doubles_strengths: latest_year.map_or_else(HashMap::new, |latest_year| doubles_strengths(doubles, latest_year)),
+    }
+}
+
+fn refresh_blocking(db: &DatabaseRef) {
+    REFRESH_STATUS.lock().unwrap().running = true;
+    let start = Instant::now();
+    let leaderboards = compute_leaderboards(db);
+    *LEADERBOARDS.lock().unwrap() = Arc::new(leaderboards);
+
+    let mut status = REFRESH_STATUS.lock().unwrap();
+    status.running = false;
+    status.last_finished = Some(chrono::Utc::now().timestamp());
+    status.log = vec![format!(
+        "[Stats] refreshed leaderboards in {} ms",
+        start.elapsed().as_millis()
+    )];
+}
+
+/// Recomputes all leaderboards from the current players, on a blocking thread as it scans every player.
+pub async fn refresh(db: &DatabaseRef) {
+    let db = db.clone();
+    if let Err(err) = tokio::task::spawn_blocking(move || refresh_blocking(&db)).await {
+        log::error!("[Stats] failed to refresh leaderboards: {}", err);
+    }
+}
+
+fn refresh_interval() -> Duration {
+    match std::env::var("LEADERBOARD_REFRESH_INTERVAL") {
+        Ok(secs) => Duration::from_secs(secs.parse::<u64>().expect("invalid LEADERBOARD_REFRESH_INTERVAL")),
+        Err(_) => Duration::from_secs(60 * 60),
+    }
+}
+
+/// Computes the leaderboards once, then refreshes them whenever requested and periodically if players changed.
+pub fn start_refresh_task(db: &DatabaseRef) {
+    let db = db.clone();
+    let interval = refresh_interval();
+    tokio::spawn(async move {
+        let mut generation = db.get_player_generation();
+        refresh(&db).await;
+        loop {
+            let requested = tokio::time::timeout(interval, REFRESH_REQUESTED.notified())
+                .await
+                .is_ok();
+            let current_generation = db.get_player_generation();
+            if requested || current_generation != generation {
+                generation = current_generation;
+                refresh(&db).await;
+            }
+        }
+    });
+}
+
+/// Asks the refresh task to recompute the leaderboards, e.g. after a download job.
+pub fn request_refresh() {
+    REFRESH_REQUESTED.notify_one();
+}
+
+pub fn refresh_status() -> RefreshStatus {
+    REFRESH_STATUS.lock().unwrap().clone()
+}
+
+/// Best-ever ITSF placements of players from the given country, per category and class.
+pub fn records(country_code: &str, include_hidden: bool) -> Vec<Record> {
+    let key = (String::from(country_code), include_hidden);
+    leaderboards().records.get(&key).cloned().unwrap_or_default()
+}
+
+/// Countries ordered by the ranking points of their players in the given year, the latest year by default.
+pub fn country_ranking(year: Option<i32>) -> Vec<CountryRanking> {
+    let leaderboards = leaderboards();
+    year.or(leaderboards.latest_year)
+        .and_then(|year| leaderboards.country_rankings.get(&year).cloned())
+        .unwrap_or_default()
+}
+
+/// Year-by-year values of the metric, optionally restricted to players of one country.
+pub fn timeseries(metric: TimeseriesMetric, country_code: Option<&str>) -> Vec<TimeseriesPoint> {
+    let key = (metric, country_code.map(String::from));
+    leaderboards().timeseries.get(&key).cloned().unwrap_or_default()
+}
+
+/// The doubles strength of a player in the category.
+pub fn doubles_strength(itsf_lic: i32, category: RankingCategory) -> DoublesStrength {
+    leaderboards()
+        .doubles_strengths
+        .get(&(itsf_lic, category))
+        .cloned()
+        .unwrap_or_default()
+}
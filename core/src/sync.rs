@@ -0,0 +1,67 @@
+//! Players changed since a revision, for clients of `/sync` keeping a local copy of all players. Tags of
+//! workspaces don't change the revision of a player.
+
+use crate::data::documents::PlayerDocument;
+use crate::data::{DatabaseRef, Player};
+use crate::replication::{SyncDocument, SyncDocuments};
+
+pub struct Changes {
+    /// Pass as `since_revision` next time.
+    pub revision: u64,
+    /// False if more changes follow after `revision`.
+    pub complete: bool,
+    /// Oldest change first.
+    pub players: Vec<Player>,
+}
+
+impl Changes {
+    /// At most `limit` players changed after `since_revision`, `limit` must be positive.
+    pub fn since(db: &DatabaseRef, since_revision: u64, limit: usize) -> Self {
+        let latest = db.get_player_revision();
+        let mut players = db.get_players_changed_since(since_revision);
+        let complete = players.len() <= limit;
+        players.truncate(limit);
+        let revision = match complete {
+            true => latest.max(since_revision),
+            false => players.last().map_or(since_revision, |player| player.revision),
+        };
+        Changes {
+            revision,
+            complete,
+            players,
+        }
+    }
+
+    /// The players as stored, with hidden and anonymized ones, for replicating instances.
+    pub fn into_documents(self, db: &DatabaseRef) -> SyncDocuments {
+        let players = self
+            .players
+            .into_iter()
+            .map(|player| SyncDocument {
+                revision: player.revision,
+                image_hash: db.get_player_image_hash(player.itsf_id),
+                document: PlayerDocument::new(&player),
+            })
+            .collect();
+        SyncDocuments {
+            revision: self.revision,
+            complete: self.complete,
+            players,
+        }
+    }
+
+    /// Splits the players into the ones to show and the licenses of the ones to remove from the copy:
+    /// anonymized players, and hidden ones unless `include_hidden`.
+    pub fn into_visible(self, include_hidden: bool) -> (Vec<Player>, Vec<i32>) {
+        let mut visible = Vec::new();
+        let mut deleted = Vec::new();
+        for player in self.players {
+            if player.anonymized || (player.hidden && !include_hidden) {
+                deleted.push(player.itsf_id);
+            } else {
+                visible.push(player);
+            }
+        }
+        (visible, deleted)
+    }
+}
@@ -0,0 +1,85 @@
+//! Players changing age category with the next season, derived from their birth year: juniors may play until
+//! the season they turn 18, seniors from the season they turn 50.
+
+use chrono::Datelike;
+
+use crate::data::DatabaseRef;
+
+pub const JUNIOR_MAX_AGE: i32 = 18;
+pub const SENIOR_MIN_AGE: i32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    /// Too old for the junior category from this season on.
+    LeavesJunior,
+    /// Old enough for the senior category from this season on.
+    BecomesSenior,
+}
+
+impl Transition {
+    /// The transition of a player born in `birth_year` with the season `year`, none for unknown birth years.
+    pub fn of(birth_year: i32, year: i32) -> Option<Self> {
+        if birth_year == 0 {
+            return None;
+        }
+        match year - birth_year {
+            age if age == JUNIOR_MAX_AGE + 1 => Some(Self::LeavesJunior),
+            SENIOR_MIN_AGE => Some(Self::BecomesSenior),
+            _ => None,
+        }
+    }
+
+    /// The transition with the next season.
+    pub fn next_season(birth_year: i32) -> Option<Self> {
+        Self::of(birth_year, next_season())
+    }
+}
+
+pub fn next_season() -> i32 {
+    chrono::Utc::now().year() + 1
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CategoryTransition {
+    pub itsf_lic: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_year: i32,
+    pub country_code: Option<String>,
+    /// Category of the profile, e.g. `junior_male`.
+    pub category: &'static str,
+    pub transition: Transition,
+}
+
+/// Players changing category with the season `year`, by transition, country and name. Hidden players are
+/// left out unless `include_hidden`, anonymized ones have no birth year.
+pub fn list(db: &DatabaseRef, year: i32, country_code: Option<&str>, include_hidden: bool) -> Vec<CategoryTransition> {
+    let mut transitions: Vec<CategoryTransition> = db.aggregate_players(|players| {
+        players
+            .filter(|player| include_hidden || !player.hidden)
+            .filter(|player| country_code.is_none() || player.country_code.as_deref() == country_code)
+            .filter_map(|player| {
+                Some(CategoryTransition {
+                    transition: Transition::of(player.birth_year, year)?,
+                    itsf_lic: player.itsf_id,
+                    first_name: player.first_name.clone(),
+                    last_name: player.last_name.clone(),
+                    birth_year: player.birth_year,
+                    country_code: player.country_code.clone(),
+                    category: player.category.code(),
+                })
+            })
+            .collect()
+    });
+    transitions.sort_by(|a, b| {
+        (a.transition, &a.country_code, &a.last_name, &a.first_name, a.itsf_lic).cmp(&(
+            b.transition,
+            &b.country_code,
+            &b.last_name,
+            &b.first_name,
+            b.itsf_lic,
+        ))
+    });
+    transitions
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use crate::data::DatabaseRef;
+
+pub use crate::data::staged::{PendingAction, StagedAction};
+
+/// How long staged actions can be undone, in seconds.
+fn undo_window() -> i64 {
+    match std::env::var("UNDO_WINDOW") {
+        Ok(secs) => secs.parse::<i64>().expect("invalid UNDO_WINDOW"),
+        Err(_) => 10 * 60,
+    }
+}
+
+/// Remembers an already carried out action, e.g. a list removed by `DatabaseRef::delete_list`, for undo or
+/// finalization.
+pub fn stage(db: &DatabaseRef, action: StagedAction, user_id: String) -> PendingAction {
+    db.stage_action(action, user_id, chrono::Utc::now().timestamp() + undo_window())
+}
+
+/// Reverts a staged action, returns `None` if it doesn't exist or was already finalized.
+pub fn undo(db: &DatabaseRef, action_id: u64) -> Option<PendingAction> {
+    db.undo_staged_action(action_id)
+}
+
+/// Periodically finalizes staged actions whose undo window has ended, starting with the ones that ended
+/// while the server wasn't running.
+pub fn start_finalizer(db: &DatabaseRef) {
+    const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+    let db = db.clone();
+    tokio::spawn(async move {
+        loop {
+            for pending in db.finalize_staged_actions(chrono::Utc::now().timestamp()) {
+                log::info!("[Undo] finalized {:?} by {}", pending.action, pending.user_id);
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
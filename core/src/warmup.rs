@@ -0,0 +1,51 @@
+//! Keeps the caches for the most requested players warm, so the first requests after a download are fast.
+
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::background::BackgroundOperationProgress;
+use crate::data::DatabaseRef;
+use crate::stats;
+
+lazy_static! {
+    /// Number of profile and image requests per player since the server started.
+    static ref PLAYER_REQUESTS: Mutex<HashMap<i32, u64>> = Mutex::new(HashMap::new());
+}
+
+/// Number of players whose images are kept in memory, configured via `WARM_PLAYERS`.
+fn warm_players() -> usize {
+    match std::env::var("WARM_PLAYERS") {
+        Ok(count) => count.parse::<usize>().expect("invalid WARM_PLAYERS"),
+        Err(_) => 100,
+    }
+}
+
+pub fn record_player_request(itsf_id: i32) {
+    *PLAYER_REQUESTS.lock().unwrap().entry(itsf_id).or_insert(0) += 1;
+}
+
+/// The players requested most often, most requested first.
+pub fn most_requested(limit: usize) -> Vec<(i32, u64)> {
+    let mut requests: Vec<(i32, u64)> = PLAYER_REQUESTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(itsf_id, count)| (*itsf_id, *count))
+        .collect();
+    requests.sort_by_key(|(itsf_id, count)| (std::cmp::Reverse(*count), *itsf_id));
+    requests.truncate(limit);
+    requests
+}
+
+/// Recomputes the leaderboards and loads the images of the most requested players into memory.
+pub async fn warm_caches(db: &DatabaseRef, progress: &BackgroundOperationProgress) {
+    stats::refresh(db).await;
+
+    let itsf_ids: Vec<i32> = most_requested(warm_players())
+        .into_iter()
+        .map(|(itsf_id, _)| itsf_id)
+        .collect();
+    let images = db.warm_player_images(&itsf_ids);
+    progress.log(format!("[Cache] warmed leaderboards and {} player images", images));
+}
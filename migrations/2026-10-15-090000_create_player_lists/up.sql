@@ -0,0 +1,4 @@
+CREATE TABLE player_lists (
+	list_id INTEGER PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
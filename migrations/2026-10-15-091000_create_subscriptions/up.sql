@@ -0,0 +1,4 @@
+CREATE TABLE subscriptions (
+	user_id TEXT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
@@ -0,0 +1,4 @@
+CREATE TABLE events (
+	event_id INTEGER PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
@@ -0,0 +1,6 @@
+CREATE TABLE job_locks (
+	name TEXT PRIMARY KEY NOT NULL,
+	token TEXT NOT NULL,
+	expires_at BIGINT NOT NULL,
+	log BLOB NOT NULL
+);
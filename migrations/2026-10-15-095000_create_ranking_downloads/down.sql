@@ -0,0 +1 @@
+DROP TABLE ranking_downloads;
@@ -0,0 +1,4 @@
+CREATE TABLE ranking_downloads (
+	ranking TEXT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
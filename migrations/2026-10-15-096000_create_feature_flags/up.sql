@@ -0,0 +1,4 @@
+CREATE TABLE feature_flags (
+	feature TEXT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
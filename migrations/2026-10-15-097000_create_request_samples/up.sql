@@ -0,0 +1,11 @@
+CREATE TABLE request_samples (
+	sample_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	timestamp BIGINT NOT NULL,
+	method TEXT NOT NULL,
+	path TEXT NOT NULL,
+	endpoint TEXT,
+	status INTEGER NOT NULL,
+	latency_ms BIGINT NOT NULL,
+	client TEXT NOT NULL
+);
+CREATE INDEX request_samples_timestamp ON request_samples (timestamp);
@@ -0,0 +1,4 @@
+CREATE TABLE workspace_notes (
+	notes_key TEXT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
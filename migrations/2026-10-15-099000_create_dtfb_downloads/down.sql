@@ -0,0 +1 @@
+DROP TABLE dtfb_downloads;
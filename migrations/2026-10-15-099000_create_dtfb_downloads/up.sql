@@ -0,0 +1,4 @@
+CREATE TABLE dtfb_downloads (
+	season INTEGER PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
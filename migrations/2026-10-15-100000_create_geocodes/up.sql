@@ -0,0 +1,4 @@
+CREATE TABLE geocodes (
+	place TEXT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
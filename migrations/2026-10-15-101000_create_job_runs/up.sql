@@ -0,0 +1,6 @@
+CREATE TABLE job_runs (
+	run_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	started_at BIGINT NOT NULL,
+	json_data BLOB NOT NULL
+);
+CREATE INDEX job_runs_started_at ON job_runs (started_at);
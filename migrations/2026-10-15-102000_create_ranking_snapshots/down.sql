@@ -0,0 +1,2 @@
+DROP TABLE ranking_snapshots;
+DROP TABLE ranking_placements;
@@ -0,0 +1,17 @@
+-- latest snapshot of every ranking
+CREATE TABLE ranking_placements (
+	ranking_key TEXT NOT NULL,
+	itsf_id INTEGER NOT NULL,
+	place INTEGER NOT NULL,
+	scraped_at BIGINT NOT NULL,
+	PRIMARY KEY (ranking_key, itsf_id)
+);
+-- older snapshots as gzip compressed JSON
+CREATE TABLE ranking_snapshots (
+	snapshot_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	ranking_key TEXT NOT NULL,
+	scraped_at BIGINT NOT NULL,
+	entries INTEGER NOT NULL,
+	data BLOB NOT NULL
+);
+CREATE INDEX ranking_snapshots_key ON ranking_snapshots (ranking_key, scraped_at);
@@ -0,0 +1,8 @@
+CREATE TABLE download_presets (
+	name TEXT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
+INSERT INTO download_presets (name, json_data) VALUES
+	('nightly-current-year', CAST('{"source":"itsf","seasons":["latest"]}' AS BLOB)),
+	('full-backfill', CAST('{"source":"itsf","seasons":["all"]}' AS BLOB)),
+	('women-only', CAST('{"source":"itsf","seasons":["latest"],"categories":["women"]}' AS BLOB));
@@ -0,0 +1,4 @@
+CREATE TABLE clubs (
+	name TEXT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
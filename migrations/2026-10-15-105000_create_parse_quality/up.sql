@@ -0,0 +1,6 @@
+CREATE TABLE parse_quality (
+	quality_key TEXT PRIMARY KEY NOT NULL,
+	day BIGINT NOT NULL,
+	json_data BLOB NOT NULL
+);
+CREATE INDEX parse_quality_day ON parse_quality (day);
@@ -0,0 +1,6 @@
+CREATE TABLE player_overrides (
+	override_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+	itsf_id INTEGER NOT NULL,
+	json_data BLOB NOT NULL
+);
+CREATE INDEX player_overrides_itsf_id ON player_overrides (itsf_id);
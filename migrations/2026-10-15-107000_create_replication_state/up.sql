@@ -0,0 +1,4 @@
+CREATE TABLE replication_state (
+	primary_url TEXT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
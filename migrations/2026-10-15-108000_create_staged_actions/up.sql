@@ -0,0 +1,4 @@
+CREATE TABLE staged_actions (
+	action_id BIGINT PRIMARY KEY NOT NULL,
+	json_data BLOB NOT NULL
+);
@@ -0,0 +1,231 @@
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, Header};
+use actix_web::http::Method;
+use actix_web::{Error, HttpMessage, HttpRequest, HttpResponse};
+use actix_web_httpauth::headers::authorization::{Authorization, Basic};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::json;
+
+/// Reads the `user:password` lines of `USERS_FILE`, empty lines are skipped.
+/// Users named `workspace/user` belong to that workspace, see `workspace`.
+pub fn load_users_file() -> Result<HashMap<String, String>, String> {
+    let path = std::env::var("USERS_FILE").map_err(|_| String::from("USERS_FILE missing from environment"))?;
+    let file = File::open(&path).map_err(|err| format!("failed to open users file {}: {}", path, err))?;
+    let mut ret = HashMap::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|err| format!("failed to read users file {}: {}", path, err))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.split_once(':') {
+            Some((user_id, password))
+                if !user_id.is_empty() && !password.is_empty() && workspace_of(user_id) != Some("") =>
+            {
+                ret.insert(String::from(user_id), String::from(password));
+            }
+            _ => return Err(format!("invalid line {} in users file {}", number + 1, path)),
+        }
+    }
+    Ok(ret)
+}
+
+lazy_static! {
+    static ref USERS: Result<HashMap<String, String>, String> = load_users_file();
+}
+
+/// Loads the users file at startup, logging instead of failing if it is missing or invalid:
+/// reads keep working in public mode and requests needing a login are answered with 500.
+pub fn init() {
+    if let Err(err) = &*USERS {
+        log::error!("Authentication unavailable: {}", err);
+    }
+}
+
+/// Outcome of checking the Basic auth credentials of a request.
+enum Credentials {
+    /// No `Authorization` header.
+    Missing,
+    /// An `Authorization` header that isn't valid Basic auth, or has no password.
+    Malformed(&'static str),
+    Invalid,
+    Valid(String),
+    /// The users file couldn't be loaded.
+    Unavailable,
+}
+
+fn check_credentials(req: &ServiceRequest) -> Credentials {
+    if !req.headers().contains_key(header::AUTHORIZATION) {
+        return Credentials::Missing;
+    }
+    let auth = match Authorization::<Basic>::parse(req) {
+        Ok(auth) => auth.into_scheme(),
+        Err(_) => return Credentials::Malformed("malformed Authorization header, expected Basic auth"),
+    };
+    let password = match auth.password() {
+        Some(password) if !password.is_empty() => password,
+        _ => return Credentials::Malformed("missing password"),
+    };
+    let users = match &*USERS {
+        Ok(users) => users,
+        Err(_) => return Credentials::Unavailable,
+    };
+    match users.get(auth.user_id().as_ref()) {
+        Some(expected) if expected == password => Credentials::Valid(auth.user_id().to_string()),
+        _ => Credentials::Invalid,
+    }
+}
+
+/// 401 asking for Basic auth credentials.
+pub fn unauthorized(message: &str) -> HttpResponse {
+    HttpResponse::Unauthorized()
+        .append_header((header::WWW_AUTHENTICATE, "Basic realm=\"ITSF Player DB\""))
+        .json(json::err(message))
+}
+
+/// Which requests need valid credentials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessMode {
+    /// Reads are open to everyone, mutations require auth.
+    Public,
+    /// Every request requires auth, for private club deployments.
+    Private,
+}
+
+impl AccessMode {
+    pub fn from_env() -> Self {
+        match std::env::var("ACCESS_MODE") {
+            Ok(mode) => Self::try_from_str(&mode).expect("invalid ACCESS_MODE"),
+            Err(_) => Self::Public,
+        }
+    }
+
+    pub fn try_from_str(mode: &str) -> Result<Self, String> {
+        match mode {
+            "public" => Ok(Self::Public),
+            "private" => Ok(Self::Private),
+            _ => Err(format!("invalid access mode: '{}'", mode)),
+        }
+    }
+
+    fn requires_auth(self, method: &Method) -> bool {
+        match self {
+            Self::Public => !matches!(*method, Method::GET | Method::HEAD),
+            Self::Private => true,
+        }
+    }
+}
+
+/// The workspace of a user, the part of the user id before the `/` if any. Users without workspace
+/// work with the shared comments, tags and lists and can change shared data like hidden players.
+pub fn workspace_of(user_id: &str) -> Option<&str> {
+    user_id.split_once('/').map(|(workspace, _)| workspace)
+}
+
+/// The workspace of the authenticated user, `None` for the shared data and for anonymous requests.
+pub fn workspace(req: &HttpRequest) -> Option<String> {
+    authenticated_user(req).and_then(|user_id| workspace_of(&user_id).map(String::from))
+}
+
+/// Requests of workspace users are limited to their workspace and can't change shared data.
+pub fn require_shared_access(req: &HttpRequest) -> Result<(), HttpResponse> {
+    match workspace(req) {
+        Some(workspace) => {
+            Err(HttpResponse::Forbidden()
+                .json(json::err(format!("not allowed for users of workspace '{}'", workspace))))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Inserted into the request extensions for requests with valid credentials.
+#[derive(Debug, Clone)]
+struct Authenticated {
+    user_id: String,
+}
+
+/// Whether the request carried valid credentials, also in public mode.
+pub fn is_authenticated(req: &HttpRequest) -> bool {
+    req.extensions().get::<Authenticated>().is_some()
+}
+
+/// The user id of an authenticated request.
+pub fn authenticated_user(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<Authenticated>().map(|auth| auth.user_id.clone())
+}
+
+/// Middleware checking Basic auth credentials for all requests, according to the configured `AccessMode`.
+pub struct Authentication {
+    mode: AccessMode,
+}
+
+impl Authentication {
+    pub fn new(mode: AccessMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Authentication
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = AuthenticationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthenticationMiddleware {
+            service,
+            mode: self.mode,
+        }))
+    }
+}
+
+pub struct AuthenticationMiddleware<S> {
+    service: S,
+    mode: AccessMode,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthenticationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // requests that don't need a login are served anonymously unless their credentials are valid
+        let response = match check_credentials(&req) {
+            Credentials::Valid(user_id) => {
+                req.extensions_mut().insert(Authenticated { user_id });
+                None
+            }
+            _ if !self.mode.requires_auth(req.method()) => None,
+            Credentials::Missing => Some(unauthorized("not authorized")),
+            Credentials::Malformed(message) => Some(unauthorized(message)),
+            Credentials::Invalid => Some(unauthorized("invalid user or password")),
+            Credentials::Unavailable => {
+                Some(HttpResponse::InternalServerError().json(json::err("authentication is not available")))
+            }
+        };
+        if let Some(response) = response {
+            return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+        }
+
+        let response = self.service.call(req);
+        Box::pin(async move { response.await.map(|res| res.map_into_left_body()) })
+    }
+}
@@ -0,0 +1,77 @@
+//! Replays recently served requests in-process and reports their latencies,
+//! to check the effect of caching and indexing changes on the real request mix.
+
+use actix_http::Request;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::{Error, HttpRequest};
+use playerdb_core::bench::{self, BenchReport, BenchTimings};
+use std::time::Instant;
+
+pub use playerdb_core::bench::recorded_requests;
+
+/// App data of the app replaying requests, so handlers can tell replayed requests from real ones.
+pub struct Replay;
+
+/// Whether the request is replayed by a benchmark, which mustn't count as interest in a player.
+pub fn is_replay(req: &HttpRequest) -> bool {
+    req.app_data::<Replay>().is_some()
+}
+
+/// Remembers a successful GET request to an API endpoint for replay, admin endpoints are left out.
+pub fn record<B>(response: &ServiceResponse<B>) {
+    let request = response.request();
+    let is_endpoint = request.match_pattern().is_some_and(|pattern| !pattern.is_empty());
+    if request.method() != Method::GET
+        || !response.status().is_success()
+        || !is_endpoint
+        || request.path().starts_with("/admin")
+    {
+        return;
+    }
+    bench::record(request.uri().to_string());
+}
+
+/// A GET request of `uri`, none if it isn't a valid URI.
+fn get_request(uri: &str) -> Option<Request> {
+    let mut request = Request::new();
+    // the head may be recycled from another request, so everything that's used must be set
+    request.head_mut().method = Method::GET;
+    request.head_mut().uri = uri.parse().ok()?;
+    Some(request)
+}
+
+/// Sends the GET requests to `app` one after another, `iterations` times, including reading the response body.
+/// `requests` must not be empty.
+pub async fn replay<S, B>(app: &S, requests: &[String], iterations: usize) -> BenchReport
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let mut timings = BenchTimings::default();
+
+    for _ in 0..iterations {
+        for uri in requests {
+            let start = Instant::now();
+            let response = match get_request(uri) {
+                Some(request) => app.call(request).await.ok(),
+                None => None,
+            };
+            let (endpoint, success) = match response {
+                Some(response) => {
+                    let endpoint = response
+                        .request()
+                        .match_pattern()
+                        .unwrap_or(response.request().path().into());
+                    let success = response.status().is_success();
+                    let body = actix_web::body::to_bytes(response.into_body()).await;
+                    (endpoint, success && body.is_ok())
+                }
+                None => (uri.clone(), false),
+            };
+            timings.add(endpoint, start.elapsed(), success);
+        }
+    }
+    timings.report()
+}
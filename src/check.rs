@@ -0,0 +1,105 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+use crate::{auth, features, mock_source, sampling, timing};
+use playerdb_core::data::{self, connection::ConnectionSettings, images, DatabaseRef};
+use playerdb_core::{geo, maintenance, retention, scraping};
+
+type CheckResult = Result<String, String>;
+
+/// Runs `f`, turning a panic, e.g. from an `expect` on an invalid setting, into an error.
+fn catch<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|panic| {
+        panic
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| panic.downcast_ref::<&str>().map(|message| message.to_string()))
+            .unwrap_or(String::from("panicked"))
+    })
+}
+
+fn env(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("{} missing from environment", name))
+}
+
+fn check_config() -> CheckResult {
+    let port = env("SERVER_PORT")?;
+    port.parse::<u16>()
+        .map_err(|_| format!("invalid SERVER_PORT: {}", port))?;
+    catch(auth::AccessMode::from_env)?;
+    catch(timing::RequestLimits::from_env)?;
+    catch(ConnectionSettings::from_env)?;
+    catch(features::disabled_by_default)?;
+    catch(sampling::SampleSettings::from_env)?;
+    catch(retention::RetentionPolicy::from_env)?;
+    catch(maintenance::MaintenanceSettings::from_env)?;
+    catch(images::max_size)?;
+    catch(geo::Provider::from_env)?;
+    let html_path = env("HTML_ROOT")?;
+    if !Path::new(&html_path).join("start.html").is_file() {
+        return Err(format!("no start.html in HTML_ROOT {}", html_path));
+    }
+    env("USERS_FILE")?;
+    let users = auth::load_users_file()?;
+    Ok(format!("{} users", users.len()))
+}
+
+fn check_database() -> Result<DatabaseRef, String> {
+    let database_path = env("DATABASE_URL")?;
+    let replica_path = std::env::var("DATABASE_READ_URL").ok();
+    let images_path = env("IMAGE_PATH")?;
+    let settings = catch(ConnectionSettings::from_env)?;
+    catch(|| DatabaseRef::load(&database_path, replica_path.as_deref(), &images_path, settings))
+}
+
+fn check_migrations() -> CheckResult {
+    let database_path = env("DATABASE_URL")?;
+    let replica_path = std::env::var("DATABASE_READ_URL").ok();
+    let settings = catch(ConnectionSettings::from_env)?;
+    for path in std::iter::once(&database_path).chain(&replica_path) {
+        catch(|| data::check_schema(path, &settings))??;
+    }
+    Ok(String::from("up to date"))
+}
+
+fn check_tls() -> CheckResult {
+    if std::env::var("CERT_PEM").is_err() {
+        return Ok(String::from("not configured, serving HTTP"));
+    }
+    catch(crate::get_rustls_config)?;
+    Ok(String::from("certificate and key loaded"))
+}
+
+/// Verifies the configuration, database, TLS files and scraped hosts, printing one line per check.
+/// Returns whether all checks passed, for `--check` used as a deployment smoke test.
+pub async fn run() -> bool {
+    // failed checks are reported as errors instead of panic messages
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let mut results: Vec<(&str, CheckResult)> = vec![("config", check_config())];
+    // an outdated schema makes loading fail with obscure query errors, so it's checked first
+    let migrations = check_migrations();
+    let migrated = migrations.is_ok();
+    results.push(("migrations", migrations));
+    if migrated {
+        let database = check_database().map(|db| format!("{} players", db.get_player_ids().len()));
+        results.push(("database", database));
+    }
+    results.push(("tls", check_tls()));
+    if mock_source::is_enabled() {
+        let started = mock_source::start().map_err(|err| err.to_string());
+        results.push(("demo mode", started.map(|_| String::from("scraping from mock source"))));
+    }
+    for (host, result) in scraping::check_hosts().await {
+        results.push((host, result.map(|status| format!("HTTP {}", status))));
+    }
+
+    let _ = std::panic::take_hook();
+    for (name, result) in &results {
+        match result {
+            Ok(message) => println!("ok      {}: {}", name, message),
+            Err(err) => println!("FAILED  {}: {}", name, err),
+        }
+    }
+    results.iter().all(|(_, result)| result.is_ok())
+}
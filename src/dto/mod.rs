@@ -0,0 +1,5 @@
+//! Response models of the API, kept apart from the stored documents of `playerdb_core::data::documents` so
+//! changes of the storage don't change responses. Every API version gets a module with its models and the
+//! conversions from the data layer.
+
+pub mod v1;
@@ -0,0 +1,177 @@
+//! Version 1 of the player document returned by `/player/{ITSF-ID}`. Categories and classes keep the codes of
+//! the data layer, they're listed with labels in `/meta/enums`.
+
+use playerdb_core::data::{self, dtfb, itsf};
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Provenance {
+    pub source_url: String,
+    /// Unix timestamp of the download.
+    pub scraped_at: i64,
+    /// Id of the download job, as listed in the job history.
+    pub job_id: String,
+}
+
+impl From<data::Provenance> for Provenance {
+    fn from(provenance: data::Provenance) -> Self {
+        Provenance {
+            source_url: provenance.source_url,
+            scraped_at: provenance.scraped_at,
+            job_id: provenance.job_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ItsfRanking {
+    pub year: i32,
+    pub place: i32,
+    pub category: itsf::RankingCategory,
+    pub class: itsf::RankingClass,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentile: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl From<itsf::Ranking> for ItsfRanking {
+    fn from(ranking: itsf::Ranking) -> Self {
+        ItsfRanking {
+            year: ranking.year,
+            place: ranking.place,
+            category: ranking.category,
+            class: ranking.class,
+            percentile: ranking.percentile,
+            provenance: ranking.provenance.map(Provenance::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DtfbRanking {
+    pub year: i32,
+    pub place: i32,
+    pub category: dtfb::ChampionshipCategory,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl From<dtfb::NationalRanking> for DtfbRanking {
+    fn from(ranking: dtfb::NationalRanking) -> Self {
+        DtfbRanking {
+            year: ranking.year,
+            place: ranking.place,
+            category: ranking.category,
+            provenance: ranking.provenance.map(Provenance::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ChampionshipResult {
+    pub year: i32,
+    pub place: i32,
+    pub category: dtfb::ChampionshipCategory,
+    pub class: dtfb::ChampionshipClass,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl From<dtfb::NationalChampionshipResult> for ChampionshipResult {
+    fn from(result: dtfb::NationalChampionshipResult) -> Self {
+        ChampionshipResult {
+            year: result.year,
+            place: result.place,
+            category: result.category,
+            class: result.class,
+            provenance: result.provenance.map(Provenance::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LeagueTeam {
+    pub year: i32,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl From<dtfb::NationalTeam> for LeagueTeam {
+    fn from(team: dtfb::NationalTeam) -> Self {
+        LeagueTeam {
+            year: team.year,
+            name: team.name,
+            provenance: team.provenance.map(Provenance::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct NationalTeamAppearance {
+    pub year: i32,
+    pub event_id: i32,
+    pub event: String,
+    /// e.g. `Men's Teams`
+    pub competition: String,
+    pub country_code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl From<itsf::NationalTeamAppearance> for NationalTeamAppearance {
+    fn from(appearance: itsf::NationalTeamAppearance) -> Self {
+        NationalTeamAppearance {
+            year: appearance.year,
+            event_id: appearance.event_id,
+            event: appearance.event,
+            competition: appearance.competition,
+            country_code: appearance.country_code,
+            place: appearance.place,
+            provenance: appearance.provenance.map(Provenance::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FormerName {
+    pub first_name: String,
+    pub last_name: String,
+    /// When the name was replaced by a newer one.
+    pub timestamp: u32,
+}
+
+impl From<data::FormerName> for FormerName {
+    fn from(name: data::FormerName) -> Self {
+        FormerName {
+            first_name: name.first_name,
+            last_name: name.last_name,
+            timestamp: name.timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CountryChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Year of the download that first showed the new country.
+    pub year: i32,
+}
+
+impl From<data::CountryChange> for CountryChange {
+    fn from(change: data::CountryChange) -> Self {
+        CountryChange {
+            from: change.from,
+            to: change.to,
+            year: change.year,
+        }
+    }
+}
+
+/// Converts all entries of a section.
+pub fn convert<T, D: From<T>>(entries: Vec<T>) -> Vec<D> {
+    entries.into_iter().map(D::from).collect()
+}
@@ -0,0 +1,173 @@
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{Error, HttpResponse};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+
+use crate::json;
+use playerdb_core::data::DatabaseRef;
+
+/// A group of endpoints that can be switched off at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Downloads from the ITSF and DTFB sites, including the live licence check.
+    Scraping,
+    /// Adding, importing and tracking comments.
+    Comments,
+    /// Downloads of the database, offline bundles and calendars.
+    Exports,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [Feature::Scraping, Feature::Comments, Feature::Exports];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Scraping => "scraping",
+            Feature::Comments => "comments",
+            Feature::Exports => "exports",
+        }
+    }
+
+    pub fn try_from_str(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|feature| feature.name() == name)
+            .ok_or_else(|| format!("unknown feature: '{}'", name))
+    }
+
+    /// Path prefixes of the endpoints belonging to the feature.
+    fn paths(self) -> &'static [&'static str] {
+        match self {
+            Feature::Scraping => &[
+                "/download_itsf",
+                "/download_missing",
+                "/download_dtfb",
+                "/download_events",
+                "/download_source/",
+                "/download_preset/",
+                "/licence_check/",
+                "/admin/refresh_priority",
+            ],
+            Feature::Comments => &[
+                "/add_comment",
+                "/import/comments",
+                "/comment_status",
+                "/comment_reaction",
+            ],
+            Feature::Exports => &["/db.zip", "/export/", "/tournaments.ics"],
+        }
+    }
+
+    fn for_path(path: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|feature| feature.paths().iter().any(|prefix| path.starts_with(prefix)))
+    }
+}
+
+/// Features disabled unless switched on at runtime, from the comma separated `DISABLED_FEATURES`.
+pub fn disabled_by_default() -> HashSet<Feature> {
+    match std::env::var("DISABLED_FEATURES") {
+        Ok(features) => features
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| Feature::try_from_str(name).expect("invalid DISABLED_FEATURES"))
+            .collect(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+lazy_static! {
+    static ref DISABLED_BY_DEFAULT: HashSet<Feature> = disabled_by_default();
+}
+
+/// Reads `DISABLED_FEATURES` at startup, so invalid settings fail early instead of on the first request.
+pub fn init() {
+    lazy_static::initialize(&DISABLED_BY_DEFAULT);
+}
+
+pub fn is_enabled(db: &DatabaseRef, feature: Feature) -> bool {
+    db.get_feature_flag(feature.name())
+        .unwrap_or(!DISABLED_BY_DEFAULT.contains(&feature))
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FeatureStatus {
+    pub feature: Feature,
+    pub enabled: bool,
+    /// Whether the feature was switched at runtime instead of using the configured default.
+    pub overridden: bool,
+}
+
+pub fn feature_statuses(db: &DatabaseRef) -> Vec<FeatureStatus> {
+    Feature::ALL
+        .into_iter()
+        .map(|feature| FeatureStatus {
+            feature,
+            enabled: is_enabled(db, feature),
+            overridden: db.get_feature_flag(feature.name()).is_some(),
+        })
+        .collect()
+}
+
+/// Middleware rejecting requests to endpoints of disabled features with 503.
+pub struct FeatureGuard {
+    db: DatabaseRef,
+}
+
+impl FeatureGuard {
+    pub fn new(db: DatabaseRef) -> Self {
+        Self { db }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for FeatureGuard
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = FeatureGuardMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(FeatureGuardMiddleware {
+            service,
+            db: self.db.clone(),
+        }))
+    }
+}
+
+pub struct FeatureGuardMiddleware<S> {
+    service: S,
+    db: DatabaseRef,
+}
+
+impl<S, B> Service<ServiceRequest> for FeatureGuardMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(feature) = Feature::for_path(req.path()).filter(|feature| !is_enabled(&self.db, *feature)) {
+            let response =
+                HttpResponse::ServiceUnavailable().json(json::err(format!("feature '{}' is disabled", feature.name())));
+            return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+        }
+
+        let response = self.service.call(req);
+        Box::pin(async move { response.await.map(|res| res.map_into_left_body()) })
+    }
+}
@@ -1,8 +1,22 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::{web, Error, HttpResponse};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::collections::{HashMap, HashSet};
+
 #[derive(serde::Serialize)]
 struct JsonOk<T: serde::Serialize> {
     data: T,
 }
 
+#[derive(serde::Serialize)]
+struct JsonPage<T: serde::Serialize> {
+    data: T,
+    /// `None` on the last page.
+    next_cursor: Option<String>,
+}
+
 #[derive(serde::Serialize)]
 struct JsonErr<T: serde::Serialize> {
     error: T,
@@ -12,6 +26,202 @@ pub fn ok<T: serde::Serialize>(data: T) -> impl serde::Serialize {
     JsonOk { data }
 }
 
+/// A page of a list, with the cursor to request the next one.
+pub fn page<T: serde::Serialize>(data: T, next_cursor: Option<String>) -> impl serde::Serialize {
+    JsonPage { data, next_cursor }
+}
+
 pub fn err<T: serde::Serialize>(error: T) -> impl serde::Serialize {
     JsonErr { error }
 }
+
+/// The fields of the comma separated `fields` parameter, e.g. `first_name,last_name`.
+fn requested_fields(query_string: &str) -> Result<Option<HashSet<String>>, String> {
+    let query = web::Query::<HashMap<String, String>>::from_query(query_string).map_err(|err| err.to_string())?;
+    let fields = match query.get("fields") {
+        Some(fields) => fields,
+        None => return Ok(None),
+    };
+    let fields: HashSet<String> = fields
+        .split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(String::from)
+        .collect();
+    if fields.is_empty() {
+        return Err(String::from("empty field selection"));
+    }
+    Ok(Some(fields))
+}
+
+/// Keeps only the selected fields of an object, or of every object of a list.
+fn select_fields(data: &mut serde_json::Value, fields: &HashSet<String>) {
+    match data {
+        serde_json::Value::Object(object) => object.retain(|field, _| fields.contains(field)),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| {
+            if let serde_json::Value::Object(object) = item {
+                object.retain(|field, _| fields.contains(field));
+            }
+        }),
+        _ => {}
+    }
+}
+
+/// Middleware trimming the `data` of successful JSON responses to the fields of the `fields` parameter,
+/// so every endpoint supports sparse fieldsets without handling the parameter itself.
+pub struct FieldSelection;
+
+impl<S, B> Transform<S, ServiceRequest> for FieldSelection
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = FieldSelectionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(FieldSelectionMiddleware { service }))
+    }
+}
+
+pub struct FieldSelectionMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for FieldSelectionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let fields = match requested_fields(req.query_string()) {
+            Ok(Some(fields)) => fields,
+            Ok(None) => {
+                let response = self.service.call(req);
+                return Box::pin(async move { response.await.map(|res| res.map_into_left_body()) });
+            }
+            Err(error) => {
+                let response = HttpResponse::BadRequest().json(err(format!("invalid fields: {}", error)));
+                return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+            }
+        };
+
+        let response = self.service.call(req);
+        Box::pin(async move {
+            let res = response.await?;
+            let is_json = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .is_some_and(|content_type| content_type.as_bytes().starts_with(b"application/json"));
+            if !is_json || !res.status().is_success() {
+                return Ok(res.map_into_left_body());
+            }
+
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+            let body = actix_web::body::to_bytes(body)
+                .await
+                .map_err(|err| actix_web::error::ErrorInternalServerError(err.into()))?;
+            let body = match serde_json::from_slice::<serde_json::Value>(&body) {
+                Ok(mut json) => {
+                    if let Some(data) = json.get_mut("data") {
+                        select_fields(data, &fields);
+                    }
+                    serde_json::to_vec(&json).expect("JSON serialization failed")
+                }
+                Err(_) => body.to_vec(),
+            };
+            Ok(ServiceResponse::new(req, res.set_body(body).map_into_boxed_body()).map_into_right_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{
+        call_and_read_body, call_and_read_body_json, call_service, init_service, read_body_json, TestRequest,
+    };
+    use actix_web::App;
+    use serde_json::json;
+
+    fn fields(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| String::from(*name)).collect()
+    }
+
+    #[test]
+    fn fields_are_comma_separated() {
+        assert_eq!(requested_fields("").unwrap(), None);
+        assert_eq!(requested_fields("limit=10").unwrap(), None);
+        assert_eq!(
+            requested_fields("fields=first_name,%20last_name,,").unwrap(),
+            Some(fields(&["first_name", "last_name"]))
+        );
+        assert!(requested_fields("fields=").is_err());
+        assert!(requested_fields("fields=,%20,").is_err());
+    }
+
+    #[test]
+    fn objects_and_lists_of_objects_are_trimmed() {
+        let selected = fields(&["itsf_lic", "last_name"]);
+        let mut player = json!({ "itsf_lic": 84000895, "first_name": "Max", "last_name": "Mustermann" });
+        select_fields(&mut player, &selected);
+        assert_eq!(player, json!({ "itsf_lic": 84000895, "last_name": "Mustermann" }));
+
+        let mut players = json!([{ "itsf_lic": 1, "first_name": "Max" }, "not an object", { "rank": 3 }]);
+        select_fields(&mut players, &selected);
+        assert_eq!(players, json!([{ "itsf_lic": 1 }, "not an object", {}]));
+
+        let mut count = json!(42);
+        select_fields(&mut count, &selected);
+        assert_eq!(count, json!(42));
+    }
+
+    #[actix_web::test]
+    async fn only_successful_json_responses_are_trimmed() {
+        let app = init_service(
+            App::new()
+                .wrap(FieldSelection)
+                .route(
+                    "/player",
+                    web::get().to(|| async {
+                        HttpResponse::Ok().json(ok(json!({ "first_name": "Max", "birth_year": 1990 })))
+                    }),
+                )
+                .route(
+                    "/missing",
+                    web::get().to(|| async { HttpResponse::NotFound().json(err("No such player")) }),
+                )
+                .route(
+                    "/text",
+                    web::get().to(|| async { HttpResponse::Ok().body("first_name") }),
+                ),
+        )
+        .await;
+        let get = |uri: &str| TestRequest::get().uri(uri).to_request();
+
+        let body: serde_json::Value = call_and_read_body_json(&app, get("/player?fields=first_name")).await;
+        assert_eq!(body, json!({ "data": { "first_name": "Max" } }));
+        let body: serde_json::Value = call_and_read_body_json(&app, get("/player")).await;
+        assert_eq!(body["data"]["birth_year"], 1990);
+
+        let response = call_service(&app, get("/missing?fields=first_name")).await;
+        assert_eq!(response.status(), 404);
+        let body: serde_json::Value = read_body_json(response).await;
+        assert_eq!(body, json!({ "error": "No such player" }));
+        let body = call_and_read_body(&app, get("/text?fields=last_name")).await;
+        assert_eq!(body, "first_name");
+
+        let response = call_service(&app, get("/player?fields=")).await;
+        assert_eq!(response.status(), 400);
+    }
+}
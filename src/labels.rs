@@ -0,0 +1,192 @@
+//! Human readable labels for the enum codes used in responses, so clients can build forms
+//! without hardcoding them. English and German are supported. Also formats the display names of players.
+
+use std::collections::HashMap;
+
+use actix_web::{web, HttpRequest};
+use playerdb_core::data::{dtfb, itsf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    En,
+    De,
+}
+
+impl Language {
+    /// The language of the `lang` parameter, English if it is missing or not supported.
+    pub fn from_param(lang: Option<&str>) -> Self {
+        match lang.map(|lang| lang.to_lowercase()).as_deref() {
+            Some("de") => Self::De,
+            _ => Self::En,
+        }
+    }
+}
+
+/// Convention for the `display_name` of players, chosen with the `name_style` parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameStyle {
+    /// `Firstname Lastname`
+    Western,
+    /// `LASTNAME Firstname`, as on ITSF result lists.
+    Itsf,
+}
+
+impl NameStyle {
+    /// The style of the `name_style` parameter, western if it is missing or not supported.
+    pub fn from_param(style: Option<&str>) -> Self {
+        match style.map(|style| style.to_lowercase()).as_deref() {
+            Some("itsf") => Self::Itsf,
+            _ => Self::Western,
+        }
+    }
+
+    /// The style of the `name_style` parameter of the request, so handlers don't each declare it.
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let params = web::Query::<HashMap<String, String>>::from_query(req.query_string()).ok();
+        Self::from_param(
+            params
+                .as_ref()
+                .and_then(|params| params.get("name_style"))
+                .map(String::as_str),
+        )
+    }
+
+    pub fn display_name(self, first_name: &str, last_name: &str) -> String {
+        match self {
+            Self::Western => format!("{} {}", first_name, last_name),
+            Self::Itsf => format!("{} {}", last_name.to_uppercase(), first_name),
+        }
+    }
+}
+
+/// The code an enum is serialized as in responses.
+fn code<T: serde::Serialize>(value: T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(code)) => code,
+        _ => unreachable!("enum codes are serialized as strings"),
+    }
+}
+
+pub fn player_category(category: itsf::PlayerCategory, language: Language) -> &'static str {
+    use itsf::PlayerCategory::*;
+    match (category, language) {
+        (Men, Language::En) => "Men",
+        (Men, Language::De) => "Herren",
+        (Women, Language::En) => "Women",
+        (Women, Language::De) => "Damen",
+        (JuniorMale, Language::En) => "Junior Male",
+        (JuniorMale, Language::De) => "Junioren",
+        (JuniorFemale, Language::En) => "Junior Female",
+        (JuniorFemale, Language::De) => "Juniorinnen",
+        (SeniorMale, Language::En) => "Senior Male",
+        (SeniorMale, Language::De) => "Senioren",
+        (SeniorFemale, Language::En) => "Senior Female",
+        (SeniorFemale, Language::De) => "Seniorinnen",
+    }
+}
+
+pub fn ranking_category(category: itsf::RankingCategory, language: Language) -> &'static str {
+    use itsf::RankingCategory::*;
+    match (category, language) {
+        (Open, Language::En) => "Open",
+        (Open, Language::De) => "Offen",
+        (Women, Language::En) => "Women",
+        (Women, Language::De) => "Damen",
+        (Junior, Language::En) => "Junior",
+        (Junior, Language::De) => "Junioren",
+        (Senior, Language::En) => "Senior",
+        (Senior, Language::De) => "Senioren",
+    }
+}
+
+pub fn ranking_class(class: itsf::RankingClass, language: Language) -> &'static str {
+    use itsf::RankingClass::*;
+    match (class, language) {
+        (Singles, Language::En) => "Singles",
+        (Singles, Language::De) => "Einzel",
+        (Doubles, Language::En) => "Doubles",
+        (Doubles, Language::De) => "Doppel",
+        (Combined, Language::En) => "Combined",
+        (Combined, Language::De) => "Kombiniert",
+    }
+}
+
+pub fn championship_category(category: dtfb::ChampionshipCategory, language: Language) -> &'static str {
+    use dtfb::ChampionshipCategory::*;
+    match (category, language) {
+        (Men, Language::En) => "Men",
+        (Men, Language::De) => "Herren",
+        (Women, Language::En) => "Women",
+        (Women, Language::De) => "Damen",
+        (Junior, Language::En) => "Junior",
+        (Junior, Language::De) => "Junioren",
+        (Senior, Language::En) => "Senior",
+        (Senior, Language::De) => "Senioren",
+    }
+}
+
+pub fn championship_class(class: dtfb::ChampionshipClass, language: Language) -> &'static str {
+    use dtfb::ChampionshipClass::*;
+    match (class, language) {
+        (Singles, Language::En) => "Singles",
+        (Singles, Language::De) => "Einzel",
+        (Doubles, Language::En) => "Doubles",
+        (Doubles, Language::De) => "Doppel",
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct EnumValue {
+    pub code: String,
+    pub label: &'static str,
+}
+
+/// Valid values of every enum used in requests and responses.
+#[derive(Debug, serde::Serialize)]
+pub struct Enums {
+    pub player_categories: Vec<EnumValue>,
+    pub ranking_categories: Vec<EnumValue>,
+    pub ranking_classes: Vec<EnumValue>,
+    pub championship_categories: Vec<EnumValue>,
+    pub championship_classes: Vec<EnumValue>,
+}
+
+pub fn enums(language: Language) -> Enums {
+    Enums {
+        player_categories: itsf::PlayerCategory::ALL
+            .into_iter()
+            .map(|category| EnumValue {
+                code: String::from(category.code()),
+                label: player_category(category, language),
+            })
+            .collect(),
+        ranking_categories: itsf::RankingCategory::ALL
+            .into_iter()
+            .map(|category| EnumValue {
+                code: code(category),
+                label: ranking_category(category, language),
+            })
+            .collect(),
+        ranking_classes: itsf::RankingClass::ALL
+            .into_iter()
+            .map(|class| EnumValue {
+                code: code(class),
+                label: ranking_class(class, language),
+            })
+            .collect(),
+        championship_categories: dtfb::ChampionshipCategory::ALL
+            .into_iter()
+            .map(|category| EnumValue {
+                code: code(category),
+                label: championship_category(category, language),
+            })
+            .collect(),
+        championship_classes: dtfb::ChampionshipClass::ALL
+            .into_iter()
+            .map(|class| EnumValue {
+                code: code(class),
+                label: championship_class(class, language),
+            })
+            .collect(),
+    }
+}
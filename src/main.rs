@@ -1,331 +1,27 @@
-#[macro_use]
-extern crate diesel;
-
-use crate::data::{dtfb, itsf};
-use actix_web::http::header::ContentType;
-use actix_web::{middleware::Logger, web, App, Error, HttpResponse, HttpServer};
-use actix_web_httpauth::extractors::basic::BasicAuth;
-use chrono::Datelike;
-use lazy_static::lazy_static;
+use actix_web::{middleware::Logger, web, App, HttpServer};
+use playerdb_core::data::images;
+use playerdb_core::{data, freshness, joblock, maintenance, notify, replication, retention, search, seed, stats, undo};
 use rustls::ServerConfig;
-use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::sync::{Mutex, MutexGuard, Weak};
-
-mod background;
-mod data;
+use std::io::BufReader;
+use std::sync::Mutex;
+
+mod auth;
+mod bench;
+mod check;
+mod dto;
+mod features;
 mod json;
-mod schema;
-mod scraping;
-
-fn load_users_file() -> HashMap<String, String> {
-    let path = std::env::var("USERS_FILE").expect("USERS_FILE missing from environment");
-    let file = File::open(path).expect("Failed to open users file");
-    let mut ret = HashMap::new();
-    for line in BufReader::new(file).lines() {
-        let line = line.expect("Failed to parse users file");
-        let parts: Vec<&str> = line.split(':').collect();
-        assert!(parts.len() == 2, "Invalid users file");
-        ret.insert(String::from(parts[0]), String::from(parts[1]));
-    }
-    ret
-}
-
-fn is_authorized(auth: BasicAuth) -> bool {
-    lazy_static! {
-        static ref USERS: HashMap<String, String> = load_users_file();
-    }
-    let user_id = auth.user_id().to_string();
-    let passwords = auth.password().zip(USERS.get(&user_id));
-    match passwords {
-        Some((pw1, pw2)) => pw1 == pw2,
-        None => false,
-    }
-}
-
-struct AppState {
-    data: data::DatabaseRef,
-    download: Mutex<Weak<background::BackgroundOperationProgress>>,
-}
-impl AppState {
-    fn get_download(
-        this: &web::Data<AppState>,
-    ) -> Result<MutexGuard<Weak<background::BackgroundOperationProgress>>, Error> {
-        this.download
-            .lock()
-            .map_err(|_| actix_web::error::ErrorInternalServerError("internal lock"))
-    }
-}
-
-#[actix_web::get("/db.zip")]
-async fn download_db_zip(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
-    match data.data.create_zip_file() {
-        Ok(data) => Ok(HttpResponse::Ok().content_type(ContentType::octet_stream()).body(data)),
-        Err(_) => Ok(HttpResponse::InternalServerError().json(json::err("error"))),
-    }
-}
-
-#[actix_web::get("/player/{itsf_lic}")]
-async fn get_player(data: web::Data<AppState>, itsf_lic: web::Path<i32>) -> Result<HttpResponse, Error> {
-    let itsf_lic = itsf_lic.into_inner();
-
-    #[derive(serde::Serialize)]
-    struct PlayerJson {
-        pub first_name: String,
-        pub last_name: String,
-        pub birth_year: i32,
-        pub country_code: String,
-        pub image_url: String,
-        pub itsf_rankings: Vec<itsf::Ranking>,
-        pub dtfb_rankings: Vec<dtfb::NationalRanking>,
-        pub dm_placements: Vec<dtfb::NationalChampionshipResult>,
-        pub dtfl_teams: Vec<dtfb::NationalTeam>,
-        pub comment: String,
-    }
-
-    match data.data.get_player(itsf_lic) {
-        Some(player) => {
-            let mut player = PlayerJson {
-                first_name: player.first_name,
-                last_name: player.last_name,
-                birth_year: player.birth_year,
-                country_code: player.country_code.unwrap_or(String::new()),
-                image_url: format!("/image/{}.jpg", itsf_lic),
-                itsf_rankings: player.itsf_rankings,
-                dtfb_rankings: player.dtfb_national_rankings,
-                dm_placements: player.dtfb_championship_results,
-                dtfl_teams: player.dtfb_league_teams,
-                comment: player.comments.last().map(|c| c.text.clone()).unwrap_or(String::new()),
-            };
-
-            player
-                .itsf_rankings
-                .retain(|ranking| ranking.class != itsf::RankingClass::Combined);
-            player.itsf_rankings.sort_by(|a, b| b.year.cmp(&a.year));
-            player.dtfb_rankings.sort_by(|a, b| b.year.cmp(&a.year));
-            player.dm_placements.sort_by(|a, b| b.year.cmp(&a.year));
-            player.dtfl_teams.sort_by(|a, b| b.year.cmp(&a.year));
-
-            Ok(HttpResponse::Ok().json(json::ok(player)))
-        }
-        None => Ok(HttpResponse::NotFound().json(json::err("No such player"))),
-    }
-}
-
-#[actix_web::get("/listplayers")]
-async fn list_players(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
-    #[derive(serde::Serialize)]
-    struct PlayerData {
-        pub itsf_lic: i32,
-        pub first_name: String,
-        pub last_name: String,
-    }
-
-    let ids = data.data.get_player_ids();
-    let players: Vec<PlayerData> = ids
-        .iter()
-        .map(|itsf_lic| {
-            let player = data.data.get_player(*itsf_lic).unwrap();
-            PlayerData {
-                itsf_lic: *itsf_lic,
-                first_name: player.first_name,
-                last_name: player.last_name,
-            }
-        })
-        .collect();
-
-    Ok(HttpResponse::Ok().json(json::ok(players)))
-}
-
-#[actix_web::get("/image/{itsf_lic}.jpg")]
-async fn get_player_image(data: web::Data<AppState>, itsf_lic: web::Path<i32>) -> Result<HttpResponse, Error> {
-    let itsf_lic = itsf_lic.into_inner();
-
-    match data.data.get_player_image(itsf_lic) {
-        Some(player_image) => Ok(HttpResponse::Ok()
-            .append_header(("Content-Type", "image/jpeg"))
-            .body(player_image.image_data)),
-        None => Ok(HttpResponse::NotFound().finish()),
-    }
-}
-
-#[derive(serde::Serialize)]
-struct DownloadStatus {
-    running: bool,
-    log: Vec<String>,
-}
-
-#[actix_web::get("/download_status")]
-async fn download_status(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
-    let download = AppState::get_download(&data)?;
-    let status = match download.upgrade() {
-        Some(download) => DownloadStatus {
-            running: true,
-            log: download.get_log(),
-        },
-        None => DownloadStatus {
-            running: false,
-            log: Vec::new(),
-        },
-    };
-    Ok(HttpResponse::Ok().json(json::ok(status)))
-}
-
-fn download_itsf(
-    data: web::Data<AppState>,
-    years: Vec<i32>,
-    max_rank: usize,
-    force: bool,
-) -> Result<HttpResponse, Error> {
-    let mut download = AppState::get_download(&data)?;
-    if download.upgrade().is_some() {
-        return Ok(HttpResponse::BadRequest().json(json::err("Ranking query still in progress")));
-    }
-
-    let categories = vec![
-        itsf::RankingCategory::Open,
-        itsf::RankingCategory::Women,
-        itsf::RankingCategory::Senior,
-        itsf::RankingCategory::Junior,
-    ];
-    let classes = vec![
-        itsf::RankingClass::Singles,
-        itsf::RankingClass::Doubles,
-        itsf::RankingClass::Combined,
-    ];
-    *download = scraping::start_itsf_rankings_download(data.data.clone(), years, categories, classes, max_rank, force);
-
-    Ok(HttpResponse::Ok().json(json::ok("Started download")))
-}
-
-#[derive(Deserialize)]
-struct DownloadParams {
-    year: Option<String>,
-    max_rank: Option<usize>,
-    force: Option<String>,
-}
-
-impl DownloadParams {
-    fn parse_year(&self) -> Option<i32> {
-        let min_year = 2010;
-        let curr_year = chrono::Utc::now().naive_local().year();
-        match &self.year {
-            Some(year_str) => year_str.parse::<i32>().ok().and_then(|year| {
-                if year >= min_year && year <= curr_year {
-                    Some(year)
-                } else {
-                    None
-                }
-            }),
-            None => Some(curr_year),
-        }
-    }
-
-    fn parse_force(&self) -> bool {
-        match &self.force {
-            Some(force_str) => force_str == "true",
-            None => false,
-        }
-    }
-}
-
-#[actix_web::post("/download_itsf")]
-async fn download_itsf_single(
-    data: web::Data<AppState>,
-    params: web::Query<DownloadParams>,
-    auth: BasicAuth,
-) -> Result<HttpResponse, Error> {
-    if !is_authorized(auth) {
-        return Ok(HttpResponse::Forbidden().json(json::err("not authorized")));
-    }
-
-    let force = params.parse_force();
-    let max_rank = params.max_rank.unwrap_or(1000);
-    match params.parse_year() {
-        Some(year) => download_itsf(data, vec![year], max_rank, force),
-        None => Ok(HttpResponse::BadRequest().json(json::err("invalid year"))),
-    }
-}
-
-#[actix_web::post("/download_itsf_all")]
-async fn download_all_itsf(data: web::Data<AppState>, auth: BasicAuth) -> Result<HttpResponse, Error> {
-    if !is_authorized(auth) {
-        return Ok(HttpResponse::Forbidden().json(json::err("not authorized")));
-    }
-
-    let curr_year = chrono::Utc::now().naive_local().year();
-    let years = (2010..curr_year + 1).collect();
-    let max_rank = 1000;
-    download_itsf(data, years, max_rank, false)
-}
-
-fn download_dtfb(
-    data: web::Data<AppState>,
-    seasons: Vec<i32>,
-    max_rank: usize,
-    force: bool,
-) -> Result<HttpResponse, Error> {
-    let mut download = AppState::get_download(&data)?;
-    if download.upgrade().is_some() {
-        return Ok(HttpResponse::BadRequest().json(json::err("Ranking query still in progress")));
-    }
-
-    *download = scraping::start_dtfb_rankings_download(data.data.clone(), seasons, max_rank, force);
-
-    Ok(HttpResponse::Ok().json(json::ok("Started download")))
-}
-
-#[actix_web::post("/download_dtfb")]
-async fn download_dtfb_single(
-    data: web::Data<AppState>,
-    params: web::Query<DownloadParams>,
-    auth: BasicAuth,
-) -> Result<HttpResponse, Error> {
-    if !is_authorized(auth) {
-        return Ok(HttpResponse::Forbidden().json(json::err("not authorized")));
-    }
-
-    let max_rank = params.max_rank.unwrap_or(1000);
-    let force = params.parse_force();
-    match params.parse_year() {
-        Some(year) => download_dtfb(data, vec![year], max_rank, force),
-        None => Ok(HttpResponse::BadRequest().json(json::err("invalid year"))),
-    }
-}
-
-#[actix_web::post("/download_dtfb_all")]
-async fn download_dtfb_all(data: web::Data<AppState>, auth: BasicAuth) -> Result<HttpResponse, Error> {
-    if !is_authorized(auth) {
-        return Ok(HttpResponse::Forbidden().json(json::err("not authorized")));
-    }
-
-    let curr_year = chrono::Utc::now().naive_local().year();
-    let years = (2010..curr_year + 1).collect();
-    let max_rank = 1000;
-    download_dtfb(data, years, max_rank, false)
-}
-
-#[derive(Deserialize)]
-struct AddCommentInfo {
-    itsf_lic: i32,
-    comment: String,
-}
-
-#[actix_web::post("/add_comment")]
-async fn add_player_comment(
-    data: web::Data<AppState>,
-    info: web::Json<AddCommentInfo>,
-    auth: BasicAuth,
-) -> Result<HttpResponse, Error> {
-    if !is_authorized(auth) {
-        return Ok(HttpResponse::Forbidden().json(json::err("not authorized")));
-    }
-
-    data.data.add_player_comment(info.itsf_lic, info.comment.clone());
-    Ok(HttpResponse::Ok().json(json::ok("added comment")))
-}
+mod labels;
+mod mock_source;
+mod opensearch;
+mod ranges;
+mod repair;
+mod routes;
+mod sampling;
+mod signing;
+mod timing;
 
 fn get_rustls_config() -> Option<ServerConfig> {
     use rustls::{Certificate, PrivateKey};
@@ -360,36 +56,151 @@ fn get_rustls_config() -> Option<ServerConfig> {
     })
 }
 
+/// Arguments of `--seed <count> [<random seed>]`, the random seed is taken from the clock if missing.
+fn seed_args() -> Option<(usize, u64)> {
+    let args: Vec<String> = std::env::args().collect();
+    let position = args.iter().position(|arg| arg == "--seed")?;
+    let usage = "usage: server --seed <count> [<random seed>]";
+    let count = args.get(position + 1).expect(usage).parse::<usize>().expect(usage);
+    let random_seed = match args.get(position + 2) {
+        Some(random_seed) => random_seed.parse::<u64>().expect(usage),
+        None => chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64,
+    };
+    Some((count, random_seed))
+}
+
+/// Fills the database with fake players, for load testing and frontend development.
+fn seed_database(count: usize, random_seed: u64) {
+    let database_path = std::env::var("DATABASE_URL").expect("DATABASE_URL missing from environment");
+    let images_path = std::env::var("IMAGE_PATH").expect("IMAGE_PATH missing from environment");
+    let db = data::DatabaseRef::load(
+        &database_path,
+        None,
+        &images_path,
+        data::connection::ConnectionSettings::from_env(),
+    );
+    let itsf_ids = seed::seed_players(&db, count, random_seed);
+    match (itsf_ids.first(), itsf_ids.last()) {
+        (Some(first), Some(last)) => println!(
+            "Added {} fake players with licenses {} to {} (random seed {})",
+            itsf_ids.len(),
+            first,
+            last,
+            random_seed
+        ),
+        _ => println!("No players added"),
+    }
+}
+
+/// Lists the migrations `diesel migration run` would apply, one per line, without applying them.
+fn print_pending_migrations() {
+    let database_path = std::env::var("DATABASE_URL").expect("DATABASE_URL missing from environment");
+    let settings = data::connection::ConnectionSettings::from_env();
+    match data::get_pending_migrations(&database_path, &settings) {
+        Ok(pending) => {
+            for migration in pending {
+                println!("{}", migration);
+            }
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv::dotenv().ok();
     env_logger::init();
 
+    if std::env::args().any(|arg| arg == "--check") {
+        let passed = check::run().await;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+    if let Some((count, random_seed)) = seed_args() {
+        seed_database(count, random_seed);
+        return Ok(());
+    }
+    if std::env::args().any(|arg| arg == "--print-pending-migrations") {
+        print_pending_migrations();
+        return Ok(());
+    }
+    if let Some(args) = repair::args() {
+        std::process::exit(repair::run(&args));
+    }
+
+    if let Err(err) = notify::init() {
+        log::error!("{}", err);
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
     let database_path = std::env::var("DATABASE_URL").expect("DATABASE_URL missing from environment");
+    let replica_path = std::env::var("DATABASE_READ_URL").ok();
+    // queries of the replica would fail with an outdated schema just as well
+    let connection_settings = data::connection::ConnectionSettings::from_env();
+    for path in std::iter::once(&database_path).chain(&replica_path) {
+        if let Err(err) = data::check_schema(path, &connection_settings) {
+            log::error!("{}", err);
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
     let images_path = std::env::var("IMAGE_PATH").expect("IMAGE_PATH missing from environment");
     let html_path = std::env::var("HTML_ROOT").expect("HTML_ROOT missing from environment");
     let port = std::env::var("SERVER_PORT").expect("SERVER_PORT missing from environment");
     let port = port.parse::<u16>().expect("invalid SERVER_PORT");
-    let state = AppState {
-        data: data::DatabaseRef::load(&database_path, &images_path),
-        download: Mutex::new(Weak::new()),
+    let access_mode = auth::AccessMode::from_env();
+    let image_access = images::ImageAccess::from_env();
+    let request_limits = timing::RequestLimits::from_env();
+    auth::init();
+    features::init();
+    sampling::init();
+    let retention_policy = retention::RetentionPolicy::from_env();
+    let maintenance_settings = maintenance::MaintenanceSettings::from_env();
+    let refresh_settings = freshness::RefreshSettings::from_env();
+    let replication_settings = replication::ReplicationSettings::from_env();
+    if mock_source::is_enabled() {
+        mock_source::start()?;
+    }
+    let db = data::DatabaseRef::load(
+        &database_path,
+        replica_path.as_deref(),
+        &images_path,
+        data::connection::ConnectionSettings::from_env(),
+    );
+    let state = routes::AppState {
+        job_lock: joblock::JobLock::from_env(&db),
+        data: db,
+        downloads: Mutex::new(HashMap::new()),
+        access_mode,
+        image_access,
     };
     let state = web::Data::new(state);
 
+    notify::email::start_retry_task();
+    search::start_index_sync(&state.data);
+    stats::start_refresh_task(&state.data);
+    undo::start_finalizer(&state.data);
+    sampling::start_writer(&state.data);
+    retention::start_pruning_task(&state.data, retention_policy);
+    maintenance::start_maintenance_task(&state.data, maintenance_settings);
+    maintenance::start_image_gc_task(&state.data);
+    match replication_settings {
+        // the players are refreshed on the primary, a secondary's changes would be replaced anyway
+        Some(settings) => replication::start_replication_task(&state.data, settings),
+        None => freshness::start_refresh_task(&state.data, state.job_lock.clone(), refresh_settings),
+    }
+
     let mut server = HttpServer::new(move || {
         App::new()
+            .wrap(json::FieldSelection)
+            .wrap(features::FeatureGuard::new(state.data.clone()))
+            .wrap(auth::Authentication::new(access_mode))
+            .wrap(timing::RequestTiming::new(request_limits))
             .wrap(Logger::default())
             .app_data(state.clone())
-            .service(download_db_zip)
-            .service(get_player)
-            .service(get_player_image)
-            .service(list_players)
-            .service(download_status)
-            .service(download_itsf_single)
-            .service(download_all_itsf)
-            .service(download_dtfb_single)
-            .service(download_dtfb_all)
-            .service(add_player_comment)
+            .configure(routes::configure)
             .service(actix_files::Files::new("", &html_path).index_file("start.html"))
     });
 
@@ -0,0 +1,504 @@
+//! Mock of the scraped federation sites for demo deployments and tests, enabled with `DEMO_MODE=true`.
+//! Serves a handful of made-up players in the page formats the scrapers expect.
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use chrono::Datelike;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use playerdb_core::{scraping::sources, seed};
+
+struct DemoPlayer {
+    itsf_id: i32,
+    dtfb_id: Option<i32>,
+    first_name: &'static str,
+    last_name: &'static str,
+    country_code: &'static str,
+    /// As written on the ITSF player page.
+    category: &'static str,
+    birth_year: i32,
+    team: Option<&'static str>,
+}
+
+const fn player(
+    itsf_id: i32,
+    dtfb_id: Option<i32>,
+    name: (&'static str, &'static str),
+    country_code: &'static str,
+    category: &'static str,
+    birth_year: i32,
+    team: Option<&'static str>,
+) -> DemoPlayer {
+    DemoPlayer {
+        itsf_id,
+        dtfb_id,
+        first_name: name.0,
+        last_name: name.1,
+        country_code,
+        category,
+        birth_year,
+        team,
+    }
+}
+
+const PLAYERS: [DemoPlayer; 12] = [
+    player(
+        84000001,
+        Some(501),
+        ("Max", "MUSTERMANN"),
+        "GER",
+        "MEN",
+        1988,
+        Some("Kickerfreunde Nord"),
+    ),
+    player(
+        84000002,
+        Some(502),
+        ("Erika", "MUSTERFRAU"),
+        "GER",
+        "WOMEN",
+        1991,
+        Some("Kickerfreunde Nord"),
+    ),
+    player(
+        84000003,
+        Some(503),
+        ("Jonas", "BEISPIEL"),
+        "GER",
+        "JUNIOR MALE",
+        2007,
+        Some("TFC Süd"),
+    ),
+    player(
+        84000004,
+        Some(504),
+        ("Petra", "PROBE"),
+        "GER",
+        "SENIOR FEMALE",
+        1965,
+        Some("TFC Süd"),
+    ),
+    player(
+        84000005,
+        Some(505),
+        ("Klaus", "KURBEL"),
+        "GER",
+        "SENIOR MALE",
+        1962,
+        Some("Stangenzauber"),
+    ),
+    player(
+        84000006,
+        Some(506),
+        ("Lena", "LAUFER"),
+        "GER",
+        "JUNIOR FEMALE",
+        2008,
+        Some("Stangenzauber"),
+    ),
+    player(84000007, None, ("Jean", "DUPONT"), "FRA", "MEN", 1985, None),
+    player(84000008, None, ("Marie", "MARTIN"), "FRA", "WOMEN", 1993, None),
+    player(84000009, None, ("Luca", "ROSSI"), "ITA", "MEN", 1990, None),
+    player(84000010, None, ("Anna", "BAUER"), "AUT", "WOMEN", 1989, None),
+    player(84000011, None, ("Tom", "SMITH"), "USA", "MEN", 1995, None),
+    player(84000012, None, ("Sofie", "JANSSENS"), "BEL", "WOMEN", 1997, None),
+];
+
+/// DTFB ranking ids per season are `{year}1` for men and `{year}2` for women.
+const DTFB_RANKINGS: [(i32, &str); 2] = [(1, "MEN"), (2, "WOMEN")];
+
+fn country_name(country_code: &str) -> &'static str {
+    match country_code {
+        "GER" => "Germany",
+        "FRA" => "France",
+        "ITA" => "Italy",
+        "AUT" => "Austria",
+        "USA" => "United States",
+        "BEL" => "Belgium",
+        _ => "",
+    }
+}
+
+fn in_category(player: &DemoPlayer, category: &str) -> bool {
+    match category {
+        "w" => player.category.contains("WOMEN") || player.category.contains("FEMALE"),
+        "j" => player.category.starts_with("JUNIOR"),
+        "s" => player.category.starts_with("SENIOR"),
+        _ => true,
+    }
+}
+
+/// Deterministic but different order of the players for every year, so rankings change over time.
+fn ranked<'a>(players: impl Iterator<Item = &'a DemoPlayer>, year: i32) -> Vec<&'a DemoPlayer> {
+    let mut players: Vec<&DemoPlayer> = players.collect();
+    players.sort_by_key(|player| (player.itsf_id as i64 * (year as i64 + 7)) % 97);
+    players
+}
+
+fn html(body: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(format!("<html><body>{}</body></html>", body))
+}
+
+fn query(req: &HttpRequest) -> HashMap<String, String> {
+    web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .map(|query| query.into_inner())
+        .unwrap_or_default()
+}
+
+fn season(req: &HttpRequest) -> i32 {
+    req.cookie("sportsmanager_filter_saison_id")
+        .and_then(|cookie| cookie.value().parse::<i32>().ok())
+        .unwrap_or(chrono::Utc::now().year())
+}
+
+fn itsf_player(itsf_id: &str) -> HttpResponse {
+    let player = PLAYERS
+        .iter()
+        .find(|player| itsf_id.parse::<i32>() == Ok(player.itsf_id));
+    match player {
+        // the real site answers unknown licences with an empty player page as well
+        None => html(String::from("<div class=\"contenu\"></div>")),
+        Some(player) => html(format!(
+            "<div class=\"nomdujoueur\">{} {} <span>({} {})</span></div>\
+             <div class=\"contenu_typeinfojoueur\">{:08}</div>\
+             <div class=\"contenu_typeinfojoueur\">{}</div>\
+             <div class=\"contenu_typeinfojoueur even\">{}</div>",
+            player.first_name,
+            player.last_name,
+            player.country_code,
+            country_name(player.country_code),
+            player.itsf_id,
+            player.birth_year,
+            player.category
+        )),
+    }
+}
+
+fn itsf_rankings(req: &HttpRequest) -> HttpResponse {
+    let query = query(req);
+    let category = query.get("category").map(|category| &category[..1]).unwrap_or("o");
+    let year = query.get("tour").and_then(|year| year.parse::<i32>().ok()).unwrap_or(0);
+    let count = query
+        .get("vues")
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or(10);
+
+    let players = ranked(PLAYERS.iter().filter(|player| in_category(player, category)), year);
+    let rows: Vec<String> = players
+        .iter()
+        .take(count)
+        .enumerate()
+        .map(|(index, player)| {
+            format!(
+                "<div id=\"place{}\" onclick=\"location.href='?page=player&numlic={:08}&'\">{} {}</div>",
+                index + 1,
+                player.itsf_id,
+                player.first_name,
+                player.last_name
+            )
+        })
+        .collect();
+    html(format!("<p>Last update: 01/07/{}</p>{}", year, rows.join("")))
+}
+
+/// A handful of made-up tournaments in every year, and the World Championships in odd years.
+fn itsf_calendar(year: &str) -> HttpResponse {
+    const EVENTS: [(i32, &str, &str, &str, &str); 4] = [
+        (1, "International Open", "03/14", "Paris (FRA)", "ITSF Pro Tour"),
+        (2, "Masters", "06/20", "Hamburg (GER)", "ITSF Master Series"),
+        (
+            WORLD_CHAMPIONSHIPS,
+            "World Championships",
+            "09/12",
+            "Nantes (FRA)",
+            "ITSF World Championships",
+        ),
+        (3, "Winter Cup", "11/07", "Vienna (AUT)", "ITSF Challenger"),
+    ];
+    let year = match year.parse::<i32>() {
+        Ok(year) => year,
+        Err(_) => return HttpResponse::NotFound().finish(),
+    };
+    let rows: Vec<String> = EVENTS
+        .iter()
+        .filter(|(id, ..)| *id != WORLD_CHAMPIONSHIPS || year % 2 == 1)
+        .map(|(id, name, start, location, category)| {
+            let (month, day) = start.split_once('/').unwrap();
+            format!(
+                "<tr><td>{day}/{month}/{year} - {end:02}/{month}/{year}</td>\
+                 <td><a href=\"?page=event&id={event_id}\">{name} {year}</a></td><td>{location}</td><td>{category}</td></tr>",
+                end = day.parse::<i32>().unwrap() + 2,
+                event_id = year * 10 + id,
+            )
+        })
+        .collect();
+    html(format!(
+        "<table><tr><th>Date</th><th>Event</th><th>Location</th><th>Category</th></tr>{}</table>",
+        rows.join("")
+    ))
+}
+
+/// Event id suffix of the World Championships in the calendar.
+const WORLD_CHAMPIONSHIPS: i32 = 4;
+
+/// Results of the World Championships: every country sends its men and its women as a team, placed in the
+/// order of the country's best player in the year's ranking. The singles results are left out of the teams.
+fn itsf_event(event_id: &str) -> HttpResponse {
+    let event_id = match event_id.parse::<i32>() {
+        Ok(event_id) if event_id % 10 == WORLD_CHAMPIONSHIPS && (event_id / 10) % 2 == 1 => event_id,
+        _ => return HttpResponse::NotFound().finish(),
+    };
+    let year = event_id / 10;
+    let player_link = |player: &DemoPlayer| {
+        format!(
+            "<a href=\"?page=player&numlic={:08}&\">{} {}</a>",
+            player.itsf_id, player.first_name, player.last_name
+        )
+    };
+    let competition = |name: &str, women: bool| {
+        let players = ranked(PLAYERS.iter().filter(|player| in_category(player, "w") == women), year);
+        let mut countries: Vec<&str> = Vec::new();
+        for player in &players {
+            if !countries.contains(&player.country_code) {
+                countries.push(player.country_code);
+            }
+        }
+        let rows: Vec<String> = countries
+            .iter()
+            .enumerate()
+            .map(|(index, country_code)| {
+                let team: Vec<String> = players
+                    .iter()
+                    .filter(|player| player.country_code == *country_code)
+                    .map(|player| player_link(player))
+                    .collect();
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    index + 1,
+                    country_code,
+                    team.join(", ")
+                )
+            })
+            .collect();
+        format!(
+            "<div class=\"competition\"><h3>{}</h3><table>{}</table></div>",
+            name,
+            rows.join("")
+        )
+    };
+    let singles: Vec<String> = ranked(PLAYERS.iter(), year)
+        .iter()
+        .enumerate()
+        .map(|(index, player)| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                index + 1,
+                player.country_code,
+                player_link(player)
+            )
+        })
+        .collect();
+    html(format!(
+        "<h2>World Championships {}</h2>{}{}<div class=\"competition\"><h3>Open Singles</h3><table>{}</table></div>",
+        year,
+        competition("Men's Teams", false),
+        competition("Women's Teams", true),
+        singles.join("")
+    ))
+}
+
+/// Nominatim style search results for the cities of the calendar.
+fn geo_search(req: &HttpRequest) -> HttpResponse {
+    const CITIES: [(&str, &str, &str); 3] = [
+        ("Paris", "48.85", "2.35"),
+        ("Hamburg", "53.55", "9.99"),
+        ("Vienna", "48.21", "16.37"),
+    ];
+    let place = query(req).get("q").cloned().unwrap_or_default();
+    let city = place.split(',').next().unwrap_or_default().trim();
+    let results: Vec<serde_json::Value> = CITIES
+        .iter()
+        .filter(|(name, _, _)| *name == city)
+        .map(|(_, lat, lon)| serde_json::json!({ "lat": lat, "lon": lon }))
+        .collect();
+    HttpResponse::Ok().json(results)
+}
+
+/// A plain coloured portrait, so demo players don't all look alike.
+fn itsf_image(itsf_id: &str) -> HttpResponse {
+    let itsf_id = match itsf_id.parse::<i32>() {
+        Ok(itsf_id) if PLAYERS.iter().any(|player| player.itsf_id == itsf_id) => itsf_id,
+        _ => return HttpResponse::NotFound().finish(),
+    };
+    HttpResponse::Ok()
+        .content_type("image/jpeg")
+        .body(seed::portrait(itsf_id))
+}
+
+fn dtfb_ranking_overview(req: &HttpRequest) -> HttpResponse {
+    let year = season(req);
+    let links: Vec<String> = DTFB_RANKINGS
+        .iter()
+        .map(|(id, name)| format!("<a href=\"?task=rangliste&id={}{}\">{}</a>", year, id, name))
+        .collect();
+    html(links.join(""))
+}
+
+fn dtfb_ranking(ranking_id: &str) -> HttpResponse {
+    let (year, id) = ranking_id.split_at(ranking_id.len().saturating_sub(1));
+    let year = year.parse::<i32>().unwrap_or(0);
+    let category = DTFB_RANKINGS
+        .iter()
+        .find(|(ranking, _)| id.parse::<i32>() == Ok(*ranking))
+        .map(|(_, category)| *category);
+    let players = PLAYERS
+        .iter()
+        .filter(|player| player.dtfb_id.is_some())
+        .filter(|player| match category {
+            Some("WOMEN") => in_category(player, "w"),
+            _ => !in_category(player, "w"),
+        });
+    let links: Vec<String> = ranked(players, year)
+        .iter()
+        .map(|player| {
+            format!(
+                "<a href=\"/component/sportsmanager?task=spieler_details&id={}\">{} {}</a>",
+                player.dtfb_id.unwrap(),
+                player.first_name,
+                player.last_name
+            )
+        })
+        .collect();
+    html(links.join(""))
+}
+
+fn dtfb_player(dtfb_id: &str) -> HttpResponse {
+    let player = match PLAYERS
+        .iter()
+        .find(|player| player.dtfb_id.is_some() && dtfb_id.parse::<i32>().ok() == player.dtfb_id)
+    {
+        Some(player) => player,
+        None => return HttpResponse::NotFound().json(serde_json::json!({ "data": null })),
+    };
+
+    let (discipline, ranking) = if in_category(player, "w") {
+        ("Damen Einzel", "Damen")
+    } else {
+        ("Herren Einzel", "Herren")
+    };
+    let current_year = chrono::Utc::now().year();
+    let mut teams = Vec::new();
+    let mut championships = Vec::new();
+    let mut rankings = Vec::new();
+    for year in current_year - 2..=current_year {
+        if let Some(team) = player.team {
+            teams.push(
+                serde_json::json!({ "saisonbezeichnung": year, "teamname": team, "bezeichnung": "1. Bundesliga" }),
+            );
+        }
+        let place = player.dtfb_id.unwrap() % 7 + (year - current_year).abs() + 1;
+        championships.push(serde_json::json!({
+            "saisonbezeichnung": year,
+            "turnierbezeichnung": "Deutsche Meisterschaft",
+            "disziplin": discipline,
+            "platz": place,
+        }));
+        rankings.push(serde_json::json!({ "saisonbezeichnung": year, "platz": place, "bezeichnung": ranking }));
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "data": {
+            "spieler": { "spieler_id": player.dtfb_id, "lizenznr": player.itsf_id },
+            "teams": teams,
+            "turnier_platzierungen": championships,
+            "ranglisten_platzierungen": rankings,
+        }
+    }))
+}
+
+fn dtfb_league_table(req: &HttpRequest) -> HttpResponse {
+    let year = season(req);
+    let mut teams: Vec<&str> = PLAYERS.iter().filter_map(|player| player.team).collect();
+    teams.dedup();
+    teams.sort_by_key(|team| (team.len() as i32 * (year + 3)) % 11);
+    let rows: Vec<String> = teams
+        .iter()
+        .enumerate()
+        .map(|(index, team)| {
+            format!(
+                "<tr><td>{}.</td><td>{}</td><td>{}</td></tr>",
+                index + 1,
+                team,
+                3 * (teams.len() - index)
+            )
+        })
+        .collect();
+    html(format!("<h2>1. Bundesliga</h2><table>{}</table>", rows.join("")))
+}
+
+async fn handle(req: HttpRequest) -> HttpResponse {
+    if let Some(latency) = req.app_data::<web::Data<Duration>>() {
+        tokio::time::sleep(*latency.get_ref()).await;
+    }
+    let path = req.path().to_string();
+    let query = query(&req);
+    let id = query.get("id").map(String::as_str).unwrap_or_default();
+
+    if let Some(itsf_id) = path.strip_prefix("/itsf/page/player&numlic=") {
+        itsf_player(itsf_id)
+    } else if let Some(year) = path.strip_prefix("/itsf/page/calendar&year=") {
+        itsf_calendar(year)
+    } else if let Some(event_id) = path.strip_prefix("/itsf/page/event&id=") {
+        itsf_event(event_id)
+    } else if path == "/geo/search" {
+        geo_search(&req)
+    } else if path == "/itsf/page/rankings" {
+        itsf_rankings(&req)
+    } else if let Some(image) = path.strip_prefix("/media/photos/players/") {
+        itsf_image(image.trim_end_matches(".jpg"))
+    } else if path == "/dtfb/wettbewerbe/turnierserie/rangliste" && id.is_empty() {
+        dtfb_ranking_overview(&req)
+    } else if path == "/dtfb/wettbewerbe/turnierserie/rangliste" {
+        dtfb_ranking(id)
+    } else if path == "/dtfb/component/sportsmanager" {
+        dtfb_player(id)
+    } else if path == "/dtfb/wettbewerbe/bundesliga/tabelle" {
+        dtfb_league_table(&req)
+    } else {
+        HttpResponse::NotFound().finish()
+    }
+}
+
+pub fn is_enabled() -> bool {
+    std::env::var("DEMO_MODE").is_ok_and(|demo| demo == "true")
+}
+
+/// Starts the mock on `MOCK_SOURCE_PORT`, or any free port if unset, and points the scrapers at it. Every
+/// response is delayed by `MOCK_SOURCE_LATENCY` milliseconds.
+pub fn start() -> std::io::Result<()> {
+    let port = match std::env::var("MOCK_SOURCE_PORT") {
+        Ok(port) => port.parse::<u16>().expect("invalid MOCK_SOURCE_PORT"),
+        Err(_) => 0,
+    };
+    let latency = match std::env::var("MOCK_SOURCE_LATENCY") {
+        Ok(latency) => Duration::from_millis(latency.parse::<u64>().expect("invalid MOCK_SOURCE_LATENCY")),
+        Err(_) => Duration::ZERO,
+    };
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(latency))
+            .default_service(web::to(handle))
+    })
+    .workers(1)
+    .bind(("127.0.0.1", port))?;
+    let address = server.addrs()[0];
+    actix_web::rt::spawn(server.run());
+
+    let url = format!("http://{}", address);
+    sources::use_mock_source(&url).map_err(std::io::Error::other)?;
+    playerdb_core::geo::use_mock_provider(&format!("{}/geo", url)).map_err(std::io::Error::other)?;
+    log::info!("Demo mode: scraping from mock source at {}", url);
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! OpenSearch description of the player search, so browsers can add the database as a search engine. Typed
+//! names are completed from `/search?format=opensearch`, searching opens the player list filtered by the query.
+
+pub const CONTENT_TYPE: &str = "application/opensearchdescription+xml";
+pub const SUGGESTIONS_CONTENT_TYPE: &str = "application/x-suggestions+json";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The description document with the URLs of the server at `base_url`.
+pub fn description(base_url: &str) -> String {
+    let base_url = escape(base_url);
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/">
+  <ShortName>ITSF Player DB</ShortName>
+  <Description>Search ITSF table soccer players by name or license</Description>
+  <InputEncoding>UTF-8</InputEncoding>
+  <Url type="text/html" method="get" template="{base_url}/players.html?q={{searchTerms}}"/>
+  <Url type="{suggestions}" method="get" template="{base_url}/search?format=opensearch&amp;q={{searchTerms}}"/>
+  <Url type="{description}" rel="self" template="{base_url}/opensearch.xml"/>
+</OpenSearchDescription>
+"#,
+        base_url = base_url,
+        suggestions = SUGGESTIONS_CONTENT_TYPE,
+        description = CONTENT_TYPE,
+    )
+}
+
+/// A completion of the search terms.
+pub struct Suggestion {
+    pub name: String,
+    pub description: String,
+    pub url: String,
+}
+
+/// Suggestions in the format browsers expect: the query, then the completions, their descriptions and URLs.
+pub fn suggestions(query: &str, suggestions: Vec<Suggestion>) -> serde_json::Value {
+    let mut names = Vec::new();
+    let mut descriptions = Vec::new();
+    let mut urls = Vec::new();
+    for suggestion in suggestions {
+        names.push(suggestion.name);
+        descriptions.push(suggestion.description);
+        urls.push(suggestion.url);
+    }
+    serde_json::json!([query, names, descriptions, urls])
+}
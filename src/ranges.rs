@@ -0,0 +1,84 @@
+//! Single byte ranges of the `Range` header, for resuming image downloads and for CDNs fetching in parts.
+//! Multiple ranges aren't supported, such requests get the whole body like ones with an invalid header.
+
+use std::ops::Range;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// No or an unsupported `Range` header.
+    Full,
+    Partial(Range<usize>),
+    /// The range starts beyond the end of the body.
+    Unsatisfiable,
+}
+
+/// The requested part of a body of `len` bytes, `bytes=0-99`, `bytes=100-` or the last bytes as `bytes=-100`.
+pub fn parse(header: Option<&str>, len: usize) -> ByteRange {
+    let spec = match header.and_then(|header| header.trim().strip_prefix("bytes=")) {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return ByteRange::Full,
+    };
+    let (start, end) = match spec.split_once('-') {
+        Some(bounds) => bounds,
+        None => return ByteRange::Full,
+    };
+    let range = match (start.parse::<usize>().ok(), end.parse::<usize>().ok()) {
+        (Some(start), _) if start >= len => return ByteRange::Unsatisfiable,
+        (Some(start), None) if end.is_empty() => start..len,
+        (Some(start), Some(end)) if end >= start => start..(end + 1).min(len),
+        (None, Some(0)) if start.is_empty() => return ByteRange::Unsatisfiable,
+        (None, Some(suffix)) if start.is_empty() => len.saturating_sub(suffix)..len,
+        _ => return ByteRange::Full,
+    };
+    match range.is_empty() {
+        true => ByteRange::Unsatisfiable,
+        false => ByteRange::Partial(range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(header: &str, len: usize) -> ByteRange {
+        parse(Some(header), len)
+    }
+
+    #[test]
+    fn start_end_and_suffix_ranges() {
+        assert_eq!(range("bytes=0-9", 100), ByteRange::Partial(0..10));
+        assert_eq!(range(" bytes= 90-99 ", 100), ByteRange::Partial(90..100));
+        assert_eq!(range("bytes=90-", 100), ByteRange::Partial(90..100));
+        assert_eq!(range("bytes=-5", 100), ByteRange::Partial(95..100));
+        assert_eq!(range("bytes=5-5", 100), ByteRange::Partial(5..6));
+    }
+
+    #[test]
+    fn ranges_are_clamped_to_the_body() {
+        assert_eq!(range("bytes=90-200", 100), ByteRange::Partial(90..100));
+        assert_eq!(range("bytes=-500", 100), ByteRange::Partial(0..100));
+        assert_eq!(range("bytes=100-", 100), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=200-300", 100), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=-0", 100), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=0-", 0), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=-5", 0), ByteRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn unsupported_headers_get_the_full_body() {
+        assert_eq!(parse(None, 100), ByteRange::Full);
+        for header in [
+            "",
+            "bytes=0-1,5-9",
+            "items=0-9",
+            "bytes=9-0",
+            "bytes=",
+            "bytes=-",
+            "bytes=5",
+            "bytes=a-b",
+            "bytes=-1-2",
+        ] {
+            assert_eq!(range(header, 100), ByteRange::Full, "{}", header);
+        }
+    }
+}
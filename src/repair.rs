@@ -0,0 +1,106 @@
+//! Operator commands for fixing single players from the shell, e.g.
+//! `server --repair fix-name 84000001 Max Mustermann`. They load the database like the server and
+//! change it through the same data layer functions, so former names and country changes are recorded
+//! as usual. Names and countries are corrected with manual overrides by the shell's `USER`, so later
+//! downloads don't undo them. Running servers keep their players in memory and only see the changes
+//! after a restart.
+
+use playerdb_core::data::{self, itsf};
+
+const USAGE: &str = "usage: server --repair <command> <arguments>
+    fix-name <ITSF license> <first name> <last name>
+    set-country <ITSF license> <country code, e.g. GER>
+    delete-ranking-entry <ITSF license> <year> <category, e.g. open> <class, e.g. singles>
+    relink-dtfb <DTFB license> <ITSF license>";
+
+/// The arguments following `--repair`, `None` if it wasn't passed.
+pub fn args() -> Option<Vec<String>> {
+    let args: Vec<String> = std::env::args().collect();
+    let position = args.iter().position(|arg| arg == "--repair")?;
+    Some(args[position + 1..].to_vec())
+}
+
+fn parse_license(license: &str) -> Result<i32, String> {
+    license
+        .parse::<i32>()
+        .map_err(|_| format!("invalid license: '{}'", license))
+}
+
+/// Author of the overrides the commands record.
+fn author() -> String {
+    std::env::var("USER").unwrap_or_else(|_| String::from("operator"))
+}
+
+fn load_database() -> data::DatabaseRef {
+    let database_path = std::env::var("DATABASE_URL").expect("DATABASE_URL missing from environment");
+    let images_path = std::env::var("IMAGE_PATH").expect("IMAGE_PATH missing from environment");
+    data::DatabaseRef::load(
+        &database_path,
+        None,
+        &images_path,
+        data::connection::ConnectionSettings::from_env(),
+    )
+}
+
+/// Runs the command, returning what was changed.
+fn run_command(args: &[String]) -> Result<String, String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args[..] {
+        ["fix-name", itsf_lic, first_name, last_name] => {
+            let itsf_lic = parse_license(itsf_lic)?;
+            load_database().fix_player_name(itsf_lic, first_name, last_name, &author())?;
+            Ok(format!("renamed player {} to {} {}", itsf_lic, first_name, last_name))
+        }
+        ["set-country", itsf_lic, country_code] => {
+            let itsf_lic = parse_license(itsf_lic)?;
+            load_database().set_player_country(itsf_lic, country_code, &author())?;
+            Ok(format!(
+                "set country of player {} to {}",
+                itsf_lic,
+                country_code.to_uppercase()
+            ))
+        }
+        ["delete-ranking-entry", itsf_lic, year, category, class] => {
+            let itsf_lic = parse_license(itsf_lic)?;
+            let year = year.parse::<i32>().map_err(|_| format!("invalid year: '{}'", year))?;
+            let category = itsf::RankingCategory::try_from_str(category)?;
+            let class = itsf::RankingClass::try_from_str(class)?;
+            let ranking = load_database().delete_player_itsf_ranking(itsf_lic, year, category, class)?;
+            Ok(format!(
+                "deleted place {} in {} {:?} {:?} of player {}",
+                ranking.place, year, category, class, itsf_lic
+            ))
+        }
+        ["relink-dtfb", dtfb_lic, itsf_lic] => {
+            let dtfb_lic = parse_license(dtfb_lic)?;
+            let itsf_lic = parse_license(itsf_lic)?;
+            match load_database().relink_dtfb_id(dtfb_lic, itsf_lic)? {
+                Some(previous) if previous != itsf_lic => Ok(format!(
+                    "moved DTFB license {} from player {} to {}",
+                    dtfb_lic, previous, itsf_lic
+                )),
+                Some(_) => Ok(format!(
+                    "DTFB license {} already belongs to player {}",
+                    dtfb_lic, itsf_lic
+                )),
+                None => Ok(format!("linked DTFB license {} to player {}", dtfb_lic, itsf_lic)),
+            }
+        }
+        _ => Err(String::from(USAGE)),
+    }
+}
+
+/// Runs the command and prints its outcome, returns the exit code.
+pub fn run(args: &[String]) -> i32 {
+    match run_command(args) {
+        Ok(message) => {
+            log::info!("operator {}", message);
+            println!("{}", message);
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
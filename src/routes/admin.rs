@@ -0,0 +1,328 @@
+//! Feature flags, undo, request statistics, maintenance and benchmarks.
+
+use actix_service::{IntoServiceFactory, ServiceFactory};
+use actix_web::dev::AppConfig;
+use actix_web::{web, App, Error, HttpRequest, HttpResponse};
+use playerdb_core::{coverage, freshness, maintenance, retention, scraping, undo, warmup};
+use serde::Deserialize;
+
+use super::{require_user, AppState};
+use crate::{auth, bench, features, json, labels, sampling};
+
+#[derive(Deserialize)]
+struct PopularParams {
+    limit: Option<usize>,
+}
+
+#[actix_web::get("/admin/popular")]
+async fn get_popular_players(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<PopularParams>,
+) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+
+    #[derive(serde::Serialize)]
+    struct PopularPlayer {
+        itsf_lic: i32,
+        first_name: String,
+        last_name: String,
+        display_name: String,
+        requests: u64,
+        /// Data the profile lacks, e.g. `image` or `birth_year`.
+        missing: Vec<&'static str>,
+    }
+
+    let limit = params.limit.unwrap_or(50).min(1000);
+    let name_style = labels::NameStyle::from_request(&req);
+    let players: Vec<PopularPlayer> = warmup::most_requested(limit)
+        .into_iter()
+        .filter_map(|(itsf_lic, requests)| {
+            let player = data.data.get_player(itsf_lic)?;
+            Some(PopularPlayer {
+                itsf_lic,
+                display_name: name_style.display_name(&player.first_name, &player.last_name),
+                missing: freshness::missing_data(&data.data, &player),
+                first_name: player.first_name,
+                last_name: player.last_name,
+                requests,
+            })
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(json::ok(players)))
+}
+
+#[actix_web::get("/admin/coverage")]
+async fn get_ranking_coverage(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    Ok(HttpResponse::Ok().json(json::ok(coverage::ranking_coverage(&data.data))))
+}
+
+#[actix_web::get("/admin/features")]
+async fn get_features(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    Ok(HttpResponse::Ok().json(json::ok(features::feature_statuses(&data.data))))
+}
+
+#[derive(Deserialize)]
+struct SetFeatureInfo {
+    feature: features::Feature,
+    enabled: bool,
+}
+
+/// Switches a feature on or off until switched again, overriding `DISABLED_FEATURES`.
+#[actix_web::post("/admin/features")]
+async fn set_feature(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<SetFeatureInfo>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    log::info!(
+        "{} {} feature {}",
+        user_id,
+        if info.enabled { "enabled" } else { "disabled" },
+        info.feature.name()
+    );
+    data.data.set_feature_flag(info.feature.name(), info.enabled);
+    Ok(HttpResponse::Ok().json(json::ok(features::feature_statuses(&data.data))))
+}
+
+#[actix_web::get("/admin/undo")]
+async fn get_pending_actions(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let workspace = auth::workspace(&req);
+    let actions: Vec<undo::PendingAction> = data
+        .data
+        .get_staged_actions()
+        .into_iter()
+        .filter(|pending| auth::workspace_of(&pending.user_id) == workspace.as_deref())
+        .collect();
+    Ok(HttpResponse::Ok().json(json::ok(actions)))
+}
+
+#[actix_web::post("/admin/undo/{action_id}")]
+async fn undo_action(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    action_id: web::Path<u64>,
+) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let action_id = action_id.into_inner();
+    let workspace = auth::workspace(&req);
+    let in_workspace =
+        data.data.get_staged_actions().iter().any(|pending| {
+            pending.action_id == action_id && auth::workspace_of(&pending.user_id) == workspace.as_deref()
+        });
+    if !in_workspace {
+        return Ok(HttpResponse::NotFound().json(json::err("No such action, or it was already finalized")));
+    }
+    match undo::undo(&data.data, action_id) {
+        Some(pending) => Ok(HttpResponse::Ok().json(json::ok(pending))),
+        None => Ok(HttpResponse::NotFound().json(json::err("No such action, or it was already finalized"))),
+    }
+}
+
+#[derive(Deserialize)]
+struct RequestUsageParams {
+    days: Option<i64>,
+}
+
+/// Requests per endpoint estimated from the sampled requests of the last `days` (default 1).
+#[actix_web::get("/admin/requests")]
+async fn get_request_usage(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<RequestUsageParams>,
+) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let since = chrono::Utc::now().timestamp() - params.days.unwrap_or(1).max(0) * 24 * 60 * 60;
+    Ok(HttpResponse::Ok().json(json::ok(sampling::usage(&data.data, since))))
+}
+
+#[derive(Deserialize)]
+struct QualityParams {
+    days: Option<i64>,
+}
+
+/// Parse success and field completeness of every scraper over the last `days` (default 30), per day.
+#[actix_web::get("/admin/quality")]
+async fn get_parse_quality(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<QualityParams>,
+) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let report = scraping::quality::report(&data.data, params.days.unwrap_or(30));
+    Ok(HttpResponse::Ok().json(json::ok(report)))
+}
+
+/// What the retention job would delete now.
+#[actix_web::get("/admin/retention")]
+async fn get_retention_report(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let report = retention::prune(&data.data, &retention::RetentionPolicy::from_env(), true);
+    Ok(HttpResponse::Ok().json(json::ok(report)))
+}
+
+/// Runs the retention job now instead of waiting for the daily run.
+#[actix_web::post("/admin/retention")]
+async fn run_retention(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    let report = retention::prune(&data.data, &retention::RetentionPolicy::from_env(), false);
+    log::info!("{} pruned data: {:?}", user_id, report.rules);
+    Ok(HttpResponse::Ok().json(json::ok(report)))
+}
+
+/// Runs the database maintenance job now instead of waiting for the next large scrape.
+#[actix_web::post("/admin/maintenance")]
+async fn run_maintenance(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    log::info!("{} started database maintenance", user_id);
+    let db = data.data.clone();
+    let run = web::block(move || maintenance::run(&db, &maintenance::MaintenanceSettings::from_env())).await?;
+    Ok(HttpResponse::Ok().json(json::ok(run)))
+}
+
+/// Orphaned player images the daily garbage collection would delete now.
+#[actix_web::get("/admin/images/gc")]
+async fn get_image_gc_report(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let db = data.data.clone();
+    match web::block(move || maintenance::collect_image_garbage(&db, true)).await? {
+        Ok(report) => Ok(HttpResponse::Ok().json(json::ok(report))),
+        Err(err) => Ok(HttpResponse::InternalServerError().json(json::err(err))),
+    }
+}
+
+/// Deletes orphaned player images now instead of waiting for the daily run.
+#[actix_web::post("/admin/images/gc")]
+async fn run_image_gc(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    let db = data.data.clone();
+    match web::block(move || maintenance::collect_image_garbage(&db, false)).await? {
+        Ok(report) => {
+            log::info!("{} deleted orphaned images: {:?}", user_id, report.orphaned);
+            Ok(HttpResponse::Ok().json(json::ok(report)))
+        }
+        Err(err) => Ok(HttpResponse::InternalServerError().json(json::err(err))),
+    }
+}
+
+#[derive(Deserialize)]
+struct BenchParams {
+    iterations: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct BenchInfo {
+    requests: Vec<String>,
+}
+
+/// Replays the recently served GET requests, or the `requests` of the JSON body, in-process without
+/// the middlewares, i.e. anonymously, and reports the latencies per endpoint. Replayed requests don't count for
+/// `/admin/popular`.
+#[actix_web::post("/admin/bench")]
+async fn run_bench(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<BenchParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    const MAX_REQUESTS: usize = 10000;
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    let requests = if body.is_empty() {
+        bench::recorded_requests()
+    } else {
+        match serde_json::from_slice::<BenchInfo>(&body) {
+            Ok(info) => info.requests,
+            Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err.to_string()))),
+        }
+    };
+    if requests.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(json::err("no requests to replay")));
+    }
+    let iterations = params.iterations.unwrap_or(1).max(1);
+    if requests
+        .len()
+        .checked_mul(iterations)
+        .is_none_or(|total| total > MAX_REQUESTS)
+    {
+        return Ok(HttpResponse::BadRequest().json(json::err(format!("more than {} requests", MAX_REQUESTS))));
+    }
+
+    let app = App::new()
+        .app_data(data.clone())
+        .app_data(bench::Replay)
+        .configure(super::configure);
+    let app = match app.into_factory().new_service(AppConfig::default()).await {
+        Ok(app) => app,
+        Err(()) => return Ok(HttpResponse::InternalServerError().json(json::err("failed to set up the routes"))),
+    };
+    let report = bench::replay(&app, &requests, iterations).await;
+    Ok(HttpResponse::Ok().json(json::ok(report)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_popular_players)
+        .service(get_ranking_coverage)
+        .service(get_features)
+        .service(set_feature)
+        .service(get_pending_actions)
+        .service(undo_action)
+        .service(get_request_usage)
+        .service(get_parse_quality)
+        .service(get_retention_report)
+        .service(run_retention)
+        .service(run_maintenance)
+        .service(get_image_gc_report)
+        .service(run_image_gc)
+        .service(run_bench);
+}
@@ -0,0 +1,134 @@
+//! The club directory.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data;
+use playerdb_core::data::clubs;
+
+use super::players::PlayerData;
+use super::{require_user, AppState};
+use crate::{auth, json, labels};
+
+#[derive(serde::Serialize)]
+struct ClubJson {
+    name: String,
+    #[serde(flatten)]
+    club: clubs::Club,
+}
+
+#[actix_web::get("/clubs")]
+async fn get_clubs(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let clubs: Vec<ClubJson> = data
+        .data
+        .get_clubs()
+        .into_iter()
+        .map(|(name, club)| ClubJson { name, club })
+        .collect();
+    Ok(HttpResponse::Ok().json(json::ok(clubs)))
+}
+
+/// A club with the players of its teams in the latest DTFB league season it played in.
+#[actix_web::get("/clubs/{name}")]
+async fn get_club(req: HttpRequest, data: web::Data<AppState>, name: web::Path<String>) -> Result<HttpResponse, Error> {
+    #[derive(serde::Serialize)]
+    struct ClubDetailsJson {
+        #[serde(flatten)]
+        club: ClubJson,
+        /// Season start year of the league players, `null` if no team of the club was downloaded.
+        league_year: Option<i32>,
+        players: Vec<PlayerData>,
+    }
+
+    let name = name.into_inner();
+    let club = match data.data.get_club(&name) {
+        Some(club) => club,
+        None => return Ok(HttpResponse::NotFound().json(json::err("No such club"))),
+    };
+
+    let include_hidden = auth::is_authenticated(&req);
+    let name_style = labels::NameStyle::from_request(&req);
+    let team_players: Vec<(i32, data::Player)> = data.data.aggregate_players(|players| {
+        players
+            .filter(|player| include_hidden || !player.hidden)
+            .flat_map(|player| {
+                player
+                    .dtfb_league_teams
+                    .iter()
+                    .filter(|team| team.name == name)
+                    .map(|team| (team.year, player.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    });
+    let league_year = team_players.iter().map(|(year, _)| *year).max();
+    let mut players: Vec<PlayerData> = team_players
+        .into_iter()
+        .filter(|(year, _)| Some(*year) == league_year)
+        .map(|(_, player)| PlayerData::new(player, name_style))
+        .collect();
+    players.sort_by_key(|player| player.itsf_lic);
+    players.dedup_by_key(|player| player.itsf_lic);
+
+    Ok(HttpResponse::Ok().json(json::ok(ClubDetailsJson {
+        club: ClubJson { name, club },
+        league_year,
+        players,
+    })))
+}
+
+/// Creates or replaces the directory data of a club.
+#[actix_web::post("/clubs/{name}")]
+async fn set_club(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+    club: web::Json<clubs::Club>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    if !clubs::is_valid_name(&name) {
+        return Ok(HttpResponse::BadRequest().json(json::err("invalid club name")));
+    }
+    let mut club = club.into_inner();
+    if let Err(err) = club.validate() {
+        return Ok(HttpResponse::BadRequest().json(json::err(err)));
+    }
+    club.updated_at = chrono::Utc::now().timestamp();
+    log::info!("{} saved club {}: {:?}", user_id, name, club);
+    data.data.set_club(&name, club.clone());
+    Ok(HttpResponse::Ok().json(json::ok(ClubJson {
+        name: name.into_inner(),
+        club,
+    })))
+}
+
+#[actix_web::delete("/clubs/{name}")]
+async fn delete_club(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    if !data.data.delete_club(&name) {
+        return Ok(HttpResponse::NotFound().json(json::err("No such club")));
+    }
+    log::info!("{} deleted club {}", user_id, name);
+    Ok(HttpResponse::Ok().json(json::ok("Deleted club")))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_clubs)
+        .service(get_club)
+        .service(set_club)
+        .service(delete_club);
+}
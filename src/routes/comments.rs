@@ -0,0 +1,161 @@
+//! Player comments.
+
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use futures_util::StreamExt;
+use playerdb_core::data::license::LicenseNumber;
+use playerdb_core::{data, import};
+use serde::Deserialize;
+
+use super::players::CommentJson;
+use super::{require_user, AppState};
+use crate::{auth, json};
+
+#[derive(Deserialize)]
+struct AddCommentInfo {
+    itsf_lic: LicenseNumber,
+    comment: String,
+    #[serde(default)]
+    visibility: data::CommentVisibility,
+    /// `open` to track the comment until it is resolved.
+    status: Option<data::CommentStatus>,
+}
+
+#[actix_web::post("/add_comment")]
+async fn add_player_comment(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<AddCommentInfo>,
+) -> Result<HttpResponse, Error> {
+    data.data.add_player_comment(
+        auth::workspace(&req).as_deref(),
+        info.itsf_lic.get(),
+        info.comment.clone(),
+        info.visibility,
+        info.status,
+    );
+    Ok(HttpResponse::Ok().json(json::ok("added comment")))
+}
+
+/// A comment is identified by its timestamp and text, as in the player's `comments`.
+#[derive(Deserialize)]
+struct CommentStatusInfo {
+    itsf_lic: LicenseNumber,
+    timestamp: u32,
+    text: String,
+    /// `open` or `resolved`, `null` to stop tracking the comment.
+    status: Option<data::CommentStatus>,
+}
+
+#[actix_web::post("/comment_status")]
+async fn set_comment_status(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<CommentStatusInfo>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    let updated = data.data.update_player_comment(
+        auth::workspace(&req).as_deref(),
+        info.itsf_lic.get(),
+        info.timestamp,
+        &info.text,
+        |comment| comment.set_status(info.status, &user_id),
+    );
+    match updated {
+        Ok(comment) => Ok(HttpResponse::Ok().json(json::ok(CommentJson::new(&req, &data, comment)))),
+        Err(err) => Ok(HttpResponse::NotFound().json(json::err(err))),
+    }
+}
+
+#[derive(Deserialize)]
+struct CommentReactionInfo {
+    itsf_lic: LicenseNumber,
+    timestamp: u32,
+    text: String,
+    /// `false` takes the thumbs-up back.
+    thumbs_up: bool,
+}
+
+#[actix_web::post("/comment_reaction")]
+async fn react_to_comment(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<CommentReactionInfo>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    let updated = data.data.update_player_comment(
+        auth::workspace(&req).as_deref(),
+        info.itsf_lic.get(),
+        info.timestamp,
+        &info.text,
+        |comment| comment.set_thumbs_up(&user_id, info.thumbs_up),
+    );
+    match updated {
+        Ok(comment) => Ok(HttpResponse::Ok().json(json::ok(CommentJson::new(&req, &data, comment)))),
+        Err(err) => Ok(HttpResponse::NotFound().json(json::err(err))),
+    }
+}
+
+#[derive(Deserialize)]
+struct ImportParams {
+    /// Only validate the rows.
+    dry_run: Option<bool>,
+}
+
+/// Imports comments from a JSON array, or from CSV if sent as `text/csv`, reporting invalid rows.
+#[actix_web::post("/import/comments")]
+async fn import_comments(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<ImportParams>,
+    mut payload: web::Payload,
+) -> Result<HttpResponse, Error> {
+    // spreadsheets can easily exceed the default payload limit
+    const MAX_IMPORT_SIZE: usize = 16 * 1024 * 1024;
+
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > MAX_IMPORT_SIZE {
+            return Ok(HttpResponse::PayloadTooLarge().json(json::err("import too large")));
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let rows = if req.content_type() == "text/csv" {
+        match std::str::from_utf8(&body) {
+            Ok(text) => import::rows_from_csv(text),
+            Err(_) => Err(String::from("CSV is not valid UTF-8")),
+        }
+    } else {
+        import::rows_from_json(&body)
+    };
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+    };
+
+    let report = import::import_comments(
+        &data.data,
+        auth::workspace(&req).as_deref(),
+        rows,
+        params.dry_run == Some(true),
+    );
+    Ok(HttpResponse::Ok().json(json::ok(report)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(add_player_comment)
+        .service(set_comment_status)
+        .service(react_to_comment)
+        .service(import_comments);
+}
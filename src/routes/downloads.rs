@@ -0,0 +1,737 @@
+//! Starting download jobs, their status and presets.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::{
+    itsf, presets,
+    season::{self, Season},
+};
+use playerdb_core::{coverage, data, freshness, scraping, stats};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::{require_user, AppState};
+use crate::{auth, json};
+
+#[derive(Deserialize)]
+struct RefreshPriorityParams {
+    /// Number of players, the ones the daily refresh downloads by default.
+    limit: Option<usize>,
+}
+
+#[derive(serde::Serialize)]
+struct RefreshPriority {
+    settings: freshness::RefreshSettings,
+    players: Vec<freshness::RefreshCandidate>,
+}
+
+/// The players the daily refresh downloads next, highest priority first.
+#[actix_web::get("/admin/refresh_priority")]
+async fn get_refresh_priority(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<RefreshPriorityParams>,
+) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let settings = freshness::RefreshSettings::from_env();
+    let limit = params.limit.unwrap_or(settings.players_per_run()).min(1000);
+    Ok(HttpResponse::Ok().json(json::ok(RefreshPriority {
+        settings,
+        players: freshness::ranked(&data.data, limit),
+    })))
+}
+
+/// Downloads the players with the highest priority now instead of waiting for the daily refresh.
+#[actix_web::post("/admin/refresh_priority")]
+async fn run_priority_refresh(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<RefreshPriorityParams>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    let settings = freshness::RefreshSettings::from_env();
+    let limit = params
+        .limit
+        .unwrap_or(settings.players_per_run())
+        .min(settings.players_per_run());
+    let itsf_ids: Vec<i32> = freshness::ranked(&data.data, limit)
+        .into_iter()
+        .map(|candidate| candidate.itsf_lic)
+        .collect();
+    log::info!("{} started a refresh of {} players", user_id, itsf_ids.len());
+    AppState::start_download(&data, scraping::SourceHost::Itsf, |lock| {
+        scraping::start_player_refresh(data.data.clone(), itsf_ids, lock)
+    })
+    .await
+}
+
+#[derive(serde::Serialize)]
+struct DownloadStatus {
+    running: bool,
+    /// Hosts scraped by running jobs.
+    sources: Vec<scraping::SourceHost>,
+    log: Vec<String>,
+}
+
+async fn get_download_status(data: &web::Data<AppState>) -> Result<DownloadStatus, Error> {
+    let mut status = DownloadStatus {
+        running: false,
+        sources: Vec::new(),
+        log: Vec::new(),
+    };
+    for host in scraping::SourceHost::ALL {
+        let log: Option<Vec<String>> = match AppState::get_download(data, host)? {
+            Some(download) => Some(download.get_log()),
+            None => match data.job_lock.running_job_log(host.lock_name()).await {
+                Ok(log) => log,
+                Err(err) => {
+                    log::error!("failed to query download lock: {}", err);
+                    None
+                }
+            },
+        };
+        if let Some(log) = log {
+            status.running = true;
+            status.sources.push(host);
+            // a DTFB job publishes its log with the ITSF lock while it scrapes ITSF profiles
+            if log.is_empty() || !status.log.ends_with(&log) {
+                status.log.extend(log);
+            }
+        }
+    }
+    Ok(status)
+}
+
+#[actix_web::get("/download_status")]
+async fn download_status(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let status = get_download_status(&data).await?;
+    Ok(HttpResponse::Ok().json(json::ok(status)))
+}
+
+/// Number of finished job runs listed by `/jobs`.
+const JOB_HISTORY_LENGTH: usize = 20;
+
+#[derive(serde::Serialize)]
+struct JobsStatus {
+    download: DownloadStatus,
+    leaderboards: stats::RefreshStatus,
+    /// Newest first.
+    history: Vec<data::jobs::JobRun>,
+}
+
+#[actix_web::get("/jobs")]
+async fn jobs_status(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let status = JobsStatus {
+        download: get_download_status(&data).await?,
+        leaderboards: stats::refresh_status(),
+        history: data.data.get_job_runs(JOB_HISTORY_LENGTH),
+    };
+    Ok(HttpResponse::Ok().json(json::ok(status)))
+}
+
+/// Places downloaded per ranking unless `max_rank` is passed.
+const DEFAULT_MAX_RANK: usize = 1000;
+
+/// A season the download endpoints accept.
+#[derive(serde::Serialize)]
+struct SeasonOption {
+    /// The `season` parameter, e.g. `2022/23`.
+    season: String,
+    year: i32,
+    /// Stored entries, 0 if nothing of the season was downloaded yet.
+    entries: usize,
+    scraped_at: Option<i64>,
+}
+
+fn season_options(seasons: Vec<Season>, stored: &[coverage::DataYear]) -> Vec<SeasonOption> {
+    seasons
+        .into_iter()
+        .map(|season| {
+            let stored = stored.iter().find(|data_year| data_year.year == season.year());
+            SeasonOption {
+                season: season.to_string(),
+                year: season.year(),
+                entries: stored.map_or(0, |data_year| data_year.entries),
+                scraped_at: stored.and_then(|data_year| data_year.scraped_at),
+            }
+        })
+        .collect()
+}
+
+/// Valid parameters of the download endpoints, for the forms of the admin UI.
+#[derive(serde::Serialize)]
+struct DownloadOptions {
+    /// Oldest first.
+    itsf_seasons: Vec<SeasonOption>,
+    dtfb_seasons: Vec<SeasonOption>,
+    categories: Vec<itsf::RankingCategory>,
+    classes: Vec<itsf::RankingClass>,
+    /// `max_rank` if none is passed.
+    default_max_rank: usize,
+    presets: Vec<PresetJson>,
+    /// Whether a job is running, another one of the same source can't be started until it finished.
+    running: bool,
+    sources: Vec<scraping::SourceHost>,
+    /// Names of the scrapers `/download_source/{source}` runs.
+    scrapers: Vec<&'static str>,
+}
+
+#[actix_web::get("/admin/download_options")]
+async fn get_download_options(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let stored = coverage::stored_years(&data.data);
+    let status = get_download_status(&data).await?;
+    let options = DownloadOptions {
+        itsf_seasons: season_options(Season::all_itsf(), &stored.itsf),
+        dtfb_seasons: season_options(Season::all_dtfb(), &stored.dtfb),
+        categories: itsf::RankingCategory::ALL.to_vec(),
+        classes: itsf::RankingClass::ALL.to_vec(),
+        default_max_rank: DEFAULT_MAX_RANK,
+        presets: data
+            .data
+            .get_download_presets()
+            .into_iter()
+            .map(|(name, preset)| PresetJson { name, preset })
+            .collect(),
+        running: status.running,
+        sources: status.sources,
+        scrapers: scraping::pipeline::registry().keys().copied().collect(),
+    };
+    Ok(HttpResponse::Ok().json(json::ok(options)))
+}
+
+#[derive(Deserialize)]
+pub struct RankingSelectionParams {
+    /// Comma separated, e.g. `women,junior`, all categories if missing.
+    categories: Option<String>,
+    /// Comma separated, e.g. `doubles`, all classes if missing.
+    classes: Option<String>,
+}
+
+/// Parses a comma separated list of codes, all values if the list is missing.
+pub fn parse_selection<T: Copy + PartialEq>(
+    list: &Option<String>,
+    all: &[T],
+    parse: fn(&str) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    let list = match list {
+        Some(list) => list,
+        None => return Ok(all.to_vec()),
+    };
+    let mut selected = Vec::new();
+    for value in list.split(',').map(str::trim).filter(|value| !value.is_empty()) {
+        let value = parse(value)?;
+        if !selected.contains(&value) {
+            selected.push(value);
+        }
+    }
+    if selected.is_empty() {
+        return Err(String::from("empty selection"));
+    }
+    Ok(selected)
+}
+
+impl RankingSelectionParams {
+    /// The selected categories and classes of the seasons.
+    pub fn rankings(
+        &self,
+        seasons: &[Season],
+    ) -> Result<Vec<(Season, itsf::RankingCategory, itsf::RankingClass)>, String> {
+        let categories = parse_selection(
+            &self.categories,
+            &itsf::RankingCategory::ALL,
+            itsf::RankingCategory::try_from_str,
+        )?;
+        let classes = parse_selection(
+            &self.classes,
+            &itsf::RankingClass::ALL,
+            itsf::RankingClass::try_from_str,
+        )?;
+        let mut rankings = Vec::new();
+        for season in seasons {
+            for category in &categories {
+                for class in &classes {
+                    rankings.push((*season, *category, *class));
+                }
+            }
+        }
+        Ok(rankings)
+    }
+}
+
+pub async fn download_itsf(
+    data: web::Data<AppState>,
+    rankings: Vec<(Season, itsf::RankingCategory, itsf::RankingClass)>,
+    max_ranks: scraping::MaxRanks,
+    force: bool,
+    force_closed: bool,
+) -> Result<HttpResponse, Error> {
+    AppState::start_download(&data, scraping::SourceHost::Itsf, |lock| {
+        scraping::start_itsf_rankings_download(data.data.clone(), rankings, max_ranks, force, force_closed, lock)
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct DownloadParams {
+    /// Either a calendar year or a DTFB season like `2022/23`, `year` is still accepted for the former.
+    season: Option<String>,
+    year: Option<String>,
+    max_rank: Option<usize>,
+    force: Option<String>,
+    /// ITSF only: also replace closed rankings.
+    force_closed: Option<bool>,
+}
+
+impl DownloadParams {
+    /// The requested season if it is available, the latest one if none was requested.
+    fn parse_season(&self, parse: fn(&str) -> Result<Season, String>, latest: Season) -> Option<Season> {
+        match self.season.as_ref().or(self.year.as_ref()) {
+            Some(season) => parse(season).ok().filter(|season| season.is_available()),
+            None => Some(latest),
+        }
+    }
+
+    fn parse_force(&self) -> bool {
+        match &self.force {
+            Some(force_str) => force_str == "true",
+            None => false,
+        }
+    }
+}
+
+/// Optional JSON body of the ITSF download endpoints, e.g. `{"max_rank_per_category": {"open": 2000, "junior": 500}}`.
+#[derive(Deserialize, Default)]
+struct ItsfDownloadInfo {
+    /// Overrides the `max_rank` parameter.
+    max_rank: Option<usize>,
+    #[serde(default)]
+    max_rank_per_category: HashMap<itsf::RankingCategory, usize>,
+}
+
+impl ItsfDownloadInfo {
+    /// Parses the body if there is one.
+    fn parse(body: &web::Bytes) -> Result<Self, HttpResponse> {
+        if body.is_empty() {
+            return Ok(Self::default());
+        }
+        serde_json::from_slice(body).map_err(|err| HttpResponse::BadRequest().json(json::err(err.to_string())))
+    }
+
+    fn max_ranks(self, max_rank: Option<usize>) -> scraping::MaxRanks {
+        scraping::MaxRanks {
+            default: self.max_rank.or(max_rank).unwrap_or(DEFAULT_MAX_RANK),
+            per_category: self.max_rank_per_category,
+        }
+    }
+}
+
+#[actix_web::post("/download_itsf")]
+async fn download_itsf_single(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<DownloadParams>,
+    selection: web::Query<RankingSelectionParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let force = params.parse_force();
+    let force_closed = params.force_closed == Some(true);
+    if force_closed {
+        if let Err(response) = auth::require_shared_access(&req) {
+            return Ok(response);
+        }
+    }
+    let max_ranks = match ItsfDownloadInfo::parse(&body) {
+        Ok(info) => info.max_ranks(params.max_rank),
+        Err(response) => return Ok(response),
+    };
+    let latest = *Season::all_itsf().last().unwrap();
+    let season = match params.parse_season(Season::parse_itsf, latest) {
+        Some(season) => season,
+        None => return Ok(HttpResponse::BadRequest().json(json::err("invalid season"))),
+    };
+    match selection.rankings(&[season]) {
+        Ok(rankings) => download_itsf(data, rankings, max_ranks, force, force_closed).await,
+        Err(err) => Ok(HttpResponse::BadRequest().json(json::err(err))),
+    }
+}
+
+#[derive(Deserialize)]
+struct DownloadMissingParams {
+    max_rank: Option<usize>,
+    /// Incomplete rankings downloaded longer ago than this are downloaded again.
+    max_age_days: Option<i64>,
+    /// Only list the rankings that would be downloaded.
+    dry_run: Option<bool>,
+}
+
+#[actix_web::post("/download_missing")]
+async fn download_missing_itsf(
+    data: web::Data<AppState>,
+    params: web::Query<DownloadMissingParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let max_ranks = match ItsfDownloadInfo::parse(&body) {
+        Ok(info) => info.max_ranks(params.max_rank),
+        Err(response) => return Ok(response),
+    };
+    let max_age = params.max_age_days.unwrap_or(7) * 24 * 60 * 60;
+    let now = chrono::Utc::now().timestamp();
+    let missing: Vec<coverage::RankingCoverage> = coverage::ranking_coverage(&data.data)
+        .into_iter()
+        .filter(|ranking| ranking.needs_download(now, max_age))
+        .collect();
+
+    if params.dry_run == Some(true) || missing.is_empty() {
+        return Ok(HttpResponse::Ok().json(json::ok(missing)));
+    }
+
+    let rankings = missing
+        .iter()
+        .map(|ranking| (Season::Itsf(ranking.year), ranking.category, ranking.class))
+        .collect();
+    let response = download_itsf(data, rankings, max_ranks, false, false).await?;
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+    Ok(HttpResponse::Ok().json(json::ok(missing)))
+}
+
+#[actix_web::post("/download_itsf_all")]
+async fn download_all_itsf(
+    data: web::Data<AppState>,
+    selection: web::Query<RankingSelectionParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let max_ranks = match ItsfDownloadInfo::parse(&body) {
+        Ok(info) => info.max_ranks(None),
+        Err(response) => return Ok(response),
+    };
+    match selection.rankings(&Season::all_itsf()) {
+        Ok(rankings) => download_itsf(data, rankings, max_ranks, false, false).await,
+        Err(err) => Ok(HttpResponse::BadRequest().json(json::err(err))),
+    }
+}
+
+async fn download_dtfb(
+    data: web::Data<AppState>,
+    seasons: Vec<Season>,
+    max_rank: usize,
+    force: bool,
+) -> Result<HttpResponse, Error> {
+    AppState::start_download(&data, scraping::SourceHost::Dtfb, |lock| {
+        let job_lock = data.job_lock.clone();
+        scraping::start_dtfb_rankings_download(data.data.clone(), seasons, max_rank, force, lock, job_lock)
+    })
+    .await
+}
+
+#[actix_web::post("/download_dtfb")]
+async fn download_dtfb_single(
+    data: web::Data<AppState>,
+    params: web::Query<DownloadParams>,
+) -> Result<HttpResponse, Error> {
+    let max_rank = params.max_rank.unwrap_or(DEFAULT_MAX_RANK);
+    let force = params.parse_force();
+    let latest = *Season::all_dtfb().last().unwrap();
+    match params.parse_season(Season::parse_dtfb, latest) {
+        Some(season) => download_dtfb(data, vec![season], max_rank, force).await,
+        None => Ok(HttpResponse::BadRequest().json(json::err("invalid season"))),
+    }
+}
+
+#[actix_web::post("/download_dtfb_all")]
+async fn download_dtfb_all(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let max_rank = DEFAULT_MAX_RANK;
+    download_dtfb(data, Season::all_dtfb(), max_rank, false).await
+}
+
+#[derive(Deserialize)]
+struct EventDownloadParams {
+    year: Option<i32>,
+}
+
+/// Downloads the ITSF calendar of the year, by default of the current and the next year.
+#[actix_web::post("/download_events")]
+async fn download_events(
+    data: web::Data<AppState>,
+    params: web::Query<EventDownloadParams>,
+) -> Result<HttpResponse, Error> {
+    use chrono::Datelike;
+    let current_year = chrono::Utc::now().year();
+    let years = match params.year {
+        Some(year) if (season::FIRST_YEAR..=current_year + 1).contains(&year) => vec![year],
+        Some(_) => return Ok(HttpResponse::BadRequest().json(json::err("invalid year"))),
+        None => vec![current_year, current_year + 1],
+    };
+    download_event_years(data, years).await
+}
+
+async fn download_event_years(data: web::Data<AppState>, years: Vec<i32>) -> Result<HttpResponse, Error> {
+    AppState::start_download(&data, scraping::SourceHost::Itsf, |lock| {
+        scraping::start_itsf_events_download(data.data.clone(), years, lock)
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct SourceDownloadParams {
+    /// In the format of the scraper, e.g. `2023` for the calendar of `itsf_events`.
+    requests: Vec<String>,
+}
+
+/// Runs a registered scraper on the requests, see `scrapers` in `/admin/download_options` for their names.
+#[actix_web::post("/download_source/{source}")]
+async fn download_source(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    params: web::Json<SourceDownloadParams>,
+) -> Result<HttpResponse, Error> {
+    let (source, scraper) = match scraping::pipeline::registry().get_key_value(path.as_str()) {
+        Some(registered) => registered,
+        None => return Ok(HttpResponse::NotFound().json(json::err("unknown source"))),
+    };
+    let requests = params.into_inner().requests;
+    if requests.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(json::err("no requests")));
+    }
+    if let Err(err) = requests.iter().try_for_each(|request| scraper.validate(request)) {
+        return Ok(HttpResponse::BadRequest().json(json::err(err)));
+    }
+    AppState::start_download(&data, scraper.host(), |lock| {
+        scraping::start_source_download(data.data.clone(), source, requests, lock)
+    })
+    .await
+}
+
+/// What a download preset starts.
+enum PresetDownload {
+    Itsf(
+        Vec<(Season, itsf::RankingCategory, itsf::RankingClass)>,
+        scraping::MaxRanks,
+        bool,
+    ),
+    Dtfb(Vec<Season>, usize, bool),
+    Events(Vec<i32>),
+}
+
+/// The seasons of a preset, the latest one if none are given.
+fn preset_seasons(
+    seasons: &[String],
+    all: Vec<Season>,
+    parse: fn(&str) -> Result<Season, String>,
+) -> Result<Vec<Season>, String> {
+    let latest = *all.last().ok_or("no seasons available")?;
+    if seasons.is_empty() {
+        return Ok(vec![latest]);
+    }
+    let mut selected = Vec::new();
+    for season in seasons {
+        let seasons = match season.as_str() {
+            "latest" => vec![latest],
+            "all" => all.clone(),
+            season => vec![parse(season)
+                .ok()
+                .filter(|season| season.is_available())
+                .ok_or(format!("invalid season: '{}'", season))?],
+        };
+        for season in seasons {
+            if !selected.contains(&season) {
+                selected.push(season);
+            }
+        }
+    }
+    Ok(selected)
+}
+
+/// Checks the preset and turns it into the download it stands for.
+fn resolve_preset(preset: &presets::DownloadPreset) -> Result<PresetDownload, String> {
+    match preset.source {
+        presets::PresetSource::Itsf => {
+            let seasons = preset_seasons(&preset.seasons, Season::all_itsf(), Season::parse_itsf)?;
+            let categories = match preset.categories.is_empty() {
+                true => itsf::RankingCategory::ALL.to_vec(),
+                false => preset.categories.clone(),
+            };
+            let classes = match preset.classes.is_empty() {
+                true => itsf::RankingClass::ALL.to_vec(),
+                false => preset.classes.clone(),
+            };
+            let mut rankings = Vec::new();
+            for season in seasons {
+                for category in &categories {
+                    for class in &classes {
+                        rankings.push((season, *category, *class));
+                    }
+                }
+            }
+            let max_ranks = scraping::MaxRanks {
+                default: preset.max_rank.unwrap_or(DEFAULT_MAX_RANK),
+                per_category: preset.max_rank_per_category.clone(),
+            };
+            Ok(PresetDownload::Itsf(rankings, max_ranks, preset.force))
+        }
+        presets::PresetSource::Dtfb => {
+            let seasons = preset_seasons(&preset.seasons, Season::all_dtfb(), Season::parse_dtfb)?;
+            Ok(PresetDownload::Dtfb(
+                seasons,
+                preset.max_rank.unwrap_or(DEFAULT_MAX_RANK),
+                preset.force,
+            ))
+        }
+        presets::PresetSource::Events => {
+            use chrono::Datelike;
+            let current_year = chrono::Utc::now().year();
+            let mut years = Vec::new();
+            for year in &preset.seasons {
+                match year.as_str() {
+                    "latest" => years.extend([current_year, current_year + 1]),
+                    year => years.push(
+                        year.parse::<i32>()
+                            .ok()
+                            .filter(|year| (season::FIRST_YEAR..=current_year + 1).contains(year))
+                            .ok_or(format!("invalid year: '{}'", year))?,
+                    ),
+                }
+            }
+            if years.is_empty() {
+                years = vec![current_year, current_year + 1];
+            }
+            years.sort();
+            years.dedup();
+            Ok(PresetDownload::Events(years))
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct PresetJson {
+    name: String,
+    #[serde(flatten)]
+    preset: presets::DownloadPreset,
+}
+
+#[actix_web::get("/presets")]
+async fn get_download_presets(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let presets: Vec<PresetJson> = data
+        .data
+        .get_download_presets()
+        .into_iter()
+        .map(|(name, preset)| PresetJson { name, preset })
+        .collect();
+    Ok(HttpResponse::Ok().json(json::ok(presets)))
+}
+
+/// Creates or replaces a download preset.
+#[actix_web::post("/presets/{name}")]
+async fn set_download_preset(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+    preset: web::Json<presets::DownloadPreset>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    if !presets::is_valid_name(&name) {
+        return Ok(HttpResponse::BadRequest().json(json::err("invalid preset name")));
+    }
+    let preset = preset.into_inner();
+    if let Err(err) = resolve_preset(&preset) {
+        return Ok(HttpResponse::BadRequest().json(json::err(err)));
+    }
+    log::info!("{} saved download preset {}: {:?}", user_id, name, preset);
+    data.data.set_download_preset(&name, preset.clone());
+    Ok(HttpResponse::Ok().json(json::ok(PresetJson {
+        name: name.into_inner(),
+        preset,
+    })))
+}
+
+#[actix_web::delete("/presets/{name}")]
+async fn delete_download_preset(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    if !data.data.delete_download_preset(&name) {
+        return Ok(HttpResponse::NotFound().json(json::err("No such preset")));
+    }
+    log::info!("{} deleted download preset {}", user_id, name);
+    Ok(HttpResponse::Ok().json(json::ok("Deleted preset")))
+}
+
+/// Starts the download stored as the named preset.
+#[actix_web::post("/download_preset/{name}")]
+async fn download_preset(data: web::Data<AppState>, name: web::Path<String>) -> Result<HttpResponse, Error> {
+    let preset = match data.data.get_download_preset(&name) {
+        Some(preset) => preset,
+        None => return Ok(HttpResponse::NotFound().json(json::err("No such preset"))),
+    };
+    match resolve_preset(&preset) {
+        Ok(PresetDownload::Itsf(rankings, max_ranks, force)) => {
+            download_itsf(data, rankings, max_ranks, force, false).await
+        }
+        Ok(PresetDownload::Dtfb(seasons, max_rank, force)) => download_dtfb(data, seasons, max_rank, force).await,
+        Ok(PresetDownload::Events(years)) => download_event_years(data, years).await,
+        // e.g. a season that is no longer available
+        Err(err) => Ok(HttpResponse::BadRequest().json(json::err(err))),
+    }
+}
+
+/// Counts the requests the download described like a preset would send and how long the budget lets them take,
+/// without downloading anything.
+#[actix_web::post("/download_estimate")]
+async fn download_estimate(
+    data: web::Data<AppState>,
+    preset: web::Json<presets::DownloadPreset>,
+) -> Result<HttpResponse, Error> {
+    let estimate = match resolve_preset(&preset) {
+        Ok(PresetDownload::Itsf(rankings, max_ranks, force)) => {
+            scraping::estimate::itsf_rankings(&data.data, &rankings, &max_ranks, force, false)
+        }
+        Ok(PresetDownload::Dtfb(seasons, max_rank, force)) => {
+            scraping::estimate::dtfb_rankings(&data.data, &seasons, max_rank, force)
+        }
+        Ok(PresetDownload::Events(years)) => scraping::estimate::itsf_events(&data.data, &years),
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+    };
+    Ok(HttpResponse::Ok().json(json::ok(estimate)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_refresh_priority)
+        .service(run_priority_refresh)
+        .service(download_status)
+        .service(jobs_status)
+        .service(get_download_options)
+        .service(download_itsf_single)
+        .service(download_missing_itsf)
+        .service(download_all_itsf)
+        .service(download_dtfb_single)
+        .service(download_dtfb_all)
+        .service(download_events)
+        .service(download_source)
+        .service(get_download_presets)
+        .service(set_download_preset)
+        .service(delete_download_preset)
+        .service(download_preset)
+        .service(download_estimate);
+}
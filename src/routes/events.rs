@@ -0,0 +1,66 @@
+//! The tournament calendar.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::{geo, ics};
+use serde::Deserialize;
+
+use super::{parse_date_param, AppState};
+use crate::json;
+
+#[derive(Deserialize)]
+struct EventsParams {
+    /// `YYYY-MM-DD`, events ending before are left out, today if missing.
+    from: Option<String>,
+    /// `YYYY-MM-DD`, events starting after are left out.
+    to: Option<String>,
+    /// `lat,lon`, only geocoded events within `radius` are returned.
+    near: Option<String>,
+    /// In kilometers around `near`, 100 if missing.
+    radius: Option<f64>,
+}
+
+#[actix_web::get("/events")]
+async fn get_events(data: web::Data<AppState>, params: web::Query<EventsParams>) -> Result<HttpResponse, Error> {
+    let (from, to) = match (
+        parse_date_param("from", &params.from),
+        parse_date_param("to", &params.to),
+    ) {
+        (Ok(from), Ok(to)) => (from.unwrap_or(chrono::Utc::now().date_naive()), to),
+        (Err(response), _) | (_, Err(response)) => return Ok(response),
+    };
+    let mut events = data.data.get_events_between(Some(from), to);
+    if let Some(near) = &params.near {
+        let near = match geo::Coordinates::parse(near) {
+            Ok(near) => near,
+            Err(_) => return Ok(HttpResponse::BadRequest().json(json::err("invalid near"))),
+        };
+        let radius = params.radius.unwrap_or(100.0);
+        events.retain(|event| {
+            event
+                .coordinates
+                .is_some_and(|coordinates| coordinates.distance_km(&near) <= radius)
+        });
+    }
+    Ok(HttpResponse::Ok().json(json::ok(events)))
+}
+
+#[actix_web::get("/tournaments.ics")]
+async fn get_tournaments_ics(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let today = chrono::Utc::now().date_naive();
+    let events = data.data.get_events_from(today);
+    let host = req
+        .connection_info()
+        .host()
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    Ok(HttpResponse::Ok()
+        .append_header(("Content-Type", "text/calendar; charset=utf-8"))
+        .body(ics::calendar(&events, &host)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_events).service(get_tournaments_ics);
+}
@@ -0,0 +1,146 @@
+//! Downloads of the database, player cards, QR codes and offline bundles.
+
+use actix_web::http::header::ContentType;
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::license::LicenseNumber;
+use playerdb_core::export;
+use serde::Deserialize;
+
+use super::images::player_image_path;
+use super::{base_url, get_visible_player, parse_license, require_user, AppState};
+use crate::{auth, json, labels};
+
+/// The raw database with its internal comments and the notes of every workspace, so only for logged-in users
+/// outside of workspaces.
+#[actix_web::get("/db.zip")]
+async fn download_db_zip(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req).and_then(|_| auth::require_shared_access(&req)) {
+        return Ok(response);
+    }
+    match data.data.create_zip_file(data.image_access) {
+        Ok(data) => Ok(HttpResponse::Ok().content_type(ContentType::octet_stream()).body(data)),
+        Err(err) => {
+            log::error!("failed to create db.zip: {}", err);
+            Ok(HttpResponse::InternalServerError().json(json::err("error")))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CardParams {
+    format: Option<String>,
+    /// Language of the category label, `en` (default) or `de`.
+    lang: Option<String>,
+}
+
+#[actix_web::get("/player/{itsf_lic}/card")]
+async fn get_player_card(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    itsf_lic: web::Path<String>,
+    params: web::Query<CardParams>,
+) -> Result<HttpResponse, Error> {
+    let itsf_lic = match parse_license(&itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+
+    let player = match get_visible_player(&req, &data, itsf_lic) {
+        Some(player) => player,
+        None => return Ok(HttpResponse::NotFound().json(json::err("No such player"))),
+    };
+
+    let base_url = base_url(&req);
+    let image_url = player_image_path(&req, &data, &player).map(|path| format!("{}{}", base_url, path));
+    let card = export::PlayerCard {
+        itsf_lic,
+        license: format!("{:08}", itsf_lic),
+        display_name: labels::NameStyle::from_request(&req).display_name(&player.first_name, &player.last_name),
+        first_name: player.first_name,
+        last_name: player.last_name,
+        birth_year: player.birth_year,
+        country_code: player.country_code.unwrap_or(String::new()),
+        category: player.category.code(),
+        category_label: labels::player_category(player.category, labels::Language::from_param(params.lang.as_deref())),
+        image_url,
+        profile_url: format!("{}/player/{}", base_url, itsf_lic),
+        qr_url: format!("{}/player/{}/qr.png", base_url, itsf_lic),
+    };
+
+    match params.format.as_deref() {
+        None | Some("json") => Ok(HttpResponse::Ok().json(json::ok(card))),
+        Some("vcard") => Ok(HttpResponse::Ok()
+            .append_header(("Content-Type", "text/vcard; charset=utf-8"))
+            .body(card.to_vcard())),
+        Some(_) => Ok(HttpResponse::BadRequest().json(json::err("invalid format"))),
+    }
+}
+
+#[actix_web::get("/player/{itsf_lic}/qr.png")]
+async fn get_player_qr(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    itsf_lic: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let itsf_lic = match parse_license(&itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+    if get_visible_player(&req, &data, itsf_lic).is_none() {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let profile_url = format!("{}/player/{}", base_url(&req), itsf_lic);
+    let png = export::qr_png(&profile_url).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok()
+        .append_header(("Content-Type", "image/png"))
+        .body(png))
+}
+
+#[derive(Deserialize)]
+struct OfflineBundleParams {
+    players: String,
+}
+
+#[actix_web::get("/export/offline_bundle")]
+async fn export_offline_bundle(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<OfflineBundleParams>,
+) -> Result<HttpResponse, Error> {
+    const MAX_PLAYERS: usize = 2000;
+
+    let mut players = Vec::new();
+    for itsf_lic in params.players.split(',').filter(|lic| !lic.trim().is_empty()) {
+        let itsf_lic = match itsf_lic.parse::<LicenseNumber>() {
+            Ok(itsf_lic) => itsf_lic.get(),
+            Err(_) => return Ok(HttpResponse::BadRequest().json(json::err(format!("invalid player '{}'", itsf_lic)))),
+        };
+        match get_visible_player(&req, &data, itsf_lic) {
+            Some(player) => players.push(player),
+            None => return Ok(HttpResponse::NotFound().json(json::err(format!("No such player: {}", itsf_lic)))),
+        }
+    }
+    if players.len() > MAX_PLAYERS {
+        return Ok(HttpResponse::BadRequest().json(json::err(format!("at most {} players per bundle", MAX_PLAYERS))));
+    }
+
+    match export::offline_bundle(&data.data, players, auth::is_authenticated(&req), data.image_access) {
+        Ok(bundle) => Ok(HttpResponse::Ok()
+            .content_type(ContentType::octet_stream())
+            .append_header(("Content-Disposition", "attachment; filename=\"offline_bundle.zip\""))
+            .body(bundle)),
+        Err(err) => {
+            log::error!("failed to create offline bundle: {}", err);
+            Ok(HttpResponse::InternalServerError().json(json::err("error")))
+        }
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(download_db_zip)
+        .service(get_player_card)
+        .service(get_player_qr)
+        .service(export_offline_bundle);
+}
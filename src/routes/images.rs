@@ -0,0 +1,128 @@
+//! Player images.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::images;
+use playerdb_core::{data, warmup};
+use serde::Deserialize;
+
+use super::{get_visible_player, parse_license, require_user, AppState};
+use crate::{auth, bench, json, ranges, signing};
+
+/// Path of the player's image, none if the image access doesn't hand it out to the request.
+pub fn player_image_path(req: &HttpRequest, data: &web::Data<AppState>, player: &data::Player) -> Option<String> {
+    data.image_access
+        .allows(auth::is_authenticated(req), player.hidden)
+        .then(|| {
+            signing::image_path(
+                player.itsf_id,
+                data.data.get_player_image_hash(player.itsf_id).as_deref(),
+            )
+        })
+}
+
+#[derive(Deserialize)]
+struct ImageParams {
+    expires: Option<i64>,
+    sig: Option<String>,
+}
+
+/// Serves `/image/{ITSF-ID}-{hash}.jpg` with headers allowing to cache it forever, `/image/{ITSF-ID}.jpg` and links
+/// with an outdated hash redirect there. Answers `HEAD` and single byte ranges.
+#[actix_web::route("/image/{name}.jpg", method = "GET", method = "HEAD")]
+async fn get_player_image(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    name: web::Path<String>,
+    params: web::Query<ImageParams>,
+) -> Result<HttpResponse, Error> {
+    let (itsf_lic, hash) = match name.split_once('-') {
+        Some((itsf_lic, hash)) => (itsf_lic, Some(hash)),
+        None => (name.as_str(), None),
+    };
+    let itsf_lic = match parse_license(itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+    match data.image_access {
+        images::ImageAccess::Off => return Ok(HttpResponse::NotFound().finish()),
+        images::ImageAccess::Login => {
+            if let Err(response) = require_user(&req) {
+                return Ok(response);
+            }
+        }
+        images::ImageAccess::Public | images::ImageAccess::Visible => {}
+    }
+    if !auth::is_authenticated(&req) && !signing::verify_image_request(itsf_lic, params.expires, params.sig.as_deref())
+    {
+        return Ok(HttpResponse::Forbidden().json(json::err("invalid or expired image link")));
+    }
+    let player = data.data.get_player(itsf_lic);
+    let hidden = player.as_ref().is_some_and(|player| player.hidden);
+    if (player.is_some() && get_visible_player(&req, &data, itsf_lic).is_none())
+        || !data.image_access.allows(auth::is_authenticated(&req), hidden)
+    {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let current_hash = match data.data.get_player_image_hash(itsf_lic) {
+        Some(current_hash) => current_hash,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+    if hash != Some(current_hash.as_str()) {
+        let query = req.query_string();
+        let mut location = format!("/image/{}-{}.jpg", itsf_lic, current_hash);
+        if !query.is_empty() {
+            location = format!("{}?{}", location, query);
+        }
+        return Ok(HttpResponse::Found()
+            .append_header(("Location", location))
+            .append_header(("Cache-Control", "no-cache"))
+            .finish());
+    }
+
+    match data.data.get_player_image(itsf_lic) {
+        Some(player_image) => {
+            if !bench::is_replay(&req) {
+                warmup::record_player_request(itsf_lic);
+            }
+            // signed links, hidden players and images requiring login must not end up in shared caches
+            let visibility = if signing::is_enabled() || hidden || data.image_access == images::ImageAccess::Login {
+                "private"
+            } else {
+                "public"
+            };
+            let len = player_image.image_data.len();
+            // without validators to compare, any If-Range is considered outdated
+            let range = match req.headers().contains_key("If-Range") {
+                true => ranges::ByteRange::Full,
+                false => ranges::parse(req.headers().get("Range").and_then(|range| range.to_str().ok()), len),
+            };
+            let (mut response, body) = match range {
+                ranges::ByteRange::Full => (HttpResponse::Ok(), player_image.image_data),
+                ranges::ByteRange::Partial(range) => {
+                    let mut response = HttpResponse::PartialContent();
+                    response.append_header((
+                        "Content-Range",
+                        format!("bytes {}-{}/{}", range.start, range.end - 1, len),
+                    ));
+                    (response, player_image.image_data[range].to_vec())
+                }
+                ranges::ByteRange::Unsatisfiable => {
+                    return Ok(HttpResponse::RangeNotSatisfiable()
+                        .append_header(("Content-Range", format!("bytes */{}", len)))
+                        .finish())
+                }
+            };
+            Ok(response
+                .append_header(("Content-Type", "image/jpeg"))
+                .append_header(("Accept-Ranges", "bytes"))
+                .append_header(("Cache-Control", format!("{}, max-age=31536000, immutable", visibility)))
+                .body(body))
+        }
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_player_image);
+}
@@ -0,0 +1,226 @@
+//! Player lists.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::license::LicenseNumber;
+use playerdb_core::{data, undo};
+use serde::Deserialize;
+
+use super::players::{with_freshness, PlayerData};
+use super::{get_visible_player, require_user, AppState};
+use crate::{auth, json, labels};
+
+pub fn license_ids(licenses: &[LicenseNumber]) -> Vec<i32> {
+    licenses.iter().map(|license| license.get()).collect()
+}
+
+pub fn find_unknown_players(data: &web::Data<AppState>, itsf_ids: &[i32]) -> Vec<i32> {
+    itsf_ids
+        .iter()
+        .copied()
+        .filter(|itsf_lic| data.data.get_player(*itsf_lic).is_none())
+        .collect()
+}
+
+/// The list if it belongs to the workspace of the request, or is shared and the request has no workspace.
+pub fn get_workspace_list(
+    req: &HttpRequest,
+    data: &web::Data<AppState>,
+    list_id: i32,
+) -> Option<data::lists::PlayerList> {
+    data.data
+        .get_list(list_id)
+        .filter(|list| list.workspace == auth::workspace(req))
+}
+
+#[actix_web::get("/lists")]
+async fn get_player_lists(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let workspace = auth::workspace(&req);
+    let lists: Vec<data::lists::PlayerList> = data
+        .data
+        .get_lists()
+        .into_iter()
+        .filter(|list| list.workspace == workspace)
+        .collect();
+    Ok(HttpResponse::Ok().json(json::ok(lists)))
+}
+
+#[derive(Deserialize)]
+struct CreateListInfo {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    players: Vec<LicenseNumber>,
+}
+
+#[actix_web::post("/lists")]
+async fn create_player_list(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<CreateListInfo>,
+) -> Result<HttpResponse, Error> {
+    let info = info.into_inner();
+    if info.name.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(json::err("empty list name")));
+    }
+    let players = license_ids(&info.players);
+    let unknown = find_unknown_players(&data, &players);
+    if !unknown.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(json::err(format!("unknown players: {:?}", unknown))));
+    }
+
+    let list = data
+        .data
+        .create_list(auth::workspace(&req).as_deref(), info.name, info.description, &players);
+    Ok(HttpResponse::Ok().json(json::ok(list)))
+}
+
+#[actix_web::get("/list/{list_id}")]
+async fn get_player_list(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    list_id: web::Path<i32>,
+) -> Result<HttpResponse, Error> {
+    match get_workspace_list(&req, &data, list_id.into_inner()) {
+        Some(list) => Ok(HttpResponse::Ok().json(json::ok(list))),
+        None => Ok(HttpResponse::NotFound().json(json::err("No such list"))),
+    }
+}
+
+#[derive(Deserialize)]
+struct UpdateListInfo {
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    add_players: Vec<LicenseNumber>,
+    #[serde(default)]
+    remove_players: Vec<LicenseNumber>,
+}
+
+#[actix_web::post("/list/{list_id}")]
+async fn update_player_list(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    list_id: web::Path<i32>,
+    info: web::Json<UpdateListInfo>,
+) -> Result<HttpResponse, Error> {
+    let list_id = list_id.into_inner();
+    if get_workspace_list(&req, &data, list_id).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such list")));
+    }
+    let info = info.into_inner();
+    if info.name.as_ref().is_some_and(|name| name.trim().is_empty()) {
+        return Ok(HttpResponse::BadRequest().json(json::err("empty list name")));
+    }
+    let add_players = license_ids(&info.add_players);
+    let remove_players = license_ids(&info.remove_players);
+    let unknown = find_unknown_players(&data, &add_players);
+    if !unknown.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(json::err(format!("unknown players: {:?}", unknown))));
+    }
+
+    let list = data.data.modify_list(list_id, |list| {
+        if let Some(name) = info.name {
+            list.name = name;
+        }
+        if let Some(description) = info.description {
+            list.description = description;
+        }
+        list.add_players(&add_players);
+        list.remove_players(&remove_players);
+    });
+    match list {
+        Some(list) => Ok(HttpResponse::Ok().json(json::ok(list))),
+        None => Ok(HttpResponse::NotFound().json(json::err("No such list"))),
+    }
+}
+
+/// Stages the deletion, which can be reverted with `/admin/undo/{action_id}` until it is finalized.
+#[actix_web::delete("/list/{list_id}")]
+async fn delete_player_list(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    list_id: web::Path<i32>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    let list_id = list_id.into_inner();
+    if get_workspace_list(&req, &data, list_id).is_some() && data.data.delete_list(list_id) {
+        let pending = undo::stage(&data.data, undo::StagedAction::DeleteList { list_id }, user_id);
+        Ok(HttpResponse::Ok().json(json::ok(pending)))
+    } else {
+        Ok(HttpResponse::NotFound().json(json::err("No such list")))
+    }
+}
+
+#[derive(Deserialize)]
+struct MergeListInfo {
+    into_list_id: i32,
+}
+
+/// Adds the players to another list and deletes the list, staged like a deletion.
+#[actix_web::post("/list/{list_id}/merge")]
+async fn merge_player_list(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    list_id: web::Path<i32>,
+    info: web::Json<MergeListInfo>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    let list_id = list_id.into_inner();
+    let into_list_id = info.into_list_id;
+    if list_id == into_list_id {
+        return Ok(HttpResponse::BadRequest().json(json::err("can't merge a list into itself")));
+    }
+    if get_workspace_list(&req, &data, list_id).is_none() || get_workspace_list(&req, &data, into_list_id).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such list")));
+    }
+    match data.data.merge_list(list_id, into_list_id) {
+        Some(added_players) => {
+            let action = undo::StagedAction::MergeList {
+                list_id,
+                into_list_id,
+                added_players,
+            };
+            Ok(HttpResponse::Ok().json(json::ok(undo::stage(&data.data, action, user_id))))
+        }
+        None => Ok(HttpResponse::NotFound().json(json::err("No such list"))),
+    }
+}
+
+#[actix_web::get("/list/{list_id}/players")]
+async fn get_player_list_players(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    list_id: web::Path<i32>,
+) -> Result<HttpResponse, Error> {
+    let list = match get_workspace_list(&req, &data, list_id.into_inner()) {
+        Some(list) => list,
+        None => return Ok(HttpResponse::NotFound().json(json::err("No such list"))),
+    };
+
+    let name_style = labels::NameStyle::from_request(&req);
+    let players: Vec<PlayerData> = list
+        .players
+        .iter()
+        .filter_map(|itsf_lic| get_visible_player(&req, &data, *itsf_lic))
+        .map(|player| PlayerData::new(player, name_style))
+        .collect();
+    let scraped_at: Vec<Option<i64>> = players.iter().map(|player| player.scraped_at).collect();
+    Ok(with_freshness(json::ok(players), scraped_at))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_player_lists)
+        .service(create_player_list)
+        .service(get_player_list)
+        .service(update_player_list)
+        .service(delete_player_list)
+        .service(merge_player_list)
+        .service(get_player_list_players);
+}
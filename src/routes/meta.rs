@@ -0,0 +1,67 @@
+//! Enums, stored years and the frontend configuration.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::{images, season::Season};
+use playerdb_core::{coverage, stats};
+use serde::Deserialize;
+
+use super::AppState;
+use crate::{auth, features, json, labels, mock_source};
+
+#[derive(Deserialize)]
+struct LanguageParams {
+    /// `en` (default) or `de`.
+    lang: Option<String>,
+}
+
+#[actix_web::get("/meta/enums")]
+async fn get_enums(params: web::Query<LanguageParams>) -> Result<HttpResponse, Error> {
+    let language = labels::Language::from_param(params.lang.as_deref());
+    Ok(HttpResponse::Ok().json(json::ok(labels::enums(language))))
+}
+
+#[actix_web::get("/meta/years")]
+async fn get_stored_years(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().json(json::ok(coverage::stored_years(&data.data))))
+}
+
+/// Deployment settings the static UI adapts to.
+#[derive(serde::Serialize)]
+struct FrontendConfig {
+    access_mode: auth::AccessMode,
+    image_access: images::ImageAccess,
+    /// Whether the request can't change data, i.e. isn't logged in.
+    read_only: bool,
+    demo_mode: bool,
+    /// Enabled feature groups, see `/admin/features`.
+    features: Vec<features::Feature>,
+    /// Country of `/records` unless another one is requested.
+    default_country: String,
+    itsf_years: Vec<i32>,
+    /// e.g. `2022/23`
+    dtfb_seasons: Vec<String>,
+}
+
+#[actix_web::get("/config/frontend")]
+async fn get_frontend_config(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let config = FrontendConfig {
+        access_mode: data.access_mode,
+        image_access: data.image_access,
+        read_only: !auth::is_authenticated(&req),
+        demo_mode: mock_source::is_enabled(),
+        features: features::Feature::ALL
+            .into_iter()
+            .filter(|feature| features::is_enabled(&data.data, *feature))
+            .collect(),
+        default_country: stats::default_country(),
+        itsf_years: Season::all_itsf().into_iter().map(Season::year).collect(),
+        dtfb_seasons: Season::all_dtfb().iter().map(Season::to_string).collect(),
+    };
+    Ok(HttpResponse::Ok().json(json::ok(config)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_enums)
+        .service(get_stored_years)
+        .service(get_frontend_config);
+}
@@ -0,0 +1,162 @@
+//! The HTTP API, one module per area.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::{self, images::ImageAccess, license::LicenseNumber};
+use playerdb_core::{background, joblock, scraping};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+use crate::{auth, json};
+
+mod admin;
+mod clubs;
+mod comments;
+mod downloads;
+mod events;
+mod exports;
+mod images;
+mod lists;
+mod meta;
+mod moderation;
+mod players;
+mod rankings;
+mod stats;
+mod subscriptions;
+mod tags;
+
+pub struct AppState {
+    pub data: data::DatabaseRef,
+    /// Download jobs of this instance, at most one per source host.
+    pub downloads: Mutex<HashMap<scraping::SourceHost, Weak<background::BackgroundOperationProgress>>>,
+    pub job_lock: joblock::JobLock,
+    pub access_mode: auth::AccessMode,
+    pub image_access: ImageAccess,
+}
+
+impl AppState {
+    fn get_downloads(
+        this: &web::Data<AppState>,
+    ) -> Result<MutexGuard<'_, HashMap<scraping::SourceHost, Weak<background::BackgroundOperationProgress>>>, Error>
+    {
+        this.downloads
+            .lock()
+            .map_err(|_| actix_web::error::ErrorInternalServerError("internal lock"))
+    }
+
+    /// The download job of this instance scraping the host, if one is running.
+    fn get_download(
+        this: &web::Data<AppState>,
+        host: scraping::SourceHost,
+    ) -> Result<Option<Arc<background::BackgroundOperationProgress>>, Error> {
+        Ok(Self::get_downloads(this)?.get(&host).and_then(Weak::upgrade))
+    }
+
+    /// Takes the deployment-wide lock of the host, or returns the response to send if that's not possible.
+    async fn acquire_download_lock(
+        this: &web::Data<AppState>,
+        host: scraping::SourceHost,
+    ) -> Result<joblock::JobLockGuard, HttpResponse> {
+        match this.job_lock.try_acquire(host.lock_name()).await {
+            Ok(Some(guard)) => Ok(guard),
+            Ok(None) => Err(HttpResponse::BadRequest().json(json::err(format!(
+                "{} download in progress on another instance or job",
+                host.name()
+            )))),
+            Err(err) => {
+                log::error!("failed to acquire download lock: {}", err);
+                Err(HttpResponse::InternalServerError().json(json::err("failed to acquire download lock")))
+            }
+        }
+    }
+
+    /// Starts a download job unless another job scrapes the same host.
+    async fn start_download(
+        this: &web::Data<AppState>,
+        host: scraping::SourceHost,
+        start: impl FnOnce(joblock::JobLockGuard) -> Weak<background::BackgroundOperationProgress>,
+    ) -> Result<HttpResponse, Error> {
+        let in_progress =
+            || HttpResponse::BadRequest().json(json::err(format!("{} download still in progress", host.name())));
+        if Self::get_download(this, host)?.is_some() {
+            return Ok(in_progress());
+        }
+        let lock = match Self::acquire_download_lock(this, host).await {
+            Ok(lock) => lock,
+            Err(response) => return Ok(response),
+        };
+        let mut downloads = Self::get_downloads(this)?;
+        if downloads.get(&host).and_then(Weak::upgrade).is_some() {
+            return Ok(in_progress());
+        }
+
+        downloads.insert(host, start(lock));
+
+        Ok(HttpResponse::Ok().json(json::ok("Started download")))
+    }
+}
+
+/// The player unless hidden from the request, with the comments and tags of the user's workspace if any.
+fn get_visible_player(req: &HttpRequest, data: &web::Data<AppState>, itsf_lic: i32) -> Option<data::Player> {
+    data.data
+        .get_player(itsf_lic)
+        .filter(|player| !player.hidden || auth::is_authenticated(req))
+        .map(|player| data.data.with_workspace_notes(auth::workspace(req).as_deref(), player))
+}
+
+/// Like `get_visible_player`, only copying the requested sections of the profile.
+fn get_visible_player_sections(
+    req: &HttpRequest,
+    data: &web::Data<AppState>,
+    itsf_lic: i32,
+    sections: &[data::PlayerSection],
+) -> Option<data::Player> {
+    data.data
+        .get_player_sections(itsf_lic, sections)
+        .filter(|player| !player.hidden || auth::is_authenticated(req))
+        .map(|player| data.data.with_workspace_notes(auth::workspace(req).as_deref(), player))
+}
+
+fn base_url(req: &HttpRequest) -> String {
+    let info = req.connection_info();
+    format!("{}://{}", info.scheme(), info.host())
+}
+
+/// Parses a license number from a URL path, so malformed licenses are rejected with 400 instead of 404.
+fn parse_license(itsf_lic: &str) -> Result<i32, HttpResponse> {
+    match itsf_lic.parse::<LicenseNumber>() {
+        Ok(itsf_lic) => Ok(itsf_lic.get()),
+        Err(err) => Err(HttpResponse::BadRequest().json(json::err(err))),
+    }
+}
+
+fn require_user(req: &HttpRequest) -> Result<String, HttpResponse> {
+    auth::authenticated_user(req).ok_or_else(|| auth::unauthorized("not authorized"))
+}
+
+fn parse_date_param(name: &str, date: &Option<String>) -> Result<Option<chrono::NaiveDate>, HttpResponse> {
+    match date {
+        Some(date) => chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| HttpResponse::BadRequest().json(json::err(format!("invalid {}", name)))),
+        None => Ok(None),
+    }
+}
+
+/// All API endpoints, shared by the server and the in-process replay of `/admin/bench`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    players::configure(cfg);
+    images::configure(cfg);
+    exports::configure(cfg);
+    stats::configure(cfg);
+    clubs::configure(cfg);
+    meta::configure(cfg);
+    downloads::configure(cfg);
+    comments::configure(cfg);
+    tags::configure(cfg);
+    moderation::configure(cfg);
+    lists::configure(cfg);
+    admin::configure(cfg);
+    subscriptions::configure(cfg);
+    events::configure(cfg);
+    rankings::configure(cfg);
+}
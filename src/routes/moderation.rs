@@ -0,0 +1,145 @@
+//! Hiding, anonymizing and correcting players.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::license::LicenseNumber;
+use playerdb_core::{data, stats};
+use serde::Deserialize;
+
+use super::{parse_license, require_user, AppState};
+use crate::{auth, json};
+
+#[derive(Deserialize)]
+struct SetHiddenInfo {
+    itsf_lic: LicenseNumber,
+    hidden: bool,
+}
+
+#[actix_web::post("/set_hidden")]
+async fn set_player_hidden(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<SetHiddenInfo>,
+) -> Result<HttpResponse, Error> {
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    if data.data.get_player(info.itsf_lic.get()).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such player")));
+    }
+
+    data.data.set_player_hidden(info.itsf_lic.get(), info.hidden);
+    stats::request_refresh();
+    Ok(HttpResponse::Ok().json(json::ok(if info.hidden { "player hidden" } else { "player visible" })))
+}
+
+/// For formal removal requests: replaces name and image with placeholders for good, unlike `/set_hidden`.
+/// Rankings and results stay for the statistics.
+#[actix_web::post("/player/{itsf_lic}/anonymize")]
+async fn anonymize_player(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    itsf_lic: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    let itsf_lic = match parse_license(&itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+    if data.data.get_player(itsf_lic).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such player")));
+    }
+
+    data.data.anonymize_player(itsf_lic);
+    stats::request_refresh();
+    log::info!("anonymized player {}", itsf_lic);
+    Ok(HttpResponse::Ok().json(json::ok("player anonymized")))
+}
+
+#[derive(serde::Serialize)]
+struct PlayerOverrides {
+    /// The overridden values in effect by field.
+    active: std::collections::BTreeMap<data::overrides::OverrideField, serde_json::Value>,
+    /// Every override, oldest first.
+    history: Vec<data::overrides::FieldOverride>,
+}
+
+#[actix_web::get("/player/{itsf_lic}/overrides")]
+async fn get_player_overrides(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    itsf_lic: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    if let Err(response) = require_user(&req) {
+        return Ok(response);
+    }
+    let itsf_lic = match parse_license(&itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+    if data.data.get_player(itsf_lic).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such player")));
+    }
+
+    let history = data.data.get_player_overrides(itsf_lic);
+    let active = data::overrides::active(&history)
+        .into_iter()
+        .map(|(field, value)| (field, value.clone()))
+        .collect();
+    Ok(HttpResponse::Ok().json(json::ok(PlayerOverrides { active, history })))
+}
+
+#[derive(Deserialize)]
+struct OverrideInfo {
+    field: data::overrides::OverrideField,
+    /// `null` revokes the override of the field.
+    value: Option<serde_json::Value>,
+    reason: String,
+}
+
+/// Corrects a profile field by hand. Downloads keep the override until it's revoked.
+#[actix_web::post("/player/{itsf_lic}/overrides")]
+async fn add_player_override(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    itsf_lic: web::Path<String>,
+    info: web::Json<OverrideInfo>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    let itsf_lic = match parse_license(&itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+    if data.data.get_player(itsf_lic).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such player")));
+    }
+
+    let info = info.into_inner();
+    let entry = data::overrides::FieldOverride {
+        itsf_id: itsf_lic,
+        field: info.field,
+        value: info.value,
+        author: user_id,
+        reason: info.reason,
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    if let Err(err) = data.data.add_player_overrides(itsf_lic, vec![entry]) {
+        return Ok(HttpResponse::BadRequest().json(json::err(err)));
+    }
+    stats::request_refresh();
+    Ok(HttpResponse::Ok().json(json::ok("override recorded")))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(set_player_hidden)
+        .service(anonymize_player)
+        .service(get_player_overrides)
+        .service(add_player_override);
+}
@@ -0,0 +1,775 @@
+//! Player profiles, listings, search and sync.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::itsf;
+use playerdb_core::{data, filter, projection, scraping, search, sync, transitions, warmup};
+use serde::Deserialize;
+
+use super::downloads::parse_selection;
+use super::images::player_image_path;
+use super::{
+    base_url, get_visible_player, get_visible_player_sections, parse_date_param, parse_license, require_user, AppState,
+};
+use crate::{auth, bench, dto, json, labels, opensearch};
+
+/// A player referenced as `#123456` in a comment.
+#[derive(serde::Serialize)]
+struct Mention {
+    itsf_lic: i32,
+    url: String,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    display_name: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CommentJson {
+    timestamp: u32,
+    text: String,
+    visibility: data::CommentVisibility,
+    author: Option<String>,
+    mentions: Vec<Mention>,
+    status: Option<data::CommentStatus>,
+    resolved_by: Option<String>,
+    resolved_at: Option<u32>,
+    /// Number of users who gave a thumbs-up.
+    thumbs_up: usize,
+}
+
+impl CommentJson {
+    pub fn new(req: &HttpRequest, data: &web::Data<AppState>, comment: data::PlayerComment) -> Self {
+        let name_style = labels::NameStyle::from_request(req);
+        let mentions = comment
+            .mentions()
+            .into_iter()
+            .map(|itsf_lic| {
+                let player = get_visible_player(req, data, itsf_lic);
+                Mention {
+                    itsf_lic,
+                    url: format!("/player/{}", itsf_lic),
+                    display_name: player
+                        .as_ref()
+                        .map(|player| name_style.display_name(&player.first_name, &player.last_name)),
+                    first_name: player.as_ref().map(|player| player.first_name.clone()),
+                    last_name: player.map(|player| player.last_name),
+                }
+            })
+            .collect();
+        CommentJson {
+            timestamp: comment.timestamp,
+            text: comment.text,
+            visibility: comment.visibility,
+            author: comment.author,
+            mentions,
+            status: comment.status,
+            resolved_by: comment.resolved_by,
+            resolved_at: comment.resolved_at,
+            thumbs_up: comment.thumbs_up.len(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PlayerParams {
+    /// Also return the ITSF rankings of the combined class, which are left out by default.
+    include_combined: Option<bool>,
+    /// Return where each ranking and result was scraped from, left out by default.
+    include_provenance: Option<bool>,
+    /// Comma separated sections to return besides the basic profile, e.g. `rankings,comments`, or `all`.
+    include: Option<String>,
+}
+
+impl PlayerParams {
+    /// The requested sections, none if `include` is missing.
+    fn sections(&self) -> Result<Vec<data::PlayerSection>, HttpResponse> {
+        match self.include.as_deref().map(str::trim) {
+            None => Ok(Vec::new()),
+            Some("all") => Ok(data::PlayerSection::ALL.to_vec()),
+            Some(_) => parse_selection(
+                &self.include,
+                &data::PlayerSection::ALL,
+                data::PlayerSection::try_from_str,
+            )
+            .map_err(|err| HttpResponse::BadRequest().json(json::err(format!("invalid include: {}", err)))),
+        }
+    }
+}
+
+#[actix_web::get("/player/{itsf_lic}")]
+async fn get_player(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    itsf_lic: web::Path<String>,
+    params: web::Query<PlayerParams>,
+) -> Result<HttpResponse, Error> {
+    let itsf_lic = match parse_license(&itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+    let sections = match params.sections() {
+        Ok(sections) => sections,
+        Err(response) => return Ok(response),
+    };
+    let player = get_visible_player_sections(&req, &data, itsf_lic, &sections);
+    Ok(player_response(
+        &req,
+        &data,
+        player,
+        &sections,
+        params.include_combined == Some(true),
+        params.include_provenance == Some(true),
+    ))
+}
+
+#[actix_web::get("/player/dtfb/{dtfb_lic}")]
+async fn get_player_by_dtfb_lic(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    dtfb_lic: web::Path<String>,
+    params: web::Query<PlayerParams>,
+) -> Result<HttpResponse, Error> {
+    let dtfb_lic = match dtfb_lic.trim().parse::<i32>() {
+        Ok(dtfb_lic) if dtfb_lic > 0 => dtfb_lic,
+        _ => {
+            return Ok(
+                HttpResponse::BadRequest().json(json::err(format!("invalid DTFB license number: '{}'", dtfb_lic)))
+            )
+        }
+    };
+    let sections = match params.sections() {
+        Ok(sections) => sections,
+        Err(response) => return Ok(response),
+    };
+    let player = data
+        .data
+        .get_itsf_id_by_dtfb_id(dtfb_lic)
+        .and_then(|itsf_lic| get_visible_player_sections(&req, &data, itsf_lic, &sections));
+    Ok(player_response(
+        &req,
+        &data,
+        player,
+        &sections,
+        params.include_combined == Some(true),
+        params.include_provenance == Some(true),
+    ))
+}
+
+/// Computes the percentile of rankings stored before it was recorded, from the size of the ranking download.
+fn fill_missing_percentiles(data: &web::Data<AppState>, rankings: &mut [dto::v1::ItsfRanking]) {
+    if rankings.iter().all(|ranking| ranking.percentile.is_some()) {
+        return;
+    }
+    let downloads = data.data.get_ranking_downloads();
+    for ranking in rankings.iter_mut().filter(|ranking| ranking.percentile.is_none()) {
+        ranking.percentile = downloads
+            .iter()
+            .find(|download| {
+                download.year == ranking.year
+                    && download.category == ranking.category
+                    && download.class == ranking.class
+            })
+            .and_then(|download| itsf::percentile(ranking.place, download.entries));
+    }
+}
+
+fn player_response(
+    req: &HttpRequest,
+    data: &web::Data<AppState>,
+    player: Option<data::Player>,
+    sections: &[data::PlayerSection],
+    include_combined: bool,
+    include_provenance: bool,
+) -> HttpResponse {
+    #[derive(serde::Serialize)]
+    struct PlayerJson {
+        pub first_name: String,
+        pub last_name: String,
+        pub display_name: String,
+        pub birth_year: i32,
+        pub country_code: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub image_url: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub itsf_rankings: Option<Vec<dto::v1::ItsfRanking>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub dtfb_rankings: Option<Vec<dto::v1::DtfbRanking>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub dm_placements: Option<Vec<dto::v1::ChampionshipResult>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub dtfl_teams: Option<Vec<dto::v1::LeagueTeam>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub comment: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub comments: Option<Vec<CommentJson>>,
+        pub tags: Vec<String>,
+        pub former_names: Vec<dto::v1::FormerName>,
+        pub country_changes: Vec<dto::v1::CountryChange>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub national_team_appearances: Option<Vec<dto::v1::NationalTeamAppearance>>,
+        pub hidden: bool,
+        pub scraped_at: Option<i64>,
+        pub stale: bool,
+        /// Failed downloads since the data was last refreshed, so clients can flag it as possibly incomplete.
+        pub data_warnings: Vec<DataWarning>,
+        /// Change of age category with the next season.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub category_transition: Option<transitions::Transition>,
+    }
+
+    match player {
+        Some(mut player) => {
+            let itsf_lic = player.itsf_id;
+            if !bench::is_replay(req) {
+                warmup::record_player_request(itsf_lic);
+            }
+            if !auth::is_authenticated(req) {
+                player
+                    .comments
+                    .retain(|comment| comment.visibility == data::CommentVisibility::Public);
+            }
+
+            let stale = player.is_stale();
+            let image_url = player_image_path(req, data, &player);
+            let section = |section: data::PlayerSection| sections.contains(&section);
+            let mut player = PlayerJson {
+                display_name: labels::NameStyle::from_request(req).display_name(&player.first_name, &player.last_name),
+                first_name: player.first_name,
+                last_name: player.last_name,
+                birth_year: player.birth_year,
+                country_code: player.country_code.unwrap_or(String::new()),
+                image_url,
+                itsf_rankings: section(data::PlayerSection::Rankings).then(|| dto::v1::convert(player.itsf_rankings)),
+                dtfb_rankings: section(data::PlayerSection::Rankings)
+                    .then(|| dto::v1::convert(player.dtfb_national_rankings)),
+                dm_placements: section(data::PlayerSection::Results)
+                    .then(|| dto::v1::convert(player.dtfb_championship_results)),
+                dtfl_teams: section(data::PlayerSection::Teams).then(|| dto::v1::convert(player.dtfb_league_teams)),
+                comment: section(data::PlayerSection::Comments)
+                    .then(|| player.comments.last().map(|c| c.text.clone()).unwrap_or(String::new())),
+                comments: section(data::PlayerSection::Comments).then(|| {
+                    player
+                        .comments
+                        .into_iter()
+                        .map(|comment| CommentJson::new(req, data, comment))
+                        .collect()
+                }),
+                tags: player.tags,
+                former_names: dto::v1::convert(player.former_names),
+                country_changes: dto::v1::convert(player.country_changes),
+                national_team_appearances: section(data::PlayerSection::Teams)
+                    .then(|| dto::v1::convert(player.national_team_appearances)),
+                hidden: player.hidden,
+                scraped_at: player.scraped_at,
+                stale,
+                data_warnings: player.refresh_errors.iter().map(DataWarning::new).collect(),
+                category_transition: transitions::Transition::next_season(player.birth_year),
+            };
+
+            if let Some(rankings) = player.itsf_rankings.as_mut() {
+                rankings.retain(|ranking| include_combined || ranking.class != itsf::RankingClass::Combined);
+                fill_missing_percentiles(data, rankings);
+            }
+            if !include_provenance {
+                player
+                    .itsf_rankings
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|r| r.provenance = None);
+                player
+                    .dtfb_rankings
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|r| r.provenance = None);
+                player
+                    .dm_placements
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|r| r.provenance = None);
+                player.dtfl_teams.iter_mut().flatten().for_each(|r| r.provenance = None);
+                player
+                    .national_team_appearances
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|a| a.provenance = None);
+            }
+            if let Some(rankings) = player.itsf_rankings.as_mut() {
+                rankings.sort_by_key(|r| std::cmp::Reverse(r.year));
+            }
+            if let Some(rankings) = player.dtfb_rankings.as_mut() {
+                rankings.sort_by_key(|r| std::cmp::Reverse(r.year));
+            }
+            if let Some(placements) = player.dm_placements.as_mut() {
+                placements.sort_by_key(|r| std::cmp::Reverse(r.year));
+            }
+            if let Some(teams) = player.dtfl_teams.as_mut() {
+                teams.sort_by_key(|r| std::cmp::Reverse(r.year));
+            }
+
+            let scraped_at = player.scraped_at;
+            with_freshness(json::ok(player), [scraped_at])
+        }
+        None => HttpResponse::NotFound().json(json::err("No such player")),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct DataWarning {
+    /// `profile_refresh_failed` or `image_refresh_failed`.
+    code: &'static str,
+    /// Unix timestamp of the failed attempt.
+    failed_at: i64,
+    message: &'static str,
+}
+
+impl DataWarning {
+    /// Leaves out the error itself, which can contain source URLs and is logged with the download job.
+    fn new(error: &data::RefreshError) -> Self {
+        let (code, message) = match error.target {
+            data::RefreshTarget::Profile => (
+                "profile_refresh_failed",
+                "The last download of the ITSF profile failed, data may be outdated or incomplete",
+            ),
+            data::RefreshTarget::Image => ("image_refresh_failed", "The last download of the player image failed"),
+        };
+        DataWarning {
+            code,
+            failed_at: error.timestamp,
+            message,
+        }
+    }
+}
+
+/// JSON response with an `X-Data-Freshness` header: seconds since the least recently downloaded of the
+/// returned players was scraped, or `unknown` if that isn't known for one of them.
+pub fn with_freshness<T: serde::Serialize>(body: T, scraped_at: impl IntoIterator<Item = Option<i64>>) -> HttpResponse {
+    let oldest = scraped_at
+        .into_iter()
+        .try_fold(None, |oldest: Option<i64>, scraped_at| {
+            scraped_at.map(|scraped_at| Some(oldest.map_or(scraped_at, |oldest| oldest.min(scraped_at))))
+        });
+    let freshness = match oldest {
+        Some(Some(oldest)) => Some((chrono::Utc::now().timestamp() - oldest).max(0).to_string()),
+        Some(None) => None,
+        None => Some(String::from("unknown")),
+    };
+
+    let mut response = HttpResponse::Ok();
+    if let Some(freshness) = freshness {
+        response.insert_header(("X-Data-Freshness", freshness));
+    }
+    response.json(body)
+}
+
+#[derive(serde::Serialize)]
+pub struct PlayerData {
+    pub itsf_lic: i32,
+    pub first_name: String,
+    pub last_name: String,
+    /// The name in the convention of the `name_style` parameter.
+    pub display_name: String,
+    pub tags: Vec<String>,
+    pub scraped_at: Option<i64>,
+    /// Whether the profile is older than `DATA_STALE_AFTER` and will be downloaded again.
+    pub stale: bool,
+}
+
+impl PlayerData {
+    pub fn new(player: data::Player, name_style: labels::NameStyle) -> Self {
+        PlayerData {
+            itsf_lic: player.itsf_id,
+            stale: player.is_stale(),
+            display_name: name_style.display_name(&player.first_name, &player.last_name),
+            first_name: player.first_name,
+            last_name: player.last_name,
+            tags: player.tags,
+            scraped_at: player.scraped_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ListPlayersParams {
+    tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CursorParams {
+    /// `next_cursor` of the previous page.
+    cursor: Option<String>,
+    /// Entries per page, the list is only split into pages if `limit` or `cursor` is given.
+    limit: Option<usize>,
+}
+
+/// A page of a player listing. Listings are ordered by ITSF license and the cursor is the last license
+/// of the previous page, so players added or removed while a client pages through don't shift the
+/// other players to another page.
+struct Page {
+    after: Option<i32>,
+    limit: usize,
+}
+
+impl CursorParams {
+    const DEFAULT_LIMIT: usize = 100;
+    const MAX_LIMIT: usize = 1000;
+
+    /// `None` if the whole list was requested.
+    fn page(&self) -> Result<Option<Page>, HttpResponse> {
+        if self.cursor.is_none() && self.limit.is_none() {
+            return Ok(None);
+        }
+        let after = match &self.cursor {
+            Some(cursor) => match cursor.parse::<i32>() {
+                Ok(itsf_lic) => Some(itsf_lic),
+                Err(_) => return Err(HttpResponse::BadRequest().json(json::err("invalid cursor"))),
+            },
+            None => None,
+        };
+        let limit = self.limit.unwrap_or(Self::DEFAULT_LIMIT);
+        if limit == 0 || limit > Self::MAX_LIMIT {
+            return Err(
+                HttpResponse::BadRequest().json(json::err(format!("limit must be between 1 and {}", Self::MAX_LIMIT)))
+            );
+        }
+        Ok(Some(Page { after, limit }))
+    }
+}
+
+impl Page {
+    /// Takes the page from players sorted by license, returns it with the cursor of the next page.
+    fn take<T>(&self, players: impl Iterator<Item = T>, itsf_lic: impl Fn(&T) -> i32) -> (Vec<T>, Option<String>) {
+        let mut page: Vec<T> = players
+            .skip_while(|player| self.after.is_some_and(|after| itsf_lic(player) <= after))
+            .take(self.limit + 1)
+            .collect();
+        if page.len() <= self.limit {
+            return (page, None);
+        }
+        page.truncate(self.limit);
+        let next_cursor = page.last().map(|player| itsf_lic(player).to_string());
+        (page, next_cursor)
+    }
+}
+
+/// The players as a whole list, or the requested page of them.
+fn player_listing(players: Vec<PlayerData>, page: Option<Page>) -> HttpResponse {
+    match page {
+        None => {
+            let scraped_at: Vec<Option<i64>> = players.iter().map(|player| player.scraped_at).collect();
+            with_freshness(json::ok(players), scraped_at)
+        }
+        Some(page) => {
+            let (players, next_cursor) = page.take(players.into_iter(), |player| player.itsf_lic);
+            let scraped_at: Vec<Option<i64>> = players.iter().map(|player| player.scraped_at).collect();
+            with_freshness(json::page(players, next_cursor), scraped_at)
+        }
+    }
+}
+
+#[actix_web::get("/listplayers")]
+async fn list_players(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<ListPlayersParams>,
+    cursor: web::Query<CursorParams>,
+) -> Result<HttpResponse, Error> {
+    let tag = match params.tag.as_deref().map(data::normalize_tag) {
+        Some(Ok(tag)) => Some(tag),
+        Some(Err(err)) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+        None => None,
+    };
+    let page = match cursor.page() {
+        Ok(page) => page,
+        Err(response) => return Ok(response),
+    };
+
+    let name_style = labels::NameStyle::from_request(&req);
+    let mut ids = data.data.get_player_ids();
+    ids.sort();
+    if let Some(after) = page.as_ref().and_then(|page| page.after) {
+        ids.retain(|itsf_lic| *itsf_lic > after);
+    }
+    let players = ids
+        .iter()
+        .filter_map(|itsf_lic| get_visible_player(&req, &data, *itsf_lic))
+        .map(|player| PlayerData::new(player, name_style))
+        .filter(|player| tag.as_ref().is_none_or(|tag| player.tags.contains(tag)));
+    let players: Vec<PlayerData> = match &page {
+        // one more than the page, to tell whether there is a next one
+        Some(page) => players.take(page.limit + 1).collect(),
+        None => players.collect(),
+    };
+    Ok(player_listing(players, page))
+}
+
+#[derive(Deserialize)]
+struct SyncParams {
+    /// `revision` of the previous sync, everything if missing.
+    since_revision: Option<u64>,
+    /// Most changes returned at once (default 1000), the rest follows with the next sync.
+    limit: Option<usize>,
+    /// `documents` for the players as stored, with hidden ones and image hashes, for replicating instances.
+    /// Requires login.
+    format: Option<String>,
+}
+
+/// Players changed since a revision, for clients keeping a local copy of all players. Tags of
+/// workspaces don't change the revision of a player.
+#[actix_web::get("/sync")]
+async fn sync_players(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<SyncParams>,
+) -> Result<HttpResponse, Error> {
+    #[derive(serde::Serialize)]
+    struct SyncPlayer {
+        revision: u64,
+        #[serde(flatten)]
+        player: PlayerData,
+        birth_year: i32,
+        country_code: String,
+    }
+
+    #[derive(serde::Serialize)]
+    struct SyncJson {
+        /// Pass as `since_revision` next time.
+        revision: u64,
+        /// False if more changes follow after `revision`.
+        complete: bool,
+        players: Vec<SyncPlayer>,
+        /// Licenses of players to remove from the copy: anonymized players, and hidden ones unless
+        /// logged in.
+        deleted: Vec<i32>,
+    }
+
+    let limit = params.limit.unwrap_or(1000);
+    if limit == 0 {
+        return Ok(HttpResponse::BadRequest().json(json::err("limit must be positive")));
+    }
+    let changes = sync::Changes::since(&data.data, params.since_revision.unwrap_or(0), limit);
+
+    match params.format.as_deref() {
+        None => {}
+        Some("documents") => {
+            if let Err(response) = require_user(&req) {
+                return Ok(response);
+            }
+            return Ok(HttpResponse::Ok().json(json::ok(changes.into_documents(&data.data))));
+        }
+        Some(_) => return Ok(HttpResponse::BadRequest().json(json::err("unknown format"))),
+    }
+
+    let workspace = auth::workspace(&req);
+    let name_style = labels::NameStyle::from_request(&req);
+    let (revision, complete) = (changes.revision, changes.complete);
+    let (players, deleted) = changes.into_visible(auth::is_authenticated(&req));
+    let players = players
+        .into_iter()
+        .map(|player| {
+            let player = data.data.with_workspace_notes(workspace.as_deref(), player);
+            SyncPlayer {
+                revision: player.revision,
+                birth_year: player.birth_year,
+                country_code: player.country_code.clone().unwrap_or_default(),
+                player: PlayerData::new(player, name_style),
+            }
+        })
+        .collect();
+    let sync = SyncJson {
+        revision,
+        complete,
+        players,
+        deleted,
+    };
+    Ok(HttpResponse::Ok().json(json::ok(sync)))
+}
+
+#[derive(Deserialize)]
+struct FilterPlayersParams {
+    filter: String,
+}
+
+#[actix_web::get("/players")]
+async fn filter_players(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<FilterPlayersParams>,
+    cursor: web::Query<CursorParams>,
+) -> Result<HttpResponse, Error> {
+    let filter = match filter::Filter::parse(&params.filter) {
+        Ok(filter) => filter,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(format!("invalid filter: {}", err)))),
+    };
+    let page = match cursor.page() {
+        Ok(page) => page,
+        Err(response) => return Ok(response),
+    };
+
+    let include_hidden = auth::is_authenticated(&req);
+    let name_style = labels::NameStyle::from_request(&req);
+    let workspace_notes = auth::workspace(&req).map(|workspace| data.data.get_workspace_notes(&workspace));
+    let mut players: Vec<PlayerData> = data.data.aggregate_players(|players| {
+        players
+            .filter(|player| include_hidden || !player.hidden)
+            .filter_map(|player| match &workspace_notes {
+                Some(notes) => {
+                    let mut player = player.clone();
+                    player.comments.clear();
+                    player.tags.clear();
+                    if let Some(notes) = notes.get(&player.itsf_id) {
+                        notes.clone().apply(&mut player);
+                    }
+                    filter.matches(&player).then_some(player)
+                }
+                None => filter.matches(player).then(|| player.clone()),
+            })
+            .map(|player| PlayerData::new(player, name_style))
+            .collect()
+    });
+    players.sort_by_key(|player| player.itsf_lic);
+    Ok(player_listing(players, page))
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    q: String,
+    limit: Option<usize>,
+    comments: Option<bool>,
+    /// `json` (default) or `opensearch` for the completions of browser search engines.
+    format: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct CommentSearchResult {
+    #[serde(flatten)]
+    player: PlayerData,
+    comment_matches: Vec<search::CommentMatch>,
+}
+
+#[actix_web::get("/search")]
+async fn search_players(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<SearchParams>,
+) -> Result<HttpResponse, Error> {
+    const MAX_LIMIT: usize = 100;
+    let limit = params.limit.unwrap_or(20).min(MAX_LIMIT);
+    let name_style = labels::NameStyle::from_request(&req);
+    match params.format.as_deref() {
+        None | Some("json") => {}
+        Some("opensearch") => {
+            let base_url = base_url(&req);
+            let suggestions = search::search_players(&data.data, &params.q, limit, auth::is_authenticated(&req))
+                .await
+                .into_iter()
+                .map(|player| opensearch::Suggestion {
+                    name: name_style.display_name(&player.first_name, &player.last_name),
+                    description: format!(
+                        "{:08}, {}",
+                        player.itsf_id,
+                        player.country_code.as_deref().unwrap_or("unknown country")
+                    ),
+                    url: format!("{}/comments.html?{}", base_url, player.itsf_id),
+                })
+                .collect();
+            return Ok(HttpResponse::Ok()
+                .content_type(opensearch::SUGGESTIONS_CONTENT_TYPE)
+                .json(opensearch::suggestions(&params.q, suggestions)));
+        }
+        Some(_) => return Ok(HttpResponse::BadRequest().json(json::err("invalid format"))),
+    }
+    if params.comments == Some(true) {
+        if let Err(response) = require_user(&req) {
+            return Ok(response);
+        }
+        let results: Vec<CommentSearchResult> =
+            search::search_players_and_comments(&data.data, auth::workspace(&req).as_deref(), &params.q, limit)
+                .into_iter()
+                .map(|(player, comment_matches)| CommentSearchResult {
+                    player: PlayerData::new(player, name_style),
+                    comment_matches,
+                })
+                .collect();
+        let scraped_at: Vec<Option<i64>> = results.iter().map(|result| result.player.scraped_at).collect();
+        return Ok(with_freshness(json::ok(results), scraped_at));
+    }
+
+    let include_hidden = auth::is_authenticated(&req);
+    let workspace = auth::workspace(&req);
+    let players: Vec<PlayerData> = search::search_players(&data.data, &params.q, limit, include_hidden)
+        .await
+        .into_iter()
+        .map(|player| data.data.with_workspace_notes(workspace.as_deref(), player))
+        .map(|player| PlayerData::new(player, name_style))
+        .collect();
+    let scraped_at: Vec<Option<i64>> = players.iter().map(|player| player.scraped_at).collect();
+    Ok(with_freshness(json::ok(players), scraped_at))
+}
+
+/// Lets browsers add the player search as a search engine, see `/search?format=opensearch`.
+#[actix_web::get("/opensearch.xml")]
+async fn get_opensearch_description(req: HttpRequest) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok()
+        .content_type(opensearch::CONTENT_TYPE)
+        .body(opensearch::description(&base_url(&req))))
+}
+
+#[actix_web::get("/licence_check/{itsf_lic}")]
+async fn licence_check(itsf_lic: web::Path<String>) -> Result<HttpResponse, Error> {
+    let itsf_lic = match parse_license(&itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+    match scraping::licence::check_licence(itsf_lic).await {
+        Ok(check) => Ok(HttpResponse::Ok().json(json::ok(check))),
+        Err(err) => {
+            log::error!("licence check failed: {}", err);
+            Ok(HttpResponse::BadGateway().json(json::err("ITSF site unavailable")))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ProjectionParams {
+    /// `YYYY-MM-DD`, today or later.
+    date: Option<String>,
+}
+
+/// The player's ranking points at a future date, with the results dropping out until then.
+#[actix_web::get("/player/{itsf_lic}/projection")]
+async fn get_points_projection(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    itsf_lic: web::Path<String>,
+    params: web::Query<ProjectionParams>,
+) -> Result<HttpResponse, Error> {
+    let itsf_lic = match parse_license(&itsf_lic) {
+        Ok(itsf_lic) => itsf_lic,
+        Err(response) => return Ok(response),
+    };
+    let date = match parse_date_param("date", &params.date) {
+        Ok(Some(date)) => date,
+        Ok(None) => return Ok(HttpResponse::BadRequest().json(json::err("date is required"))),
+        Err(response) => return Ok(response),
+    };
+    let player = match get_visible_player_sections(&req, &data, itsf_lic, &[data::PlayerSection::Rankings]) {
+        Some(player) => player,
+        None => return Ok(HttpResponse::NotFound().json(json::err("No such player"))),
+    };
+
+    match projection::project(&player, chrono::Utc::now().date_naive(), date) {
+        Ok(projection) => Ok(HttpResponse::Ok().json(json::ok(projection))),
+        Err(err) => Ok(HttpResponse::BadRequest().json(json::err(err))),
+    }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_player)
+        .service(get_player_by_dtfb_lic)
+        .service(list_players)
+        .service(sync_players)
+        .service(filter_players)
+        .service(search_players)
+        .service(get_opensearch_description)
+        .service(licence_check)
+        .service(get_points_projection);
+}
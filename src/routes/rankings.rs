@@ -0,0 +1,161 @@
+//! Ranking history, snapshots and closing rankings.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::{itsf, season::Season, snapshots};
+use serde::Deserialize;
+
+use super::downloads::RankingSelectionParams;
+use super::{require_user, AppState};
+use crate::{auth, json};
+
+/// Parses the `{year}/{category}/{class}` of a ranking path, e.g. `2022/open/singles`.
+fn parse_ranking_path(
+    path: &(i32, String, String),
+) -> Result<(i32, itsf::RankingCategory, itsf::RankingClass), HttpResponse> {
+    let category = itsf::RankingCategory::try_from_str(&path.1);
+    let class = itsf::RankingClass::try_from_str(&path.2);
+    match (category, class) {
+        (Ok(category), Ok(class)) => Ok((path.0, category, class)),
+        (Err(err), _) | (_, Err(err)) => Err(HttpResponse::BadRequest().json(json::err(err))),
+    }
+}
+
+/// Downloads of a ranking, newest first, whose placements can be compared with `/diff`.
+#[actix_web::get("/rankings/{year}/{category}/{class}/history")]
+async fn get_ranking_history(
+    data: web::Data<AppState>,
+    path: web::Path<(i32, String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (year, category, class) = match parse_ranking_path(&path) {
+        Ok(ranking) => ranking,
+        Err(response) => return Ok(response),
+    };
+    Ok(HttpResponse::Ok().json(json::ok(data.data.get_ranking_snapshots(year, category, class))))
+}
+
+/// Closes the downloaded final rankings of a past season at their latest snapshot, so later downloads
+/// don't replace them unless `force_closed=true` is passed to `/download_itsf`.
+#[actix_web::post("/admin/rankings/{year}/close")]
+async fn close_rankings(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    year: web::Path<i32>,
+    selection: web::Query<RankingSelectionParams>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if let Err(response) = auth::require_shared_access(&req) {
+        return Ok(response);
+    }
+    use chrono::Datelike;
+    let year = year.into_inner();
+    if year >= chrono::Utc::now().year() {
+        return Ok(HttpResponse::BadRequest().json(json::err("Only the rankings of past seasons can be closed")));
+    }
+    let rankings = match selection.rankings(&[Season::Itsf(year)]) {
+        Ok(rankings) => rankings,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+    };
+
+    #[derive(serde::Serialize)]
+    struct ClosedRankingJson {
+        category: itsf::RankingCategory,
+        class: itsf::RankingClass,
+        #[serde(flatten)]
+        closure: itsf::RankingClosure,
+    }
+    let closed: Vec<ClosedRankingJson> = rankings
+        .into_iter()
+        .filter_map(|(_, category, class)| {
+            let closure = data.data.close_ranking(year, category, class, &user_id)?;
+            Some(ClosedRankingJson {
+                category,
+                class,
+                closure,
+            })
+        })
+        .collect();
+    if closed.is_empty() {
+        return Ok(HttpResponse::NotFound().json(json::err("No downloaded rankings of this season")));
+    }
+    log::info!("{} closed {} rankings of {}", user_id, closed.len(), year);
+    Ok(HttpResponse::Ok().json(json::ok(closed)))
+}
+
+/// The closure of a ranking, with `intact` telling whether the closed snapshot still matches its checksum.
+#[actix_web::get("/rankings/{year}/{category}/{class}/closure")]
+async fn get_ranking_closure(
+    data: web::Data<AppState>,
+    path: web::Path<(i32, String, String)>,
+) -> Result<HttpResponse, Error> {
+    let (year, category, class) = match parse_ranking_path(&path) {
+        Ok(ranking) => ranking,
+        Err(response) => return Ok(response),
+    };
+
+    #[derive(serde::Serialize)]
+    struct ClosureJson {
+        #[serde(flatten)]
+        closure: itsf::RankingClosure,
+        intact: bool,
+    }
+    match data.data.verify_ranking_closure(year, category, class) {
+        Some((closure, intact)) => Ok(HttpResponse::Ok().json(json::ok(ClosureJson { closure, intact }))),
+        None => Ok(HttpResponse::NotFound().json(json::err("Ranking is not closed"))),
+    }
+}
+
+#[derive(Deserialize)]
+struct RankingDiffParams {
+    /// `scraped_at` of the older snapshot, the one before `to` if missing.
+    from: Option<i64>,
+    /// `scraped_at` of the newer snapshot, the latest if missing.
+    to: Option<i64>,
+}
+
+/// Players who entered, left or moved in a ranking between two downloads.
+#[actix_web::get("/rankings/{year}/{category}/{class}/diff")]
+async fn get_ranking_diff(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<(i32, String, String)>,
+    params: web::Query<RankingDiffParams>,
+) -> Result<HttpResponse, Error> {
+    let (year, category, class) = match parse_ranking_path(&path) {
+        Ok(ranking) => ranking,
+        Err(response) => return Ok(response),
+    };
+    let history: Vec<i64> = data
+        .data
+        .get_ranking_snapshots(year, category, class)
+        .iter()
+        .map(|snapshot| snapshot.scraped_at)
+        .collect();
+    let to = params.to.or(history.first().copied());
+    let from = params.from.or_else(|| {
+        let to = to?;
+        history.iter().copied().find(|scraped_at| *scraped_at < to)
+    });
+    let snapshot = |scraped_at: Option<i64>| data.data.get_ranking_snapshot(year, category, class, scraped_at?);
+    let mut diff = match (snapshot(from), snapshot(to)) {
+        (Some(from), Some(to)) => snapshots::diff(&from, &to),
+        _ => return Ok(HttpResponse::NotFound().json(json::err("No such snapshots of this ranking"))),
+    };
+
+    if !auth::is_authenticated(&req) {
+        let visible = |itsf_id: i32| data.data.get_player(itsf_id).is_none_or(|player| !player.hidden);
+        diff.entered.retain(|placement| visible(placement.itsf_id));
+        diff.left.retain(|placement| visible(placement.itsf_id));
+        diff.moved.retain(|change| visible(change.itsf_id));
+    }
+    Ok(HttpResponse::Ok().json(json::ok(diff)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_ranking_history)
+        .service(close_rankings)
+        .service(get_ranking_closure)
+        .service(get_ranking_diff);
+}
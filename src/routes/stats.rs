@@ -0,0 +1,277 @@
+//! Statistics, records, national teams and league tables.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::{itsf, season::Season};
+use playerdb_core::{stats, transitions};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::players::PlayerData;
+use super::{parse_license, AppState};
+use crate::{auth, json, labels};
+
+#[actix_web::get("/db_stats")]
+async fn db_stats(data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().json(json::ok(data.data.get_connection_stats())))
+}
+
+/// The players of a country's national teams at the World Championships of a year.
+#[actix_web::get("/national_team/{country}/{year}")]
+async fn get_national_team(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    path: web::Path<(String, i32)>,
+) -> Result<HttpResponse, Error> {
+    #[derive(serde::Serialize)]
+    struct Competition {
+        event_id: i32,
+        event: String,
+        competition: String,
+        place: Option<i32>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct RosterPlayer {
+        #[serde(flatten)]
+        player: PlayerData,
+        competitions: Vec<Competition>,
+    }
+
+    let (country_code, year) = path.into_inner();
+    let country_code = country_code.to_uppercase();
+    let include_hidden = auth::is_authenticated(&req);
+    let name_style = labels::NameStyle::from_request(&req);
+    let mut roster: Vec<RosterPlayer> = data.data.aggregate_players(|players| {
+        players
+            .filter(|player| include_hidden || !player.hidden)
+            .filter_map(|player| {
+                let competitions: Vec<Competition> = player
+                    .national_team_appearances
+                    .iter()
+                    .filter(|appearance| appearance.year == year && appearance.country_code == country_code)
+                    .map(|appearance| Competition {
+                        event_id: appearance.event_id,
+                        event: appearance.event.clone(),
+                        competition: appearance.competition.clone(),
+                        place: appearance.place,
+                    })
+                    .collect();
+                (!competitions.is_empty()).then(|| RosterPlayer {
+                    player: PlayerData::new(player.clone(), name_style),
+                    competitions,
+                })
+            })
+            .collect()
+    });
+    if roster.is_empty() {
+        return Ok(HttpResponse::NotFound().json(json::err("No national team appearances recorded")));
+    }
+    roster.sort_by_key(|player| player.player.itsf_lic);
+    Ok(HttpResponse::Ok().json(json::ok(roster)))
+}
+
+#[derive(Deserialize)]
+struct RecordsParams {
+    country: Option<String>,
+}
+
+#[actix_web::get("/records")]
+async fn get_records(req: HttpRequest, params: web::Query<RecordsParams>) -> Result<HttpResponse, Error> {
+    let country_code = params.country.clone().unwrap_or_else(stats::default_country);
+    let records = stats::records(&country_code, auth::is_authenticated(&req));
+    Ok(HttpResponse::Ok().json(json::ok(records)))
+}
+
+#[derive(Deserialize)]
+struct TransitionsParams {
+    /// The season of the transition, the next one by default.
+    year: Option<i32>,
+    country: Option<String>,
+}
+
+/// Players aging out of the junior or into the senior category, e.g. for the planning of national coaches.
+#[actix_web::get("/transitions")]
+async fn get_category_transitions(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<TransitionsParams>,
+) -> Result<HttpResponse, Error> {
+    let year = params.year.unwrap_or_else(transitions::next_season);
+    let country_code = params.country.as_deref().map(str::to_uppercase);
+    let transitions = transitions::list(&data.data, year, country_code.as_deref(), auth::is_authenticated(&req));
+    Ok(HttpResponse::Ok().json(json::ok(transitions)))
+}
+
+#[derive(Deserialize)]
+struct CountryRankingParams {
+    year: Option<i32>,
+}
+
+#[actix_web::get("/countries/ranking")]
+async fn get_country_ranking(params: web::Query<CountryRankingParams>) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok().json(json::ok(stats::country_ranking(params.year))))
+}
+
+#[derive(Deserialize)]
+struct PairRatingParams {
+    /// Two comma separated ITSF licenses.
+    players: String,
+    /// Ranking category of the doubles places, `open` by default.
+    category: Option<String>,
+}
+
+/// Strength of a doubles pair for seeding team tournaments, the sum of the doubles strengths of both players.
+/// The strengths are computed with the leaderboards, so they follow downloads after the next refresh.
+#[actix_web::get("/pairs/rating")]
+async fn get_pair_rating(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<PairRatingParams>,
+) -> Result<HttpResponse, Error> {
+    #[derive(serde::Serialize)]
+    struct PairPlayer {
+        itsf_lic: i32,
+        first_name: String,
+        last_name: String,
+        #[serde(flatten)]
+        strength: stats::DoublesStrength,
+    }
+
+    #[derive(serde::Serialize)]
+    struct PairRating {
+        category: itsf::RankingCategory,
+        rating: f64,
+        players: Vec<PairPlayer>,
+    }
+
+    let category = match itsf::RankingCategory::try_from_str(params.category.as_deref().unwrap_or("open")) {
+        Ok(category) => category,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+    };
+    let mut itsf_lics = Vec::new();
+    for itsf_lic in params.players.split(',') {
+        match parse_license(itsf_lic.trim()) {
+            Ok(itsf_lic) => itsf_lics.push(itsf_lic),
+            Err(response) => return Ok(response),
+        }
+    }
+    if itsf_lics.len() != 2 || itsf_lics[0] == itsf_lics[1] {
+        return Ok(HttpResponse::BadRequest().json(json::err("players must be two different licenses")));
+    }
+
+    let mut players = Vec::new();
+    for itsf_lic in itsf_lics {
+        let player = match data.data.get_player(itsf_lic) {
+            Some(player) if !player.hidden || auth::is_authenticated(&req) => player,
+            _ => return Ok(HttpResponse::NotFound().json(json::err(format!("No such player: {}", itsf_lic)))),
+        };
+        players.push(PairPlayer {
+            itsf_lic,
+            first_name: player.first_name,
+            last_name: player.last_name,
+            strength: stats::doubles_strength(itsf_lic, category),
+        });
+    }
+    let rating = players.iter().map(|player| player.strength.strength).sum::<f64>();
+    Ok(HttpResponse::Ok().json(json::ok(PairRating {
+        category,
+        rating: (rating * 100.0).round() / 100.0,
+        players,
+    })))
+}
+
+#[derive(Deserialize)]
+struct TimeseriesParams {
+    metric: stats::TimeseriesMetric,
+    country: Option<String>,
+}
+
+#[actix_web::get("/stats/timeseries")]
+async fn get_timeseries(params: web::Query<TimeseriesParams>) -> Result<HttpResponse, Error> {
+    let series = stats::timeseries(params.metric, params.country.as_deref());
+    Ok(HttpResponse::Ok().json(json::ok(series)))
+}
+
+#[actix_web::get("/leagues/{season}")]
+async fn get_league_tables(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    season: web::Path<String>,
+) -> Result<HttpResponse, Error> {
+    let season = match Season::parse_dtfb(&season) {
+        Ok(season) => season,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+    };
+
+    #[derive(serde::Serialize)]
+    struct StandingJson {
+        position: i32,
+        team: String,
+        points: i32,
+        /// Players listed for the team in this season.
+        players: Vec<PlayerData>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct LeagueTableJson {
+        division: String,
+        standings: Vec<StandingJson>,
+    }
+
+    let tables = match data.data.get_league_tables(season) {
+        Some(tables) => tables,
+        None => return Ok(HttpResponse::NotFound().json(json::err("No league tables for this season"))),
+    };
+
+    let include_hidden = auth::is_authenticated(&req);
+    let name_style = labels::NameStyle::from_request(&req);
+    let mut team_players: HashMap<String, Vec<PlayerData>> = data.data.aggregate_players(|players| {
+        let mut team_players: HashMap<String, Vec<PlayerData>> = HashMap::new();
+        for player in players.filter(|player| include_hidden || !player.hidden) {
+            for team in player
+                .dtfb_league_teams
+                .iter()
+                .filter(|team| team.year == season.year())
+            {
+                team_players
+                    .entry(team.name.clone())
+                    .or_default()
+                    .push(PlayerData::new(player.clone(), name_style));
+            }
+        }
+        team_players
+    });
+
+    let tables: Vec<LeagueTableJson> = tables
+        .into_iter()
+        .map(|table| LeagueTableJson {
+            division: table.division,
+            standings: table
+                .standings
+                .into_iter()
+                .map(|standing| {
+                    let mut players = team_players.remove(&standing.team).unwrap_or_default();
+                    players.sort_by_key(|player| player.itsf_lic);
+                    StandingJson {
+                        position: standing.position,
+                        team: standing.team,
+                        points: standing.points,
+                        players,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(json::ok(tables)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(db_stats)
+        .service(get_national_team)
+        .service(get_records)
+        .service(get_category_transitions)
+        .service(get_country_ranking)
+        .service(get_pair_rating)
+        .service(get_timeseries)
+        .service(get_league_tables);
+}
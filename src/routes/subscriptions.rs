@@ -0,0 +1,107 @@
+//! Subscriptions to player changes.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data::license::LicenseNumber;
+use playerdb_core::notify;
+use serde::Deserialize;
+
+use super::{require_user, AppState};
+use crate::json;
+
+#[actix_web::get("/subscriptions")]
+async fn get_subscriptions(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    Ok(HttpResponse::Ok().json(json::ok(data.data.get_subscription(&user_id))))
+}
+
+#[derive(Deserialize)]
+struct SubscribeInfo {
+    itsf_lic: LicenseNumber,
+}
+
+#[actix_web::post("/subscribe")]
+async fn subscribe_player(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<SubscribeInfo>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    if data.data.get_player(info.itsf_lic.get()).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such player")));
+    }
+
+    let subscription = data.data.modify_subscription(&user_id, |subscription| {
+        if !subscription.players.contains(&info.itsf_lic.get()) {
+            subscription.players.push(info.itsf_lic.get());
+        }
+    });
+    Ok(HttpResponse::Ok().json(json::ok(subscription)))
+}
+
+#[actix_web::post("/unsubscribe")]
+async fn unsubscribe_player(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<SubscribeInfo>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+
+    let subscription = data.data.modify_subscription(&user_id, |subscription| {
+        subscription.players.retain(|itsf_lic| *itsf_lic != info.itsf_lic.get());
+    });
+    Ok(HttpResponse::Ok().json(json::ok(subscription)))
+}
+
+#[derive(Deserialize)]
+struct SubscriptionTargetInfo {
+    webhook_url: Option<String>,
+    email: Option<String>,
+}
+
+#[actix_web::post("/subscriptions/target")]
+async fn set_subscription_target(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<SubscriptionTargetInfo>,
+) -> Result<HttpResponse, Error> {
+    let user_id = match require_user(&req) {
+        Ok(user_id) => user_id,
+        Err(response) => return Ok(response),
+    };
+    let info = info.into_inner();
+    if let Some(url) = &info.webhook_url {
+        if let Err(err) = notify::webhook::resolve(url).await {
+            return Ok(HttpResponse::BadRequest().json(json::err(err)));
+        }
+    }
+    if let Some(email) = &info.email {
+        if !notify::email::is_configured() {
+            return Ok(HttpResponse::BadRequest().json(json::err("email notifications are not configured")));
+        }
+        if !email.contains('@') {
+            return Ok(HttpResponse::BadRequest().json(json::err("invalid email address")));
+        }
+    }
+
+    let subscription = data.data.modify_subscription(&user_id, |subscription| {
+        subscription.webhook_url = info.webhook_url;
+        subscription.email = info.email;
+    });
+    Ok(HttpResponse::Ok().json(json::ok(subscription)))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_subscriptions)
+        .service(subscribe_player)
+        .service(unsubscribe_player)
+        .service(set_subscription_target);
+}
@@ -0,0 +1,142 @@
+//! Player tags.
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use playerdb_core::data;
+use playerdb_core::data::license::LicenseNumber;
+use serde::Deserialize;
+
+use super::lists::{find_unknown_players, get_workspace_list, license_ids};
+use super::AppState;
+use crate::{auth, json};
+
+#[derive(serde::Serialize)]
+struct TagInfo {
+    tag: String,
+    players: usize,
+}
+
+#[actix_web::get("/tags")]
+async fn list_tags(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, Error> {
+    let tags: Vec<TagInfo> = data
+        .data
+        .get_tags(auth::workspace(&req).as_deref())
+        .into_iter()
+        .map(|(tag, players)| TagInfo { tag, players })
+        .collect();
+    Ok(HttpResponse::Ok().json(json::ok(tags)))
+}
+
+#[derive(Deserialize)]
+struct TagInfoParams {
+    itsf_lic: LicenseNumber,
+    tag: String,
+}
+
+#[actix_web::post("/add_tag")]
+async fn add_player_tag(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<TagInfoParams>,
+) -> Result<HttpResponse, Error> {
+    let tag = match data::normalize_tag(&info.tag) {
+        Ok(tag) => tag,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+    };
+    if data.data.get_player(info.itsf_lic.get()).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such player")));
+    }
+
+    data.data
+        .add_player_tag(auth::workspace(&req).as_deref(), info.itsf_lic.get(), tag);
+    Ok(HttpResponse::Ok().json(json::ok("added tag")))
+}
+
+#[actix_web::post("/remove_tag")]
+async fn remove_player_tag(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<TagInfoParams>,
+) -> Result<HttpResponse, Error> {
+    let tag = match data::normalize_tag(&info.tag) {
+        Ok(tag) => tag,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+    };
+    if data.data.get_player(info.itsf_lic.get()).is_none() {
+        return Ok(HttpResponse::NotFound().json(json::err("No such player")));
+    }
+
+    data.data
+        .remove_player_tag(auth::workspace(&req).as_deref(), info.itsf_lic.get(), &tag);
+    Ok(HttpResponse::Ok().json(json::ok("removed tag")))
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum TagOperation {
+    Add,
+    Remove,
+}
+
+/// Either `players` or `list_id` selects the players.
+#[derive(Deserialize)]
+struct BulkTagInfo {
+    tag: String,
+    operation: TagOperation,
+    players: Option<Vec<LicenseNumber>>,
+    list_id: Option<i32>,
+}
+
+#[derive(serde::Serialize)]
+struct BulkTagSummary {
+    /// Players that got or lost the tag.
+    changed: usize,
+    /// Players that already had the tag, or didn't have it when removing.
+    unchanged: usize,
+    /// Requested licences without a stored player, skipped.
+    unknown: Vec<i32>,
+}
+
+#[actix_web::post("/tags/bulk")]
+async fn bulk_tag_players(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    info: web::Json<BulkTagInfo>,
+) -> Result<HttpResponse, Error> {
+    let tag = match data::normalize_tag(&info.tag) {
+        Ok(tag) => tag,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json::err(err))),
+    };
+    let mut players = match (&info.players, info.list_id) {
+        (Some(players), None) => license_ids(players),
+        (None, Some(list_id)) => match get_workspace_list(&req, &data, list_id) {
+            Some(list) => list.players,
+            None => return Ok(HttpResponse::NotFound().json(json::err("No such list"))),
+        },
+        _ => {
+            return Ok(HttpResponse::BadRequest().json(json::err("either players or list_id is required")));
+        }
+    };
+    players.sort_unstable();
+    players.dedup();
+    let unknown = find_unknown_players(&data, &players);
+    players.retain(|itsf_lic| !unknown.contains(itsf_lic));
+
+    let changed = data.data.set_players_tag(
+        auth::workspace(&req).as_deref(),
+        &players,
+        &tag,
+        info.operation == TagOperation::Add,
+    );
+    Ok(HttpResponse::Ok().json(json::ok(BulkTagSummary {
+        changed,
+        unchanged: players.len() - changed,
+        unknown,
+    })))
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_tags)
+        .service(add_player_tag)
+        .service(remove_player_tag)
+        .service(bulk_tag_players);
+}
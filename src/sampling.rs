@@ -0,0 +1,186 @@
+//! Stores a random sample of requests with anonymized clients, for capacity planning.
+//! Configured via `REQUEST_SAMPLE_RATE` (fraction of requests, off by default),
+//! old samples are deleted by the retention job, see `retention`.
+
+use actix_web::dev::ServiceRequest;
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use playerdb_core::bench::Latencies;
+use playerdb_core::data::{samples::RequestSample, DatabaseRef};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SampleSettings {
+    pub rate: f64,
+}
+
+impl SampleSettings {
+    pub fn from_env() -> Self {
+        let rate = match std::env::var("REQUEST_SAMPLE_RATE") {
+            Ok(rate) => rate
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| (0.0..=1.0).contains(rate))
+                .expect("invalid REQUEST_SAMPLE_RATE"),
+            Err(_) => 0.0,
+        };
+        Self { rate }
+    }
+}
+
+lazy_static! {
+    static ref SETTINGS: SampleSettings = SampleSettings::from_env();
+    static ref PENDING: Mutex<Vec<RequestSample>> = Mutex::new(Vec::new());
+    /// Random per process, so anonymized clients can't be matched against hashes of all addresses.
+    static ref SALT: u64 = RandomState::new().build_hasher().finish();
+}
+
+static SAMPLE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Reads the settings, so that invalid ones fail at startup instead of in the first request.
+pub fn init() -> SampleSettings {
+    *SETTINGS
+}
+
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(SAMPLE_COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish() as f64 / u64::MAX as f64
+}
+
+/// Hash of the client's network, i.e. the IPv4 /24 or IPv6 /48, which changes every day.
+/// Enough to count distinct clients per day without storing addresses.
+fn anonymize(address: &str) -> String {
+    let ip = address
+        .parse::<SocketAddr>()
+        .map(|address| address.ip())
+        .or_else(|_| address.parse::<IpAddr>());
+    let network = match ip {
+        Ok(IpAddr::V4(ip)) => {
+            let octets = ip.octets();
+            format!("{}.{}.{}", octets[0], octets[1], octets[2])
+        }
+        Ok(IpAddr::V6(ip)) => {
+            let segments = ip.segments();
+            format!("{:x}:{:x}:{:x}", segments[0], segments[1], segments[2])
+        }
+        Err(_) => String::from(address),
+    };
+    let day = chrono::Utc::now().date_naive();
+    let hash = Sha256::digest(format!("{}/{}/{}", *SALT, day, network));
+    hash[..6].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Request data captured before the request is handled, if the request was chosen for the sample.
+pub struct PendingSample {
+    timestamp: i64,
+    method: String,
+    path: String,
+    client: String,
+}
+
+/// Decides whether the request is sampled.
+pub fn start(req: &ServiceRequest) -> Option<PendingSample> {
+    if SETTINGS.rate == 0.0 || random_fraction() >= SETTINGS.rate {
+        return None;
+    }
+    Some(PendingSample {
+        timestamp: chrono::Utc::now().timestamp(),
+        method: req.method().to_string(),
+        path: req.path().to_string(),
+        client: anonymize(req.connection_info().realip_remote_addr().unwrap_or_default()),
+    })
+}
+
+/// Queues the sample for the writer task.
+pub fn finish(sample: PendingSample, endpoint: Option<String>, status: u16, latency: Duration) {
+    PENDING.lock().unwrap().push(RequestSample {
+        timestamp: sample.timestamp,
+        method: sample.method,
+        path: sample.path,
+        endpoint,
+        status,
+        latency_ms: latency.as_millis() as i64,
+        client: sample.client,
+    });
+}
+
+/// Periodically writes the queued samples to the database.
+pub fn start_writer(db: &DatabaseRef) {
+    const WRITE_INTERVAL: Duration = Duration::from_secs(10);
+    if SETTINGS.rate == 0.0 {
+        return;
+    }
+    let db = db.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(WRITE_INTERVAL).await;
+            let samples: Vec<RequestSample> = std::mem::take(&mut *PENDING.lock().unwrap());
+            if !samples.is_empty() {
+                db.add_request_samples(&samples);
+            }
+        }
+    });
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct EndpointUsage {
+    pub endpoint: String,
+    /// Sampled requests divided by the sample rate.
+    pub estimated_requests: u64,
+    /// Distinct anonymized clients, which change daily, so the same client counts once per day.
+    pub clients: usize,
+    #[serde(flatten)]
+    pub latencies: Latencies,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct UsageReport {
+    pub sample_rate: f64,
+    pub since: i64,
+    pub endpoints: Vec<EndpointUsage>,
+}
+
+/// Summarizes the samples taken since `since` per endpoint, most requested first.
+pub fn usage(db: &DatabaseRef, since: i64) -> UsageReport {
+    let mut endpoints: HashMap<String, Vec<RequestSample>> = HashMap::new();
+    for sample in db.get_request_samples(since) {
+        let endpoint = sample.endpoint.clone().unwrap_or(String::from("(no route)"));
+        endpoints.entry(endpoint).or_default().push(sample);
+    }
+
+    let rate = SETTINGS.rate;
+    let mut endpoints: Vec<EndpointUsage> = endpoints
+        .into_iter()
+        .map(|(endpoint, samples)| {
+            let clients: HashSet<&str> = samples.iter().map(|sample| sample.client.as_str()).collect();
+            let errors = samples.iter().filter(|sample| sample.status >= 400).count();
+            let durations = samples
+                .iter()
+                .map(|sample| Duration::from_millis(sample.latency_ms as u64))
+                .collect();
+            EndpointUsage {
+                estimated_requests: match rate > 0.0 {
+                    true => (samples.len() as f64 / rate).round() as u64,
+                    false => samples.len() as u64,
+                },
+                clients: clients.len(),
+                endpoint,
+                latencies: Latencies::from(durations, errors),
+            }
+        })
+        .collect();
+    endpoints.sort_by_key(|usage| std::cmp::Reverse(usage.latencies.requests));
+    UsageReport {
+        sample_rate: rate,
+        since,
+        endpoints,
+    }
+}
@@ -0,0 +1,80 @@
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs image URLs so they expire, configured via `IMAGE_URL_SECRET` and `IMAGE_URL_TTL` (seconds).
+struct ImageSigner {
+    secret: Vec<u8>,
+    ttl: i64,
+}
+
+impl ImageSigner {
+    fn from_env() -> Option<Self> {
+        let secret = std::env::var("IMAGE_URL_SECRET").ok()?;
+        let ttl = match std::env::var("IMAGE_URL_TTL") {
+            Ok(ttl) => ttl.parse::<i64>().expect("invalid IMAGE_URL_TTL"),
+            Err(_) => 60 * 60,
+        };
+        Some(ImageSigner {
+            secret: secret.into_bytes(),
+            ttl,
+        })
+    }
+
+    fn mac(&self, itsf_id: i32, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(format!("{}:{}", itsf_id, expires).as_bytes());
+        mac
+    }
+}
+
+lazy_static! {
+    static ref SIGNER: Option<ImageSigner> = ImageSigner::from_env();
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    (0..text.len())
+        .step_by(2)
+        .map(|pos| u8::from_str_radix(text.get(pos..pos + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn is_enabled() -> bool {
+    SIGNER.is_some()
+}
+
+/// Path of the player image, named after its content hash if known so it can be cached forever,
+/// with an expiring signature if image URLs are signed.
+pub fn image_path(itsf_id: i32, hash: Option<&str>) -> String {
+    let path = match hash {
+        Some(hash) => format!("/image/{}-{}.jpg", itsf_id, hash),
+        None => format!("/image/{}.jpg", itsf_id),
+    };
+    match SIGNER.as_ref() {
+        Some(signer) => {
+            let expires = chrono::Utc::now().timestamp() + signer.ttl;
+            let signature = to_hex(&signer.mac(itsf_id, expires).finalize().into_bytes());
+            format!("{}?expires={}&sig={}", path, expires, signature)
+        }
+        None => path,
+    }
+}
+
+/// Whether an image request carries a valid, unexpired signature, always true if image URLs aren't signed.
+pub fn verify_image_request(itsf_id: i32, expires: Option<i64>, signature: Option<&str>) -> bool {
+    let signer = match SIGNER.as_ref() {
+        Some(signer) => signer,
+        None => return true,
+    };
+    let (expires, signature) = match (expires, signature.and_then(from_hex)) {
+        (Some(expires), Some(signature)) => (expires, signature),
+        _ => return false,
+    };
+    expires >= chrono::Utc::now().timestamp() && signer.mac(itsf_id, expires).verify_slice(&signature).is_ok()
+}
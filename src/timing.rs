@@ -0,0 +1,116 @@
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::InternalError;
+use actix_web::{Error, HttpResponse};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::time::{Duration, Instant};
+
+use crate::{bench, json, sampling};
+
+/// Limits for request handling, configured via `REQUEST_TIMEOUT` (seconds) and `SLOW_REQUEST` (milliseconds).
+#[derive(Debug, Clone, Copy)]
+pub struct RequestLimits {
+    timeout: Duration,
+    slow: Duration,
+}
+
+impl RequestLimits {
+    pub fn from_env() -> Self {
+        let timeout = match std::env::var("REQUEST_TIMEOUT") {
+            Ok(secs) => Duration::from_secs(secs.parse::<u64>().expect("invalid REQUEST_TIMEOUT")),
+            Err(_) => Duration::from_secs(30),
+        };
+        let slow = match std::env::var("SLOW_REQUEST") {
+            Ok(millis) => Duration::from_millis(millis.parse::<u64>().expect("invalid SLOW_REQUEST")),
+            Err(_) => Duration::from_millis(1000),
+        };
+        Self { timeout, slow }
+    }
+}
+
+/// Middleware aborting requests that exceed the timeout and logging slow ones with their parameters.
+pub struct RequestTiming {
+    limits: RequestLimits,
+}
+
+impl RequestTiming {
+    pub fn new(limits: RequestLimits) -> Self {
+        Self { limits }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTiming
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestTimingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTimingMiddleware {
+            service,
+            limits: self.limits,
+        }))
+    }
+}
+
+pub struct RequestTimingMiddleware<S> {
+    service: S,
+    limits: RequestLimits,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTimingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let limits = self.limits;
+        let description = format!("{} {}", req.method(), req.uri());
+        let sample = sampling::start(&req);
+        let response = self.service.call(req);
+
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = tokio::time::timeout(limits.timeout, response).await;
+            let elapsed = start.elapsed();
+
+            match result {
+                Ok(response) => {
+                    if let Ok(response) = &response {
+                        bench::record(response);
+                        if let Some(sample) = sample {
+                            let endpoint = response.request().match_pattern();
+                            sampling::finish(sample, endpoint, response.status().as_u16(), elapsed);
+                        }
+                    }
+                    if elapsed > limits.slow {
+                        log::warn!("Slow request: {} took {} ms", description, elapsed.as_millis());
+                    }
+                    response
+                }
+                Err(_) => {
+                    if let Some(sample) = sample {
+                        sampling::finish(sample, None, 504, elapsed);
+                    }
+                    log::error!(
+                        "Request timed out after {} s: {}",
+                        limits.timeout.as_secs(),
+                        description
+                    );
+                    let response = HttpResponse::GatewayTimeout().json(json::err("request timed out"));
+                    Err(InternalError::from_response("request timed out", response).into())
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,2625 @@
+mod common;
+
+use chrono::Datelike;
+use common::{TestServer, CLUB, ERIKA, HIDDEN, MAX, MAX_DTFB_ID, PASSWORD, USER, WORKSPACE_USER};
+use playerdb_client::{
+    CategoryTransition, CommentImport, CommentStatus, CommentVisibility, Error, RankingCategory, RankingClass,
+    TagOperation,
+};
+use reqwest::{Method, StatusCode};
+
+fn status(err: Error) -> u16 {
+    match err {
+        Error::Api { status, .. } => status,
+        Error::Http(err) => panic!("request failed: {}", err),
+    }
+}
+
+#[actix_web::test]
+async fn player_lookup() {
+    let server = TestServer::start();
+    let client = server.client();
+
+    let player = client.player(MAX).await.unwrap();
+    assert_eq!(
+        (player.first_name.as_str(), player.last_name.as_str()),
+        ("Max", "Mustermann")
+    );
+    assert_eq!(player.display_name, "Max Mustermann");
+    assert_eq!(player.itsf_rankings.len(), 1);
+    assert_eq!(player.itsf_rankings[0].category, RankingCategory::Open);
+    assert_eq!(player.itsf_rankings[0].percentile, Some(5.0));
+    assert_eq!(player.tags, vec!["goalie"]);
+    assert!(player.data_warnings.is_empty());
+
+    let warnings = client.player(ERIKA).await.unwrap().data_warnings;
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code, "profile_refresh_failed");
+    assert!(!warnings[0].message.contains("tablesoccer.org"));
+
+    assert!(player.country_changes.is_empty());
+    let erika = client.player(ERIKA).await.unwrap();
+    assert_eq!(erika.country_code, "AUT");
+    assert_eq!(erika.country_changes.len(), 1);
+    assert_eq!(erika.country_changes[0].from.as_deref(), Some("GER"));
+    assert_eq!(erika.country_changes[0].to.as_deref(), Some("AUT"));
+    assert_eq!(erika.country_changes[0].year, chrono::Utc::now().year());
+
+    let by_dtfb = client.player_by_dtfb_license(MAX_DTFB_ID).await.unwrap();
+    assert_eq!(by_dtfb, player);
+
+    assert_eq!(status(client.player(12345678).await.unwrap_err()), 404);
+    let response = server.request(Method::GET, "/player/abc").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let player: serde_json::Value = server
+        .request(
+            Method::GET,
+            &format!("/player/{}?include_combined=true&include=rankings", MAX),
+        )
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let classes: Vec<&str> = player["data"]["itsf_rankings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|ranking| ranking["class"].as_str().unwrap())
+        .collect();
+    assert_eq!(classes, vec!["singles", "combined"]);
+
+    let get = |path: String| {
+        let request = server.request(Method::GET, &path);
+        async move {
+            let response: serde_json::Value = request.send().await.unwrap().json().await.unwrap();
+            response["data"].clone()
+        }
+    };
+    let player = get(format!("/player/{}?name_style=itsf", MAX)).await;
+    assert_eq!(player["display_name"], "MUSTERMANN Max");
+    let players = get(String::from("/listplayers?name_style=ITSF")).await;
+    let max = players
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|player| player["itsf_lic"] == MAX)
+        .unwrap();
+    assert_eq!(max["display_name"], "MUSTERMANN Max");
+    let card = get(format!("/player/{}/card?name_style=unknown", MAX)).await;
+    assert_eq!(card["display_name"], "Max Mustermann");
+}
+
+#[actix_web::test]
+async fn heavy_player_sections_are_opt_in() {
+    let server = TestServer::start();
+    let get = |path: String| {
+        let request = server.request(Method::GET, &path);
+        async move { request.send().await.unwrap() }
+    };
+
+    let response = get(format!("/player/{}", MAX)).await;
+    let player: serde_json::Value = response.json().await.unwrap();
+    let player = player["data"].as_object().unwrap();
+    assert_eq!(player["first_name"], "Max");
+    for section in [
+        "itsf_rankings",
+        "dtfb_rankings",
+        "dm_placements",
+        "dtfl_teams",
+        "comments",
+    ] {
+        assert!(!player.contains_key(section), "{} returned by default", section);
+    }
+
+    let response = get(format!("/player/{}?include=rankings, comments", MAX)).await;
+    let player: serde_json::Value = response.json().await.unwrap();
+    let player = player["data"].as_object().unwrap();
+    assert!(player.contains_key("itsf_rankings") && player.contains_key("comments"));
+    assert!(!player.contains_key("dtfl_teams") && !player.contains_key("dm_placements"));
+
+    let player = server.client().player_sections(MAX, &["teams"]).await.unwrap();
+    assert!(player.itsf_rankings.is_empty());
+
+    let response = get(format!("/player/{}?include=rankings,everything", MAX)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn responses_can_be_trimmed_to_selected_fields() {
+    let server = TestServer::start();
+    let response = server
+        .request(
+            Method::GET,
+            &format!(
+                "/player/{}?include=rankings&fields=first_name, last_name,itsf_rankings",
+                MAX
+            ),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("X-Data-Freshness"));
+    let player: serde_json::Value = response.json().await.unwrap();
+    let mut fields: Vec<&String> = player["data"].as_object().unwrap().keys().collect();
+    fields.sort();
+    assert_eq!(fields, vec!["first_name", "itsf_rankings", "last_name"]);
+
+    let players: serde_json::Value = server
+        .request(Method::GET, "/listplayers?fields=itsf_lic")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let players = players["data"].as_array().unwrap();
+    assert!(!players.is_empty());
+    assert!(players.iter().all(|player| player.as_object().unwrap().len() == 1));
+
+    let response = server
+        .request(Method::GET, "/listplayers?fields=,")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = server
+        .request(Method::GET, "/player/12345678?fields=first_name")
+        .send()
+        .await
+        .unwrap();
+    let error: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(error["error"], "No such player");
+}
+
+#[actix_web::test]
+async fn enums_have_codes_and_labels() {
+    let server = TestServer::start();
+    let get = |path: &str| {
+        let request = server.request(Method::GET, path);
+        async move {
+            let response: serde_json::Value = request.send().await.unwrap().json().await.unwrap();
+            response["data"].clone()
+        }
+    };
+
+    let enums = get("/meta/enums?lang=de").await;
+    assert_eq!(
+        enums["ranking_classes"][0],
+        serde_json::json!({"code": "singles", "label": "Einzel"})
+    );
+    assert_eq!(enums["player_categories"][2]["code"], "junior_male");
+    assert_eq!(enums["championship_classes"].as_array().unwrap().len(), 2);
+
+    let card = get(&format!("/player/{}/card", MAX)).await;
+    assert_eq!(
+        (&card["category"], &card["category_label"]),
+        (&"men".into(), &"Men".into())
+    );
+}
+
+#[actix_web::test]
+async fn stored_years_are_listed() {
+    let server = TestServer::start();
+    let years: serde_json::Value = server
+        .request(Method::GET, "/meta/years")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let itsf = years["data"]["itsf"].as_array().unwrap();
+    assert_eq!(itsf.len(), 1);
+    assert_eq!(itsf[0]["year"], 2022);
+    assert_eq!(itsf[0]["entries"], 2);
+    assert!(itsf[0]["scraped_at"].is_i64());
+    // league teams of the fixture players, without a recorded download
+    assert_eq!(
+        years["data"]["dtfb"],
+        serde_json::json!([
+            {"year": 2021, "entries": 2, "scraped_at": null},
+            {"year": 2022, "entries": 1, "scraped_at": null},
+        ])
+    );
+}
+
+#[actix_web::test]
+async fn frontend_config_reflects_deployment() {
+    let server = TestServer::start_with_env(&[("DISABLED_FEATURES", "exports"), ("RECORDS_COUNTRY", "AUT")]);
+    let config: serde_json::Value = server
+        .request(Method::GET, "/config/frontend")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let config = &config["data"];
+    assert_eq!(config["access_mode"], "public");
+    assert_eq!(config["read_only"], true);
+    assert_eq!(config["features"], serde_json::json!(["scraping", "comments"]));
+    assert_eq!(config["default_country"], "AUT");
+    assert_eq!(config["itsf_years"][0], 2010);
+    assert_eq!(config["dtfb_seasons"][0], "2010/11");
+
+    let config: serde_json::Value = server
+        .request(Method::GET, "/config/frontend")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(config["data"]["read_only"], false);
+}
+
+#[actix_web::test]
+async fn hidden_players_and_internal_comments_need_login() {
+    let server = TestServer::start();
+
+    assert_eq!(status(server.client().player(HIDDEN).await.unwrap_err()), 404);
+    assert!(server.authenticated_client().player(HIDDEN).await.unwrap().hidden);
+
+    let public = server.client().player(MAX).await.unwrap();
+    assert_eq!(public.comments.len(), 1);
+    let internal = server.authenticated_client().player(MAX).await.unwrap();
+    assert_eq!(internal.comments.len(), 2);
+
+    let response = server.request(Method::GET, "/db.zip").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = server
+        .request(Method::GET, "/db.zip")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn comments_can_be_tracked_until_resolved() {
+    let server = TestServer::start();
+    let client = server.authenticated_client();
+    let player = client.player(MAX).await.unwrap();
+    let note = player
+        .comments
+        .iter()
+        .find(|comment| comment.text == "scouting note")
+        .unwrap();
+    assert_eq!(note.status, None);
+
+    let err = server
+        .client()
+        .set_comment_status(MAX, note, Some(CommentStatus::Open))
+        .await
+        .unwrap_err();
+    assert_eq!(status(err), 401);
+    let opened = client
+        .set_comment_status(MAX, note, Some(CommentStatus::Open))
+        .await
+        .unwrap();
+    assert_eq!(opened.status, Some(CommentStatus::Open));
+    for _ in 0..2 {
+        let liked = client.set_comment_thumbs_up(MAX, note, true).await.unwrap();
+        assert_eq!(liked.thumbs_up, 1);
+    }
+    let resolved = client
+        .set_comment_status(MAX, note, Some(CommentStatus::Resolved))
+        .await
+        .unwrap();
+    assert_eq!(resolved.resolved_by.as_deref(), Some(USER));
+    assert!(resolved.resolved_at.is_some());
+
+    let player = client.player(MAX).await.unwrap();
+    let note = player
+        .comments
+        .iter()
+        .find(|comment| comment.text == "scouting note")
+        .unwrap();
+    assert_eq!(note.status, Some(CommentStatus::Resolved));
+    assert_eq!(note.thumbs_up, 1);
+    let reopened = client
+        .set_comment_status(MAX, note, Some(CommentStatus::Open))
+        .await
+        .unwrap();
+    assert_eq!(reopened.resolved_by, None);
+
+    let mut edited = note.clone();
+    edited.text = String::from("another note");
+    let err = client.set_comment_thumbs_up(MAX, &edited, true).await.unwrap_err();
+    assert_eq!(status(err), 404);
+}
+
+#[actix_web::test]
+async fn search_and_listing() {
+    let server = TestServer::start();
+    let client = server.client();
+
+    let results = client.search("muster", None).await.unwrap();
+    let mut found: Vec<i32> = results.iter().map(|player| player.itsf_lic).collect();
+    found.sort();
+    assert_eq!(found, vec![MAX, ERIKA]);
+    assert_eq!(client.search("AUT", None).await.unwrap()[0].itsf_lic, ERIKA);
+
+    let goalies = client.players(Some("goalie")).await.unwrap();
+    assert_eq!(goalies.len(), 1);
+    assert_eq!(goalies[0].itsf_lic, MAX);
+}
+
+#[actix_web::test]
+async fn browsers_can_search_with_opensearch() {
+    let server = TestServer::start();
+    let response = server.request(Method::GET, "/opensearch.xml").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/opensearchdescription+xml"
+    );
+    let description = response.text().await.unwrap();
+    assert!(description.contains("<ShortName>ITSF Player DB</ShortName>"));
+    assert!(description.contains("/search?format=opensearch&amp;q={searchTerms}"));
+    assert!(description.contains("/players.html?q={searchTerms}"));
+
+    let response = server
+        .request(Method::GET, "/search?q=erika&format=opensearch")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["content-type"], "application/x-suggestions+json");
+    let suggestions: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(suggestions[0], "erika");
+    assert_eq!(suggestions[1], serde_json::json!(["Erika Musterfrau"]));
+    assert_eq!(suggestions[2][0], format!("{:08}, AUT", ERIKA));
+    assert!(suggestions[3][0]
+        .as_str()
+        .unwrap()
+        .ends_with(&format!("/comments.html?{}", ERIKA)));
+
+    let response = server
+        .request(Method::GET, "/search?q=erika&format=xml")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn category_transitions_are_derived_from_birth_years() {
+    let server = TestServer::start();
+    let transitions = |query: &'static str, authenticated: bool| {
+        let mut request = server.request(Method::GET, &format!("/transitions?{}", query));
+        if authenticated {
+            request = request.basic_auth(USER, Some(PASSWORD));
+        }
+        async move {
+            let response: serde_json::Value = request.send().await.unwrap().json().await.unwrap();
+            response["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|entry| (entry["itsf_lic"].as_i64().unwrap(), entry["transition"].clone()))
+                .collect::<Vec<_>>()
+        }
+    };
+    // the fixture players are born in 1990
+    let leaves_junior = serde_json::json!("leaves_junior");
+    assert_eq!(
+        transitions("year=2009", false).await,
+        vec![(ERIKA as i64, leaves_junior.clone()), (MAX as i64, leaves_junior)]
+    );
+    assert_eq!(transitions("year=2009", true).await.len(), 3);
+    assert_eq!(
+        transitions("year=2040&country=ger", false).await,
+        vec![(MAX as i64, serde_json::json!("becomes_senior"))]
+    );
+    assert!(transitions("year=2020", false).await.is_empty());
+
+    let client = server.client();
+    assert_eq!(client.player(MAX).await.unwrap().category_transition, None);
+    let response = server
+        .request(Method::POST, &format!("/player/{}/overrides", MAX))
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({
+            "field": "birth_year",
+            "value": chrono::Utc::now().year() + 1 - 50,
+            "reason": "passport",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        client.player(MAX).await.unwrap().category_transition,
+        Some(CategoryTransition::BecomesSenior)
+    );
+}
+
+#[actix_web::test]
+async fn changes_need_login() {
+    let server = TestServer::start();
+
+    let err = server.client().add_tag(ERIKA, "defender").await.unwrap_err();
+    assert_eq!(status(err), 401);
+
+    server.authenticated_client().add_tag(ERIKA, "Defender").await.unwrap();
+    assert_eq!(server.client().player(ERIKA).await.unwrap().tags, vec!["defender"]);
+    server
+        .authenticated_client()
+        .remove_tag(ERIKA, "defender")
+        .await
+        .unwrap();
+    assert!(server.client().player(ERIKA).await.unwrap().tags.is_empty());
+}
+
+#[actix_web::test]
+async fn tags_can_be_changed_in_bulk() {
+    let server = TestServer::start();
+    let err = server
+        .client()
+        .bulk_tag(&[MAX, ERIKA], "entry", TagOperation::Add)
+        .await
+        .unwrap_err();
+    assert_eq!(status(err), 401);
+
+    let client = server.authenticated_client();
+    let summary = client
+        .bulk_tag(&[MAX, ERIKA, ERIKA, 12345678], "Entry", TagOperation::Add)
+        .await
+        .unwrap();
+    assert_eq!(
+        (summary.changed, summary.unchanged, summary.unknown),
+        (2, 0, vec![12345678])
+    );
+    assert_eq!(client.players(Some("entry")).await.unwrap().len(), 2);
+
+    let response = server
+        .request(Method::POST, "/lists")
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "name": "finals", "players": [MAX] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let list_id = client.lists().await.unwrap()[0].list_id;
+    let summary = client
+        .bulk_tag_list(list_id, "entry", TagOperation::Remove)
+        .await
+        .unwrap();
+    assert_eq!((summary.changed, summary.unchanged), (1, 0));
+    assert_eq!(client.player(ERIKA).await.unwrap().tags, vec!["entry"]);
+    assert_eq!(
+        status(client.bulk_tag_list(999, "entry", TagOperation::Add).await.unwrap_err()),
+        404
+    );
+
+    let response = server
+        .request(Method::POST, "/tags/bulk")
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({"tag": "entry", "operation": "add", "players": [MAX], "list_id": list_id}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn comment_import() {
+    let server = TestServer::start();
+    let client = server.authenticated_client();
+
+    let comments = vec![
+        CommentImport {
+            license: ERIKA,
+            comment: String::from("plays left"),
+            author: Some(String::from("Coach")),
+            date: Some(String::from("01.03.2022")),
+            visibility: CommentVisibility::Public,
+        },
+        CommentImport {
+            license: 1,
+            comment: String::from("unknown"),
+            author: None,
+            date: None,
+            visibility: CommentVisibility::Public,
+        },
+    ];
+    let report = client.import_comments(&comments, false).await.unwrap();
+    assert_eq!((report.imported, report.duplicates), (1, 0));
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].row, 2);
+
+    let report = client.import_comments(&comments[..1], false).await.unwrap();
+    assert_eq!((report.imported, report.duplicates), (0, 1));
+
+    let player = client.player(ERIKA).await.unwrap();
+    assert_eq!(player.comments.len(), 1);
+    assert_eq!(player.comments[0].author.as_deref(), Some("Coach"));
+
+    let csv = "license;comment\n84001234;\"left; fast\"\n";
+    let response = server
+        .request(Method::POST, "/import/comments")
+        .basic_auth(USER, Some(PASSWORD))
+        .header("Content-Type", "text/csv")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let player = client.player(ERIKA).await.unwrap();
+    assert!(player.comments.iter().any(|comment| comment.text == "left; fast"));
+}
+
+#[actix_web::test]
+async fn workspaces_have_their_own_comments_tags_and_lists() {
+    let server = TestServer::start();
+    let shared = server.authenticated_client();
+    let club = server.client().with_credentials(WORKSPACE_USER, PASSWORD);
+
+    club.add_tag(MAX, "captain").await.unwrap();
+    club.add_comment(MAX, "club note", CommentVisibility::Internal)
+        .await
+        .unwrap();
+    let player = club.player(MAX).await.unwrap();
+    assert_eq!(player.tags, vec!["captain"]);
+    assert_eq!(player.comments.len(), 1);
+    assert_eq!(player.comments[0].text, "club note");
+    assert_eq!(player.itsf_rankings.len(), 1);
+
+    let player = shared.player(MAX).await.unwrap();
+    assert_eq!(player.tags, vec!["goalie"]);
+    assert!(player.comments.iter().all(|comment| comment.text != "club note"));
+
+    let response = server
+        .request(Method::POST, "/lists")
+        .basic_auth(WORKSPACE_USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "name": "club squad", "players": [MAX] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let lists = club.lists().await.unwrap();
+    assert_eq!(lists.len(), 1);
+    assert_eq!(lists[0].workspace.as_deref(), Some("club"));
+    assert!(shared.lists().await.unwrap().is_empty());
+    assert_eq!(status(shared.list_players(lists[0].list_id).await.unwrap_err()), 404);
+
+    let response = server
+        .request(Method::POST, "/set_hidden")
+        .basic_auth(WORKSPACE_USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "itsf_lic": MAX, "hidden": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = server
+        .request(Method::GET, "/db.zip")
+        .basic_auth(WORKSPACE_USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn anonymized_players_keep_only_their_results() {
+    let server = TestServer::start();
+    let path = format!("/player/{}/anonymize", MAX);
+    let response = server.request(Method::POST, &path).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = server
+        .request(Method::POST, &path)
+        .basic_auth(WORKSPACE_USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = server
+        .request(Method::POST, "/player/12345678/anonymize")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let image = format!("/image/{}.jpg", MAX);
+    let response = server.request(Method::GET, &image).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = server
+        .request(Method::POST, &path)
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let client = server.authenticated_client();
+    let player = client.player(MAX).await.unwrap();
+    assert_eq!(
+        (player.first_name.as_str(), player.last_name.as_str()),
+        ("Anonymized", "Player")
+    );
+    assert!(player.comments.is_empty() && player.tags.is_empty());
+    assert_eq!(player.itsf_rankings.len(), 1);
+    assert!(client.search("Mustermann", None).await.unwrap().is_empty());
+    assert_eq!(
+        status(client.player_by_dtfb_license(MAX_DTFB_ID).await.unwrap_err()),
+        404
+    );
+    let response = server.request(Method::GET, &image).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Where the image of Max is handed out: the image itself, its URL in the profile and the offline bundle.
+async fn image_exposure(server: &TestServer, logged_in: bool) -> (StatusCode, bool, bool) {
+    let request = |path: String| match logged_in {
+        true => server.request(Method::GET, &path).basic_auth(USER, Some(PASSWORD)),
+        false => server.request(Method::GET, &path),
+    };
+    let image = request(format!("/image/{}.jpg", MAX)).send().await.unwrap().status();
+    let player: serde_json::Value = request(format!("/player/{}", MAX))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let bundle = request(format!("/export/offline_bundle?players={}", MAX))
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    let mut bundle = zip::ZipArchive::new(std::io::Cursor::new(bundle)).unwrap();
+    let thumbnail = bundle.by_name(&format!("thumbnails/{}.jpg", MAX)).is_ok();
+    (image, player["data"]["image_url"].is_string(), thumbnail)
+}
+
+/// Whether `/db.zip` contains the image of Max.
+async fn database_download_has_image(server: &TestServer) -> bool {
+    let download = server
+        .request(Method::GET, "/db.zip")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .bytes()
+        .await
+        .unwrap();
+    let download = zip::ZipArchive::new(std::io::Cursor::new(download)).unwrap();
+    let image = format!("/{}.jpg", MAX);
+    let has_image = download.file_names().any(|name| name.ends_with(&image));
+    has_image
+}
+
+#[actix_web::test]
+async fn image_access_can_be_restricted() {
+    let ok = StatusCode::OK;
+    for (access, anonymous, logged_in) in [
+        ("public", (ok, true, true), (ok, true, true)),
+        ("login", (StatusCode::UNAUTHORIZED, false, false), (ok, true, true)),
+        (
+            "off",
+            (StatusCode::NOT_FOUND, false, false),
+            (StatusCode::NOT_FOUND, false, false),
+        ),
+        ("visible", (ok, true, true), (ok, true, true)),
+    ] {
+        let server = TestServer::start_with_env(&[("IMAGE_ACCESS", access)]);
+        assert_eq!(image_exposure(&server, false).await, anonymous, "{}", access);
+        assert_eq!(image_exposure(&server, true).await, logged_in, "{}", access);
+        assert_eq!(
+            database_download_has_image(&server).await,
+            access != "off",
+            "{}",
+            access
+        );
+
+        if access == "visible" {
+            let response = server
+                .request(Method::POST, "/set_hidden")
+                .basic_auth(USER, Some(PASSWORD))
+                .json(&serde_json::json!({ "itsf_lic": MAX, "hidden": true }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                image_exposure(&server, true).await,
+                (StatusCode::NOT_FOUND, false, false)
+            );
+            assert!(!database_download_has_image(&server).await);
+        }
+    }
+}
+
+#[actix_web::test]
+async fn images_can_be_downloaded_in_ranges() {
+    let server = TestServer::start();
+    let response = server
+        .request(Method::GET, &format!("/image/{}.jpg", MAX))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["Accept-Ranges"], "bytes");
+    let path = String::from(response.url().path());
+    let image = response.bytes().await.unwrap();
+    let len = image.len();
+
+    let response = server.request(Method::HEAD, &path).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["Content-Length"], len.to_string().as_str());
+    assert!(response.bytes().await.unwrap().is_empty());
+
+    let range = |range: String| server.request(Method::GET, &path).header("Range", range).send();
+    let response = range(String::from("bytes=0-9")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.headers()["Content-Range"],
+        format!("bytes 0-9/{}", len).as_str()
+    );
+    assert_eq!(response.bytes().await.unwrap(), image[..10]);
+
+    let response = range(String::from("bytes=-5")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.bytes().await.unwrap(), image[len - 5..]);
+
+    let response = range(format!("bytes={}-", len)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.headers()["Content-Range"], format!("bytes */{}", len).as_str());
+
+    let response = range(String::from("bytes=0-1,5-9")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.bytes().await.unwrap(), image);
+}
+
+#[actix_web::test]
+async fn list_deletion_can_be_undone() {
+    let server = TestServer::start();
+    let authenticated = |method, path: &str| server.request(method, path).basic_auth(USER, Some(PASSWORD));
+
+    let response = authenticated(Method::POST, "/lists")
+        .json(&serde_json::json!({ "name": "squad", "players": [MAX, ERIKA] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let lists = server.client().lists().await.unwrap();
+    let list = &lists[0];
+    assert_eq!(list.players.len(), 2);
+
+    let response: serde_json::Value = authenticated(Method::DELETE, &format!("/list/{}", list.list_id))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(server.client().lists().await.unwrap().is_empty());
+
+    let action_id = response["data"]["action_id"].as_u64().unwrap();
+    let response = authenticated(Method::POST, &format!("/admin/undo/{}", action_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(server.client().list_players(list.list_id).await.unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn list_merges_can_be_undone_after_restarts() {
+    let mut server = TestServer::start();
+    let authenticated =
+        |server: &TestServer, method, path: &str| server.request(method, path).basic_auth(USER, Some(PASSWORD));
+    for (name, players) in [("forwards", vec![MAX, ERIKA]), ("defenders", vec![ERIKA])] {
+        let response = authenticated(&server, Method::POST, "/lists")
+            .json(&serde_json::json!({ "name": name, "players": players }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let lists = server.client().lists().await.unwrap();
+    let (forwards, defenders) = (lists[0].list_id, lists[1].list_id);
+
+    let merge = |server: &TestServer, into_list_id: i32| {
+        authenticated(server, Method::POST, &format!("/list/{}/merge", forwards))
+            .json(&serde_json::json!({ "into_list_id": into_list_id }))
+            .send()
+    };
+    assert_eq!(
+        merge(&server, forwards).await.unwrap().status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(merge(&server, 999).await.unwrap().status(), StatusCode::NOT_FOUND);
+    let response: serde_json::Value = merge(&server, defenders).await.unwrap().json().await.unwrap();
+    assert_eq!(response["data"]["action"]["type"], "merge_list");
+    assert_eq!(response["data"]["action"]["added_players"], serde_json::json!([MAX]));
+    let lists = server.client().lists().await.unwrap();
+    assert_eq!(lists.len(), 1);
+    assert_eq!(server.client().list_players(defenders).await.unwrap().len(), 2);
+
+    // still pending after a restart
+    server.run_offline(&["--print-pending-migrations"]);
+    let pending: serde_json::Value = authenticated(&server, Method::GET, "/admin/undo")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let action_id = response["data"]["action_id"].as_u64().unwrap();
+    assert_eq!(pending["data"][0]["action_id"], action_id);
+    assert_eq!(server.client().lists().await.unwrap().len(), 1);
+
+    let response = authenticated(&server, Method::POST, &format!("/admin/undo/{}", action_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(server.client().list_players(forwards).await.unwrap().len(), 2);
+    assert_eq!(server.client().list_players(defenders).await.unwrap().len(), 1);
+}
+
+#[actix_web::test]
+async fn expired_actions_are_finalized_after_restarts() {
+    let mut server = TestServer::start_with_env(&[("UNDO_WINDOW", "0")]);
+    let authenticated =
+        |server: &TestServer, method, path: &str| server.request(method, path).basic_auth(USER, Some(PASSWORD));
+    let response = authenticated(&server, Method::POST, "/lists")
+        .json(&serde_json::json!({ "name": "squad", "players": [MAX] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let list_id = server.client().lists().await.unwrap()[0].list_id;
+    let response: serde_json::Value = authenticated(&server, Method::DELETE, &format!("/list/{}", list_id))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let action_id = response["data"]["action_id"].as_u64().unwrap();
+
+    server.run_offline(&["--print-pending-migrations"]);
+    let mut finalized = false;
+    for _ in 0..50 {
+        let pending: serde_json::Value = authenticated(&server, Method::GET, "/admin/undo")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if pending["data"].as_array().unwrap().is_empty() {
+            finalized = true;
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(finalized);
+    let response = authenticated(&server, Method::POST, &format!("/admin/undo/{}", action_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(server.client().lists().await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn disabled_features_are_rejected() {
+    let server = TestServer::start_with_env(&[("DISABLED_FEATURES", "comments")]);
+    let client = server.authenticated_client();
+
+    let err = client
+        .add_comment(MAX, "not saved", CommentVisibility::Public)
+        .await
+        .unwrap_err();
+    assert_eq!(status(err), 503);
+
+    let response = server
+        .request(Method::POST, "/admin/features")
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "feature": "comments", "enabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    client
+        .add_comment(MAX, "saved", CommentVisibility::Public)
+        .await
+        .unwrap();
+}
+
+#[actix_web::test]
+async fn private_mode_requires_login_for_reads() {
+    let server = TestServer::start_with_env(&[("ACCESS_MODE", "private")]);
+
+    assert_eq!(status(server.client().player(MAX).await.unwrap_err()), 401);
+    server.authenticated_client().player(MAX).await.unwrap();
+}
+
+#[actix_web::test]
+async fn demo_mode_scrapes_from_mock_source() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let response = server
+        .request(Method::POST, "/download_dtfb?max_rank=10")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let client = server.client();
+    let start = std::time::Instant::now();
+    let player = loop {
+        match client.player(84000001).await {
+            Ok(player) if !player.dtfl_teams.is_empty() => break player,
+            _ => assert!(start.elapsed().as_secs() < 30, "demo players weren't scraped"),
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    };
+    assert_eq!(
+        (player.first_name.as_str(), player.last_name.as_str()),
+        ("Max", "Mustermann")
+    );
+    assert_eq!(player.country_code, "GER");
+}
+
+#[actix_web::test]
+async fn downloaded_images_are_reencoded_and_scaled_down() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true"), ("IMAGE_MAX_SIZE", "80")]);
+    let response = server
+        .request(Method::POST, "/download_dtfb?max_rank=10")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let start = std::time::Instant::now();
+    let image = loop {
+        let response = server
+            .request(Method::GET, "/image/84000001.jpg")
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+            .await
+            .unwrap();
+        if response.status() == StatusCode::OK {
+            break response.bytes().await.unwrap();
+        }
+        assert!(start.elapsed().as_secs() < 30, "demo player image wasn't scraped");
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    };
+    // the mock serves 120x160 portraits
+    let image = image::load_from_memory_with_format(&image, image::ImageFormat::Jpeg).unwrap();
+    assert_eq!((image.width(), image.height()), (60, 80));
+}
+
+#[actix_web::test]
+async fn itsf_calendar_is_scraped_into_events() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let response = server
+        .request(Method::POST, "/download_events?year=2000")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = server
+        .request(Method::POST, "/download_events?year=2022")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+
+    let events = |query: &'static str| {
+        let request = server.request(Method::GET, &format!("/events?{}", query));
+        async move {
+            let response = request.send().await.unwrap();
+            let status = response.status();
+            (status, response.json::<serde_json::Value>().await.unwrap())
+        }
+    };
+    let (_, year) = events("from=2022-01-01&to=2022-12-31").await;
+    let year = year["data"].as_array().unwrap().clone();
+    assert_eq!(year.len(), 3);
+    assert_eq!(year[0]["name"], "International Open 2022");
+    assert_eq!(year[0]["start_date"], "2022-03-14");
+    assert_eq!(year[0]["end_date"], "2022-03-16");
+    assert_eq!(year[0]["location"], "Paris");
+    assert_eq!(year[0]["country_code"], "FRA");
+    assert_eq!(year[0]["category"], "ITSF Pro Tour");
+    let (_, june) = events("from=2022-06-01&to=2022-06-30").await;
+    assert_eq!(june["data"].as_array().unwrap().len(), 1);
+    let (status, _) = events("from=tomorrow").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn national_team_appearances_are_recorded_from_world_championships() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let start = |path: &'static str| {
+        server
+            .request(Method::POST, path)
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+    };
+    assert_eq!(
+        start("/download_itsf?max_rank=20").await.unwrap().status(),
+        StatusCode::OK
+    );
+    wait_for_download(&server).await;
+    assert_eq!(
+        start("/download_events?year=2023").await.unwrap().status(),
+        StatusCode::OK
+    );
+    wait_for_download(&server).await;
+
+    let roster: serde_json::Value = server
+        .request(Method::GET, "/national_team/ger/2023")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let roster = roster["data"].as_array().unwrap();
+    assert_eq!(roster.len(), 6);
+    assert!(roster
+        .iter()
+        .all(|player| player["competitions"].as_array().unwrap().len() == 1));
+    assert_eq!(roster[0]["itsf_lic"], 84000001);
+    assert_eq!(roster[0]["competitions"][0]["competition"], "Men's Teams");
+    assert_eq!(roster[0]["competitions"][0]["event"], "World Championships 2023");
+    assert_eq!(roster[1]["competitions"][0]["competition"], "Women's Teams");
+
+    let player: serde_json::Value = server
+        .request(Method::GET, "/player/84000007?include=teams")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let appearances = player["data"]["national_team_appearances"].as_array().unwrap();
+    assert_eq!(appearances.len(), 1);
+    assert_eq!(appearances[0]["country_code"], "FRA");
+    assert_eq!(appearances[0]["year"], 2023);
+
+    let response = server
+        .request(Method::GET, "/national_team/GER/2022")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn registered_scrapers_can_be_run_by_name() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let start = |path: &'static str, requests: serde_json::Value| {
+        server
+            .request(Method::POST, path)
+            .basic_auth(USER, Some(PASSWORD))
+            .json(&serde_json::json!({ "requests": requests }))
+            .send()
+    };
+    let status = |path, requests| {
+        let response = start(path, requests);
+        async move { response.await.unwrap().status() }
+    };
+    assert_eq!(
+        status("/download_source/itsf_unknown", serde_json::json!(["1"])).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        status("/download_source/itsf_players", serde_json::json!(["max"])).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        status("/download_source/itsf_rankings", serde_json::json!(["2022:open"])).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        status("/download_source/itsf_players", serde_json::json!(["84000001"])).await,
+        StatusCode::OK
+    );
+    wait_for_download(&server).await;
+
+    let player: serde_json::Value = server
+        .request(Method::GET, "/player/84000001")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(player["data"]["first_name"], "Max");
+    assert_eq!(player["data"]["last_name"], "Mustermann");
+    let response = server.request(Method::GET, "/player/84000002").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn events_can_be_found_near_a_location() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let response = server
+        .request(Method::POST, "/download_events?year=2022")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+
+    let response = server
+        .request(
+            Method::GET,
+            "/events?from=2022-01-01&to=2022-12-31&near=53.5,10.0&radius=50",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let events = response.json::<serde_json::Value>().await.unwrap()["data"].clone();
+    let events = events.as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["name"], "Masters 2022");
+    assert_eq!(events[0]["coordinates"]["lat"], 53.55);
+
+    let response = server
+        .request(
+            Method::GET,
+            "/events?from=2022-01-01&to=2022-12-31&near=48.5,9.0&radius=2000",
+        )
+        .send()
+        .await
+        .unwrap();
+    let events = response.json::<serde_json::Value>().await.unwrap()["data"].clone();
+    assert_eq!(events.as_array().unwrap().len(), 3);
+
+    let response = server.request(Method::GET, "/events?near=north").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn itsf_download_selection_is_validated() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    for query in ["categories=women,men", "classes=triples", "categories=,"] {
+        let response = server
+            .request(Method::POST, &format!("/download_itsf?{}", query))
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+    }
+    let response = server
+        .request(
+            Method::POST,
+            "/download_itsf?max_rank=5&categories=women&classes=doubles",
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn itsf_download_accepts_max_rank_per_category() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    for body in [
+        "{\"max_rank_per_category\": {\"men\": 10}}",
+        "{\"max_rank\": -1}",
+        "max_rank=10",
+    ] {
+        let response = server
+            .request(Method::POST, "/download_itsf")
+            .basic_auth(USER, Some(PASSWORD))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+    }
+    let response = server
+        .request(Method::POST, "/download_itsf")
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({"max_rank": 5, "max_rank_per_category": {"open": 10, "junior": 2}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn parse_quality_is_tracked_per_scraper() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let response = server.request(Method::GET, "/admin/quality").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = server
+        .request(
+            Method::POST,
+            "/download_itsf?season=2022&categories=women&classes=singles&max_rank=5",
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+
+    let report: serde_json::Value = server
+        .request(Method::GET, "/admin/quality?days=1")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let parser = |name: &str| {
+        report["data"]["parsers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|parser| parser["parser"] == name)
+            .unwrap()
+            .clone()
+    };
+    let rankings = parser("itsf_rankings");
+    assert_eq!(rankings["pages"], 1);
+    assert_eq!(rankings["success_rate"], 1.0);
+    assert_eq!(rankings["days"].as_array().unwrap().len(), 1);
+    let players = parser("itsf_players");
+    assert!(players["records"].as_u64().unwrap() > 0);
+    assert_eq!(players["failed"], 0);
+    assert_eq!(players["completeness"]["birth_year"], 1.0);
+    let leagues = parser("dtfb_leagues");
+    assert_eq!(leagues["pages"], 0);
+    assert!(leagues["success_rate"].is_null());
+}
+
+#[actix_web::test]
+async fn clubs_can_be_maintained_by_users() {
+    let server = TestServer::start();
+    let path = format!("/clubs/{}", CLUB.replace(' ', "%20"));
+    let club = serde_json::json!({
+        "venue_address": "Hauptstraße 1, 20095 Hamburg",
+        "training_nights": [{"weekday": "Tue", "time": "19:00-22:00"}, {"weekday": "Thu"}],
+        "contact_email": "info@kickertreff.example",
+    });
+
+    let response = server.request(Method::POST, &path).json(&club).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = server
+        .request(Method::POST, &path)
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({"contact_email": "not an email"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = server
+        .request(Method::POST, &path)
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&club)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let clubs: serde_json::Value = server
+        .request(Method::GET, "/clubs")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(clubs["data"][0]["name"], CLUB);
+    assert_eq!(clubs["data"][0]["training_nights"][0]["weekday"], "Tue");
+
+    let club: serde_json::Value = server
+        .request(Method::GET, &path)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(club["data"]["contact_email"], "info@kickertreff.example");
+    assert_eq!(club["data"]["league_year"], 2022);
+    let players: Vec<&serde_json::Value> = club["data"]["players"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|player| &player["itsf_lic"])
+        .collect();
+    assert_eq!(players, vec![MAX]);
+
+    let response = server
+        .request(Method::DELETE, &path)
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = server.request(Method::GET, &path).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn only_one_download_per_source_host_runs_at_a_time() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true"), ("MOCK_SOURCE_LATENCY", "200")]);
+    let start = |path: &'static str| {
+        server
+            .request(Method::POST, path)
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+    };
+    let response = start("/download_itsf?max_rank=5").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = start("/download_itsf?max_rank=5").await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("ITSF"), "{}", body);
+    let response = start("/download_events").await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = start("/download_dtfb?max_rank=10").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: serde_json::Value = server
+        .request(Method::GET, "/download_status")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status["data"]["running"], true);
+    assert_eq!(status["data"]["sources"], serde_json::json!(["itsf", "dtfb"]));
+
+    wait_for_download(&server).await;
+    let response = start("/download_itsf?max_rank=5").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+}
+
+#[actix_web::test]
+async fn points_are_projected_with_results_dropping_after_a_year() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let response = server
+        .request(
+            Method::POST,
+            "/download_itsf?max_rank=20&categories=open&classes=singles",
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+
+    let projection = |date: String| {
+        let request = server.request(Method::GET, &format!("/player/84000001/projection{}", date));
+        async move {
+            let response = request.send().await.unwrap();
+            let status = response.status();
+            (
+                status,
+                response.json::<serde_json::Value>().await.unwrap()["data"].clone(),
+            )
+        }
+    };
+    let today = chrono::Utc::now().date_naive();
+    let player = server.client().player(84000001).await.unwrap();
+    let ranking = player
+        .itsf_rankings
+        .iter()
+        .find(|ranking| ranking.year == today.year())
+        .unwrap();
+    let points = 101 - ranking.place as i64;
+
+    let (status, now) = projection(format!("?date={}", today)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(now["points"], points);
+    assert_eq!(now["points_today"], points);
+    assert_eq!(now["counted"].as_array().unwrap().len(), 1);
+    assert_eq!(now["rankings"][0]["category"], "open");
+
+    let in_a_year = today + chrono::Months::new(12);
+    let (_, later) = projection(format!("?date={}", in_a_year)).await;
+    assert_eq!(later["points"], 0);
+    assert_eq!(later["points_today"], points);
+    assert_eq!(later["expiring"][0]["expires_on"], in_a_year.to_string());
+
+    for query in ["", "?date=2000-01-01", "?date=soon"] {
+        let (status, _) = projection(String::from(query)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[actix_web::test]
+async fn doubles_pairs_are_rated_after_downloads() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let rating = |query: &'static str| {
+        let request = server.request(Method::GET, &format!("/pairs/rating?{}", query));
+        async move {
+            let response = request.send().await.unwrap();
+            let status = response.status();
+            (
+                status,
+                response.json::<serde_json::Value>().await.unwrap()["data"].clone(),
+            )
+        }
+    };
+    for query in [
+        "players=84000001",
+        "players=84000001,84000001",
+        "players=84000001,84000002&category=mixed",
+    ] {
+        assert_eq!(rating(query).await.0, StatusCode::BAD_REQUEST, "{}", query);
+    }
+    assert_eq!(rating("players=84000001,12345678").await.0, StatusCode::NOT_FOUND);
+
+    let response = server
+        .request(
+            Method::POST,
+            "/download_itsf?max_rank=20&categories=open&classes=doubles",
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+
+    let client = server.client();
+    let mut expected = 0;
+    for itsf_lic in [84000001, 84000002] {
+        let player = client.player(itsf_lic).await.unwrap();
+        let ranking = player
+            .itsf_rankings
+            .iter()
+            .find(|ranking| ranking.class == RankingClass::Doubles)
+            .unwrap();
+        expected += 101 - ranking.place as i64;
+    }
+    let start = std::time::Instant::now();
+    let pair = loop {
+        let (status, pair) = rating("players=84000001,84000002").await;
+        assert_eq!(status, StatusCode::OK);
+        if pair["rating"] != 0.0 {
+            break pair;
+        }
+        assert!(start.elapsed().as_secs() < 30, "doubles strengths weren't computed");
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    };
+    assert_eq!(pair["rating"], expected as f64);
+    assert_eq!(pair["category"], "open");
+    assert_eq!(pair["players"][0]["first_name"], "Max");
+    assert_eq!(pair["players"][0]["seasons"], 1);
+    let (_, women) = rating("players=84000001,84000002&category=women").await;
+    assert_eq!(women["rating"], 0.0);
+}
+
+#[actix_web::test]
+async fn provenance_of_rankings_is_returned_on_request() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let response = server
+        .request(
+            Method::POST,
+            "/download_itsf?max_rank=20&categories=open&classes=singles",
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+
+    let get = |path: &'static str| {
+        let request = server.request(Method::GET, path).basic_auth(USER, Some(PASSWORD));
+        async move {
+            let response: serde_json::Value = request.send().await.unwrap().json().await.unwrap();
+            response["data"].clone()
+        }
+    };
+    let player = get("/player/84000001?include=rankings").await;
+    assert!(player["itsf_rankings"][0].get("provenance").is_none());
+
+    let player = get("/player/84000001?include=rankings&include_provenance=true").await;
+    let provenance = &player["itsf_rankings"][0]["provenance"];
+    assert!(provenance["source_url"]
+        .as_str()
+        .unwrap()
+        .contains("/itsf/page/rankings?category=os"));
+    assert!(provenance["scraped_at"].is_i64());
+    let jobs = get("/jobs").await;
+    let run = jobs["history"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|run| run["id"] == provenance["job_id"])
+        .unwrap();
+    assert_eq!(run["job"], "itsf_rankings");
+}
+
+async fn wait_for_download(server: &TestServer) {
+    let start = std::time::Instant::now();
+    loop {
+        let status: serde_json::Value = server
+            .request(Method::GET, "/download_status")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if status["data"]["running"] == false {
+            return;
+        }
+        assert!(start.elapsed().as_secs() < 30, "download didn't finish");
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+}
+
+async fn women_singles_coverage(server: &TestServer) -> serde_json::Value {
+    let coverage: serde_json::Value = server
+        .request(Method::GET, "/admin/coverage")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let year = coverage["data"].as_array().unwrap().last().unwrap()["year"].clone();
+    coverage["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|ranking| ranking["year"] == year && ranking["category"] == "women" && ranking["class"] == "singles")
+        .unwrap()
+        .clone()
+}
+
+#[actix_web::test]
+async fn unchanged_itsf_rankings_are_skipped() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    for (query, scraped_again) in [
+        ("max_rank=5", true),
+        ("max_rank=5", false),
+        ("max_rank=10", true),
+        ("max_rank=10&force=true", true),
+    ] {
+        let before = women_singles_coverage(&server).await;
+        // coverage is in whole seconds
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        let response = server
+            .request(
+                Method::POST,
+                &format!("/download_itsf?categories=women&classes=singles&{}", query),
+            )
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        wait_for_download(&server).await;
+        let after = women_singles_coverage(&server).await;
+        assert_eq!(before["scraped_at"] != after["scraped_at"], scraped_again, "{}", query);
+        assert!(after["source_updated"].as_str().unwrap().ends_with("-07-01"));
+    }
+}
+
+#[actix_web::test]
+async fn ranking_snapshots_are_archived_and_compared() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    for max_rank in [3, 6] {
+        // snapshots are identified by their download time in whole seconds
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        let response = server
+            .request(
+                Method::POST,
+                &format!(
+                    "/download_itsf?year=2022&categories=open&classes=singles&max_rank={}",
+                    max_rank
+                ),
+            )
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        wait_for_download(&server).await;
+    }
+
+    let get = |path: &'static str| {
+        let request = server.request(Method::GET, path);
+        async move {
+            let response = request.send().await.unwrap();
+            let status = response.status();
+            (status, response.json::<serde_json::Value>().await.unwrap())
+        }
+    };
+    let (_, history) = get("/rankings/2022/open/singles/history").await;
+    let history = history["data"].as_array().unwrap().clone();
+    assert_eq!(history.len(), 2);
+    assert_eq!(
+        (history[0]["entries"].as_u64(), history[0]["archived"].as_bool()),
+        (Some(6), Some(false))
+    );
+    assert_eq!(
+        (history[1]["entries"].as_u64(), history[1]["archived"].as_bool()),
+        (Some(3), Some(true))
+    );
+
+    let (_, diff) = get("/rankings/2022/open/singles/diff").await;
+    assert_eq!(diff["data"]["from"], history[1]["scraped_at"]);
+    assert_eq!(diff["data"]["to"], history[0]["scraped_at"]);
+    let entered: Vec<i64> = diff["data"]["entered"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|placement| placement["place"].as_i64().unwrap())
+        .collect();
+    assert_eq!(entered, vec![4, 5, 6]);
+    assert!(diff["data"]["left"].as_array().unwrap().is_empty());
+    assert!(diff["data"]["moved"].as_array().unwrap().is_empty());
+
+    let (status, _) = get("/rankings/2021/open/singles/diff").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get("/rankings/2022/mixed/singles/history").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn closed_rankings_are_only_replaced_when_forced() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let download = |query: &'static str| {
+        let request = server
+            .request(
+                Method::POST,
+                &format!("/download_itsf?year=2022&categories=open&classes=singles&{}", query),
+            )
+            .basic_auth(USER, Some(PASSWORD));
+        async move {
+            // snapshots are identified by their download time in whole seconds
+            tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+            assert_eq!(request.send().await.unwrap().status(), StatusCode::OK);
+        }
+    };
+    let entries = || async {
+        let history: serde_json::Value = server
+            .request(Method::GET, "/rankings/2022/open/singles/history")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        history["data"].as_array().unwrap().len()
+    };
+    download("max_rank=3").await;
+    wait_for_download(&server).await;
+
+    let response = server
+        .request(
+            Method::POST,
+            "/admin/rankings/2022/close?categories=open&classes=singles",
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let closed: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(closed["data"].as_array().unwrap().len(), 1);
+    assert_eq!(closed["data"][0]["closed_by"], USER);
+    let checksum = closed["data"][0]["checksum"].clone();
+
+    download("max_rank=6&force=true").await;
+    wait_for_download(&server).await;
+    assert_eq!(entries().await, 1);
+    let closure: serde_json::Value = server
+        .request(Method::GET, "/rankings/2022/open/singles/closure")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(closure["data"]["checksum"], checksum);
+    assert_eq!(closure["data"]["intact"], true);
+
+    download("max_rank=6&force_closed=true").await;
+    wait_for_download(&server).await;
+    assert_eq!(entries().await, 2);
+    let closure: serde_json::Value = server
+        .request(Method::GET, "/rankings/2022/open/singles/closure")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_ne!(closure["data"]["checksum"], checksum);
+    assert_eq!(closure["data"]["intact"], true);
+
+    let response = server
+        .request(Method::GET, "/rankings/2022/women/singles/closure")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = server
+        .request(
+            Method::POST,
+            &format!("/admin/rankings/{}/close", chrono::Utc::now().year()),
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn downloads_can_be_started_from_presets() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let presets: serde_json::Value = server
+        .request(Method::GET, "/presets")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let names: Vec<&str> = presets["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|preset| preset["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["full-backfill", "nightly-current-year", "women-only"]);
+
+    let preset = serde_json::json!({
+        "source": "itsf",
+        "seasons": ["2022"],
+        "categories": ["women"],
+        "classes": ["singles"],
+        "max_rank": 3,
+    });
+    let response = server
+        .request(Method::POST, "/presets/women-2022")
+        .json(&preset)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let invalid = serde_json::json!({"source": "itsf", "seasons": ["1850"]});
+    for (name, preset, expected) in [
+        ("women-2022", &preset, StatusCode::OK),
+        ("women-1850", &invalid, StatusCode::BAD_REQUEST),
+        ("Women 2022", &preset, StatusCode::BAD_REQUEST),
+    ] {
+        let response = server
+            .request(Method::POST, &format!("/presets/{}", name.replace(' ', "%20")))
+            .basic_auth(USER, Some(PASSWORD))
+            .json(preset)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected, "{}", name);
+    }
+
+    let response = server
+        .request(Method::POST, "/download_preset/women-2022")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+    let history: serde_json::Value = server
+        .request(Method::GET, "/rankings/2022/women/singles/history")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(history["data"][0]["entries"], 3);
+
+    let response = server
+        .request(Method::DELETE, "/presets/women-2022")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = server
+        .request(Method::POST, "/download_preset/women-2022")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn download_estimates_count_requests_from_the_stored_data() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true"), ("SCRAPE_REQUESTS_PER_MINUTE", "600")]);
+    let estimate = |preset: serde_json::Value| {
+        let request = server
+            .request(Method::POST, "/download_estimate")
+            .basic_auth(USER, Some(PASSWORD))
+            .json(&preset);
+        async move {
+            let response = request.send().await.unwrap();
+            let status = response.status();
+            (status, response.json::<serde_json::Value>().await.unwrap())
+        }
+    };
+    let preset = serde_json::json!({
+        "source": "itsf",
+        "seasons": ["2022"],
+        "categories": ["women"],
+        "classes": ["singles"],
+        "max_rank": 3,
+    });
+    let (status, before) = estimate(preset.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(before["data"]["pages"]["itsf_rankings"], 1);
+    assert_eq!(before["data"]["pages"]["itsf_players"], 3);
+    assert_eq!(before["data"]["pages"]["itsf_images"], 3);
+    assert_eq!(before["data"]["requests"], 7);
+    assert_eq!(before["data"]["seconds"], 1);
+    assert_eq!(before["data"]["limited_by"], "requests");
+    let (status, _) = estimate(serde_json::json!({"source": "itsf", "seasons": ["1850"]})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let response = server
+        .request(
+            Method::POST,
+            "/download_itsf?season=2022&categories=women&classes=singles&max_rank=3",
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+
+    // the "last update" check and the ranking, the players are fresh
+    let (_, after) = estimate(preset).await;
+    assert_eq!(after["data"]["pages"]["itsf_rankings"], 2);
+    assert_eq!(after["data"]["pages"]["itsf_players"], 0);
+    assert_eq!(after["data"]["requests"], 2);
+}
+
+#[actix_web::test]
+async fn download_options_list_valid_parameters() {
+    let server = TestServer::start();
+    let response = server
+        .request(Method::GET, "/admin/download_options")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let options: serde_json::Value = server
+        .request(Method::GET, "/admin/download_options")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let options = &options["data"];
+    assert_eq!(
+        options["categories"],
+        serde_json::json!(["open", "women", "senior", "junior"])
+    );
+    assert_eq!(
+        options["classes"],
+        serde_json::json!(["singles", "doubles", "combined"])
+    );
+    assert_eq!(options["running"], false);
+    let itsf_2022 = options["itsf_seasons"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|season| season["season"] == "2022")
+        .unwrap();
+    assert_eq!(itsf_2022["entries"], 2);
+    assert!(itsf_2022["scraped_at"].is_i64());
+    let dtfb_seasons = options["dtfb_seasons"].as_array().unwrap();
+    assert_eq!(dtfb_seasons[0]["entries"], 0);
+    let dtfb_2021 = dtfb_seasons.iter().find(|season| season["year"] == 2021).unwrap();
+    assert_eq!(dtfb_2021["season"], "2021/22");
+    assert_eq!(dtfb_2021["entries"], 2);
+    assert!(!options["presets"].as_array().unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn retention_report_and_pruning() {
+    let server = TestServer::start_with_env(&[("EVENT_RETENTION", "10")]);
+    let response = server.request(Method::GET, "/admin/retention").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    for method in [Method::GET, Method::POST] {
+        let report: serde_json::Value = server
+            .request(method.clone(), "/admin/retention")
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(report["data"]["dry_run"], method == Method::GET);
+        let rules: Vec<&str> = report["data"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["rule"].as_str().unwrap())
+            .collect();
+        assert_eq!(rules, vec!["job_logs", "job_history", "request_samples", "events"]);
+        assert_eq!(report["data"]["rules"][3]["retention_days"], 10);
+    }
+}
+
+#[actix_web::test]
+async fn maintenance_runs_are_recorded_in_the_job_history() {
+    let server = TestServer::start_with_env(&[("MAINTENANCE_TABLES", "2"), ("MAINTENANCE_VACUUM", "true")]);
+    let response = server.request(Method::POST, "/admin/maintenance").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let run: serde_json::Value = server
+        .request(Method::POST, "/admin/maintenance")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let log = run["data"]["log"].as_array().unwrap();
+    assert_eq!(log.len(), 3);
+    // the test database is tiny, players ties with other tables
+    assert!(log[..2]
+        .iter()
+        .any(|line| line.as_str().unwrap().starts_with("[Maintenance] analyzed players")));
+    assert!(log[2].as_str().unwrap().starts_with("[Maintenance] vacuumed"));
+
+    let jobs: serde_json::Value = server
+        .request(Method::GET, "/jobs")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let history = jobs["data"]["history"].as_array().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["job"], "maintenance");
+    assert_eq!(history[0], run["data"]);
+}
+
+async fn refresh_priority(server: &TestServer) -> Vec<(i64, serde_json::Value)> {
+    let priority: serde_json::Value = server
+        .request(Method::GET, "/admin/refresh_priority")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    priority["data"]["players"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|player| (player["itsf_lic"].as_i64().unwrap(), player["missing"].clone()))
+        .collect()
+}
+
+#[actix_web::test]
+async fn popular_and_incomplete_players_are_refreshed_first() {
+    let server = TestServer::start_with_env(&[
+        ("DEMO_MODE", "true"),
+        ("REFRESH_PLAYERS_PER_DAY", "5"),
+        ("REFRESH_REQUESTS_PER_DAY", "4"),
+    ]);
+    let response = server
+        .request(Method::GET, "/admin/refresh_priority")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // the request budget allows two players
+    let priority = refresh_priority(&server).await;
+    let missing = serde_json::json!(["image", "itsf_rankings"]);
+    assert_eq!(
+        priority,
+        vec![(ERIKA as i64, missing.clone()), (HIDDEN as i64, missing)]
+    );
+
+    for _ in 0..5 {
+        server
+            .request(Method::GET, &format!("/player/{}", MAX))
+            .send()
+            .await
+            .unwrap();
+    }
+    assert_eq!(refresh_priority(&server).await[0].0, MAX as i64);
+
+    let response = server
+        .request(Method::POST, "/admin/refresh_priority?limit=1")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_for_download(&server).await;
+    let jobs: serde_json::Value = server
+        .request(Method::GET, "/jobs")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let run = &jobs["data"]["history"][0];
+    assert_eq!(run["job"], "player_refresh");
+    assert!(run["log"][0]
+        .as_str()
+        .unwrap()
+        .contains("Downloading 1 ITSF player profiles"));
+}
+
+#[actix_web::test]
+async fn manual_overrides_survive_downloads() {
+    let server = TestServer::start_with_env(&[("DEMO_MODE", "true")]);
+    let download = |path: &'static str| {
+        let request = server.request(Method::POST, path).basic_auth(USER, Some(PASSWORD));
+        let server = &server;
+        async move {
+            let response = request.send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            wait_for_download(server).await;
+        }
+    };
+    download("/download_dtfb?max_rank=10").await;
+    let path = "/player/84000001/overrides";
+    let set = |value: serde_json::Value, user: &'static str| {
+        let request = server
+            .request(Method::POST, path)
+            .basic_auth(user, Some(PASSWORD))
+            .json(&serde_json::json!({"field": "last_name", "value": value, "reason": "married"}));
+        async move { request.send().await.unwrap().status() }
+    };
+    assert_eq!(
+        set(serde_json::json!("Musterfrau"), WORKSPACE_USER).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(set(serde_json::json!(""), USER).await, StatusCode::BAD_REQUEST);
+    assert_eq!(set(serde_json::json!("Musterfrau"), USER).await, StatusCode::OK);
+
+    let client = server.client();
+    let player = client.player(84000001).await.unwrap();
+    assert_eq!(player.last_name, "Musterfrau");
+    assert_eq!(player.former_names[0].last_name, "Mustermann");
+    download("/admin/refresh_priority?limit=100").await;
+    assert_eq!(client.player(84000001).await.unwrap().last_name, "Musterfrau");
+
+    assert_eq!(set(serde_json::Value::Null, USER).await, StatusCode::OK);
+    assert_eq!(client.player(84000001).await.unwrap().last_name, "Mustermann");
+    let overrides: serde_json::Value = server
+        .request(Method::GET, path)
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(overrides["data"]["active"], serde_json::json!({}));
+    let history = overrides["data"]["history"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["value"], "Musterfrau");
+    assert_eq!(history[0]["author"], USER);
+    assert_eq!(history[0]["reason"], "married");
+    assert_eq!(history[1]["value"], serde_json::Value::Null);
+}
+
+#[actix_web::test]
+async fn orphaned_images_are_collected() {
+    let server = TestServer::start();
+    let images = server.image_directory();
+    std::fs::write(images.join("12345678.jpg"), [0u8; 100]).unwrap();
+    std::fs::write(images.join("README"), "not an image").unwrap();
+
+    for method in [Method::GET, Method::POST] {
+        let report: serde_json::Value = server
+            .request(method.clone(), "/admin/images/gc")
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(report["data"]["dry_run"], method == Method::GET);
+        assert_eq!(report["data"]["scanned"], 2);
+        assert_eq!(report["data"]["orphaned"], serde_json::json!(["12345678.jpg"]));
+        assert_eq!(report["data"]["reclaimed_bytes"], 100);
+        assert_eq!(images.join("12345678.jpg").exists(), method == Method::GET);
+    }
+    assert!(images.join(format!("{}.jpg", MAX)).exists());
+    assert!(images.join("README").exists());
+
+    let jobs: serde_json::Value = server
+        .request(Method::GET, "/jobs")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(jobs["data"]["history"][0]["job"], "image_gc");
+}
+
+#[actix_web::test]
+async fn bench_replays_recorded_requests() {
+    let server = TestServer::start();
+    server.client().player(MAX).await.unwrap();
+    server.client().search("muster", None).await.unwrap();
+
+    let report: serde_json::Value = server
+        .request(Method::POST, "/admin/bench?iterations=3")
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["data"]["total"]["requests"], 6);
+    assert_eq!(report["data"]["total"]["errors"], 0);
+    let endpoints = report["data"]["endpoints"].as_array().unwrap();
+    assert!(endpoints
+        .iter()
+        .any(|endpoint| endpoint["endpoint"] == "/player/{itsf_lic}"));
+
+    // the total of requests times iterations mustn't overflow past the limit
+    let response = server
+        .request(Method::POST, &format!("/admin/bench?iterations={}", usize::MAX / 2 + 1))
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "requests": ["/player/84000895", "/players"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let report: serde_json::Value = server
+        .request(Method::POST, "/admin/bench")
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "requests": ["/player/84000895", "not a uri"] }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["data"]["total"]["errors"], 1);
+}
+
+#[actix_web::test]
+async fn bench_runs_dont_count_as_player_requests() {
+    let server = TestServer::start();
+    server.client().player(MAX).await.unwrap();
+    let popular = || async {
+        server
+            .request(Method::GET, "/admin/popular")
+            .basic_auth(USER, Some(PASSWORD))
+            .send()
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap()
+    };
+    let before = popular().await;
+    assert_eq!(before["data"][0]["requests"], 1);
+
+    let response = server
+        .request(Method::POST, "/admin/bench?iterations=5")
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "requests": [format!("/player/{}", MAX), format!("/image/{}.jpg", MAX)] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(popular().await, before);
+}
+
+#[actix_web::test]
+async fn webhooks_must_point_to_public_hosts() {
+    let server = TestServer::start();
+    let set_webhook = |url: &str| {
+        server
+            .request(Method::POST, "/subscriptions/target")
+            .basic_auth(USER, Some(PASSWORD))
+            .json(&serde_json::json!({ "webhook_url": url }))
+            .send()
+    };
+
+    for url in [
+        "ftp://93.184.216.34/hook",
+        "not a url",
+        "http://127.0.0.1:8080/hook",
+        "http://localhost/hook",
+        "http://10.0.0.1/hook",
+        "http://192.168.1.1/hook",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://[::1]/hook",
+        "http://[fd00::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+    ] {
+        let response = set_webhook(url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", url);
+    }
+
+    let response = set_webhook("https://93.184.216.34/hook").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let subscription: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(subscription["data"]["webhook_url"], "https://93.184.216.34/hook");
+}
+
+#[actix_web::test]
+async fn bad_credentials_are_rejected_with_a_challenge() {
+    let server = TestServer::start();
+    let add_tag = |authorization: &str| {
+        server
+            .request(Method::POST, "/add_tag")
+            .header("Authorization", authorization)
+            .json(&serde_json::json!({ "itsf_lic": MAX, "tag": "defender" }))
+            .send()
+    };
+
+    // "test:" without password, "test" without colon, and garbage
+    for authorization in ["Basic dGVzdDo=", "Basic dGVzdA==", "Basic !!!", "Bearer token"] {
+        let response = add_tag(authorization).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", authorization);
+        assert!(response.headers().contains_key("WWW-Authenticate"));
+    }
+    let err = server
+        .client()
+        .with_credentials(USER, "wrong")
+        .add_tag(MAX, "defender")
+        .await
+        .unwrap_err();
+    assert_eq!(status(err), 401);
+
+    // reads stay public, ignoring unusable credentials
+    let response = server
+        .request(Method::GET, &format!("/player/{}", MAX))
+        .header("Authorization", "Basic !!!")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn missing_users_file_fails_logins_only() {
+    let server = TestServer::start_with_env(&[("USERS_FILE", "missing.txt")]);
+
+    server.client().player(MAX).await.unwrap();
+    let err = server
+        .authenticated_client()
+        .add_tag(MAX, "defender")
+        .await
+        .unwrap_err();
+    assert_eq!(status(err), 500);
+}
+
+#[actix_web::test]
+async fn outdated_schema_is_reported_before_starting() {
+    let directory = std::env::temp_dir().join(format!("playerdb-test-{}-schema", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let database = directory.join("db.sqlite");
+    let server = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_server"))
+            .args(args)
+            .env_clear()
+            .env("DATABASE_URL", &database)
+            .env("IMAGE_PATH", &directory)
+            .env("HTML_ROOT", concat!(env!("CARGO_MANIFEST_DIR"), "/html"))
+            .env("SERVER_PORT", "0")
+            .output()
+            .unwrap()
+    };
+
+    let output = server(&["--print-pending-migrations"]);
+    assert!(output.status.success());
+    let pending = String::from_utf8(output.stdout).unwrap();
+    assert!(pending.lines().next().unwrap().contains("create_players"));
+    assert!(pending.lines().any(|migration| migration.contains("create_job_runs")));
+
+    let output = server(&[]);
+    assert!(!output.status.success());
+    let message = String::from_utf8(output.stderr).unwrap();
+    assert!(message.contains("pending migrations"), "{}", message);
+    assert!(message.contains("diesel migration run"), "{}", message);
+
+    // an outdated read replica fails just as well, instead of at its first query
+    let settings = playerdb_core::data::connection::ConnectionSettings::from_env();
+    playerdb_core::data::run_migrations(database.to_str().unwrap(), &settings).unwrap();
+    let replica = directory.join("replica.sqlite");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_server"))
+        .env_clear()
+        .env("DATABASE_URL", &database)
+        .env("DATABASE_READ_URL", &replica)
+        .env("IMAGE_PATH", &directory)
+        .env("HTML_ROOT", concat!(env!("CARGO_MANIFEST_DIR"), "/html"))
+        .env("SERVER_PORT", "0")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let message = String::from_utf8(output.stderr).unwrap();
+    assert!(message.contains("replica.sqlite"), "{}", message);
+    assert!(message.contains("pending migrations"), "{}", message);
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[actix_web::test]
+async fn invalid_notification_settings_are_reported_before_starting() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_server"))
+        .env_clear()
+        .env("DATABASE_URL", "missing.sqlite")
+        .env("NOTIFY_TOP_PLACES", "ten")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let message = String::from_utf8(output.stderr).unwrap();
+    assert!(message.contains("invalid NOTIFY_TOP_PLACES"), "{}", message);
+}
+
+#[actix_web::test]
+async fn operators_can_fix_players_from_the_shell() {
+    let mut server = TestServer::start();
+    let max = MAX.to_string();
+    let erika = ERIKA.to_string();
+    for args in [
+        vec!["fix-name", &max, "Maximilian", "Mustermann"],
+        vec!["set-country", &max, "aut"],
+        vec!["delete-ranking-entry", &max, "2022", "open", "singles"],
+        vec!["relink-dtfb", "12345", &erika],
+    ] {
+        let output = server.run_offline(&[&["--repair"], &args[..]].concat());
+        assert!(
+            output.status.success(),
+            "{:?}: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let output = server.run_offline(&["--repair", "set-country", &max, "Austria"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid country code"));
+
+    let client = server.client();
+    let player = client.player(MAX).await.unwrap();
+    assert_eq!(player.first_name, "Maximilian");
+    assert_eq!(player.former_names[0].first_name, "Max");
+    assert_eq!(player.country_code, "AUT");
+    assert_eq!(player.country_changes.last().unwrap().to.as_deref(), Some("AUT"));
+    assert!(player.itsf_rankings.is_empty());
+    let player = client.player_by_dtfb_license(MAX_DTFB_ID).await.unwrap();
+    assert_eq!(player.first_name, "Erika");
+}
+
+#[actix_web::test]
+async fn player_listings_can_be_paged_with_cursors() {
+    let server = TestServer::start();
+    let client = server.authenticated_client();
+    let mut all: Vec<i32> = client.players(None).await.unwrap().iter().map(|p| p.itsf_lic).collect();
+    all.sort();
+
+    let mut paged = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = client.players_page(cursor.as_deref(), 2).await.unwrap();
+        assert!(page.items.len() <= 2);
+        paged.extend(page.items.iter().map(|player| player.itsf_lic));
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(paged, all);
+
+    let page: serde_json::Value = server
+        .request(
+            Method::GET,
+            &format!("/players?filter=country=GER&limit=1&cursor={}", MAX),
+        )
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let players = page["data"].as_array().unwrap();
+    assert_eq!(players.len(), 1);
+    assert!(players[0]["itsf_lic"].as_i64().unwrap() > MAX as i64);
+
+    for query in ["limit=0", "cursor=abc", "limit=5000"] {
+        let response = server
+            .request(Method::GET, &format!("/listplayers?{}", query))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[actix_web::test]
+async fn a_lagging_read_replica_loses_no_data() {
+    use playerdb_core::data::{self, connection::ConnectionSettings};
+
+    // a replica that hasn't caught up with any of the players yet
+    let replica = std::env::temp_dir().join(format!("playerdb-test-replica-{}.sqlite", std::process::id()));
+    let replica = replica.to_str().unwrap();
+    data::run_migrations(replica, &ConnectionSettings::from_env()).unwrap();
+
+    let mut server = TestServer::start_with_env(&[("DATABASE_READ_URL", replica)]);
+    let client = server.authenticated_client();
+    client.add_tag(MAX, "defender").await.unwrap();
+    server.run_offline(&["--print-pending-migrations"]);
+
+    let player = server.authenticated_client().player(MAX).await.unwrap();
+    assert_eq!(player.tags, vec!["defender", "goalie"]);
+    assert_eq!(player.comments.len(), 2);
+    assert!(server.client().player(ERIKA).await.is_ok());
+    let _ = std::fs::remove_file(replica);
+}
+
+#[actix_web::test]
+async fn clients_can_sync_changed_players() {
+    let server = TestServer::start();
+    let sync = |since_revision: u64| {
+        let request = server.request(Method::GET, &format!("/sync?since_revision={}", since_revision));
+        async move {
+            let response: serde_json::Value = request.send().await.unwrap().json().await.unwrap();
+            response["data"].clone()
+        }
+    };
+    let licenses = |sync: &serde_json::Value, list: &str| -> Vec<i64> {
+        sync[list]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.get("itsf_lic").unwrap_or(entry).as_i64().unwrap())
+            .collect()
+    };
+
+    let all = sync(0).await;
+    assert_eq!(all["complete"], true);
+    assert!(licenses(&all, "players").contains(&(MAX as i64)));
+    assert!(!licenses(&all, "players").contains(&(HIDDEN as i64)));
+    let revision = all["revision"].as_u64().unwrap();
+    assert!(licenses(&sync(revision).await, "players").is_empty());
+
+    let client = server.authenticated_client();
+    client.add_tag(MAX, "defender").await.unwrap();
+    let response = server
+        .request(Method::POST, "/set_hidden")
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "itsf_lic": ERIKA, "hidden": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let changes = sync(revision).await;
+    assert_eq!(licenses(&changes, "players"), vec![MAX as i64]);
+    assert_eq!(changes["players"][0]["tags"], serde_json::json!(["defender", "goalie"]));
+    assert_eq!(licenses(&changes, "deleted"), vec![ERIKA as i64]);
+    assert!(changes["revision"].as_u64().unwrap() > revision);
+
+    let first: serde_json::Value = server
+        .request(Method::GET, &format!("/sync?since_revision={}&limit=1", revision))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(first["data"]["complete"], false);
+    assert_eq!(licenses(&first["data"], "players"), vec![MAX as i64]);
+    let rest = sync(first["data"]["revision"].as_u64().unwrap()).await;
+    assert_eq!(licenses(&rest, "deleted"), vec![ERIKA as i64]);
+}
+
+#[actix_web::test]
+async fn secondaries_replicate_players_from_their_primary() {
+    let primary = TestServer::start();
+    primary
+        .authenticated_client()
+        .add_comment(ERIKA, "plays left", CommentVisibility::Public)
+        .await
+        .unwrap();
+    let response = primary
+        .request(Method::POST, "/set_hidden")
+        .basic_auth(USER, Some(PASSWORD))
+        .json(&serde_json::json!({ "itsf_lic": HIDDEN, "hidden": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let secondary = TestServer::start_with_env(&[
+        ("REPLICATE_FROM", &primary.url),
+        ("REPLICATE_USER", USER),
+        ("REPLICATE_PASSWORD", PASSWORD),
+        ("REPLICATE_INTERVAL", "1"),
+    ]);
+    let client = secondary.authenticated_client();
+    let start = std::time::Instant::now();
+    let erika = loop {
+        let erika = client.player(ERIKA).await.unwrap();
+        if !erika.comments.is_empty() {
+            break erika;
+        }
+        assert!(start.elapsed().as_secs() < 30, "comment wasn't replicated");
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    };
+    assert_eq!(erika.comments[0].text, "plays left");
+    // no longer hidden on the primary, so listed without login
+    let response = secondary
+        .request(Method::GET, &format!("/player/{}", HIDDEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = secondary
+        .request(Method::GET, &format!("/image/{}.jpg", MAX))
+        .basic_auth(USER, Some(PASSWORD))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let jobs: serde_json::Value = secondary
+        .request(Method::GET, "/jobs")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let history = jobs["data"]["history"].as_array().unwrap();
+    assert!(history.iter().any(|run| run["job"] == "replication"));
+}
@@ -0,0 +1,278 @@
+//! Runs the server binary against a temporary database seeded with fixture players.
+
+use std::collections::BTreeMap;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use playerdb_core::data::{
+    self, connection::ConnectionSettings, dtfb, itsf, CommentVisibility, DatabaseRef, Player, PlayerImage,
+    RefreshTarget,
+};
+
+pub const USER: &str = "test";
+pub const PASSWORD: &str = "secret";
+/// A user of the `club` workspace, with the same password.
+pub const WORKSPACE_USER: &str = "club/coach";
+
+/// Licenses of the fixture players.
+pub const MAX: i32 = 84000895;
+pub const ERIKA: i32 = 84001234;
+pub const HIDDEN: i32 = 84009999;
+pub const MAX_DTFB_ID: i32 = 12345;
+/// League team of the fixture players.
+pub const CLUB: &str = "Kickertreff Hamburg";
+
+static NEXT_DIRECTORY: AtomicUsize = AtomicUsize::new(0);
+
+fn player(itsf_id: i32, first_name: &str, last_name: &str, country_code: &str) -> Player {
+    Player {
+        itsf_id,
+        first_name: String::from(first_name),
+        last_name: String::from(last_name),
+        birth_year: 1990,
+        country_code: Some(String::from(country_code)),
+        category: itsf::PlayerCategory::Men,
+        itsf_rankings: Vec::new(),
+        dtfb_id: None,
+        dtfb_national_rankings: Vec::new(),
+        dtfb_championship_results: Vec::new(),
+        dtfb_league_teams: Vec::new(),
+        comments: Vec::new(),
+        tags: Vec::new(),
+        former_names: Vec::new(),
+        country_changes: Vec::new(),
+        national_team_appearances: Vec::new(),
+        hidden: false,
+        scraped_at: Some(chrono::Utc::now().timestamp()),
+        refresh_errors: Vec::new(),
+        anonymized: false,
+        revision: 0,
+        overridden: BTreeMap::new(),
+    }
+}
+
+fn seed(db: &DatabaseRef) {
+    db.add_player(player(MAX, "Max", "Mustermann", "GER"));
+    db.set_player_dtfb_id(MAX, MAX_DTFB_ID);
+    db.set_player_image(PlayerImage {
+        itsf_id: MAX,
+        image_data: playerdb_core::seed::portrait(MAX),
+        image_format: String::from("jpg"),
+    })
+    .unwrap();
+    db.add_player_itsf_ranking(
+        MAX,
+        itsf::Ranking {
+            year: 2022,
+            place: 3,
+            category: itsf::RankingCategory::Open,
+            class: itsf::RankingClass::Singles,
+            percentile: None,
+            provenance: None,
+        },
+    );
+    db.add_player_itsf_ranking(
+        MAX,
+        itsf::Ranking {
+            year: 2022,
+            place: 5,
+            category: itsf::RankingCategory::Open,
+            class: itsf::RankingClass::Combined,
+            percentile: None,
+            provenance: None,
+        },
+    );
+    db.record_ranking_download(itsf::RankingDownload {
+        year: 2022,
+        category: itsf::RankingCategory::Open,
+        class: itsf::RankingClass::Singles,
+        scraped_at: chrono::Utc::now().timestamp(),
+        entries: 60,
+        max_rank: Some(100),
+        source_updated: None,
+        closed: None,
+    });
+    db.add_player_dtfb_team(
+        MAX,
+        dtfb::NationalTeam {
+            year: 2021,
+            name: String::from(CLUB),
+            provenance: None,
+        },
+    );
+    db.add_player_dtfb_team(
+        MAX,
+        dtfb::NationalTeam {
+            year: 2022,
+            name: String::from(CLUB),
+            provenance: None,
+        },
+    );
+    db.add_player_tag(None, MAX, String::from("goalie"));
+    db.add_player_comment(
+        None,
+        MAX,
+        String::from("strong pull shot"),
+        CommentVisibility::Public,
+        None,
+    );
+    db.add_player_comment(
+        None,
+        MAX,
+        String::from("scouting note"),
+        CommentVisibility::Internal,
+        None,
+    );
+
+    // switched federations since the first download
+    db.add_player(player(ERIKA, "Erika", "Musterfrau", "GER"));
+    db.add_player(player(ERIKA, "Erika", "Musterfrau", "AUT"));
+    db.add_player_dtfb_team(
+        ERIKA,
+        dtfb::NationalTeam {
+            year: 2021,
+            name: String::from(CLUB),
+            provenance: None,
+        },
+    );
+    db.record_refresh_error(
+        ERIKA,
+        RefreshTarget::Profile,
+        String::from("Player[https://www.tablesoccer.org/page/player&numlic=84001234]: no name"),
+    );
+
+    db.add_player(player(HIDDEN, "Hidden", "Player", "GER"));
+    db.set_player_hidden(HIDDEN, true);
+}
+
+/// A running server with its own database, image directory and users file, all removed when dropped.
+pub struct TestServer {
+    pub url: String,
+    directory: PathBuf,
+    env: Vec<(String, String)>,
+    process: Child,
+}
+
+impl TestServer {
+    pub fn start() -> Self {
+        Self::start_with_env(&[])
+    }
+
+    /// Starts the server with additional environment variables, e.g. to configure optional settings.
+    pub fn start_with_env(env: &[(&str, &str)]) -> Self {
+        let directory = std::env::temp_dir().join(format!(
+            "playerdb-test-{}-{}",
+            std::process::id(),
+            NEXT_DIRECTORY.fetch_add(1, Ordering::Relaxed)
+        ));
+        let images = directory.join("images");
+        std::fs::create_dir_all(&images).expect("failed to create test directory");
+        let database = directory.join("db.sqlite");
+        let users = directory.join("users.txt");
+        let users_file = format!("{}:{}\n{}:{}\n", USER, PASSWORD, WORKSPACE_USER, PASSWORD);
+        std::fs::write(&users, users_file).expect("failed to write users file");
+
+        let database = database.to_str().unwrap();
+        let images = images.to_str().unwrap();
+        let settings = ConnectionSettings::from_env();
+        data::run_migrations(database, &settings).expect("failed to migrate test database");
+        seed(&DatabaseRef::load(database, None, images, settings));
+
+        // a port that was free a moment ago, good enough for tests
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("no free port")
+            .port();
+        let env: Vec<(String, String)> = [
+            ("DATABASE_URL", database),
+            ("IMAGE_PATH", images),
+            ("HTML_ROOT", concat!(env!("CARGO_MANIFEST_DIR"), "/html")),
+            ("USERS_FILE", users.to_str().unwrap()),
+            ("SERVER_PORT", &port.to_string()),
+        ]
+        .iter()
+        .chain(env)
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let process = Self::command(&directory, &env)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start server");
+
+        let server = TestServer {
+            url: format!("http://127.0.0.1:{}", port),
+            directory,
+            env,
+            process,
+        };
+        server.wait_until_ready();
+        server
+    }
+
+    /// The server binary with the server's environment.
+    fn command(directory: &Path, env: &[(String, String)]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_server"));
+        command
+            .current_dir(directory)
+            .env_clear()
+            .envs(env.iter().map(|(name, value)| (name, value)));
+        command
+    }
+
+    /// Stops the server, runs the binary with `args` against its database and starts the server again,
+    /// returning the command's output.
+    pub fn run_offline(&mut self, args: &[&str]) -> std::process::Output {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let output = Self::command(&self.directory, &self.env)
+            .args(args)
+            .output()
+            .expect("failed to run server binary");
+        self.process = Self::command(&self.directory, &self.env)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start server");
+        self.wait_until_ready();
+        output
+    }
+
+    fn wait_until_ready(&self) {
+        const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+        let start = Instant::now();
+        while std::net::TcpStream::connect(self.url.trim_start_matches("http://")).is_err() {
+            assert!(start.elapsed() < STARTUP_TIMEOUT, "server didn't start");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    pub fn client(&self) -> playerdb_client::Client {
+        playerdb_client::Client::new(&self.url)
+    }
+
+    pub fn authenticated_client(&self) -> playerdb_client::Client {
+        self.client().with_credentials(USER, PASSWORD)
+    }
+
+    /// Where the server stores player images.
+    pub fn image_directory(&self) -> PathBuf {
+        self.directory.join("images")
+    }
+
+    /// Raw requests, for checking status codes and endpoints the client doesn't cover.
+    pub fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        reqwest::Client::new().request(method, format!("{}{}", self.url, path))
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}